@@ -1,17 +1,25 @@
 mod parser;
-mod tree;
+pub mod tree;
 
-pub use parser::parse_stream;
+#[cfg(test)]
+pub(crate) use parser::parse_valid;
+pub use parser::{
+    error::{Expected, Severity},
+    parse_stream, Options, SyntaxError,
+};
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        ast::tree::{control::If, Declarator, Expression, Statement},
+        ast::tree::{
+            BaseType, BinaryOp, Declarator, Expression, Initializer, Parameters, Signedness,
+            Statement,
+        },
         lexer::{
             stream::TokenStream,
-            token::{Ident, Keyword, Token, TokenKind},
+            token::{Ident, TokenKind},
         },
-        span::Span, preprocessor::preprocess,
+        preprocessor::preprocess,
     };
 
     use super::*;
@@ -19,19 +27,20 @@ mod tests {
     #[test]
     fn test_parse_empty_main() {
         const SOURCE: &str = "int main() {}";
-        const IDENT_INT: Ident = Ident::new("int");
         const IDENT_MAIN: Ident = Ident::new("main");
-        const IDENT_VOID: Ident = Ident::new("void");
 
         let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
 
-        let program = parse_stream(TokenStream::new(&tokens));
+        let (program, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
         assert_eq!(
             program,
             vec![Statement::new_func_decl(
-                IDENT_INT,
-                IDENT_MAIN,
-                vec![],
+                BaseType::Int(Signedness::Signed).into(),
+                Declarator::Function {
+                    inner: Box::new(IDENT_MAIN.into()),
+                    params: Parameters::Unspecified,
+                },
                 vec![],
             )]
         );
@@ -43,10 +52,13 @@ mod tests {
 
         let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
 
-        if let [t] = parse_stream(TokenStream::new(&tokens)).as_slice() {
+        let (program, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        if let [t] = program.as_slice() {
             let ty = t.as_typedef().expect("Expected typedef");
-            assert_eq!(ty.ty, vec![Ident::new("int")]);
-            assert_eq!(ty.name, Ident::new("int32_t"));
+            assert!(ty.ty.is_typedef());
+            assert_eq!(ty.ty.ty, BaseType::Int(Signedness::Signed));
+            assert_eq!(ty.names, vec![Declarator::Ident(Ident::new("int32_t"))]);
         } else {
             panic!("Expected one statement")
         }
@@ -56,10 +68,17 @@ mod tests {
     fn test_simple_equals() {
         const SOURCE: &str = "x == y";
         let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
-        let (rest, parsed) = parser::parse_top_level_expression(TokenStream::new(&tokens))
-            .expect("Could not parse expression");
+        let (rest, parsed) = parser::parse_top_level_expression(
+            TokenStream::new(&tokens).with(parser::State::new(Options::default())),
+        )
+        .expect("Could not parse expression");
         assert_eq!(rest.tokens, &[TokenKind::Eof]);
-        if let Expression::Equals { lhs, rhs } = parsed {
+        if let Expression::Binary {
+            op: BinaryOp::Equals,
+            lhs,
+            rhs,
+        } = parsed
+        {
             assert_eq!(*lhs, Expression::Ident(Ident::new("x")));
             assert_eq!(*rhs, Expression::Ident(Ident::new("y")));
         } else {
@@ -67,6 +86,148 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_declarators() {
+        const SOURCE: &str = "int (*(*fp)(int))[10];
+        char *name(void) {}";
+
+        let (program, errors) = parse_errors(SOURCE);
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(fp), Statement::FuncDecl(name)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert_eq!(fp.to_string(), "int (*(*fp)(int))[10];\n");
+        assert_eq!(name.name(), Ident::new("name"));
+        assert_eq!(name.params().to_string(), "(void)");
+        assert!(matches!(name.declarator, Declarator::Pointer { .. }));
+    }
+
+    #[test]
+    fn test_multiple_declarators() {
+        const SOURCE: &str = "typedef int a, *pa;
+        a x, *p = &x, y;";
+
+        let (program, errors) = parse_errors(SOURCE);
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::Typedef(types), Statement::VarDecl(vars)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert_eq!(vars.declarators.len(), 3);
+        assert_eq!(vars.to_string(), "a x, *p = &x, y;\n");
+        assert_eq!(types.to_string(), "typedef int a, *pa;\n");
+    }
+
+    fn parse_errors(source: &str) -> (Vec<Statement<'_>>, Vec<SyntaxError<'_>>) {
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(source)).collect();
+        parse_stream(TokenStream::new(&tokens), Options::default())
+    }
+
+    #[test]
+    fn test_error_message() {
+        let (_, errors) = parse_errors("int x = y }");
+        let [error, _stray_brace] = errors.as_slice() else {
+            panic!("Expected two errors, got {errors:?}")
+        };
+        assert_eq!(
+            error.to_string(),
+            "expected ';' after declaration, found '}'"
+        );
+        assert_eq!(error.position(), 4);
+        assert_eq!(error.span().map(|s| s.line_col()), Some((1, 11)));
+    }
+
+    #[test]
+    fn test_error_expected_identifier() {
+        let (_, errors) = parse_errors("typedef int;");
+        let [error] = errors.as_slice() else {
+            panic!("Expected one error, got {errors:?}")
+        };
+        assert_eq!(error.expected_set(), &[Expected::Identifier]);
+        assert_eq!(error.to_string(), "expected identifier, found ';'");
+    }
+
+    #[test]
+    fn test_recover_statements() {
+        const SOURCE: &str = "void main() {
+            int a = 1;
+            = 2;
+            int b = 3;
+        }
+        typedef int;
+        int c = 4;";
+
+        let (program, errors) = parse_errors(SOURCE);
+        assert_eq!(errors.len(), 2, "Expected two errors, got {errors:?}");
+        let [Statement::FuncDecl(main), Statement::Error, Statement::VarDecl(c)] =
+            program.as_slice()
+        else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(matches!(
+            main.body.as_deref().unwrap_or_default(),
+            [
+                Statement::VarDecl(_),
+                Statement::Error,
+                Statement::VarDecl(_)
+            ]
+        ));
+        assert_eq!(
+            c.declarators[0].declarator,
+            Declarator::Ident(Ident::new("c"))
+        );
+    }
+
+    #[test]
+    fn test_recover_insert_tokens() {
+        const SOURCE: &str = "void main() {
+            int a = 1
+            if (a {
+                a = 2
+            }
+        }";
+
+        let (program, errors) = parse_errors(SOURCE);
+        let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected ';' after declaration, found 'if'",
+                "expected ')', found '{'",
+                "expected ';' after expression, found '}'",
+            ]
+        );
+        let [Statement::FuncDecl(main)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(matches!(
+            main.body.as_deref().unwrap_or_default(),
+            [Statement::VarDecl(_), Statement::If(_)]
+        ));
+    }
+
+    #[test]
+    fn test_recover_expression() {
+        let (program, errors) = parse_errors("int a = ;");
+        assert_eq!(errors.len(), 1);
+        let [Statement::VarDecl(a)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert_eq!(
+            a.declarators[0].value,
+            Some(Initializer::Expression(Expression::Error(";")))
+        );
+    }
+
+    #[test]
+    fn test_recover_missing_brace() {
+        let (program, errors) = parse_errors("void main() { int a;");
+        let [error] = errors.as_slice() else {
+            panic!("Expected one error, got {errors:?}")
+        };
+        assert_eq!(error.to_string(), "expected '}', found end of file");
+        assert!(matches!(program.as_slice(), [Statement::FuncDecl(_)]));
+    }
+
     /* #[test]
     fn test_simple_if() {
         const IDENT_INT: Ident = Ident::new("int");
@@ -1,107 +1,233 @@
+use std::cell::RefCell;
+
 use nom::{
     branch::alt,
-    bytes::complete::take,
-    combinator::{map, map_opt, opt, verify},
-    multi::{many0, many1, separated_list0},
-    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
-    IResult, Parser,
+    combinator::{map, opt},
+    error::context,
+    multi::{many0, separated_list1},
+    sequence::{pair, preceded, terminated, tuple},
+    Parser,
 };
 
 use crate::lexer::{
     stream::TokenStream,
-    token::{Ident, Keyword},
+    token::{Ident, Keyword, TokenKind},
 };
 
-use super::tree::{control::If, Assignment, Declarator, FuncDecl, Statement, Typedef, VarDecl};
+use super::tree::{
+    control::{Compound, If},
+    BaseType, DeclSpecifiers, Declarator, Expression, InitDeclarator, Initializer, Statement,
+    TypeName, Typedef, VarDecl,
+};
 
+mod asm;
+mod assertions;
+mod attributes;
 mod blocks;
+mod control;
+mod declarators;
+mod enums;
+pub mod error;
 pub mod expr;
+mod functions;
+mod gnu;
+mod initializers;
+mod input;
+mod labels;
+mod records;
+mod recovery;
+mod scopes;
+mod specifiers;
 mod tags;
 
-pub use expr::parse_top_level_expression;
+pub(crate) use control::StatementContext;
+use declarators::parse_declarator;
+pub use error::SyntaxError;
+use error::{Expected, ParseResult};
+pub(crate) use expr::parse_top_level_expression;
+pub use input::Options;
+pub(crate) use input::{Input, State};
+pub(crate) use scopes::Scopes;
 
-pub(super) fn parse_ident<'i, 't>(
-    i: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Ident<'i>> {
-    map_opt(take(1usize), |t: TokenStream| {
-        t.tokens[0].kind.as_ident().copied()
-    })(i)
+pub(super) fn parse_ident<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Ident<'i>> {
+    match i.peek(0).and_then(|t| t.kind.as_ident()) {
+        Some(&ident) => Ok((nom::Slice::slice(&i, 1..), ident)),
+        None => Err(nom::Err::Error(SyntaxError::expected(
+            i,
+            Expected::Identifier,
+        ))),
+    }
 }
 
-pub(super) fn parse_declarator<'i, 't>(
-    i: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Declarator<'i>> {
-    alt((
-        map(parse_ident, Declarator::Ident),
-        map(preceded(tags::star, parse_declarator), |d| {
-            Declarator::Pointer(Box::new(d))
-        }),
-    ))(i)
+/// Whether the stream starts with a type name, used to tell casts apart from
+/// expressions in parenthesis
+pub(super) fn starts_type_name(i: Input<'_, '_>) -> bool {
+    use Keyword::*;
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Keyword(keyword)) => matches!(
+            keyword,
+            Void | Char
+                | Short
+                | Int
+                | Long
+                | Float
+                | Double
+                | Signed
+                | Unsigned
+                | Bool
+                | Complex
+                | Struct
+                | Union
+                | Enum
+                | Const
+                | Volatile
+                | Restrict
+                | Atomic
+                | Typeof
+        ),
+        Some(TokenKind::Ident(name)) => scopes::is_typedef_name(&i, name),
+        _ => false,
+    }
 }
 
-fn parse_fn<'i, 't>(input: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, FuncDecl<'i>> {
-    let params_parser = alt((
-        verify(parse_ident, |&ident| ident.name == "void").map(|_| vec![]),
-        separated_list0(tags::comma, pair(parse_ident, parse_declarator)),
-    ));
-
+/// Parses a type name, as in casts and `sizeof`
+pub(super) fn parse_type_name<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, TypeName<'i>> {
     map(
-        tuple((
-            parse_ident,
-            parse_ident,
-            blocks::parens(params_parser),
-            blocks::braces(many0(parse_statement)),
-        )),
-        |(ty, name, args, body)| FuncDecl {
-            ret: ty,
-            name,
-            args,
-            body,
+        pair(
+            specifiers::parse_specifier_qualifiers,
+            declarators::parse_abstract_declarator,
+        ),
+        |(specifiers, declarator)| TypeName {
+            specifiers,
+            declarator,
         },
-    )(input)
+    )(i)
 }
 
-fn parse_var_decl<'i, 't>(input: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, VarDecl<'i>> {
-    map(
-        tuple((
-            parse_ident,
-            parse_declarator,
-            opt(preceded(tags::assign, parse_top_level_expression)),
-            tags::semi_colon,
-        )),
-        |(ty, name, value, _)| VarDecl { ty, name, value },
-    )(input)
+/// Parses an optional `= initializer`
+fn parse_initializer<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Option<Initializer<'i>>> {
+    opt(preceded(tags::assign, initializers::parse_initializer))(i)
 }
 
-fn parse_typedef<'i, 't>(i: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, Typedef<'i>> {
-    delimited(
-        tags::keyword(Keyword::Typedef),
-        verify(many1(parse_ident), |t: &Vec<Ident>| t.len() >= 2),
-        tags::semi_colon,
-    )
-    .map(|mut idents| {
-        let name = idents.pop().unwrap();
-        Typedef { ty: idents, name }
-    })
-    .parse(i)
+/// Parses a declarator, and declares its name in the current scope
+fn parse_declared<'i: 't, 't>(
+    is_typedef: bool,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Declarator<'i>> {
+    move |i| {
+        let (rest, declarator) = parse_declarator(i)?;
+        if let Some(name) = declarator.name() {
+            scopes::declare(&i, name, is_typedef);
+        }
+        Ok((rest, declarator))
+    }
+}
+
+/// Parses the rest of a variable declaration, after its first declarator
+fn parse_var_decl<'i, 't>(
+    i: Input<'i, 't>,
+    ty: DeclSpecifiers<'i>,
+    first: Declarator<'i>,
+) -> ParseResult<'i, 't, VarDecl<'i>> {
+    let init_declarator = map(
+        pair(parse_declared(false), parse_initializer),
+        |(declarator, value)| InitDeclarator { declarator, value },
+    );
+    let (i, (value, rest, _)) = tuple((
+        parse_initializer,
+        many0(preceded(tags::comma, init_declarator)),
+        recovery::insert_semi_colon(context("after declaration", tags::semi_colon)),
+    ))(i)?;
+
+    let mut declarators = vec![InitDeclarator {
+        declarator: first,
+        value,
+    }];
+    declarators.extend(rest);
+    Ok((i, VarDecl { ty, declarators }))
+}
+
+/// Reports `__auto_type` declarations it can not deduce a type for
+fn check_auto_type<'i>(
+    i: Input<'i, '_>,
+    decl: &VarDecl<'i>,
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    let problem = match decl.declarators.as_slice() {
+        [InitDeclarator {
+            declarator: Declarator::Ident(_),
+            value: Some(Initializer::Expression(_)),
+        }] => return Ok(()),
+        [_] => "'__auto_type' requires an initialized data declaration",
+        _ => "'__auto_type' may only be used with a single declarator",
+    };
+    recovery::diagnose(&i, SyntaxError::message(i, problem))
+}
+
+/// Parses the rest of a typedef, after its specifiers
+fn parse_typedef<'i, 't>(
+    i: Input<'i, 't>,
+    ty: DeclSpecifiers<'i>,
+) -> ParseResult<'i, 't, Typedef<'i>> {
+    let (i, names) = terminated(
+        separated_list1(tags::comma, parse_declared(true)),
+        recovery::insert_semi_colon(context("after typedef", tags::semi_colon)),
+    )(i)?;
+    Ok((i, Typedef { ty, names }))
 }
 
-fn parse_if<'i, 't>(i: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, If<'i>> {
-    fn block_or_stmt<'i, 't>(
-        i: TokenStream<'i, 't>,
-    ) -> IResult<TokenStream<'i, 't>, Vec<Statement<'i>>> {
-        alt((
-            blocks::braces(many0(parse_statement)),
-            parse_statement.map(|s| vec![s]),
-        ))(i)
+/// Parses a declaration, or a function definition, starting at its specifiers
+fn parse_declaration<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Statement<'i>> {
+    let (i, specifiers) = specifiers::parse_decl_specifiers(i)?;
+    if specifiers.is_typedef() {
+        let (i, typedef) = parse_typedef(i, specifiers)?;
+        return Ok((i, Statement::Typedef(typedef)));
     }
 
+    // Declares nothing but a tag or enumerators, as in `struct node;`
+    if let BaseType::Record(_) | BaseType::Enum(_) = specifiers.ty {
+        if let Ok((i, _)) = tags::semi_colon(i) {
+            let decl = VarDecl {
+                ty: specifiers,
+                declarators: Vec::new(),
+            };
+            return Ok((i, Statement::VarDecl(decl)));
+        }
+    }
+
+    let start = i;
+    let (i, declarator) = parse_declared(false)(i)?;
+    if let Some(params) = declarator.function_params() {
+        if functions::continues_function(i, params) {
+            let (i, func) = functions::parse_function(i, specifiers, declarator)?;
+            return Ok((i, Statement::FuncDecl(func)));
+        }
+    }
+
+    let (i, decl) = parse_var_decl(i, specifiers, declarator)?;
+    if let BaseType::AutoType = decl.ty.ty {
+        check_auto_type(start, &decl)?;
+    }
+    let is_function = |d: &InitDeclarator| d.declarator.function_params().is_some();
+    if !decl.declarators.iter().all(is_function) {
+        for (is_set, keyword) in [
+            (decl.ty.is_inline, "inline"),
+            (decl.ty.is_noreturn, "_Noreturn"),
+        ] {
+            if is_set {
+                let message = format!("'{keyword}' can only appear on functions");
+                recovery::diagnose(&start, SyntaxError::message(start, message))?;
+            }
+        }
+    }
+    Ok((i, Statement::VarDecl(decl)))
+}
+
+fn parse_if<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, If<'i>> {
     preceded(
         tags::keyword(Keyword::If),
         tuple((
-            blocks::parens(parse_top_level_expression),
-            block_or_stmt,
-            opt(preceded(tags::keyword(Keyword::Else), block_or_stmt)),
+            blocks::parens(recovery::expression(parse_top_level_expression)),
+            control::parse_body,
+            opt(preceded(tags::keyword(Keyword::Else), control::parse_body)),
         )),
     )
     .map(|(cond, then, r#else)| If {
@@ -112,33 +238,73 @@ fn parse_if<'i, 't>(i: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, If<'
     .parse(i)
 }
 
-fn parse_assignment<'i, 't>(
-    input: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Assignment<'i>> {
-    map(
-        terminated(
-            separated_pair(parse_ident, tags::assign, parse_top_level_expression),
-            tags::semi_colon,
-        ),
-        |(lhs, rhs)| Assignment::from((lhs, rhs)),
-    )(input)
+/// Parses an expression statement, as in `f(x);`
+fn parse_expression_statement<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    terminated(
+        parse_top_level_expression,
+        recovery::insert_semi_colon(context("after expression", tags::semi_colon)),
+    )(i)
 }
 
-fn parse_statement<'i, 't>(
-    input: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Statement<'i>> {
+/// Parses `{ statement* }`
+fn parse_block<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Vec<Statement<'i>>> {
+    scopes::scoped(blocks::block(parse_statement, |i| {
+        (recovery::skip_statement(i), Statement::Error)
+    }))(i)
+}
+
+fn parse_statement<'i, 't>(input: Input<'i, 't>) -> ParseResult<'i, 't, Statement<'i>> {
     alt((
-        map(parse_fn, Statement::FuncDecl),
-        map(parse_var_decl, Statement::VarDecl),
-        map(parse_typedef, Statement::Typedef),
+        map(control::parse_labeled, Statement::Labeled),
+        gnu::extension(parse_statement),
+        parse_declaration,
+        map(assertions::parse_static_assert, Statement::StaticAssert),
+        attributes::parse_attributed_statement,
         map(parse_if, Statement::If),
-        map(parse_assignment, Statement::Assign),
+        map(control::parse_while, Statement::While),
+        map(control::parse_do_while, Statement::DoWhile),
+        map(control::parse_for, |r#for| Statement::For(Box::new(r#for))),
+        map(control::parse_switch, Statement::Switch),
+        control::parse_goto,
+        control::parse_jump,
+        map(asm::parse_asm, |asm| Statement::Asm(Box::new(asm))),
+        map(control::parse_return, Statement::Return),
+        map(parse_block, |body| Statement::Compound(Compound { body })),
+        map(tags::semi_colon, |_| Statement::Null),
+        map(parse_expression_statement, Statement::Expression),
     ))(input)
 }
 
-pub fn parse_stream<'i>(tokens: TokenStream<'i, '_>) -> Vec<Statement<'i>> {
-    match terminated(many0(parse_statement), tags::eof)(tokens) {
-        Ok((rest, program)) => {
+/// Parses a whole translation unit.
+///
+/// Syntax errors do not stop the parser: broken statements are replaced by
+/// [`Statement::Error`] and broken expressions by `Expression::Error`, and every error
+/// found is returned along with the program.
+pub fn parse_stream<'i>(
+    tokens: TokenStream<'i, '_>,
+    options: Options,
+) -> (Vec<Statement<'i>>, Vec<SyntaxError<'i>>) {
+    let tokens = tokens.with(State::new(options));
+    // Tokens the lexer could not make sense of are reported once here, the parser
+    // then fails on them like on any unexpected token
+    let invalid = tokens
+        .tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(n, token)| match token.kind {
+            TokenKind::Invalid(invalid) => Some(SyntaxError::message(
+                nom::Slice::slice(&tokens, n..),
+                invalid.message(),
+            )),
+            _ => None,
+        });
+    let errors = RefCell::new(invalid.collect());
+    let scopes = RefCell::new(Scopes::default());
+    let result = blocks::many_until(parse_statement, tags::eof, |i| {
+        (recovery::skip_declaration(i), Statement::Error)
+    })(tokens.with_errors(&errors).with_scopes(&scopes));
+    let program = match result {
+        Ok((rest, (program, _))) => {
             if !rest.tokens.is_empty() {
                 println!("Warning: {} tokens left over", rest.tokens.len());
                 for t in rest.tokens {
@@ -146,41 +312,94 @@ pub fn parse_stream<'i>(tokens: TokenStream<'i, '_>) -> Vec<Statement<'i>> {
                 }
             }
 
-            program
+            Ok(program)
         }
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(e),
+        Err(nom::Err::Incomplete(_)) => unreachable!("complete parsers never return Incomplete"),
+    };
+
+    let mut errors = errors.into_inner();
+    match program {
+        Ok(program) => (program, errors),
         Err(e) => {
-            panic!("Error: {:?}", e);
+            errors.push(e);
+            (vec![Statement::Error], errors)
         }
     }
 }
 
+/// Parses `source` with `options`, returning the program along with the messages of
+/// the errors found
+#[cfg(test)]
+pub(crate) fn parse(source: &str, options: Options) -> (Vec<Statement<'_>>, Vec<String>) {
+    let source = crate::preprocessor::preprocess(source);
+    let tokens: Vec<_> = crate::lexer::parse_tokens(source)
+        .with_dialect(options.dialect())
+        .collect();
+    let (program, errors) = parse_stream(TokenStream::new(&tokens), options);
+    (program, errors.iter().map(ToString::to_string).collect())
+}
+
+/// Parses `source` as GNU C, asserting that it has no errors
+#[cfg(test)]
+pub(crate) fn parse_valid(source: &str) -> Vec<Statement<'_>> {
+    let (program, errors) = parse(source, options(true));
+    assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    program
+}
+
+/// The statements of the body of the last function in `program`
+#[cfg(test)]
+pub(crate) fn body<'a, 'i>(program: &'a [Statement<'i>]) -> &'a [Statement<'i>] {
+    match program.last() {
+        Some(Statement::FuncDecl(f)) => f.body.as_deref().unwrap_or_default(),
+        _ => panic!("Unexpected program {program:?}"),
+    }
+}
+
+/// GNU C, or strict C where GNU extensions are reported as with `-pedantic`
+#[cfg(test)]
+pub(crate) fn options(gnu: bool) -> Options {
+    Options {
+        gnu_extensions: gnu,
+        pedantic: (!gnu).then_some(error::Severity::Warning),
+        ..Options::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         ast::{
-            parser::parse_if,
-            tree::{Expression, Statement, VarDecl},
+            parser::{parse_if, parse_stream, Options, State},
+            tree::{BaseType, Expression, Signedness, Statement, VarDecl},
         },
         lexer::{
             stream::TokenStream,
-            token::{Ident, Keyword, Token, TokenKind},
-        }, preprocessor::preprocess,
+            token::{Ident, TokenKind},
+        },
+        preprocessor::preprocess,
     };
 
     #[test]
     fn test_parse_if() {
-        const IDENT_INT: Ident = Ident::new("int");
         const IDENT_A: Ident = Ident::new("a");
         const IDENT_B: Ident = Ident::new("b");
-        let var_decl: VarDecl = VarDecl::new(IDENT_INT, IDENT_B, None);
+        let var_decl: VarDecl =
+            VarDecl::new(BaseType::Int(Signedness::Signed).into(), IDENT_B, None);
 
         const SOURCE: &str = "if (a) {int b;}";
         let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
 
-        let (rest, r#if) =
-            parse_if(TokenStream::new(&tokens)).expect("Could not parse token stream");
+        let (rest, r#if) = parse_if(TokenStream::new(&tokens).with(State::new(Options::default())))
+            .expect("Could not parse token stream");
         assert!(rest.tokens.len() == 1, "Expected 1 token left over");
-        assert_eq!(rest.tokens[0], TokenKind::Eof, "Expected EOF, got {:?}", rest.tokens);
+        assert_eq!(
+            rest.tokens[0],
+            TokenKind::Eof,
+            "Expected EOF, got {:?}",
+            rest.tokens
+        );
 
         if let Expression::Ident(id) = r#if.condition {
             assert_eq!(id, IDENT_A);
@@ -198,4 +417,64 @@ mod tests {
             panic!("Expected var decl");
         }
     }
+
+    #[test]
+    fn test_statements() {
+        const SOURCE: &str = "int f(int *p, int y) {
+            f(y);
+            *p = y;
+            ;
+            { int x; { x = 1; } }
+            if (y) return;
+            return y + 1;
+        }";
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
+        let (program, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::FuncDecl(f)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        let [Statement::Expression(_), Statement::Expression(_), Statement::Null, Statement::Compound(block), Statement::If(_), Statement::Return(ret)] =
+            f.body.as_deref().unwrap_or_default()
+        else {
+            panic!("Unexpected body {:?}", f.body)
+        };
+        assert!(matches!(
+            block.body.as_slice(),
+            [Statement::VarDecl(_), Statement::Compound(_)]
+        ));
+        assert!(ret.value.is_some());
+        let body: Vec<_> = f.body.iter().flatten().map(ToString::to_string).collect();
+        assert_eq!(
+            body,
+            [
+                "f(y);",
+                "*p = y;",
+                ";",
+                "{int x;\n{x = 1;}\n}\n",
+                "if(y) {return;}\n",
+                "return y + 1;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        const SOURCE: &str = "int x = 1 \\ 2; int y = @; int n = 08 + 0x; int z;";
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
+        let (program, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "stray '\\' in program",
+                "stray '@' in program",
+                "invalid numeric constant '08'",
+                "invalid numeric constant '0x'",
+                "expected ';' after declaration, found '\\'",
+                "expected expression, found '@'",
+            ]
+        );
+        assert!(matches!(program.last(), Some(Statement::VarDecl(_))));
+    }
 }
@@ -0,0 +1,290 @@
+//! GNU inline assembly statements, basic as in `asm("nop");` and extended with operands,
+//! clobbers and the labels of `asm goto`.
+
+use nom::{
+    combinator::opt,
+    error::context,
+    multi::{many1, separated_list0},
+    sequence::{delimited, preceded},
+    Slice,
+};
+
+use crate::{
+    ast::tree::{Asm, AsmOperand, AsmQualifiers},
+    lexer::token::Keyword,
+};
+
+use super::{
+    blocks,
+    error::{ParseResult, SyntaxError},
+    expr::{parse_string_literal, parse_top_level_expression},
+    parse_ident, recovery, tags, Input,
+};
+
+/// Parses the qualifiers after `asm`, reporting the repeated ones
+fn parse_qualifiers<'i, 't>(mut i: Input<'i, 't>) -> ParseResult<'i, 't, AsmQualifiers> {
+    let mut qualifiers = AsmQualifiers::default();
+    while let Some(keyword) = i.peek(0).and_then(|t| t.kind.as_keyword()) {
+        let qualifier = match keyword {
+            Keyword::Volatile => &mut qualifiers.is_volatile,
+            Keyword::Inline => &mut qualifiers.is_inline,
+            Keyword::Goto => &mut qualifiers.is_goto,
+            _ => break,
+        };
+        if *qualifier {
+            let message = format!("duplicate 'asm' qualifier '{keyword}'");
+            recovery::diagnose(&i, SyntaxError::message(i, message))?;
+        }
+        *qualifier = true;
+        i = i.slice(1..);
+    }
+    Ok((i, qualifiers))
+}
+
+/// Parses `[name] "constraint"(expression)`, where the name is optional
+fn parse_operand<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, AsmOperand<'i>> {
+    let (rest, name) = opt(delimited(
+        tags::open_bracket,
+        parse_ident,
+        tags::close_bracket,
+    ))(i)?;
+    let (rest, constraint) = parse_string_literal(rest)?;
+    let (rest, expr) = blocks::parens(recovery::expression(parse_top_level_expression))(rest)?;
+    Ok((
+        rest,
+        AsmOperand {
+            name,
+            constraint,
+            expr,
+        },
+    ))
+}
+
+/// Parses the operands of an `asm` statement, after its template
+fn parse_sections<'i, 't>(i: Input<'i, 't>, asm: &mut Asm<'i>) -> ParseResult<'i, 't, ()> {
+    let operands = || separated_list0(tags::comma, parse_operand);
+    let (rest, outputs) = opt(preceded(tags::colon, operands()))(i)?;
+    let Some(outputs) = outputs else {
+        return Ok((rest, ()));
+    };
+    asm.is_basic = false;
+    asm.outputs = outputs;
+
+    let (rest, inputs) = opt(preceded(tags::colon, operands()))(rest)?;
+    asm.inputs = inputs.unwrap_or_default();
+    let (rest, clobbers) = opt(preceded(
+        tags::colon,
+        separated_list0(tags::comma, parse_string_literal),
+    ))(rest)?;
+    asm.clobbers = clobbers.unwrap_or_default();
+    if !asm.qualifiers.is_goto {
+        return Ok((rest, ()));
+    }
+    let (rest, labels) = opt(preceded(
+        tags::colon,
+        separated_list0(tags::comma, parse_ident),
+    ))(rest)?;
+    asm.labels = labels.unwrap_or_default();
+    Ok((rest, ()))
+}
+
+/// Parses an `asm` statement, as in `asm volatile("mov %1, %0" : "=r"(x) : "r"(y));`
+pub(super) fn parse_asm<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Asm<'i>> {
+    let (rest, _) = tags::keyword(Keyword::Asm)(i)?;
+    let (rest, qualifiers) = parse_qualifiers(rest)?;
+    let (rest, asm) = blocks::parens(|i| {
+        let (i, template) = many1(parse_string_literal)(i)?;
+        let mut asm = Asm {
+            qualifiers,
+            template,
+            is_basic: true,
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+            clobbers: Vec::new(),
+            labels: Vec::new(),
+        };
+        let (i, _) = parse_sections(i, &mut asm)?;
+        Ok((i, asm))
+    })(rest)?;
+    let (rest, _) =
+        recovery::insert_semi_colon(context("after asm statement", tags::semi_colon))(rest)?;
+
+    check_asm(i, &asm)?;
+    Ok((rest, asm))
+}
+
+/// Reports invalid constraints, duplicate operand names and references in the template
+/// to operands that do not exist
+fn check_asm<'i>(i: Input<'i, '_>, asm: &Asm<'i>) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    let mut problems = Vec::new();
+    for output in asm.outputs.iter() {
+        if !output.constraint[1..].starts_with(['=', '+']) {
+            problems.push("output operand constraint lacks '='".to_string());
+        }
+    }
+    for input in asm.inputs.iter() {
+        if let Some(modifier) = input.constraint.chars().find(|c| matches!(c, '=' | '+')) {
+            problems.push(format!("input operand constraint contains '{modifier}'"));
+        }
+    }
+    let names: Vec<_> = asm
+        .outputs
+        .iter()
+        .chain(asm.inputs.iter())
+        .filter_map(|operand| operand.name)
+        .collect();
+    for (n, name) in names.iter().enumerate() {
+        if names[..n].contains(name) {
+            problems.push(format!("duplicate asm operand name '{name}'"));
+        }
+    }
+    if let Err(problem) = asm.substitute(0, |_, _| String::new()) {
+        problems.push(format!("invalid 'asm': {problem}"));
+    }
+
+    for problem in problems {
+        recovery::diagnose(&i, SyntaxError::message(i, problem))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        parser::{parse, Options},
+        tree::{Asm, Statement},
+    };
+
+    /// GNU C, where `asm` is accepted
+    fn options() -> Options {
+        Options {
+            gnu_extensions: true,
+            ..Options::default()
+        }
+    }
+
+    /// The `asm` statements in the body of the only function in `program`
+    fn statements<'a, 'i>(program: &'a [Statement<'i>]) -> Vec<&'a Asm<'i>> {
+        let Some(Statement::FuncDecl(f)) = program.last() else {
+            panic!("Unexpected program {program:?}")
+        };
+        f.body
+            .iter()
+            .flatten()
+            .filter_map(|statement| match statement {
+                Statement::Asm(asm) => Some(&**asm),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let (program, errors) = parse(
+            r#"void f(int x, int y) {
+                asm("nop");
+                __asm__ __volatile__("mfence" ::: "memory");
+                asm("add %1, %0" : "+r"(x) : "g"(y) : "cc");
+                asm volatile("mov %[src], %[dst]" : [dst] "=r"(x) : [src] "r"(y));
+                asm("cpuid" "\n\t" "nop" : : "a"(x));
+                asm goto("jmp %l0" :::: done);
+            done:
+                return;
+            }"#,
+            options(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let statements: Vec<_> = statements(&program)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            statements,
+            [
+                r#"asm("nop");"#,
+                r#"asm volatile("mfence" : : : "memory");"#,
+                r#"asm("add %1, %0" : "+r"(x) : "g"(y) : "cc");"#,
+                r#"asm volatile("mov %[src], %[dst]" : [dst] "=r"(x) : [src] "r"(y));"#,
+                r#"asm("cpuid" "\n\t" "nop" : : "a"(x));"#,
+                r#"asm goto("jmp %l0" : : : : done);"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_substitution() {
+        let (program, errors) = parse(
+            r#"void f(int x, int y) {
+                asm("100%%\n\tmov %1, %0" : "=r"(x) : "r"(y));
+                asm("mov %[in], %k[out]\n%=:" : [out] "=r"(x) : [in] "m"(y));
+                asm goto("jz %l1; jmp %l[out]" : : "r"(x) : : out);
+                asm("movl $1, %eax");
+            out:
+                return;
+            }"#,
+            options(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let registers = ["%eax", "%ebx"];
+        let text: Vec<_> = statements(&program)
+            .iter()
+            .map(|asm| {
+                asm.substitute(7, |n, modifier| match modifier {
+                    Some('l') => format!(".L{}", asm.labels[n - asm.inputs.len()]),
+                    Some(modifier) => format!("{modifier}:{}", registers[n]),
+                    None => registers[n].to_string(),
+                })
+            })
+            .collect();
+        assert_eq!(
+            text,
+            [
+                Ok("100%\n\tmov %ebx, %eax".to_string()),
+                Ok("mov %ebx, k:%eax\n7:".to_string()),
+                Ok("jz .Lout; jmp .Lout".to_string()),
+                Ok("movl $1, %eax".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_asm_errors() {
+        let (_, errors) = parse(
+            r#"void f(int x, int y) {
+                asm volatile volatile("nop");
+                asm("mov %1, %0" : "r"(x) : "=r"(y));
+                asm("" : [a] "=r"(x) : [a] "r"(y));
+                asm("mov %2, %0" : "=r"(x) : "r"(y));
+                asm("%[z]" : "=r"(x));
+                asm("%k" : "=r"(x));
+                asm goto("jmp %l0" :::: nowhere);
+            }"#,
+            options(),
+        );
+        assert_eq!(
+            errors,
+            [
+                "duplicate 'asm' qualifier 'volatile'",
+                "output operand constraint lacks '='",
+                "input operand constraint contains '='",
+                "duplicate asm operand name 'a'",
+                "invalid 'asm': operand number out of range",
+                "invalid 'asm': undefined named operand 'z'",
+                "invalid 'asm': operand number missing after %-letter",
+                "use of undeclared label 'nowhere'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_asm_identifier() {
+        // Plain `asm` is an ordinary identifier in ISO C, unlike `__asm__`
+        let (program, errors) = parse(
+            r#"int asm = 2; void f(void) { __asm__("nop"); __asm volatile("nop"); }"#,
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        assert_eq!(program[0].to_string(), "int asm = 2;\n");
+        assert_eq!(statements(&program).len(), 2);
+    }
+}
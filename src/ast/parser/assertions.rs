@@ -0,0 +1,91 @@
+//! `_Static_assert` declarations, which may appear wherever a declaration may, including
+//! among the members of a record.
+
+use nom::{
+    combinator::opt,
+    error::context,
+    sequence::{pair, preceded},
+};
+
+use crate::{ast::tree::StaticAssert, lexer::token::Keyword, standard::Standard};
+
+use super::{
+    blocks,
+    error::{ParseResult, SyntaxError},
+    expr::{parse_conditional_expression, parse_string_literal},
+    recovery, tags, Input,
+};
+
+/// Parses `_Static_assert(condition, "message");`. The condition is evaluated by
+/// semantic analysis, which knows the types of the program.
+pub(super) fn parse_static_assert<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, StaticAssert<'i>> {
+    let (rest, _) = tags::keyword(Keyword::StaticAssert)(i)?;
+    let (rest, (condition, message)) = blocks::parens(pair(
+        recovery::expression(parse_conditional_expression),
+        opt(preceded(tags::comma, parse_string_literal)),
+    ))(rest)?;
+    let (rest, _) =
+        recovery::insert_semi_colon(context("after static assertion", tags::semi_colon))(rest)?;
+
+    if message.is_none() && i.standard() < Standard::C23 {
+        recovery::diagnose(
+            &i,
+            SyntaxError::message(i, "'_Static_assert' with no message is a C23 extension"),
+        )?;
+    }
+    Ok((rest, StaticAssert { condition, message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{parse, Options},
+            tree::Statement,
+        },
+        standard::Standard,
+    };
+
+    fn options(standard: Standard) -> Options {
+        Options {
+            standard,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_static_assert() {
+        let (program, errors) = parse(
+            "_Static_assert(sizeof(struct hdr) == 16, \"header size\");
+            struct s { int a; _Static_assert(1 + 1 == 2, \"math\"); int b; };
+            void f(void) { _Static_assert(2 > 1, \"order\"); }",
+            options(Standard::C11),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        assert_eq!(
+            program[0].to_string(),
+            "_Static_assert(sizeof(struct hdr) == 16, \"header size\");\n"
+        );
+        let Statement::VarDecl(ref s) = program[1] else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert_eq!(
+            s.to_string(),
+            "struct s { int a; _Static_assert(1 + 1 == 2, \"math\"); int b; };\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_message() {
+        let (_, errors) = parse("_Static_assert(0);", options(Standard::C11));
+        assert_eq!(
+            errors,
+            ["'_Static_assert' with no message is a C23 extension"]
+        );
+
+        let (_, errors) = parse("_Static_assert(1);", options(Standard::C23));
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+}
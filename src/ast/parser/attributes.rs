@@ -0,0 +1,291 @@
+//! Attributes, written `[[...]]` since C23 and `__attribute__((...))` in GNU C.
+
+use nom::{combinator::opt, error::context, multi::separated_list0, sequence::tuple, Slice};
+
+use crate::{
+    ast::tree::{Attribute, AttributeList, AttributeSyntax, Attributes, KnownAttribute, Statement},
+    lexer::token::{Ident, Keyword, TokenKind},
+    standard::Standard,
+};
+
+use super::{
+    blocks,
+    error::{Expected, ParseResult, SyntaxError},
+    expr::parse_assignment_expression,
+    parse_statement, recovery, tags, Input,
+};
+
+/// Whether the stream starts with `[[` or `__attribute__`
+pub(super) fn starts_attribute(i: Input<'_, '_>) -> bool {
+    matches!(
+        (i.peek(0).map(|t| t.kind), i.peek(1).map(|t| t.kind)),
+        (Some(TokenKind::OpenBracket), Some(TokenKind::OpenBracket))
+            | (Some(TokenKind::Keyword(Keyword::Attribute)), _)
+    )
+}
+
+/// Parses the attribute lists at the start of the stream, which may be none
+pub(super) fn parse_attributes<'i, 't>(
+    mut i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Attributes<'i>> {
+    let mut lists = Vec::new();
+    while starts_attribute(i) {
+        let (rest, list) = parse_attribute_list(i)?;
+        lists.push(list);
+        i = rest;
+    }
+    Ok((i, Attributes(lists)))
+}
+
+/// Parses `[[a, b]]` or `__attribute__((a, b))`. Either may have empty elements.
+fn parse_attribute_list<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, AttributeList<'i>> {
+    let (syntax, mut rest) = match tags::keyword(Keyword::Attribute)(i) {
+        Ok((rest, _)) => {
+            let (rest, _) = tuple((tags::open_paren, tags::open_paren))(rest)?;
+            (AttributeSyntax::Gnu, rest)
+        }
+        Err(_) => {
+            if i.standard() < Standard::C23 {
+                recovery::later_standard(&i, "'[[]]' attributes are a C23 extension")?;
+            }
+            (AttributeSyntax::Standard, i.slice(2..))
+        }
+    };
+
+    let mut attributes = Vec::new();
+    loop {
+        if rest.peek(0).is_some_and(|t| is_attribute_name(t.kind)) {
+            let (after, attribute) = parse_attribute(rest)?;
+            check_attribute(rest, &attribute)?;
+            attributes.push(attribute);
+            rest = after;
+        }
+        match tags::comma(rest) {
+            Ok((after, _)) => rest = after,
+            Err(_) => break,
+        }
+    }
+
+    let (rest, _) = match syntax {
+        AttributeSyntax::Standard => context(
+            "after attributes",
+            tuple((tags::close_bracket, tags::close_bracket)),
+        )(rest)?,
+        AttributeSyntax::Gnu => context(
+            "after attributes",
+            tuple((tags::close_paren, tags::close_paren)),
+        )(rest)?,
+    };
+    Ok((rest, AttributeList { syntax, attributes }))
+}
+
+/// Attribute names may be keywords, as in GNU `__attribute__((const))`
+fn is_attribute_name(kind: TokenKind<'_>) -> bool {
+    matches!(kind, TokenKind::Ident(_) | TokenKind::Keyword(_))
+}
+
+fn parse_name<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Ident<'i>> {
+    match i.peek(0) {
+        Some(t) if is_attribute_name(t.kind) => Ok((i.slice(1..), Ident::new(t.span.get()))),
+        _ => Err(nom::Err::Error(SyntaxError::expected(
+            i,
+            Expected::Identifier,
+        ))),
+    }
+}
+
+/// Parses `name`, `prefix::name`, and their arguments if they have some
+fn parse_attribute<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Attribute<'i>> {
+    let (rest, first) = parse_name(i)?;
+    let (rest, prefix, name) = match tuple((tags::colon, tags::colon))(rest) {
+        Ok((rest, _)) => {
+            let (rest, name) = parse_name(rest)?;
+            (rest, Some(first), name)
+        }
+        Err(_) => (rest, None, first),
+    };
+    let (rest, args) = opt(blocks::parens(separated_list0(
+        tags::comma,
+        recovery::expression(parse_assignment_expression),
+    )))(rest)?;
+    Ok((rest, Attribute { prefix, name, args }))
+}
+
+/// Checks the arguments of the attributes the compiler acts on
+fn check_attribute<'i>(
+    i: Input<'i, '_>,
+    attribute: &Attribute<'i>,
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    let Some(kind) = attribute.kind() else {
+        return Ok(());
+    };
+    let args = attribute.args.as_deref().unwrap_or_default();
+    let problem = if !kind.takes_arguments() && attribute.args.is_some() {
+        format!("'{}' attribute takes no arguments", attribute.name)
+    } else if args.len() > 1 {
+        format!("'{}' attribute takes at most one argument", attribute.name)
+    } else {
+        return Ok(());
+    };
+    recovery::diagnose(&i, SyntaxError::message(i, problem))
+}
+
+/// Parses the attributes of a statement, as in `[[fallthrough]];`, and the statement
+pub(super) fn parse_attributed_statement<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Statement<'i>> {
+    if !starts_attribute(i) {
+        return Err(nom::Err::Error(SyntaxError::new(i)));
+    }
+    let (rest, attributes) = parse_attributes(i)?;
+    let (rest, statement) = parse_statement(rest)?;
+    if attributes.has(KnownAttribute::Fallthrough) && !matches!(statement, Statement::Null) {
+        recovery::diagnose(
+            &i,
+            SyntaxError::message(
+                i,
+                "'fallthrough' attribute only applies to empty statements",
+            ),
+        )?;
+    }
+    Ok((rest, Statement::Attributed(attributes, Box::new(statement))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{parse, parse_stream, Options},
+            tree::{BaseType, KnownAttribute, Statement},
+            Severity,
+        },
+        lexer::stream::TokenStream,
+        preprocessor::preprocess,
+        standard::Standard,
+    };
+
+    /// GNU C of the given revision
+    fn options(standard: Standard) -> Options {
+        Options {
+            standard,
+            gnu_extensions: true,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "[[nodiscard]] int f(void);\n",
+            "int x [[maybe_unused]] = 1;\n",
+            "int *[[gnu::aligned(8)]] p;\n",
+            "struct __attribute__((packed)) s { char c; int i; };\n",
+            "enum [[deprecated]] e { A [[deprecated(\"old\")]] = 1, B };\n",
+            "void g(void) __attribute__((noreturn));\n",
+            "int printf(const char*, ...) __attribute__((format(printf, 1, 2), nonnull));\n",
+            "void h([[maybe_unused]] int unused);\n",
+        ] {
+            let (program, errors) = parse(source, options(Standard::C23));
+            assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+            assert_eq!(program[0].to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_known_attributes() {
+        let (program, errors) = parse(
+            "struct s { char c; } __attribute__((__packed__, aligned(16)));
+            void f(void) __attribute__((noreturn));",
+            options(Standard::C17),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(s), Statement::FuncDecl(f)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        let BaseType::Record(ref record) = s.ty.ty else {
+            panic!("Expected a record, got {:?}", s.ty.ty)
+        };
+        assert!(record.attributes.has(KnownAttribute::Packed));
+        let aligned = record.attributes.find(KnownAttribute::Aligned);
+        assert_eq!(
+            aligned.map(ToString::to_string).as_deref(),
+            Some("aligned(16)")
+        );
+        assert!(f
+            .declarator
+            .attributes()
+            .any(|attributes| attributes.has(KnownAttribute::Noreturn)));
+    }
+
+    #[test]
+    fn test_attributed_statements() {
+        let (program, errors) = parse(
+            "int f(int n) {
+                switch (n) {
+                case 0:
+                    n++;
+                    [[fallthrough]];
+                case 1:
+                    return n;
+                }
+                [[fallthrough]] n++;
+                return 0;
+            }",
+            options(Standard::C23),
+        );
+        assert_eq!(
+            errors,
+            ["'fallthrough' attribute only applies to empty statements"]
+        );
+        let Statement::FuncDecl(ref f) = program[0] else {
+            panic!("Unexpected program {program:?}")
+        };
+        let body = f.body.as_deref().unwrap_or_default();
+        let Statement::Switch(ref switch) = body[0] else {
+            panic!("Expected a switch, got {:?}", body[0])
+        };
+        assert_eq!(switch.body[1].to_string(), "[[fallthrough]] ;");
+        assert!(matches!(body[1], Statement::Attributed(_, _)));
+    }
+
+    #[test]
+    fn test_attribute_errors() {
+        let (_, errors) = parse(
+            "struct s { int x; } __attribute__((packed(1)));
+            int a __attribute__((aligned(3)));
+            [[nodiscard]] int f(void);",
+            options(Standard::C17),
+        );
+        assert_eq!(errors, ["'packed' attribute takes no arguments",]);
+
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(
+            "int a __attribute__((unused)); [[deprecated]] int b;",
+        ))
+        .collect();
+        let options = Options {
+            pedantic: Some(Severity::Warning),
+            ..Options::default()
+        };
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), options);
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| (error.severity(), error.to_string()))
+            .collect();
+        // `__attribute__` is reserved to the implementation, so it is no extension
+        assert_eq!(
+            errors,
+            [(
+                Severity::Warning,
+                "'[[]]' attributes are a C23 extension".to_string()
+            )]
+        );
+
+        let options = Options {
+            standard: Standard::C23,
+            pedantic: Some(Severity::Error),
+            ..Options::default()
+        };
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), options);
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+}
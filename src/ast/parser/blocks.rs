@@ -1,29 +1,100 @@
-use nom::{sequence::delimited, IResult, Parser};
+use nom::{error::ParseError, sequence::delimited, Parser};
 
-use crate::lexer::stream::TokenStream;
+use crate::lexer::token::TokenKind;
 
-use super::tags;
+use super::{
+    error::{ParseResult, SyntaxError},
+    recovery, tags, Input,
+};
 
-pub(super) fn braces<'i: 't, 't, P, O>(
+/// Applies `parser` until `terminator` matches, returning what both produced.
+///
+/// Unlike `many0(parser)` followed by `terminator`, if neither matches the error of
+/// `parser` is not thrown away, which usually is the one that explains what went wrong.
+/// When recovering from errors, the error is reported instead and `sync` skips past
+/// the broken item, returning a placeholder for it.
+pub(super) fn many_until<'i: 't, 't, P, O, T, OT, S>(
+    mut parser: P,
+    mut terminator: T,
+    mut sync: S,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, (Vec<O>, OT)>
+where
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+    T: Parser<Input<'i, 't>, OT, SyntaxError<'i>>,
+    S: FnMut(Input<'i, 't>) -> (Input<'i, 't>, O),
+{
+    move |mut i: Input<'i, 't>| {
+        let mut items = Vec::new();
+        loop {
+            let terminator_err = match terminator.parse(i) {
+                Ok((rest, end)) => return Ok((rest, (items, end))),
+                Err(nom::Err::Error(e)) => e,
+                Err(e) => return Err(e),
+            };
+            let checkpoint = recovery::checkpoint(&i);
+            match parser.parse(i) {
+                Ok((rest, item)) => {
+                    items.push(item);
+                    i = rest;
+                }
+                Err(nom::Err::Error(e)) => {
+                    let error = terminator_err.or(e);
+                    if i.errors().is_none() {
+                        return Err(nom::Err::Error(error));
+                    }
+                    recovery::rewind(&i, checkpoint);
+                    let (rest, placeholder) = sync(i);
+                    if rest.position() == i.position() {
+                        return Err(nom::Err::Error(error));
+                    }
+                    recovery::report(&i, error);
+                    items.push(placeholder);
+                    i = rest;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Parses `{ parser* }`, see [`many_until`]. A missing `}` at the end of the file is
+/// inserted when recovering from errors.
+pub(super) fn block<'i: 't, 't, P, O, S>(
     parser: P,
-) -> impl FnMut(
-    TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, O, nom::error::Error<TokenStream<'i, 't>>>
+    sync: S,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Vec<O>>
 where
-    P: Parser<TokenStream<'i, 't>, O, nom::error::Error<TokenStream<'i, 't>>>,
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+    S: FnMut(Input<'i, 't>) -> (Input<'i, 't>, O),
 {
-    delimited(tags::open_brace, parser, tags::close_brace)
+    let mut body = many_until(
+        parser,
+        recovery::insert(tags::close_brace, &[TokenKind::Eof]),
+        sync,
+    );
+    move |i: Input<'i, 't>| {
+        let (i, _) = tags::open_brace(i)?;
+        let (i, (items, _)) = body(i)?;
+        Ok((i, items))
+    }
 }
 
+/// Parses `( parser )`. A missing `)` is inserted before a `{` or `;` when recovering
+/// from errors.
 pub(super) fn parens<'i: 't, 't, P, O>(
     parser: P,
-) -> impl FnMut(
-    TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, O, nom::error::Error<TokenStream<'i, 't>>>
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, O>
 where
-    P: Parser<TokenStream<'i, 't>, O, nom::error::Error<TokenStream<'i, 't>>>,
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
 {
-    delimited(tags::open_paren, parser, tags::close_paren)
+    delimited(
+        tags::open_paren,
+        parser,
+        recovery::insert(
+            tags::close_paren,
+            &[TokenKind::OpenBrace, TokenKind::SemiColon],
+        ),
+    )
 }
 
 #[cfg(test)]
@@ -0,0 +1,435 @@
+//! Control flow statements: loops, and the jumps out of them.
+
+use nom::{
+    branch::alt,
+    error::context,
+    sequence::{preceded, terminated},
+    Parser, Slice,
+};
+
+use crate::{
+    ast::tree::{
+        control::{DoWhile, For, ForInit, Label, Labeled, Return, Switch, While},
+        Expression, Statement, StorageClass,
+    },
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    blocks,
+    error::{ParseResult, SyntaxError},
+    expr::parse_conditional_expression,
+    parse_block, parse_declaration, parse_ident, parse_statement, parse_top_level_expression,
+    recovery, scopes, tags, Input,
+};
+
+/// Statements enclosing the statement being parsed, which decide where jumps such as
+/// `break` are allowed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct StatementContext {
+    pub(crate) in_loop: bool,
+    pub(crate) in_switch: bool,
+}
+impl StatementContext {
+    pub(crate) const fn new() -> Self {
+        Self {
+            in_loop: false,
+            in_switch: false,
+        }
+    }
+}
+
+/// Parses the body of a control statement, either a block or a single statement
+pub(super) fn parse_body<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Vec<Statement<'i>>> {
+    alt((parse_block, parse_statement.map(|s| vec![s])))(i)
+}
+
+/// Parses the body of a loop, in which `break` and `continue` are allowed
+fn parse_loop_body<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Vec<Statement<'i>>> {
+    let outer = i.context();
+    let (rest, body) = parse_body(i.with_context(StatementContext {
+        in_loop: true,
+        ..outer
+    }))?;
+    Ok((rest.with_context(outer), body))
+}
+
+fn parse_condition<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    blocks::parens(recovery::expression(parse_top_level_expression))(i)
+}
+
+pub(super) fn parse_while<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, While<'i>> {
+    let (i, _) = tags::keyword(Keyword::While)(i)?;
+    let (i, condition) = parse_condition(i)?;
+    let (i, body) = parse_loop_body(i)?;
+    Ok((i, While { condition, body }))
+}
+
+pub(super) fn parse_do_while<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, DoWhile<'i>> {
+    let (i, _) = tags::keyword(Keyword::Do)(i)?;
+    let (i, body) = parse_loop_body(i)?;
+    let (i, _) = tags::keyword(Keyword::While)(i)?;
+    let (i, condition) = parse_condition(i)?;
+    let (i, _) = recovery::insert_semi_colon(context("after do/while", tags::semi_colon))(i)?;
+    Ok((i, DoWhile { body, condition }))
+}
+
+pub(super) fn parse_for<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, For<'i>> {
+    let (i, _) = tags::keyword(Keyword::For)(i)?;
+    // What the first clause declares is only in scope in the loop
+    scopes::scoped(parse_for_clauses)(i)
+}
+
+/// Parses the clauses of a `for` loop and its body, after the keyword
+fn parse_for_clauses<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, For<'i>> {
+    let (i, _) = tags::open_paren(i)?;
+    let (i, init) = parse_for_init(i)?;
+    let (i, condition) = terminated(
+        optional_clause(TokenKind::SemiColon),
+        context("after 'for' condition", tags::semi_colon),
+    )(i)?;
+    let (i, step) = terminated(optional_clause(TokenKind::CloseParen), tags::close_paren)(i)?;
+    let (i, body) = parse_loop_body(i)?;
+    Ok((
+        i,
+        For {
+            init,
+            condition,
+            step,
+            body,
+        },
+    ))
+}
+
+/// Parses an expression, unless the clause is empty and the stream starts at `end`
+fn optional_clause<'i: 't, 't>(
+    end: TokenKind<'static>,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Option<Expression<'i>>> {
+    move |i: Input<'i, 't>| {
+        if i.peek(0).is_some_and(|t| t.kind == end) {
+            return Ok((i, None));
+        }
+        let (i, expr) = recovery::expression(parse_top_level_expression)(i)?;
+        Ok((i, Some(expr)))
+    }
+}
+
+/// Parses the first clause of a `for` loop, `;` included
+fn parse_for_init<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Option<ForInit<'i>>> {
+    if let Ok((i, _)) = tags::semi_colon(i) {
+        return Ok((i, None));
+    }
+
+    let checkpoint = recovery::checkpoint(&i);
+    match parse_declaration(i) {
+        Ok((rest, Statement::VarDecl(decl))) => {
+            if let Some(StorageClass::Static | StorageClass::Extern) = decl.ty.storage {
+                recovery::diagnose(
+                    &i,
+                    SyntaxError::message(i, "declaration of non-local variable in 'for' loop"),
+                )?;
+            }
+            return Ok((rest, Some(ForInit::Declaration(decl))));
+        }
+        Ok((rest, _)) => {
+            recovery::diagnose(
+                &i,
+                SyntaxError::message(i, "only variables may be declared in a 'for' loop"),
+            )?;
+            return Ok((rest, None));
+        }
+        Err(nom::Err::Error(_)) => recovery::rewind(&i, checkpoint),
+        Err(e) => return Err(e),
+    }
+
+    let (i, init) = terminated(
+        recovery::expression(parse_top_level_expression),
+        context("after 'for' initializer", tags::semi_colon),
+    )(i)?;
+    Ok((i, Some(ForInit::Expression(init))))
+}
+
+pub(super) fn parse_switch<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Switch<'i>> {
+    let (rest, _) = tags::keyword(Keyword::Switch)(i)?;
+    let (rest, condition) = parse_condition(rest)?;
+    let outer = rest.context();
+    let (rest, body) = parse_body(rest.with_context(StatementContext {
+        in_switch: true,
+        ..outer
+    }))?;
+    check_cases(i, &body)?;
+    Ok((rest.with_context(outer), Switch { condition, body }))
+}
+
+/// Reports `default` labels that appear twice in the same switch. Duplicate `case`
+/// values are left to semantic analysis, which converts them to the type of the
+/// controlling expression.
+fn check_cases<'i>(
+    switch: Input<'i, '_>,
+    body: &[Statement<'i>],
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    fn collect<'s, 'i>(body: &'s [Statement<'i>], labels: &mut Vec<&'s Label<'i>>) {
+        for statement in body {
+            match statement {
+                Statement::Labeled(labeled) => {
+                    labels.push(&labeled.label);
+                    collect(std::slice::from_ref(&labeled.body), labels);
+                }
+                Statement::If(r#if) => {
+                    collect(&r#if.body, labels);
+                    collect(r#if.else_body.as_deref().unwrap_or_default(), labels);
+                }
+                Statement::While(r#while) => collect(&r#while.body, labels),
+                Statement::DoWhile(do_while) => collect(&do_while.body, labels),
+                Statement::For(r#for) => collect(&r#for.body, labels),
+                Statement::Compound(compound) => collect(&compound.body, labels),
+                Statement::Attributed(_, statement) => {
+                    collect(std::slice::from_ref(&**statement), labels);
+                }
+                // Labels of nested switches belong to them
+                _ => {}
+            }
+        }
+    }
+    let mut labels = Vec::new();
+    collect(body, &mut labels);
+
+    let mut has_default = false;
+    for label in labels {
+        if !matches!(label, Label::Default) {
+            continue;
+        }
+        if has_default {
+            let problem = "multiple default labels in one switch";
+            recovery::diagnose(&switch, SyntaxError::message(switch, problem))?;
+        }
+        has_default = true;
+    }
+    Ok(())
+}
+
+/// Parses a statement with a label, as in `case 1: ...` or `cleanup: ...`
+pub(super) fn parse_labeled<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Labeled<'i>> {
+    let (rest, label) = match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Keyword(Keyword::Case)) => {
+            let (rest, value) = recovery::expression(parse_conditional_expression)(i.slice(1..))?;
+            let (rest, end) = match tags::ellipsis(rest) {
+                Ok((rest, _)) => {
+                    recovery::extension(&rest, "case range")?;
+                    let (rest, end) = recovery::expression(parse_conditional_expression)(rest)?;
+                    (rest, Some(Box::new(end)))
+                }
+                Err(_) => (rest, None),
+            };
+            if !i.context().in_switch {
+                recovery::diagnose(
+                    &i,
+                    SyntaxError::message(i, "'case' statement not in switch statement"),
+                )?;
+            }
+            (rest, Label::Case { value, end })
+        }
+        Some(TokenKind::Keyword(Keyword::Default)) => {
+            if !i.context().in_switch {
+                recovery::diagnose(
+                    &i,
+                    SyntaxError::message(i, "'default' statement not in switch statement"),
+                )?;
+            }
+            (i.slice(1..), Label::Default)
+        }
+        // Only a `:` tells a label apart from an expression
+        Some(TokenKind::Ident(name)) if i.peek(1).map(|t| t.kind) == Some(TokenKind::Colon) => {
+            (i.slice(1..), Label::Named(name))
+        }
+        _ => return Err(nom::Err::Error(SyntaxError::new(i))),
+    };
+    let (rest, _) = context("after label", tags::colon)(rest)?;
+    let (rest, body) = parse_statement(rest)?;
+    Ok((
+        rest,
+        Labeled {
+            label,
+            body: Box::new(body),
+        },
+    ))
+}
+
+/// Parses `goto label;`, or the GNU `goto *address;`
+pub(super) fn parse_goto<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Statement<'i>> {
+    let (i, _) = tags::keyword(Keyword::Goto)(i)?;
+    let (rest, statement) = match tags::star(i) {
+        Ok((rest, _)) => {
+            recovery::extension(&i, "computed goto")?;
+            let (rest, address) = recovery::expression(parse_top_level_expression)(rest)?;
+            (rest, Statement::ComputedGoto(address))
+        }
+        Err(_) => {
+            let (rest, label) = parse_ident(i)?;
+            (rest, Statement::Goto(label))
+        }
+    };
+    let (rest, _) = recovery::insert_semi_colon(context("after goto", tags::semi_colon))(rest)?;
+    Ok((rest, statement))
+}
+
+pub(super) fn parse_return<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Return<'i>> {
+    let (i, _) = tags::keyword(Keyword::Return)(i)?;
+    let (i, value) = optional_clause(TokenKind::SemiColon)(i)?;
+    let (i, _) = recovery::insert_semi_colon(context("after return", tags::semi_colon))(i)?;
+    Ok((i, Return { value }))
+}
+
+/// Parses `break;` or `continue;`
+pub(super) fn parse_jump<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Statement<'i>> {
+    let context = i.context();
+    let (rest, statement) = alt((
+        preceded(tags::keyword(Keyword::Break), |i| Ok((i, Statement::Break))),
+        preceded(tags::keyword(Keyword::Continue), |i| {
+            Ok((i, Statement::Continue))
+        }),
+    ))(i)?;
+
+    let problem = match statement {
+        Statement::Break if !context.in_loop && !context.in_switch => {
+            Some("'break' statement not in loop or switch statement")
+        }
+        Statement::Continue if !context.in_loop => {
+            Some("'continue' statement not in loop statement")
+        }
+        _ => None,
+    };
+    if let Some(problem) = problem {
+        recovery::diagnose(&i, SyntaxError::message(i, problem))?;
+    }
+
+    let (rest, _) = recovery::insert_semi_colon(tags::semi_colon)(rest)?;
+    Ok((rest, statement))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        parser::{options, parse},
+        tree::{control::ForInit, Statement},
+    };
+
+    fn parse_body(source: &str) -> (Vec<Statement<'_>>, Vec<String>) {
+        let (mut program, errors) = parse(source, options(false));
+        let Some(Statement::FuncDecl(func)) = program.pop() else {
+            panic!("Expected a function, got {program:?}")
+        };
+        (func.body.expect("Expected a definition"), errors)
+    }
+
+    #[test]
+    fn test_loops() {
+        let (body, errors) = parse_body(
+            "void f(void) {
+                while (a) b = 1;
+                do { continue; } while (a);
+                for (int i = 0, j; i; i = j) { break; }
+                for (a = 0;;) {}
+                for (;;) break;
+            }",
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::While(_), Statement::DoWhile(do_while), Statement::For(decl), Statement::For(expr), Statement::For(empty)] =
+            body.as_slice()
+        else {
+            panic!("Unexpected body {body:?}")
+        };
+        assert_eq!(do_while.body, [Statement::Continue]);
+        let Some(ForInit::Declaration(ref init)) = decl.init else {
+            panic!("Expected a declaration, got {:?}", decl.init)
+        };
+        assert_eq!(init.declarators.len(), 2);
+        assert!(matches!(expr.init, Some(ForInit::Expression(_))));
+        assert!(expr.condition.is_none() && expr.step.is_none());
+        assert!(empty.init.is_none());
+        assert_eq!(empty.body, [Statement::Break]);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "while(a) {b = 1;}\n",
+            "do {continue;} while(a);\n",
+            "for(int i = 0; i; i = 1) {break;}\n",
+            "for(a = 0;;) {}\n",
+            "for(;;) {}\n",
+        ] {
+            let source_in_fn = format!("void f(void) {{ {source} }}");
+            let (body, errors) = parse_body(&source_in_fn);
+            assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+            assert_eq!(body[0].to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_jumps_outside_loops() {
+        let (_, errors) = parse_body(
+            "void f(void) {
+                break;
+                continue;
+                while (a) { if (b) { break; } }
+                for (static int i; ;) {}
+            }",
+        );
+        assert_eq!(
+            errors,
+            [
+                "'break' statement not in loop or switch statement",
+                "'continue' statement not in loop statement",
+                "declaration of non-local variable in 'for' loop",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch() {
+        let (body, errors) = parse_body(
+            "void f(void) {
+                switch (state) {
+                case 0:
+                case 1: state = 2; break;
+                default: while (a) { case 3: continue; }
+                }
+            }",
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::Switch(switch)] = body.as_slice() else {
+            panic!("Unexpected body {body:?}")
+        };
+        assert_eq!(
+            switch.to_string(),
+            "switch(state) {case 0: case 1: state = 2;break;default: while(a) {case 3: continue;}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_case_errors() {
+        let (_, errors) = parse_body(
+            "void f(void) {
+                case 1: a = 1;
+                switch (a) { case 1: case 2 - 1: break; default: default: break; }
+                switch (a) { case 1: switch (b) { case 1: break; } }
+                switch (a) { case 0 ... 5: break; }
+            }",
+        );
+        assert_eq!(
+            errors,
+            [
+                "'case' statement not in switch statement",
+                "multiple default labels in one switch",
+                "case range is a GNU extension",
+            ]
+        );
+
+        let (_, errors) = parse(
+            "void f(void) { switch (a) { case 0 ... 5: case 6 ... 9: case 3: break; } }",
+            options(true),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+}
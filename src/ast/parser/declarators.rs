@@ -0,0 +1,354 @@
+//! Declarators, the part of a declaration that names something and wraps its type in
+//! pointers, arrays and functions, as in `(*fp)(int)`.
+
+use nom::{
+    combinator::{map, peek},
+    multi::separated_list1,
+    sequence::terminated,
+    Slice,
+};
+
+use crate::{
+    ast::tree::{ArraySize, BaseType, Declarator, Parameters, StorageClass},
+    lexer::token::{Keyword, TokenKind},
+    standard::Standard,
+};
+
+use super::{
+    attributes::{parse_attributes, starts_attribute},
+    blocks,
+    error::{Expected, ParseResult, SyntaxError},
+    expr::parse_assignment_expression,
+    parse_ident, recovery, scopes,
+    specifiers::{parse_decl_specifiers, parse_type_qualifiers},
+    tags, Input,
+};
+
+/// Whether a declarator must, must not or may declare a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Name {
+    Required,
+    /// Abstract declarators, as in type names
+    Forbidden,
+    /// Parameters, which may be unnamed
+    Optional,
+}
+
+/// Parses a declarator that declares a name
+pub(super) fn parse_declarator<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Declarator<'i>> {
+    declarator(i, Name::Required)
+}
+
+/// Parses an abstract declarator, as in `(int (*)[3])`. It may be empty.
+pub(super) fn parse_abstract_declarator<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Declarator<'i>> {
+    declarator(i, Name::Forbidden)
+}
+
+fn declarator<'i, 't>(mut i: Input<'i, 't>, name: Name) -> ParseResult<'i, 't, Declarator<'i>> {
+    let mut pointers = Vec::new();
+    while let Ok((rest, _)) = tags::star(i) {
+        let (rest, attributes) = parse_attributes(rest)?;
+        let (rest, qualifiers) = parse_type_qualifiers(rest)?;
+        pointers.push((qualifiers, attributes));
+        i = rest;
+    }
+
+    let (mut i, mut declarator) = direct_declarator(i, name)?;
+    loop {
+        match i.peek(0).map(|t| t.kind) {
+            _ if starts_attribute(i) => {
+                let (rest, attributes) = parse_attributes(i)?;
+                i = rest;
+                declarator = Declarator::Attributed {
+                    inner: Box::new(declarator),
+                    attributes,
+                };
+            }
+            Some(TokenKind::OpenBracket) => {
+                let (rest, array) = array_declarator(i.slice(1..), declarator)?;
+                (i, declarator) = (rest, array);
+            }
+            Some(TokenKind::OpenParen) => {
+                let (rest, params) = blocks::parens(scopes::scoped(parse_params))(i)?;
+                i = rest;
+                declarator = Declarator::Function {
+                    inner: Box::new(declarator),
+                    params,
+                };
+            }
+            _ => break,
+        }
+    }
+
+    // The first `*` applies last, `* const * p` is a pointer to a const pointer
+    for (qualifiers, attributes) in pointers.into_iter().rev() {
+        declarator = Declarator::Pointer {
+            qualifiers,
+            inner: Box::new(declarator),
+        };
+        if !attributes.is_empty() {
+            declarator = Declarator::Attributed {
+                inner: Box::new(declarator),
+                attributes,
+            };
+        }
+    }
+    Ok((i, declarator))
+}
+
+/// Parses a name, or a declarator in parenthesis
+fn direct_declarator<'i, 't>(i: Input<'i, 't>, name: Name) -> ParseResult<'i, 't, Declarator<'i>> {
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Ident(_)) if name != Name::Forbidden => {
+            map(parse_ident, Declarator::Ident)(i)
+        }
+        Some(TokenKind::OpenParen) if is_grouping(i.slice(1..), name) => {
+            blocks::parens(|i| declarator(i, name))(i)
+        }
+        _ if name != Name::Required => Ok((i, Declarator::Abstract)),
+        _ => Err(nom::Err::Error(SyntaxError::expected(
+            i,
+            Expected::Identifier,
+        ))),
+    }
+}
+
+/// Whether the `(` before `i` groups a declarator rather than starting a parameter
+/// list, as in `(*)(int)`
+fn is_grouping(i: Input<'_, '_>, name: Name) -> bool {
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Star | TokenKind::OpenParen | TokenKind::OpenBracket) => true,
+        Some(TokenKind::Ident(_)) => name != Name::Forbidden,
+        _ => false,
+    }
+}
+
+/// Parses an array declarator, after the `[`
+fn array_declarator<'i, 't>(
+    i: Input<'i, 't>,
+    inner: Declarator<'i>,
+) -> ParseResult<'i, 't, Declarator<'i>> {
+    let (i, static_before) = take_static(i);
+    let (i, qualifiers) = parse_type_qualifiers(i)?;
+    let (i, static_after) = take_static(i);
+
+    let next = |n: usize| i.peek(n).map(|t| t.kind);
+    let (i, size) = match (next(0), next(1)) {
+        (Some(TokenKind::CloseBracket), _) => (i, ArraySize::Unspecified),
+        (Some(TokenKind::Star), Some(TokenKind::CloseBracket)) => {
+            (i.slice(1..), ArraySize::Variable)
+        }
+        _ => {
+            let (i, size) = recovery::expression(parse_assignment_expression)(i)?;
+            (i, ArraySize::Expression(Box::new(size)))
+        }
+    };
+    let (i, _) = recovery::insert(
+        tags::close_bracket,
+        &[TokenKind::SemiColon, TokenKind::Assign],
+    )(i)?;
+
+    Ok((
+        i,
+        Declarator::Array {
+            inner: Box::new(inner),
+            size,
+            qualifiers,
+            is_static: static_before || static_after,
+        },
+    ))
+}
+
+fn take_static<'i, 't>(i: Input<'i, 't>) -> (Input<'i, 't>, bool) {
+    match tags::keyword(Keyword::Static)(i) {
+        Ok((rest, _)) => (rest, true),
+        Err(_) => (i, false),
+    }
+}
+
+/// Parses the parameters of a function declarator, inside the parenthesis. Their names
+/// are in scope until the end of the declarator.
+fn parse_params<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Parameters<'i>> {
+    if peek(tags::close_paren)(i).is_ok() {
+        return Ok((i, Parameters::Unspecified));
+    }
+    if let Ok((rest, _)) = terminated(tags::keyword(Keyword::Void), peek(tags::close_paren))(i) {
+        let params = Parameters::Prototype {
+            params: Vec::new(),
+            is_variadic: false,
+        };
+        return Ok((rest, params));
+    }
+    if i.standard() == Standard::C89 && is_identifier_list(i) {
+        return map(
+            separated_list1(tags::comma, parse_ident),
+            Parameters::Identifiers,
+        )(i);
+    }
+
+    let mut params = Vec::new();
+    let mut rest = i;
+    let is_variadic = loop {
+        if let Ok((after, _)) = tags::ellipsis(rest) {
+            if params.is_empty() && i.standard() < Standard::C23 {
+                recovery::diagnose(
+                    &rest,
+                    SyntaxError::message(rest, "ISO C requires a named parameter before '...'"),
+                )?;
+            }
+            rest = after;
+            break true;
+        }
+
+        let (after, ty) = parse_decl_specifiers(rest)?;
+        let (after, declarator) = declarator(after, Name::Optional)?;
+        if let Some(name) = declarator.name() {
+            scopes::declare(&rest, name, false);
+        }
+        if ty
+            .storage
+            .is_some_and(|storage| storage != StorageClass::Register)
+        {
+            recovery::diagnose(
+                &rest,
+                SyntaxError::message(
+                    rest,
+                    "invalid storage class specifier in function declarator",
+                ),
+            )?;
+        }
+        if ty.ty == BaseType::Void && declarator == Declarator::Abstract {
+            recovery::diagnose(
+                &rest,
+                SyntaxError::message(
+                    rest,
+                    "'void' must be the first and only parameter if specified",
+                ),
+            )?;
+        }
+        params.push((ty, declarator));
+
+        match tags::comma(after) {
+            Ok((after, _)) => rest = after,
+            Err(_) => {
+                rest = after;
+                break false;
+            }
+        }
+    };
+    Ok((
+        rest,
+        Parameters::Prototype {
+            params,
+            is_variadic,
+        },
+    ))
+}
+
+/// Whether the parameters are only names, as in the K&R definition `f(a, b) int a, b;`
+fn is_identifier_list(i: Input<'_, '_>) -> bool {
+    matches!(
+        (i.peek(0).map(|t| t.kind), i.peek(1).map(|t| t.kind)),
+        (
+            Some(TokenKind::Ident(name)),
+            Some(TokenKind::Comma | TokenKind::CloseParen)
+        ) if !scopes::is_typedef_name(&i, name)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::tree::{ArraySize, Declarator, Expression, TypeQualifiers},
+        lexer::{
+            stream::TokenStream,
+            token::{Ident, Literal},
+        },
+        preprocessor::preprocess,
+    };
+
+    use super::{
+        super::{Options, State},
+        parse_abstract_declarator, parse_declarator,
+    };
+
+    fn parse(source: &str) -> Declarator<'_> {
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(source)).collect();
+        let (rest, declarator) =
+            parse_declarator(TokenStream::new(&tokens).with(State::new(Options::default())))
+                .expect("Could not parse declarator");
+        assert_eq!(rest.len(), 1, "Left over tokens: {:?}", rest.tokens);
+        declarator
+    }
+
+    #[test]
+    fn test_nesting() {
+        // `fp` is a pointer to a function returning a pointer to an array of 10 ints
+        let fp = parse("(*(*fp)(int))[10]");
+        let Declarator::Array { inner, size, .. } = &fp else {
+            panic!("Expected array, got {fp:?}")
+        };
+        assert_eq!(
+            *size,
+            ArraySize::Expression(Box::new(Expression::Literal(Literal::Int("10"))))
+        );
+        let Declarator::Pointer { inner, .. } = &**inner else {
+            panic!("Expected pointer, got {inner:?}")
+        };
+        let Declarator::Function { inner, params } = &**inner else {
+            panic!("Expected function, got {inner:?}")
+        };
+        assert_eq!(params.declarations().len(), 1);
+        assert_eq!(**inner, Declarator::new_pointer(Ident::new("fp").into()));
+        assert_eq!(fp.name(), Some(Ident::new("fp")));
+    }
+
+    #[test]
+    fn test_qualified_pointers() {
+        let p = parse("* const *volatile restrict p");
+        let Declarator::Pointer { qualifiers, inner } = &p else {
+            panic!("Expected pointer, got {p:?}")
+        };
+        assert!(qualifiers.is_const);
+        assert_eq!(
+            **inner,
+            Declarator::Pointer {
+                qualifiers: TypeQualifiers {
+                    is_volatile: true,
+                    is_restrict: true,
+                    ..Default::default()
+                },
+                inner: Box::new(Ident::new("p").into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "(*(*fp)(int))[10]",
+            "*const *volatile restrict p",
+            "a[]",
+            "matrix[3][4]",
+            "f(int x, char *argv[static const 4], double[*])",
+            "*(*signal(int sig, void (*handler)(int)))(int)",
+        ] {
+            assert_eq!(parse(source).to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_abstract() {
+        for source in ["", "*", "(*)(int)", "[3]", "*const [2]", "(int, char)"] {
+            let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(source)).collect();
+            let (rest, declarator) = parse_abstract_declarator(
+                TokenStream::new(&tokens).with(State::new(Options::default())),
+            )
+            .expect("Could not parse abstract declarator");
+            assert_eq!(rest.len(), 1, "Left over tokens: {:?}", rest.tokens);
+            assert_eq!(declarator.name(), None, "{source}");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! `enum` specifiers and their enumerators.
+
+use nom::{combinator::opt, error::ParseError, sequence::preceded};
+
+use crate::{
+    ast::tree::{Attributes, BaseType, DeclSpecifiers, EnumSpecifier, Enumerator},
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    attributes::parse_attributes,
+    error::{Expected, ParseResult, SyntaxError},
+    expr::parse_conditional_expression,
+    parse_ident, recovery, scopes,
+    specifiers::parse_specifier_qualifiers,
+    tags, Input,
+};
+
+/// Parses an `enum` specifier, starting at the keyword
+pub(super) fn parse_enum_specifier<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, EnumSpecifier<'i>> {
+    let (i, _) = tags::keyword(Keyword::Enum)(i)?;
+    let (i, mut attributes) = parse_attributes(i)?;
+    let (i, tag) = opt(parse_ident)(i)?;
+    let (i, underlying) = opt(preceded(tags::colon, parse_underlying_type))(i)?;
+
+    if i.peek(0).map(|t| t.kind) != Some(TokenKind::OpenBrace) {
+        if tag.is_none() {
+            return Err(nom::Err::Error(
+                SyntaxError::expected(i, Expected::Identifier)
+                    .or(SyntaxError::expected(i, TokenKind::OpenBrace)),
+            ));
+        }
+        return Ok((
+            i,
+            EnumSpecifier {
+                attributes,
+                tag,
+                underlying,
+                enumerators: None,
+            },
+        ));
+    }
+
+    let (mut i, _) = tags::open_brace(i)?;
+    let mut enumerators = Vec::new();
+    loop {
+        let (rest, enumerator) = parse_enumerator(i)?;
+        enumerators.push(enumerator);
+        i = rest;
+        // A trailing comma is allowed before the `}`
+        match tags::comma(i) {
+            Ok((rest, _)) if rest.peek(0).map(|t| t.kind) == Some(TokenKind::CloseBrace) => {
+                i = rest;
+                break;
+            }
+            Ok((rest, _)) => i = rest,
+            Err(_) => break,
+        }
+    }
+    let (i, _) = recovery::insert(tags::close_brace, &[TokenKind::SemiColon])(i)?;
+    let (i, Attributes(trailing)) = parse_attributes(i)?;
+    attributes.0.extend(trailing);
+
+    Ok((
+        i,
+        EnumSpecifier {
+            attributes,
+            tag,
+            underlying,
+            enumerators: Some(enumerators),
+        },
+    ))
+}
+
+/// Parses `NAME` or `NAME = value`, with attributes after the name
+fn parse_enumerator<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Enumerator<'i>> {
+    let (rest, name) = parse_ident(i)?;
+    let (rest, attributes) = parse_attributes(rest)?;
+    // Enumerators hide typedef names of outer scopes, from their own value onward
+    scopes::declare(&i, name, false);
+    let (rest, value) = opt(preceded(
+        tags::assign,
+        recovery::expression(parse_conditional_expression),
+    ))(rest)?;
+    Ok((
+        rest,
+        Enumerator {
+            name,
+            attributes,
+            value,
+        },
+    ))
+}
+
+/// Parses the fixed underlying type after the `:`, which must be an integer type
+fn parse_underlying_type<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Box<DeclSpecifiers<'i>>> {
+    let (rest, ty) = parse_specifier_qualifiers(i)?;
+
+    let is_integer = matches!(
+        ty.ty,
+        BaseType::Bool
+            | BaseType::Char(_)
+            | BaseType::Short(_)
+            | BaseType::Int(_)
+            | BaseType::Long(_)
+            | BaseType::LongLong(_)
+            | BaseType::TypedefName(_)
+            | BaseType::Enum(_)
+    );
+    if !is_integer {
+        recovery::diagnose(
+            &i,
+            SyntaxError::message(
+                i,
+                format!("'{}' is not a valid underlying type for an enum", ty.ty),
+            ),
+        )?;
+    }
+    Ok((rest, Box::new(ty)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{parse, Options},
+            tree::{BaseType, Signedness, Statement},
+        },
+        lexer::token::Ident,
+    };
+
+    #[test]
+    fn test_enumerators() {
+        let (program, errors) = parse(
+            "enum color { RED, GREEN = 5, BLUE, ALIAS = RED, LAST = BLUE * 2 + 1, };",
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(decl)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        let BaseType::Enum(ref color) = decl.ty.ty else {
+            panic!("Expected an enum, got {:?}", decl.ty.ty)
+        };
+        let enumerators: Vec<_> = color
+            .enumerators
+            .iter()
+            .flatten()
+            .map(|e| (e.name.name, e.value.as_ref().map(ToString::to_string)))
+            .collect();
+        assert_eq!(
+            enumerators,
+            [
+                ("RED", None),
+                ("GREEN", Some("5".to_string())),
+                ("BLUE", None),
+                ("ALIAS", Some("RED".to_string())),
+                ("LAST", Some("BLUE * 2 + 1".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_underlying_type() {
+        let (program, errors) = parse(
+            "enum flags : unsigned char { A = 1, B = 2 } f;
+            typedef unsigned char uint8_t;
+            enum small : uint8_t { X };
+            enum later : long;",
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(flags), Statement::Typedef(_), Statement::VarDecl(small), Statement::VarDecl(later)] =
+            program.as_slice()
+        else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert_eq!(
+            flags.to_string(),
+            "enum flags : unsigned char { A = 1, B = 2 } f;\n"
+        );
+        let BaseType::Enum(ref small) = small.ty.ty else {
+            panic!("Expected an enum, got {:?}", small.ty.ty)
+        };
+        assert_eq!(
+            small.underlying.as_ref().map(|ty| &ty.ty),
+            Some(&BaseType::TypedefName(Ident::new("uint8_t")))
+        );
+        let BaseType::Enum(ref later) = later.ty.ty else {
+            panic!("Expected an enum, got {:?}", later.ty.ty)
+        };
+        assert!(!later.is_definition());
+        assert_eq!(
+            later.underlying.as_ref().map(|ty| &ty.ty),
+            Some(&BaseType::Long(Signedness::Signed))
+        );
+
+        let (_, errors) = parse("enum e : float { A };", Options::default());
+        assert_eq!(
+            errors,
+            ["'float' is not a valid underlying type for an enum"]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt::Display;
+
+use nom::{
+    error::{ContextError, ErrorKind, ParseError},
+    IResult,
+};
+
+use crate::{ast::parser::Input, lexer::token::TokenKind, span::Span};
+
+pub(crate) type ParseResult<'i, 't, O> = IResult<Input<'i, 't>, O, SyntaxError<'i>>;
+
+/// Something the parser would have accepted where it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected<'i> {
+    Token(TokenKind<'i>),
+    Identifier,
+    Literal,
+    Expression,
+}
+impl<'i> Display for Expected<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Token(TokenKind::Eof) => write!(f, "end of file"),
+            Self::Token(kind) => write!(f, "'{kind}'"),
+            Self::Identifier => write!(f, "identifier"),
+            Self::Literal => write!(f, "literal"),
+            Self::Expression => write!(f, "expression"),
+        }
+    }
+}
+impl<'i> From<TokenKind<'i>> for Expected<'i> {
+    fn from(kind: TokenKind<'i>) -> Self {
+        Self::Token(kind)
+    }
+}
+
+/// How serious a diagnostic is. Only errors make the compilation fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum Severity {
+    #[display(fmt = "error")]
+    Error,
+    /// Reported for valid code, such as GNU extensions under `-pedantic`
+    #[display(fmt = "warning")]
+    Warning,
+}
+
+/// Error produced when the token stream does not match the grammar.
+///
+/// When several alternatives fail, the error that got the furthest is kept, and the
+/// expectations of errors at the same position are merged.
+#[derive(Debug, Clone)]
+pub struct SyntaxError<'i> {
+    /// Index of the offending token
+    position: usize,
+    /// Offending token, `None` if the stream ran out of tokens
+    found: Option<TokenKind<'i>>,
+    span: Option<Span<'i>>,
+    reason: Reason<'i>,
+    /// Innermost context the error happened in, such as "after declaration"
+    context: Option<&'static str>,
+    severity: Severity,
+}
+
+#[derive(Debug, Clone)]
+enum Reason<'i> {
+    Expected(Vec<Expected<'i>>),
+    /// Explanation for errors that are not about a missing token, such as
+    /// "cannot combine 'long' with previous 'short'"
+    Message(Box<str>),
+}
+impl<'i> SyntaxError<'i> {
+    pub(crate) fn new(input: Input<'i, '_>) -> Self {
+        Self {
+            position: input.position(),
+            found: input.peek(0).map(|t| t.kind),
+            span: input.peek(0).map(|t| t.span),
+            reason: Reason::Expected(Vec::new()),
+            context: None,
+            severity: Severity::Error,
+        }
+    }
+
+    pub(crate) fn message<M>(input: Input<'i, '_>, message: M) -> Self
+    where
+        M: Into<Box<str>>,
+    {
+        let mut error = Self::new(input);
+        error.reason = Reason::Message(message.into());
+        error
+    }
+
+    pub(crate) fn expected<E>(input: Input<'i, '_>, expected: E) -> Self
+    where
+        E: Into<Expected<'i>>,
+    {
+        let mut error = Self::new(input);
+        error.reason = Reason::Expected(vec![expected.into()]);
+        error
+    }
+
+    /// Reports the diagnostic as a warning instead of an error, or the other way around
+    #[must_use]
+    pub(crate) fn with_severity(self, severity: Severity) -> Self {
+        Self { severity, ..self }
+    }
+
+    /// Index of the offending token in the token vector
+    #[inline]
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Span of the offending token
+    #[must_use]
+    pub fn span(&self) -> Option<Span<'i>> {
+        self.span
+    }
+
+    /// Kind of the offending token
+    #[must_use]
+    pub fn found(&self) -> Option<TokenKind<'i>> {
+        self.found
+    }
+
+    #[must_use]
+    pub fn expected_set(&self) -> &[Expected<'i>] {
+        match self.reason {
+            Reason::Expected(ref expected) => expected,
+            Reason::Message(_) => &[],
+        }
+    }
+
+    #[must_use]
+    pub fn context(&self) -> Option<&'static str> {
+        self.context
+    }
+
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+impl<'i> Display for SyntaxError<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let expected = match self.reason {
+            Reason::Expected(ref expected) => expected,
+            Reason::Message(ref message) => return write!(f, "{message}"),
+        };
+        if let Some((last, rest)) = expected.split_last() {
+            write!(f, "expected ")?;
+            for (i, expected) in rest.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{expected}")?;
+            }
+            if !rest.is_empty() {
+                write!(f, " or ")?;
+            }
+            write!(f, "{last}")?;
+        } else {
+            write!(f, "unexpected token")?;
+        }
+        if let Some(context) = self.context {
+            write!(f, " {context}")?;
+        }
+        match self.found {
+            Some(TokenKind::Eof) | None => write!(f, ", found end of file"),
+            Some(kind) => write!(f, ", found '{kind}'"),
+        }
+    }
+}
+impl<'i> std::error::Error for SyntaxError<'i> {}
+impl<'i, 't> ParseError<Input<'i, 't>> for SyntaxError<'i> {
+    fn from_error_kind(input: Input<'i, 't>, _kind: ErrorKind) -> Self {
+        Self::new(input)
+    }
+
+    fn append(_input: Input<'i, 't>, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        match self.position.cmp(&other.position) {
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Less => other,
+            std::cmp::Ordering::Equal => {
+                if let (Reason::Expected(ours), Reason::Expected(theirs)) =
+                    (&mut self.reason, other.reason)
+                {
+                    for expected in theirs {
+                        if !ours.contains(&expected) {
+                            ours.push(expected);
+                        }
+                    }
+                }
+                self.context = self.context.or(other.context);
+                self
+            }
+        }
+    }
+}
+impl<'i, 't> ContextError<Input<'i, 't>> for SyntaxError<'i> {
+    fn add_context(_input: Input<'i, 't>, ctx: &'static str, mut other: Self) -> Self {
+        other.context = other.context.or(Some(ctx));
+        other
+    }
+}
@@ -1,38 +1,587 @@
 use nom::{
     branch::alt,
-    bytes::complete::take,
-    combinator::{map, map_opt},
-    sequence::separated_pair,
-    IResult,
+    combinator::map,
+    error::context,
+    multi::{many1, separated_list0},
+    sequence::{pair, preceded, tuple},
+    Slice,
 };
 
 use crate::{
-    ast::tree::Expression,
-    lexer::{stream::TokenStream, token::Literal},
+    ast::tree::{
+        is_same_type, AssignOp, BinaryOp, Expression, GenericAssociation, GenericSelection,
+        PostfixOp, StringLiteral, UnaryOp, PRECEDENCE_ASSIGN, PRECEDENCE_CONDITIONAL,
+    },
+    lexer::token::{Invalid, Keyword, Literal, TokenKind},
 };
 
-use super::{parse_ident, tags};
+use super::{
+    blocks,
+    error::{Expected, ParseResult, SyntaxError},
+    gnu,
+    initializers::parse_initializer_list,
+    parse_ident, parse_type_name, recovery, starts_type_name, tags, Input,
+};
+
+pub(crate) fn parse_literal<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Literal<'i>> {
+    match i.peek(0).and_then(|t| t.kind.as_literal()) {
+        Some(&literal) => Ok((i.slice(1..), literal)),
+        None => Err(nom::Err::Error(SyntaxError::expected(i, Expected::Literal))),
+    }
+}
 
-pub(crate) fn parse_literal<'i, 't>(
-    i: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Literal> {
-    map_opt(take(1usize), |t: TokenStream| {
-        t.tokens[0].kind.as_literal().copied()
-    })(i)
+/// Parses a string literal, keeping its quotes
+pub(crate) fn parse_string_literal<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, &'i str> {
+    match parse_literal(i)? {
+        (rest, Literal::String(string)) => Ok((rest, string)),
+        _ => Err(nom::Err::Error(SyntaxError::expected(i, Expected::Literal))),
+    }
 }
 
-pub(crate) fn parse_value<'i, 't>(
-    i: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Expression<'i>> {
+/// Takes a numeric constant the lexer could not read, which is reported already
+fn parse_invalid_number<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, &'i str> {
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Invalid(Invalid::Number(number))) => Ok((i.slice(1..), number)),
+        _ => Err(nom::Err::Error(SyntaxError::expected(i, Expected::Literal))),
+    }
+}
+
+pub(crate) fn parse_value<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
     alt((
+        map(many1(parse_string_literal), |pieces| {
+            Expression::String(StringLiteral { pieces })
+        }),
         map(parse_literal, Expression::Literal),
+        map(parse_invalid_number, Expression::Error),
         map(parse_ident, Expression::Ident),
     ))(i)
 }
 
-pub fn parse_top_level_expression<'i, 't>(
-    i: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, Expression<'i>> {
-    let parse_equals = separated_pair(parse_value, tags::equals, parse_value);
-    alt((map(parse_equals, Expression::new_equals), parse_value))(i)
+/// Parses a primary expression: a constant, an identifier or an expression in parenthesis
+fn parse_primary<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    alt((
+        parse_value,
+        map(parse_generic, Expression::Generic),
+        gnu::parse_builtin,
+        map(gnu::parse_statement_expression, |body| {
+            Expression::Statement(tags::spelling(&i), body)
+        }),
+        blocks::parens(recovery::expression(parse_top_level_expression)),
+    ))(i)
+    .map_err(|e| {
+        // Rather than every token an expression may start with
+        e.map(|error| match error.expected_set() {
+            [_, ..] if error.position() == i.position() => {
+                SyntaxError::expected(i, Expected::Expression)
+            }
+            _ => error,
+        })
+    })
+}
+
+/// Parses `_Generic(controlling, type: value, default: value)`
+fn parse_generic<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, GenericSelection<'i>> {
+    let (rest, _) = tags::keyword(Keyword::Generic)(i)?;
+    let (rest, (controlling, associations)) = blocks::parens(pair(
+        recovery::expression(parse_assignment_expression),
+        many1(preceded(tags::comma, parse_association)),
+    ))(rest)?;
+
+    for (n, (at, association)) in associations.iter().enumerate() {
+        let previous = associations[..n]
+            .iter()
+            .find_map(|(_, previous)| match (&previous.ty, &association.ty) {
+                (Some(previous), Some(ty)) if is_same_type(previous, ty) => Some(format!(
+                    "type '{ty}' in generic association compatible with previously specified type '{previous}'"
+                )),
+                (None, None) => Some("duplicate default generic association".to_string()),
+                _ => None,
+            });
+        if let Some(problem) = previous {
+            recovery::diagnose(at, SyntaxError::message(*at, problem))?;
+        }
+    }
+
+    let associations = associations.into_iter().map(|(_, a)| a).collect();
+    Ok((
+        rest,
+        GenericSelection {
+            controlling: Box::new(controlling),
+            associations,
+        },
+    ))
+}
+
+/// Parses `type: value` or `default: value`, along with where it starts
+fn parse_association<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, (Input<'i, 't>, GenericAssociation<'i>)> {
+    let (rest, ty) = match tags::keyword(Keyword::Default)(i) {
+        Ok((rest, _)) => (rest, None),
+        Err(_) => map(parse_type_name, Some)(i)?,
+    };
+    let (rest, _) = context("after generic association type", tags::colon)(rest)?;
+    let (rest, value) = recovery::expression(parse_assignment_expression)(rest)?;
+    Ok((rest, (i, GenericAssociation { ty, value })))
+}
+
+/// Takes a token that `op` maps to something
+fn take_op<'i, 't, O>(
+    i: Input<'i, 't>,
+    op: impl Fn(TokenKind<'i>) -> Option<O>,
+) -> Option<(Input<'i, 't>, O)> {
+    let op = op(i.peek(0)?.kind)?;
+    Some((i.slice(1..), op))
+}
+
+fn parse_postfix<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    let (i, expr) = parse_primary(i)?;
+    parse_postfix_operators(i, expr)
+}
+
+/// Parses the postfix operators applied to `expr`
+fn parse_postfix_operators<'i, 't>(
+    mut i: Input<'i, 't>,
+    mut expr: Expression<'i>,
+) -> ParseResult<'i, 't, Expression<'i>> {
+    while let Some(kind) = i.peek(0).map(|t| t.kind) {
+        let rest = i.slice(1..);
+        (i, expr) = match kind {
+            TokenKind::OpenBracket => {
+                let (rest, index) = recovery::expression(parse_top_level_expression)(rest)?;
+                let (rest, _) =
+                    recovery::insert(tags::close_bracket, &[TokenKind::SemiColon])(rest)?;
+                (
+                    rest,
+                    Expression::Index {
+                        array: Box::new(expr),
+                        index: Box::new(index),
+                    },
+                )
+            }
+            TokenKind::OpenParen => {
+                // Checked first, as recovery would make an argument out of nothing
+                let (rest, args) = if rest.peek(0).map(|t| t.kind) == Some(TokenKind::CloseParen) {
+                    (rest, Vec::new())
+                } else {
+                    separated_list0(
+                        tags::comma,
+                        recovery::expression(parse_assignment_expression),
+                    )(rest)?
+                };
+                let (rest, _) = recovery::insert(tags::close_paren, &[TokenKind::SemiColon])(rest)?;
+                (
+                    rest,
+                    Expression::Call {
+                        callee: Box::new(expr),
+                        args,
+                    },
+                )
+            }
+            TokenKind::Dot | TokenKind::Arrow => {
+                let (rest, member) = parse_ident(rest)?;
+                (
+                    rest,
+                    Expression::Member {
+                        object: Box::new(expr),
+                        member,
+                        arrow: kind == TokenKind::Arrow,
+                    },
+                )
+            }
+            TokenKind::Increment | TokenKind::Decrement => {
+                let op = if kind == TokenKind::Increment {
+                    PostfixOp::Increment
+                } else {
+                    PostfixOp::Decrement
+                };
+                (
+                    rest,
+                    Expression::Postfix {
+                        op,
+                        operand: Box::new(expr),
+                    },
+                )
+            }
+            _ => break,
+        };
+    }
+    Ok((i, expr))
+}
+
+fn unary_op(kind: TokenKind) -> Option<UnaryOp> {
+    Some(match kind {
+        TokenKind::Ampersand => UnaryOp::AddressOf,
+        TokenKind::Star => UnaryOp::Deref,
+        TokenKind::Plus => UnaryOp::Plus,
+        TokenKind::Minus => UnaryOp::Minus,
+        TokenKind::Tilde => UnaryOp::BitNot,
+        TokenKind::Bang => UnaryOp::Not,
+        TokenKind::Increment => UnaryOp::PreIncrement,
+        TokenKind::Decrement => UnaryOp::PreDecrement,
+        _ => return None,
+    })
+}
+
+/// Parses a unary expression, including casts
+fn parse_unary<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    if let Some((rest, op)) = take_op(i, unary_op) {
+        let (rest, operand) = recovery::expression(parse_unary)(rest)?;
+        return Ok((rest, Expression::new_unary(op, operand)));
+    }
+
+    if i.peek(0).map(|t| t.kind) == Some(TokenKind::Keyword(Keyword::Extension)) {
+        return gnu::extension(recovery::expression(parse_unary))(i);
+    }
+
+    if i.peek(0).is_some_and(|t| t.kind == TokenKind::LogicalAnd) {
+        let (rest, label) = parse_ident(i.slice(1..))?;
+        recovery::extension(&i, "use of address-of-label")?;
+        return Ok((rest, Expression::LabelAddress(label)));
+    }
+
+    if let Ok((rest, _)) = tags::keyword(Keyword::Sizeof)(i) {
+        let keyword = tags::spelling(&i);
+        if starts_type_name(rest.slice(1..)) {
+            let paren = tags::spelling(&rest);
+            if let Ok((rest, ty)) = blocks::parens(parse_type_name)(rest) {
+                if rest.peek(0).is_none_or(|t| t.kind != TokenKind::OpenBrace) {
+                    return Ok((rest, Expression::SizeofType(keyword, ty)));
+                }
+                // The operand is a compound literal, as in `sizeof (int){1}`
+                let (rest, init) = parse_initializer_list(rest)?;
+                let literal = Expression::CompoundLiteral {
+                    paren,
+                    ty: Box::new(ty),
+                    init,
+                };
+                let (rest, operand) = parse_postfix_operators(rest, literal)?;
+                return Ok((rest, Expression::SizeofExpr(Box::new(operand))));
+            }
+        }
+        let (rest, operand) = recovery::expression(parse_unary)(rest)?;
+        return Ok((rest, Expression::SizeofExpr(Box::new(operand))));
+    }
+
+    if let Ok((rest, _)) = tags::keyword(Keyword::Alignof)(i) {
+        let (rest, ty) = blocks::parens(parse_type_name)(rest)?;
+        return Ok((rest, Expression::AlignofType(tags::spelling(&i), ty)));
+    }
+
+    if i.peek(0).is_some_and(|t| t.kind == TokenKind::OpenParen) && starts_type_name(i.slice(1..)) {
+        let (rest, ty) = blocks::parens(parse_type_name)(i)?;
+        if rest.peek(0).is_some_and(|t| t.kind == TokenKind::OpenBrace) {
+            let (rest, init) = parse_initializer_list(rest)?;
+            let paren = tags::spelling(&i);
+            return parse_postfix_operators(
+                rest,
+                Expression::CompoundLiteral {
+                    paren,
+                    ty: Box::new(ty),
+                    init,
+                },
+            );
+        }
+        let (rest, expr) = recovery::expression(parse_unary)(rest)?;
+        return Ok((
+            rest,
+            Expression::Cast {
+                ty,
+                expr: Box::new(expr),
+            },
+        ));
+    }
+
+    parse_postfix(i)
+}
+
+fn binary_op(kind: TokenKind) -> Option<BinaryOp> {
+    Some(match kind {
+        TokenKind::Star => BinaryOp::Mul,
+        TokenKind::Slash => BinaryOp::Div,
+        TokenKind::Percent => BinaryOp::Mod,
+        TokenKind::Plus => BinaryOp::Add,
+        TokenKind::Minus => BinaryOp::Sub,
+        TokenKind::ShiftLeft => BinaryOp::Shl,
+        TokenKind::ShiftRight => BinaryOp::Shr,
+        TokenKind::Less => BinaryOp::Less,
+        TokenKind::Greater => BinaryOp::Greater,
+        TokenKind::LessEquals => BinaryOp::LessEquals,
+        TokenKind::GreaterEquals => BinaryOp::GreaterEquals,
+        TokenKind::Equals => BinaryOp::Equals,
+        TokenKind::NotEquals => BinaryOp::NotEquals,
+        TokenKind::Ampersand => BinaryOp::BitAnd,
+        TokenKind::Caret => BinaryOp::BitXor,
+        TokenKind::Pipe => BinaryOp::BitOr,
+        TokenKind::LogicalAnd => BinaryOp::And,
+        TokenKind::LogicalOr => BinaryOp::Or,
+        TokenKind::Comma => BinaryOp::Comma,
+        _ => return None,
+    })
+}
+
+fn assign_op(kind: TokenKind) -> Option<AssignOp> {
+    Some(AssignOp(match kind {
+        TokenKind::Assign => None,
+        TokenKind::MulAssign => Some(BinaryOp::Mul),
+        TokenKind::DivAssign => Some(BinaryOp::Div),
+        TokenKind::ModAssign => Some(BinaryOp::Mod),
+        TokenKind::AddAssign => Some(BinaryOp::Add),
+        TokenKind::SubAssign => Some(BinaryOp::Sub),
+        TokenKind::ShlAssign => Some(BinaryOp::Shl),
+        TokenKind::ShrAssign => Some(BinaryOp::Shr),
+        TokenKind::AndAssign => Some(BinaryOp::BitAnd),
+        TokenKind::XorAssign => Some(BinaryOp::BitXor),
+        TokenKind::OrAssign => Some(BinaryOp::BitOr),
+        _ => return None,
+    }))
+}
+
+/// Precedence climbing over binary, conditional and assignment operators, only taking
+/// operators that bind at least as tight as `min_precedence`.
+fn parse_binary<'i, 't>(
+    i: Input<'i, 't>,
+    min_precedence: u8,
+) -> ParseResult<'i, 't, Expression<'i>> {
+    let (mut i, mut lhs) = parse_unary(i)?;
+    loop {
+        if let Some((rest, op)) = take_op(i, binary_op) {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            let (rest, rhs) = recovery::expression(|i| parse_binary(i, precedence + 1))(rest)?;
+            (i, lhs) = (rest, Expression::new_binary(op, lhs, rhs));
+        } else if let Some((rest, op)) = take_op(i, assign_op) {
+            if PRECEDENCE_ASSIGN < min_precedence {
+                break;
+            }
+            // Right associative
+            let (rest, rhs) = recovery::expression(|i| parse_binary(i, PRECEDENCE_ASSIGN))(rest)?;
+            (i, lhs) = (rest, Expression::new_assign(op, lhs, rhs));
+        } else if i.peek(0).is_some_and(|t| t.kind == TokenKind::Question) {
+            if PRECEDENCE_CONDITIONAL < min_precedence {
+                break;
+            }
+            let (rest, (_, then, _, r#else)) = tuple((
+                tags::question,
+                recovery::expression(parse_top_level_expression),
+                tags::colon,
+                recovery::expression(|i| parse_binary(i, PRECEDENCE_CONDITIONAL)),
+            ))(i)?;
+            (i, lhs) = (
+                rest,
+                Expression::Conditional {
+                    condition: Box::new(lhs),
+                    then: Box::new(then),
+                    r#else: Box::new(r#else),
+                },
+            );
+        } else {
+            break;
+        }
+    }
+    Ok((i, lhs))
+}
+
+/// Parses an assignment expression, that is, an expression without top level commas,
+/// as found in initializers and function arguments
+pub(crate) fn parse_assignment_expression<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Expression<'i>> {
+    parse_binary(i, PRECEDENCE_ASSIGN)
+}
+
+/// Parses a conditional expression, the grammar of constant expressions such as
+/// array sizes and `_Alignas` operands
+pub(crate) fn parse_conditional_expression<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Expression<'i>> {
+    parse_binary(i, PRECEDENCE_CONDITIONAL)
+}
+
+/// Parses a full expression, commas included
+pub(crate) fn parse_top_level_expression<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Expression<'i>> {
+    parse_binary(i, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::tree::{
+            AssignOp, BaseType, BinaryOp, Declarator, Expression, PostfixOp, Signedness, TypeName,
+            UnaryOp,
+        },
+        lexer::{
+            stream::TokenStream,
+            token::{Ident, TokenKind},
+        },
+        preprocessor::preprocess,
+    };
+
+    use super::{
+        super::{parse_stream, Options, State},
+        parse_top_level_expression,
+    };
+
+    fn ident(name: &str) -> Expression<'_> {
+        Expression::Ident(Ident::new(name))
+    }
+
+    /// Parses `source`, checking it is a single expression
+    fn parse(source: &str) -> Expression<'_> {
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(source)).collect();
+        let (rest, expr) = parse_top_level_expression(
+            TokenStream::new(&tokens).with(State::new(Options::default())),
+        )
+        .expect("Could not parse");
+        assert_eq!(rest.tokens, &[TokenKind::Eof], "Expected EOF");
+        expr
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            parse("a + b * c"),
+            Expression::new_binary(
+                BinaryOp::Add,
+                ident("a"),
+                Expression::new_binary(BinaryOp::Mul, ident("b"), ident("c")),
+            )
+        );
+        assert_eq!(
+            parse("a - b - c"),
+            Expression::new_binary(
+                BinaryOp::Sub,
+                Expression::new_binary(BinaryOp::Sub, ident("a"), ident("b")),
+                ident("c"),
+            )
+        );
+        assert_eq!(
+            parse("a = b += c"),
+            Expression::new_assign(
+                AssignOp(None),
+                ident("a"),
+                Expression::new_assign(AssignOp(Some(BinaryOp::Add)), ident("b"), ident("c")),
+            )
+        );
+    }
+
+    #[test]
+    fn test_unary_and_postfix() {
+        assert_eq!(
+            parse("*p++"),
+            Expression::new_unary(
+                UnaryOp::Deref,
+                Expression::Postfix {
+                    op: PostfixOp::Increment,
+                    operand: Box::new(ident("p")),
+                },
+            )
+        );
+        assert_eq!(
+            parse("sizeof(unsigned int *)"),
+            Expression::SizeofType(
+                "sizeof",
+                TypeName {
+                    specifiers: BaseType::Int(Signedness::Unsigned).into(),
+                    declarator: Declarator::new_pointer(Declarator::Abstract),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "a + b * c",
+            "(a + b) * c",
+            "a - (b - c)",
+            "a, b = c",
+            "x += y << 2",
+            "a && b || c & d ^ e | f",
+            "x ? y : z ? w : v",
+            "(x ? y : z) = w",
+            "!~-x",
+            "- -x",
+            "-(int)x",
+            "(char*)&x",
+            "sizeof x + sizeof(int)",
+            "sizeof (a + b)",
+            "sizeof (int){ 1 } + sizeof (struct p){ 1, 2 }.x",
+            "f(a, (b, c))(d)[1].m->n",
+            "a[i++] = --b[j]",
+            "x <= 1 != y >= 2",
+            "1u + 16 * 'c' - 1.5",
+            "s = \"hello\"",
+            "w = L\"wide\" \"text\" + L'c'",
+            "_Alignof(double) + 1",
+            "_Generic(x, int: f, char*: g, default: (a, b))(x)",
+        ] {
+            assert_eq!(parse(source).to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_generic_selection() {
+        let Expression::Generic(generic) = parse("_Generic(x, long: 1, float*: 2, default: 3)")
+        else {
+            panic!("Expected a generic selection")
+        };
+        let associations: Vec<_> = generic
+            .associations
+            .iter()
+            .map(|a| (a.ty.as_ref().map(ToString::to_string), a.value.to_string()))
+            .collect();
+        assert_eq!(
+            associations,
+            [
+                (Some("long".to_string()), "1".to_string()),
+                (Some("float*".to_string()), "2".to_string()),
+                (None, "3".to_string()),
+            ]
+        );
+
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(
+            "n = _Generic(x, int: 1, signed: 2, default: 3, default: 4);",
+        ))
+        .collect();
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "type 'int' in generic association compatible with previously specified type 'int'",
+                "duplicate default generic association",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calls_without_arguments() {
+        let tokens: Vec<_> =
+            crate::lexer::parse_tokens(preprocess("int f(void); int g(void) { f(); return f(); }"))
+                .collect();
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let tokens: Vec<_> =
+            crate::lexer::parse_tokens(preprocess("int a = ; int b = (1 + ); int c = -;"))
+                .collect();
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "expected expression, found ';'",
+                "expected expression, found ')'",
+                "expected expression, found ';'",
+            ]
+        );
+    }
 }
@@ -0,0 +1,274 @@
+//! Function prototypes and definitions.
+
+use nom::{Offset, Slice};
+
+use crate::{
+    ast::tree::{
+        DeclSpecifiers, Declarator, FuncDecl, Parameters, Statement, StorageClass, VarDecl,
+    },
+    lexer::token::TokenKind,
+    standard::Standard,
+};
+
+use super::{
+    error::{ParseResult, SyntaxError},
+    labels, parse_block, parse_declaration, recovery, scopes, tags, Input,
+};
+
+/// Whether a declaration whose declarator is a function one continues as a function
+/// declaration rather than as a variable declaration, after the declarator
+pub(super) fn continues_function(i: Input<'_, '_>, params: &Parameters<'_>) -> bool {
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::OpenBrace | TokenKind::SemiColon) => true,
+        // A K&R definition declares its parameters before its body
+        Some(TokenKind::Comma | TokenKind::Assign) | None => false,
+        Some(_) => matches!(params, Parameters::Identifiers(_)),
+    }
+}
+
+/// Parses the rest of a function declaration, after its declarator: the `;` of a
+/// prototype, or the body of a definition
+pub(super) fn parse_function<'i, 't>(
+    i: Input<'i, 't>,
+    ret: DeclSpecifiers<'i>,
+    declarator: Declarator<'i>,
+) -> ParseResult<'i, 't, FuncDecl<'i>> {
+    let params = declarator
+        .function_params()
+        .expect("only function declarators continue as functions");
+    if let Some(StorageClass::Auto | StorageClass::Register) = ret.storage {
+        recovery::diagnose(
+            &i,
+            SyntaxError::message(i, "illegal storage class on function"),
+        )?;
+    }
+
+    if let Ok((rest, _)) = tags::semi_colon(i) {
+        if let Parameters::Identifiers(_) = params {
+            recovery::diagnose(
+                &i,
+                SyntaxError::message(
+                    i,
+                    "a parameter list without types is only allowed in a function definition",
+                ),
+            )?;
+        }
+        let decl = FuncDecl {
+            ret,
+            declarator,
+            declarations: Vec::new(),
+            body: None,
+        };
+        return Ok((rest, decl));
+    }
+
+    let (rest, (declarations, body)) = scopes::scoped(|i| parse_definition(i, params))(i)?;
+    Ok((
+        rest,
+        FuncDecl {
+            ret,
+            declarator,
+            declarations,
+            body: Some(body),
+        },
+    ))
+}
+
+/// Parses the K&R parameter declarations and the body of a function definition, in
+/// the scope of its parameters
+fn parse_definition<'i, 't>(
+    i: Input<'i, 't>,
+    params: &Parameters<'i>,
+) -> ParseResult<'i, 't, (Vec<VarDecl<'i>>, Vec<Statement<'i>>)> {
+    match params {
+        Parameters::Identifiers(names) => {
+            for &name in names {
+                scopes::declare(&i, name, false);
+            }
+        }
+        _ => {
+            for name in params.declarations().iter().filter_map(|(_, d)| d.name()) {
+                scopes::declare(&i, name, false);
+            }
+        }
+    }
+
+    let mut declarations = Vec::new();
+    let mut i = i;
+    if let Parameters::Identifiers(names) = params {
+        while i.peek(0).is_some_and(|t| t.kind != TokenKind::OpenBrace) {
+            let (rest, declaration) = parse_declaration(i)?;
+            let Statement::VarDecl(decl) = declaration else {
+                recovery::diagnose(
+                    &i,
+                    SyntaxError::message(i, "only parameters may be declared before the body"),
+                )?;
+                i = rest;
+                continue;
+            };
+            for declarator in decl.declarators.iter() {
+                let Some(name) = declarator.declarator.name() else {
+                    continue;
+                };
+                let problem = if !names.contains(&name) {
+                    format!("declaration for parameter '{name}' but no such parameter")
+                } else if declarator.value.is_some() {
+                    format!("parameter '{name}' is initialized")
+                } else {
+                    continue;
+                };
+                recovery::diagnose(&i, SyntaxError::message(i, problem))?;
+            }
+            declarations.push(decl);
+            i = rest;
+        }
+    }
+
+    if i.standard() < Standard::C23 {
+        for (_, param) in params.declarations() {
+            if param.name().is_none() {
+                recovery::diagnose(
+                    &i,
+                    SyntaxError::message(i, "parameter name omitted in function definition"),
+                )?;
+            }
+        }
+    }
+
+    let (rest, body) = parse_block(i)?;
+    labels::check_labels(i.slice(..i.offset(&rest)), &body)?;
+    Ok((rest, (declarations, body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{parse, Options},
+            tree::{Declarator, Parameters, Statement},
+        },
+        standard::Standard,
+    };
+
+    fn options(standard: Standard) -> Options {
+        Options {
+            standard,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_prototypes() {
+        let (program, errors) = parse(
+            "int printf(const char *format, ...);
+            void free(void *);
+            int rand(void);
+            int old();
+            static inline int square(int x) { return x * x; }",
+            options(Standard::C17),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::FuncDecl(printf), Statement::FuncDecl(free), Statement::FuncDecl(rand), Statement::FuncDecl(old), Statement::FuncDecl(square)] =
+            program.as_slice()
+        else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(!printf.is_definition());
+        assert!(matches!(
+            printf.params(),
+            Parameters::Prototype {
+                is_variadic: true,
+                ..
+            }
+        ));
+        assert_eq!(
+            free.params().declarations()[0].1,
+            Declarator::new_pointer(Declarator::Abstract)
+        );
+        assert_eq!(rand.to_string(), "int rand(void);\n");
+        assert_eq!(*old.params(), Parameters::Unspecified);
+        assert!(square.is_definition());
+        assert!(square.ret.is_inline);
+    }
+
+    #[test]
+    fn test_prototype_errors() {
+        let (_, errors) = parse(
+            "int f(...);
+            int g(void, int);
+            int h(static int x);
+            register int k(void);
+            inline int x;
+            int unnamed(int) { return 0; }",
+            options(Standard::C17),
+        );
+        assert_eq!(
+            errors,
+            [
+                "ISO C requires a named parameter before '...'",
+                "'void' must be the first and only parameter if specified",
+                "invalid storage class specifier in function declarator",
+                "illegal storage class on function",
+                "'inline' can only appear on functions",
+                "parameter name omitted in function definition",
+            ]
+        );
+
+        let (_, errors) = parse(
+            "int f(...); int unnamed(int) { return 0; }",
+            options(Standard::C23),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+
+    #[test]
+    fn test_old_style_definition() {
+        let (program, errors) = parse(
+            "int add(a, b) int a; long b; { return a + b; }",
+            options(Standard::C89),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::FuncDecl(add)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(matches!(add.params(), Parameters::Identifiers(names) if names.len() == 2));
+        assert_eq!(add.declarations.len(), 2);
+        assert_eq!(
+            add.to_string(),
+            "int add(a, b)\nint a;\nlong b;\n{\nreturn a + b; \n}\n"
+        );
+
+        let (_, errors) = parse("int f(a) int b; { return 0; }", options(Standard::C89));
+        assert_eq!(
+            errors,
+            ["declaration for parameter 'b' but no such parameter"]
+        );
+        // Identifier lists are not accepted at all after C89
+        let (_, errors) = parse("int f(a, b) { return 0; }", options(Standard::C17));
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn test_adjust_parameter() {
+        let (mut program, errors) = parse(
+            "void f(int a[static const 4], int g(void), char *argv[], int (*rows)[3]);",
+            options(Standard::C17),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let Some(Statement::FuncDecl(f)) = program.pop() else {
+            panic!("Unexpected program {program:?}")
+        };
+        let Declarator::Function {
+            params: Parameters::Prototype { params, .. },
+            ..
+        } = f.declarator
+        else {
+            panic!("Expected a prototype, got {:?}", f.declarator)
+        };
+        let adjusted: Vec<_> = params
+            .into_iter()
+            .map(|(_, declarator)| declarator.adjust_parameter().to_string())
+            .collect();
+        assert_eq!(adjusted, ["*const a", "(*g)(void)", "**argv", "(*rows)[3]"]);
+    }
+}
@@ -0,0 +1,252 @@
+//! GNU extensions that need more than a keyword to parse: `__extension__`, statement
+//! expressions, `typeof` and the builtins that take types as arguments.
+
+use nom::{sequence::tuple, Parser, Slice};
+
+use crate::{
+    ast::tree::{Designator, Expression, Statement, TypeofSpecifier},
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    blocks,
+    error::{ParseResult, SyntaxError},
+    expr::parse_top_level_expression,
+    parse_block, parse_ident, parse_type_name, recovery, starts_type_name, tags, Input,
+};
+
+/// Parses `__extension__` followed by what `parser` parses, without reporting the GNU
+/// extensions in it under `-pedantic`. The keyword has no other effect, so it is not
+/// kept in the tree.
+pub(super) fn extension<'i: 't, 't, P, O>(
+    mut parser: P,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, O>
+where
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+{
+    move |i: Input<'i, 't>| {
+        let (rest, _) = tags::keyword(Keyword::Extension)(i)?;
+        let (rest, output) = parser.parse(rest.with_pedantic(None))?;
+        Ok((rest.with_pedantic(i.pedantic()), output))
+    }
+}
+
+/// Parses `({ ... })`, a block whose last statement gives its value
+pub(super) fn parse_statement_expression<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Vec<Statement<'i>>> {
+    let starts_block = i.peek(1).is_some_and(|t| t.kind == TokenKind::OpenBrace);
+    if !starts_block || i.peek(0).is_none_or(|t| t.kind != TokenKind::OpenParen) {
+        return Err(nom::Err::Error(SyntaxError::new(i)));
+    }
+    recovery::extension(&i, "statement expression")?;
+    blocks::parens(parse_block)(i)
+}
+
+/// Parses `typeof(expression)` or `typeof(type)`. Plain `typeof` only lexes as a
+/// keyword where it is reserved, while `__typeof__` always does.
+pub(super) fn parse_typeof<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, TypeofSpecifier<'i>> {
+    let (rest, _) = tags::keyword(Keyword::Typeof)(i)?;
+    if starts_type_name(rest.slice(1..)) {
+        let (rest, ty) = blocks::parens(parse_type_name)(rest)?;
+        Ok((rest, TypeofSpecifier::Type(ty)))
+    } else {
+        let (rest, expr) = blocks::parens(recovery::expression(parse_top_level_expression))(rest)?;
+        Ok((rest, TypeofSpecifier::Expression(expr)))
+    }
+}
+
+/// Parses `__builtin_offsetof(type, member)` and `__builtin_types_compatible_p(a, b)`
+pub(super) fn parse_builtin<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>> {
+    if let Ok((rest, _)) = tags::keyword(Keyword::BuiltinTypesCompatible)(i) {
+        let (rest, (a, _, b)) =
+            blocks::parens(tuple((parse_type_name, tags::comma, parse_type_name)))(rest)?;
+        let builtin = Expression::TypesCompatible(tags::spelling(&i), Box::new(a), Box::new(b));
+        return Ok((rest, builtin));
+    }
+
+    let (rest, _) = tags::keyword(Keyword::BuiltinOffsetof)(i)?;
+    let (rest, (ty, _, member, designators)) = blocks::parens(tuple((
+        parse_type_name,
+        tags::comma,
+        parse_ident,
+        parse_member_designators,
+    )))(rest)?;
+    Ok((
+        rest,
+        Expression::Offsetof {
+            ty: Box::new(ty),
+            member,
+            designators,
+        },
+    ))
+}
+
+/// Parses the `.member` and `[index]` that follow the first member of `offsetof`
+fn parse_member_designators<'i, 't>(
+    mut i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Vec<Designator<'i>>> {
+    let mut designators = Vec::new();
+    loop {
+        let (rest, designator) = match i.peek(0).map(|t| t.kind) {
+            Some(TokenKind::Dot) => {
+                let (rest, member) = parse_ident(i.slice(1..))?;
+                (rest, Designator::Member(member))
+            }
+            Some(TokenKind::OpenBracket) => {
+                let (rest, index) = recovery::expression(parse_top_level_expression)(i.slice(1..))?;
+                let (rest, _) =
+                    recovery::insert(tags::close_bracket, &[TokenKind::CloseParen])(rest)?;
+                (rest, Designator::Index(index))
+            }
+            _ => return Ok((i, designators)),
+        };
+        designators.push(designator);
+        i = rest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{body, options, parse, parse_stream, Options},
+            tree::{BaseType, Expression, Initializer, Statement, TypeofSpecifier},
+            Severity,
+        },
+        lexer::stream::TokenStream,
+        preprocessor::preprocess,
+        standard::Standard,
+    };
+
+    #[test]
+    fn test_statement_expressions() {
+        let (program, errors) = parse(
+            "int f(int x) {
+                int y = ({ int t = x * 2; t + 1; });
+                return ({ y; }) + __extension__ ({ 1; });
+            }",
+            options(true),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let body = body(&program);
+        assert_eq!(body[0].to_string(), "int y = ({int t = x * 2;\nt + 1;});\n");
+        let Statement::VarDecl(ref y) = body[0] else {
+            panic!("Expected a declaration, got {:?}", body[0])
+        };
+        let Some(Initializer::Expression(Expression::Statement(_, ref inner))) =
+            y.declarators[0].value
+        else {
+            panic!("Expected a statement expression, got {y:?}")
+        };
+        assert_eq!(inner.len(), 2);
+    }
+
+    #[test]
+    fn test_typeof() {
+        let (program, errors) = parse(
+            "int a;
+            typeof(a) b;
+            __typeof__(int *) c, d;
+            void f(void) { __auto_type e = a + 1; typeof(e) g = (typeof(e))2; }",
+            options(true),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        assert_eq!(program[1].to_string(), "typeof(a) b;\n");
+        assert_eq!(program[2].to_string(), "typeof(int*) c, d;\n");
+        let Statement::VarDecl(ref c) = program[2] else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(matches!(
+            c.ty.ty,
+            BaseType::Typeof(ref operand) if matches!(**operand, TypeofSpecifier::Type(_))
+        ));
+        let body = body(&program);
+        assert_eq!(body[0].to_string(), "__auto_type e = a + 1;\n");
+        assert_eq!(body[1].to_string(), "typeof(e) g = (typeof(e))2;\n");
+
+        let (_, errors) = parse(
+            "void f(void) { __auto_type a; __auto_type b = 1, c = 2; __auto_type *p = 0; }",
+            options(true),
+        );
+        assert_eq!(
+            errors,
+            [
+                "'__auto_type' requires an initialized data declaration",
+                "'__auto_type' may only be used with a single declarator",
+                "'__auto_type' requires an initialized data declaration",
+            ]
+        );
+
+        // Plain `typeof` is an ordinary identifier in ISO C before C23
+        let (program, errors) = parse("int typeof = 1; __typeof__(typeof) x;", options(false));
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        assert_eq!(program[1].to_string(), "typeof(typeof) x;\n");
+        let c23 = Options {
+            standard: Standard::C23,
+            ..options(false)
+        };
+        let (_, errors) = parse("typeof(1) a;", c23);
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+
+    #[test]
+    fn test_builtins() {
+        let (program, errors) = parse(
+            "struct s { int a; struct { int x[4]; } b; };
+            unsigned long o = __builtin_offsetof(struct s, b.x[2]);
+            int same = __builtin_types_compatible_p(const int, int);
+            int other = __builtin_types_compatible_p(int, long);
+            int likely = __builtin_expect(1 + 1, 1);",
+            options(false),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        assert_eq!(
+            program[1].to_string(),
+            "unsigned long o = __builtin_offsetof(struct s, b.x[2]);\n"
+        );
+        assert_eq!(
+            program[2].to_string(),
+            "int same = __builtin_types_compatible_p(const int, int);\n"
+        );
+        assert!(matches!(
+            program[4],
+            Statement::VarDecl(ref decl) if matches!(
+                decl.declarators[0].value,
+                Some(Initializer::Expression(Expression::Call { .. }))
+            )
+        ));
+    }
+
+    #[test]
+    fn test_pedantic() {
+        const SOURCE: &str = "void f(int x) {
+            int y = ({ x; });
+            __typeof__(x) z = __extension__ ({ y; });
+            __extension__ __typeof__(x) w;
+            switch (x) { case 1 ... 2: break; }
+        }";
+        let (_, errors) = parse(SOURCE, options(true));
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+
+        let (_, errors) = parse(SOURCE, options(false));
+        assert_eq!(
+            errors,
+            [
+                "statement expression is a GNU extension",
+                "case range is a GNU extension",
+            ]
+        );
+
+        // Extensions are only reported under -pedantic, and only as warnings with it
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), Options::default());
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let options = Options {
+            pedantic: Some(Severity::Warning),
+            ..Options::default()
+        };
+        let (_, errors) = parse_stream(TokenStream::new(&tokens), options);
+        assert!(errors.iter().all(|e| e.severity() == Severity::Warning));
+    }
+}
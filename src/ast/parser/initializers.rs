@@ -0,0 +1,142 @@
+//! Initializers, including brace-enclosed lists with designators.
+
+use nom::{error::context, Slice};
+
+use crate::{
+    ast::tree::{DesignatedInitializer, Designator, Initializer},
+    lexer::token::TokenKind,
+};
+
+use super::{
+    error::{ParseResult, SyntaxError},
+    expr::{parse_assignment_expression, parse_conditional_expression},
+    parse_ident, recovery, tags, Input,
+};
+
+/// Parses an expression or a `{ ... }` list
+pub(super) fn parse_initializer<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Initializer<'i>> {
+    if i.peek(0).is_some_and(|t| t.kind == TokenKind::OpenBrace) {
+        let (i, list) = parse_initializer_list(i)?;
+        return Ok((i, Initializer::List(list)));
+    }
+    let (i, expr) = recovery::expression(parse_assignment_expression)(i)?;
+    Ok((i, Initializer::Expression(expr)))
+}
+
+/// Parses `{ ... }`, which may be empty and may end with a comma
+pub(super) fn parse_initializer_list<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, Vec<DesignatedInitializer<'i>>> {
+    let (mut i, _) = tags::open_brace(i)?;
+    let mut list = Vec::new();
+    while i.peek(0).is_some_and(|t| t.kind != TokenKind::CloseBrace) {
+        let (rest, item) = parse_designated_initializer(i)?;
+        list.push(item);
+        i = rest;
+        match tags::comma(i) {
+            Ok((rest, _)) => i = rest,
+            Err(_) => break,
+        }
+    }
+    let (i, _) = recovery::insert(tags::close_brace, &[TokenKind::SemiColon])(i)?;
+    Ok((i, list))
+}
+
+fn parse_designated_initializer<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, DesignatedInitializer<'i>> {
+    let mut designators = Vec::new();
+    let mut i = i;
+    while let Some((rest, designator)) = parse_designator(i)? {
+        designators.push(designator);
+        i = rest;
+    }
+    if !designators.is_empty() {
+        (i, _) = context("after designator", tags::assign)(i)?;
+    }
+    let (i, value) = parse_initializer(i)?;
+    Ok((i, DesignatedInitializer { designators, value }))
+}
+
+/// Parses `.member`, `[index]` or `[first ... last]`, if there is one
+fn parse_designator<'i, 't>(
+    i: Input<'i, 't>,
+) -> Result<Option<(Input<'i, 't>, Designator<'i>)>, nom::Err<SyntaxError<'i>>> {
+    match i.peek(0).map(|t| t.kind) {
+        Some(TokenKind::Dot) => {
+            let (rest, member) = parse_ident(i.slice(1..))?;
+            Ok(Some((rest, Designator::Member(member))))
+        }
+        Some(TokenKind::OpenBracket) => {
+            let (rest, first) = recovery::expression(parse_conditional_expression)(i.slice(1..))?;
+            let (rest, designator) = match tags::ellipsis(rest) {
+                Ok((rest, _)) => {
+                    recovery::extension(&i, "array range designator")?;
+                    let (rest, last) = recovery::expression(parse_conditional_expression)(rest)?;
+                    (rest, Designator::Range(first, last))
+                }
+                Err(_) => (rest, Designator::Index(first)),
+            };
+            let (rest, _) = recovery::insert(tags::close_bracket, &[TokenKind::Assign])(rest)?;
+            Ok(Some((rest, designator)))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        parser::{options, parse},
+        tree::{Designator, Initializer, Statement},
+    };
+
+    #[test]
+    fn test_designators() {
+        let (program, errors) = parse(
+            "struct s x = { .a.b[2] = 1, [3] = { 4, 5, }, 6 };
+            int table[10] = { [0 ... 4] = 1, [5] = 2 };",
+            options(true),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(x), Statement::VarDecl(table)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        let Some(Initializer::List(ref list)) = x.declarators[0].value else {
+            panic!("Expected a list, got {:?}", x.declarators[0].value)
+        };
+        assert_eq!(list.len(), 3);
+        assert!(matches!(
+            list[0].designators.as_slice(),
+            [
+                Designator::Member(_),
+                Designator::Member(_),
+                Designator::Index(_)
+            ]
+        ));
+        assert!(matches!(list[1].value, Initializer::List(ref inner) if inner.len() == 2));
+        assert!(list[2].designators.is_empty());
+        assert_eq!(
+            table.to_string(),
+            "int table[10] = { [0 ... 4] = 1, [5] = 2 };\n"
+        );
+
+        let (_, errors) = parse("int table[10] = { [0 ... 4] = 1 };", options(false));
+        assert_eq!(errors, ["array range designator is a GNU extension"]);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "int a[2][2] = { 1, 2, 3, 4 };\n",
+            "int m[2][2] = { { 1, 2 }, { 3, 4 } };\n",
+            "struct s empty = {};\n",
+            "struct p *p = &(struct p){ .x = 1, 2 };\n",
+            "int n = (int){ 3 } + 1;\n",
+        ] {
+            let (program, errors) = parse(source, options(false));
+            assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+            assert_eq!(program[0].to_string(), source);
+        }
+    }
+}
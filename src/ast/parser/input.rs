@@ -0,0 +1,129 @@
+//! What the parser carries along the tokens: how they are parsed, and what it
+//! learned from the tokens it already went through.
+
+use std::cell::RefCell;
+
+use crate::{
+    lexer::stream::TokenStream,
+    standard::{Dialect, Standard},
+};
+
+use super::{error::Severity, Scopes, StatementContext, SyntaxError};
+
+/// The tokens being parsed, along with the state of the parser
+pub(crate) type Input<'i, 't> = TokenStream<'i, 't, State<'i, 't>>;
+
+/// How a token stream is parsed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Revision of C the tokens are parsed as
+    pub standard: Standard,
+    /// Whether GNU extensions, such as case ranges, are accepted
+    pub gnu_extensions: bool,
+    /// How GNU extensions are reported when they are not accepted, as `-pedantic`
+    /// and `-pedantic-errors` do. `None` lets them through silently.
+    pub pedantic: Option<Severity>,
+}
+
+impl Options {
+    /// The dialect the tokens must be lexed in to be parsed with these options
+    #[must_use]
+    pub const fn dialect(&self) -> Dialect {
+        Dialect {
+            standard: self.standard,
+            gnu_extensions: self.gnu_extensions,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct State<'i, 't> {
+    /// Where the parser reports the errors it recovered from. Recovery is disabled
+    /// when `None`.
+    errors: Option<&'t RefCell<Vec<SyntaxError<'i>>>>,
+    /// Identifiers declared so far, to tell typedef names apart. Without them no
+    /// identifier names a type.
+    scopes: Option<&'t RefCell<Scopes<'i>>>,
+    /// Statements enclosing the tokens, such as loops
+    context: StatementContext,
+    options: Options,
+}
+
+impl State<'_, '_> {
+    /// Parses with `options`, without error recovery nor scopes
+    pub(crate) const fn new(options: Options) -> Self {
+        Self {
+            errors: None,
+            scopes: None,
+            context: StatementContext::new(),
+            options,
+        }
+    }
+}
+
+impl<'i, 't> Input<'i, 't> {
+    /// Enables error recovery, reporting recovered errors to `errors`
+    #[must_use]
+    pub(crate) fn with_errors(mut self, errors: &'t RefCell<Vec<SyntaxError<'i>>>) -> Self {
+        self.extra.errors = Some(errors);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn errors(&self) -> Option<&'t RefCell<Vec<SyntaxError<'i>>>> {
+        self.extra.errors
+    }
+
+    /// Keeps track of the identifiers declared in `scopes`
+    #[must_use]
+    pub(crate) fn with_scopes(mut self, scopes: &'t RefCell<Scopes<'i>>) -> Self {
+        self.extra.scopes = Some(scopes);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn scopes(&self) -> Option<&'t RefCell<Scopes<'i>>> {
+        self.extra.scopes
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn context(&self) -> StatementContext {
+        self.extra.context
+    }
+
+    /// Changes what the statements in the stream are enclosed by
+    #[must_use]
+    pub(crate) fn with_context(mut self, context: StatementContext) -> Self {
+        self.extra.context = context;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn gnu_extensions(&self) -> bool {
+        self.extra.options.gnu_extensions
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn pedantic(&self) -> Option<Severity> {
+        self.extra.options.pedantic
+    }
+
+    /// Reports GNU extensions with the given severity instead, `None` letting them
+    /// through silently
+    #[must_use]
+    pub(crate) fn with_pedantic(mut self, pedantic: Option<Severity>) -> Self {
+        self.extra.options.pedantic = pedantic;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn standard(&self) -> Standard {
+        self.extra.options.standard
+    }
+}
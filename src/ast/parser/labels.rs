@@ -0,0 +1,185 @@
+//! Checks on the labels of a function body, which `goto` can jump to from anywhere in
+//! the function.
+
+use nom::Slice;
+
+use crate::{
+    ast::tree::{control::Label, Statement},
+    lexer::token::{Ident, Keyword, TokenKind},
+};
+
+use super::{error::SyntaxError, recovery, Input};
+
+/// Labels and `goto`s of a function, in order
+#[derive(Default)]
+struct Jumps<'i> {
+    labels: Vec<Ident<'i>>,
+    gotos: Vec<Ident<'i>>,
+}
+impl<'i> Jumps<'i> {
+    fn block(&mut self, body: &[Statement<'i>]) {
+        for statement in body {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement<'i>) {
+        match statement {
+            Statement::If(r#if) => {
+                self.block(&r#if.body);
+                self.block(r#if.else_body.as_deref().unwrap_or_default());
+            }
+            Statement::While(r#while) => self.block(&r#while.body),
+            Statement::DoWhile(do_while) => self.block(&do_while.body),
+            Statement::For(r#for) => self.block(&r#for.body),
+            Statement::Switch(switch) => self.block(&switch.body),
+            Statement::Compound(compound) => self.block(&compound.body),
+            Statement::Labeled(labeled) => {
+                if let Label::Named(name) = labeled.label {
+                    self.labels.push(name);
+                }
+                self.statement(&labeled.body);
+            }
+            Statement::Attributed(_, statement) => self.statement(statement),
+            Statement::Goto(label) => self.gotos.push(*label),
+            Statement::Asm(asm) => self.gotos.extend(asm.labels.iter().copied()),
+            _ => {}
+        }
+    }
+}
+
+/// Finds the `n`th occurrence of `pattern` in `tokens`, to point errors found in the
+/// tree at the tokens they come from
+fn find<'i, 't>(tokens: Input<'i, 't>, pattern: &[TokenKind<'i>], n: usize) -> Input<'i, 't> {
+    tokens
+        .tokens
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| window.iter().map(|t| t.kind).eq(pattern.iter().copied()))
+        .nth(n)
+        .map_or(tokens, |(start, _)| tokens.slice(start..))
+}
+
+/// Reports labels defined twice and `goto`s to labels that do not exist. `body` holds
+/// the tokens of the function body. Jumps into the scope of a variable length array
+/// are left to semantic analysis, which knows which arrays have a constant length.
+pub(super) fn check_labels<'i>(
+    body: Input<'i, '_>,
+    statements: &[Statement<'i>],
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    let mut jumps = Jumps::default();
+    jumps.block(statements);
+
+    for (n, name) in jumps.labels.iter().enumerate() {
+        let previous = jumps.labels[..n].iter().filter(|&l| l == name).count();
+        if previous > 0 {
+            let at = find(body, &[TokenKind::Ident(*name), TokenKind::Colon], previous);
+            recovery::diagnose(
+                &at,
+                SyntaxError::message(at, format!("redefinition of label '{name}'")),
+            )?;
+        }
+    }
+
+    for (n, name) in jumps.gotos.iter().enumerate() {
+        if jumps.labels.contains(name) {
+            continue;
+        }
+        let previous = jumps.gotos[..n].iter().filter(|&l| l == name).count();
+        let at = find(
+            body,
+            &[TokenKind::Keyword(Keyword::Goto), TokenKind::Ident(*name)],
+            previous,
+        );
+        recovery::diagnose(
+            &at,
+            SyntaxError::message(at, format!("use of undeclared label '{name}'")),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        parser::{options, parse},
+        tree::Statement,
+    };
+
+    #[test]
+    fn test_goto() {
+        let (program, errors) = parse(
+            "int f(void) {
+                if (a) goto cleanup;
+                a = 1;
+                cleanup: a = 0;
+            }",
+            options(false),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let Statement::FuncDecl(ref f) = program[0] else {
+            panic!("Unexpected program {program:?}")
+        };
+        let body = f.body.as_ref().expect("Expected a definition");
+        assert_eq!(body[0].to_string(), "if(a) {goto cleanup;}\n");
+        assert_eq!(body[2].to_string(), "cleanup: a = 0;");
+    }
+
+    #[test]
+    fn test_label_errors() {
+        let (_, errors) = parse(
+            "void f(int n) {
+                goto missing;
+                again: a = 1;
+                again: a = 2;
+                goto inside;
+                if (n) {
+                    int buf[n];
+                    inside: a = 3;
+                    goto inside;
+                }
+                if (n) {
+                    int fixed[4];
+                    fine: a = 4;
+                }
+                goto fine;
+                {
+                    int vla[n];
+                    { nested: ; }
+                    goto nested;
+                }
+                goto nested;
+            }",
+            options(false),
+        );
+        assert_eq!(
+            errors,
+            [
+                "redefinition of label 'again'",
+                "use of undeclared label 'missing'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_computed_goto() {
+        const SOURCE: &str = "void f(void) { void *p = &&done; goto *p; done: a = 1; }";
+        let (program, errors) = parse(SOURCE, options(true));
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let Statement::FuncDecl(ref f) = program[0] else {
+            panic!("Unexpected program {program:?}")
+        };
+        let body = f.body.as_ref().expect("Expected a definition");
+        assert_eq!(body[0].to_string(), "void *p = &&done;\n");
+        assert_eq!(body[1].to_string(), "goto *p;");
+
+        let (_, errors) = parse(SOURCE, options(false));
+        assert_eq!(
+            errors,
+            [
+                "use of address-of-label is a GNU extension",
+                "computed goto is a GNU extension",
+            ]
+        );
+    }
+}
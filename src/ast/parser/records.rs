@@ -0,0 +1,250 @@
+//! `struct` and `union` specifiers and their members.
+
+use nom::{
+    branch::alt,
+    combinator::{map, opt},
+    error::{context, ParseError},
+    multi::separated_list0,
+    sequence::{pair, preceded},
+    Slice,
+};
+
+use crate::{
+    ast::tree::{
+        ArraySize, Attributes, Declarator, MemberDecl, MemberDeclarator, MemberItem, RecordKind,
+        RecordSpecifier,
+    },
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    assertions::parse_static_assert,
+    attributes::parse_attributes,
+    blocks,
+    declarators::parse_declarator,
+    error::{Expected, ParseResult, SyntaxError},
+    expr::parse_conditional_expression,
+    gnu, parse_ident, recovery,
+    specifiers::parse_specifier_qualifiers,
+    tags, Input,
+};
+
+/// Parses a `struct` or `union` specifier, starting at the keyword
+pub(super) fn parse_record_specifier<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, RecordSpecifier<'i>> {
+    let kind = match i.peek(0).and_then(|t| t.kind.as_keyword()) {
+        Some(Keyword::Struct) => RecordKind::Struct,
+        Some(Keyword::Union) => RecordKind::Union,
+        _ => {
+            return Err(nom::Err::Error(SyntaxError::expected(
+                i,
+                TokenKind::Keyword(Keyword::Struct),
+            )))
+        }
+    };
+    let i = i.slice(1..);
+
+    let (i, mut attributes) = parse_attributes(i)?;
+    let (i, tag) = opt(parse_ident)(i)?;
+    if i.peek(0).map(|t| t.kind) != Some(TokenKind::OpenBrace) {
+        return match tag {
+            Some(_) => Ok((
+                i,
+                RecordSpecifier {
+                    kind,
+                    attributes,
+                    tag,
+                    members: None,
+                },
+            )),
+            None => Err(nom::Err::Error(
+                SyntaxError::expected(i, Expected::Identifier)
+                    .or(SyntaxError::expected(i, TokenKind::OpenBrace)),
+            )),
+        };
+    }
+
+    let (rest, members) = blocks::block(
+        |i| {
+            alt((
+                map(parse_static_assert, |assert| Some((i, assert.into()))),
+                map(parse_member_decl, |member| Some((i, member.into()))),
+                map(gnu::extension(parse_member_decl), |member| {
+                    Some((i, member.into()))
+                }),
+            ))(i)
+        },
+        |i| (recovery::skip_statement(i), None),
+    )(i)?;
+    let members: Vec<(_, MemberItem)> = members.into_iter().flatten().collect();
+    // Static assertions declare no members
+    let declarations: Vec<_> = members
+        .iter()
+        .filter_map(|(at, member)| Some((*at, member.declaration()?)))
+        .collect();
+    check_flexible_arrays(kind, &declarations)?;
+    let (rest, Attributes(trailing)) = parse_attributes(rest)?;
+    attributes.0.extend(trailing);
+
+    Ok((
+        rest,
+        RecordSpecifier {
+            kind,
+            attributes,
+            tag,
+            members: Some(members.into_iter().map(|(_, member)| member).collect()),
+        },
+    ))
+}
+
+/// Parses a member declaration, as in `unsigned flag : 1, *p;`
+fn parse_member_decl<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, MemberDecl<'i>> {
+    let (i, ty) = parse_specifier_qualifiers(i)?;
+    let (i, declarators) = separated_list0(tags::comma, parse_member_declarator)(i)?;
+    let (i, _) = recovery::insert_semi_colon(context("after member", tags::semi_colon))(i)?;
+    Ok((i, MemberDecl { ty, declarators }))
+}
+
+fn parse_member_declarator<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, MemberDeclarator<'i>> {
+    let width = |i| {
+        preceded(
+            tags::colon,
+            recovery::expression(parse_conditional_expression),
+        )(i)
+    };
+
+    if i.peek(0).map(|t| t.kind) == Some(TokenKind::Colon) {
+        let (i, width) = width(i)?;
+        return Ok((
+            i,
+            MemberDeclarator {
+                declarator: Declarator::Abstract,
+                width: Some(width),
+            },
+        ));
+    }
+
+    map(pair(parse_declarator, opt(width)), |(declarator, width)| {
+        MemberDeclarator { declarator, width }
+    })(i)
+}
+
+/// A flexible array member, as in `char data[];`, may only be the last member of a
+/// struct with other named members
+fn check_flexible_arrays<'i>(
+    kind: RecordKind,
+    members: &[(Input<'i, '_>, &MemberDecl<'i>)],
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    let named_members = members
+        .iter()
+        .flat_map(|(_, member)| member.declarators.iter())
+        .filter(|d| d.declarator.name().is_some())
+        .count();
+
+    for (n, (at, member)) in members.iter().enumerate() {
+        for (m, declarator) in member.declarators.iter().enumerate() {
+            let Declarator::Array {
+                inner,
+                size: ArraySize::Unspecified,
+                ..
+            } = &declarator.declarator
+            else {
+                continue;
+            };
+            let Declarator::Ident(name) = **inner else {
+                continue;
+            };
+
+            let problem = if kind == RecordKind::Union {
+                "in a union"
+            } else if n + 1 != members.len() || m + 1 != member.declarators.len() {
+                "must be the last member"
+            } else if named_members == 1 {
+                "in a struct with no named members"
+            } else {
+                continue;
+            };
+            recovery::diagnose(
+                at,
+                SyntaxError::message(*at, format!("flexible array member '{name}' {problem}")),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parser::{parse, Options},
+            tree::{BaseType, Declarator, RecordKind, Statement},
+        },
+        lexer::token::Ident,
+    };
+
+    #[test]
+    fn test_self_reference() {
+        let (program, errors) = parse(
+            "struct node { int value; struct node *next; };",
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let [Statement::VarDecl(decl)] = program.as_slice() else {
+            panic!("Unexpected program {program:?}")
+        };
+        assert!(decl.declarators.is_empty());
+        let BaseType::Record(ref node) = decl.ty.ty else {
+            panic!("Expected a record, got {:?}", decl.ty.ty)
+        };
+        assert_eq!(node.kind, RecordKind::Struct);
+        assert_eq!(node.tag, Some(Ident::new("node")));
+        let members = node.members.as_ref().expect("Expected a definition");
+        let next = members[1].declaration().expect("Expected a member");
+        let BaseType::Record(ref record) = next.ty.ty else {
+            panic!("Expected a record, got {:?}", next.ty.ty)
+        };
+        assert!(!record.is_definition());
+        assert_eq!(
+            next.declarators[0].declarator,
+            Declarator::new_pointer(Ident::new("next").into())
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "struct node;\n",
+            "union { int i; float f; } u;\n",
+            "struct flags { unsigned int a : 1, b : 2; int : 0; } f;\n",
+            "struct outer { union { int i; float f; }; struct { int x; int y; }; } o;\n",
+            "typedef struct buffer { unsigned long len; char data[]; } buffer;\n",
+        ] {
+            let (program, errors) = parse(source, Options::default());
+            assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+            assert_eq!(program[0].to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_flexible_arrays() {
+        for (source, message) in [
+            (
+                "struct s { char data[]; int len; };",
+                "flexible array member 'data' must be the last member",
+            ),
+            (
+                "struct s { char data[]; };",
+                "flexible array member 'data' in a struct with no named members",
+            ),
+            (
+                "union u { int i; char data[]; };",
+                "flexible array member 'data' in a union",
+            ),
+        ] {
+            let (_, errors) = parse(source, Options::default());
+            assert_eq!(errors, [message]);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Error recovery.
+//!
+//! Recovery only happens when the [`Input`] has somewhere to report errors to,
+//! otherwise every helper here behaves as a plain parser that fails.
+
+use nom::{Parser, Slice};
+
+use crate::{
+    ast::tree::Expression,
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    error::{ParseResult, Severity, SyntaxError},
+    tags, Input,
+};
+
+/// Records an error the parser recovered from
+pub(super) fn report<'i>(i: &Input<'i, '_>, error: SyntaxError<'i>) {
+    if let Some(errors) = i.errors() {
+        errors.borrow_mut().push(error);
+    }
+}
+
+/// Reports an error that does not stop the parser, such as an invalid combination of
+/// specifiers. Without somewhere to report it to, it fails like any other error, unless
+/// it is a warning.
+pub(super) fn diagnose<'i, 't>(
+    i: &Input<'i, 't>,
+    error: SyntaxError<'i>,
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    if i.errors().is_some() {
+        report(i, error);
+        Ok(())
+    } else if error.severity() == Severity::Warning {
+        Ok(())
+    } else {
+        Err(nom::Err::Error(error))
+    }
+}
+
+/// Reports the use of a GNU extension, such as case ranges, under `-pedantic`. They are
+/// accepted silently otherwise, and always in GNU mode.
+pub(super) fn extension<'i>(
+    i: &Input<'i, '_>,
+    what: &str,
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    match i.pedantic() {
+        Some(severity) if !i.gnu_extensions() => diagnose(
+            i,
+            SyntaxError::message(*i, format!("{what} is a GNU extension")).with_severity(severity),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Reports the use of a feature of a later revision of C, such as `[[...]]` attributes
+/// before C23, under `-pedantic`. They are accepted silently otherwise.
+pub(super) fn later_standard<'i>(
+    i: &Input<'i, '_>,
+    message: &str,
+) -> Result<(), nom::Err<SyntaxError<'i>>> {
+    match i.pedantic() {
+        Some(severity) => diagnose(i, SyntaxError::message(*i, message).with_severity(severity)),
+        None => Ok(()),
+    }
+}
+
+/// Number of errors reported so far, used to discard those of an alternative that
+/// ended up failing
+pub(super) fn checkpoint(i: &Input<'_, '_>) -> usize {
+    i.errors().map_or(0, |errors| errors.borrow().len())
+}
+
+pub(super) fn rewind(i: &Input<'_, '_>, checkpoint: usize) {
+    if let Some(errors) = i.errors() {
+        errors.borrow_mut().truncate(checkpoint);
+    }
+}
+
+/// Runs `parser`, but if it fails and the next token is one of `followers`, reports
+/// the error and continues as if the missing token were there.
+pub(super) fn insert<'i: 't, 't, P, O>(
+    mut parser: P,
+    followers: &'static [TokenKind<'static>],
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Option<O>>
+where
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+{
+    move |i: Input<'i, 't>| match parser.parse(i) {
+        Ok((rest, o)) => Ok((rest, Some(o))),
+        Err(nom::Err::Error(e)) if i.errors().is_some() && is_followed_by(&i, followers) => {
+            report(&i, e);
+            Ok((i, None))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn is_followed_by<'i>(i: &Input<'i, '_>, followers: &[TokenKind<'i>]) -> bool {
+    i.peek(0).is_some_and(|t| followers.contains(&t.kind))
+}
+
+/// Like [`insert`], for a `;` ending a statement. The `;` is only inserted when
+/// nothing else can follow, that is, before a `}`, the end of the file, or a new line.
+pub(super) fn insert_semi_colon<'i: 't, 't, P, O>(
+    mut parser: P,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Option<O>>
+where
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+{
+    move |i: Input<'i, 't>| match parser.parse(i) {
+        Ok((rest, o)) => Ok((rest, Some(o))),
+        Err(nom::Err::Error(e))
+            if i.errors().is_some()
+                && (is_followed_by(&i, &[TokenKind::CloseBrace, TokenKind::Eof])
+                    || i.peek(0).is_some_and(|t| t.span.is_at_line_start())) =>
+        {
+            report(&i, e);
+            Ok((i, None))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Runs `parser`, replacing a broken expression with [`Expression::Error`].
+pub(super) fn expression<'i: 't, 't, P>(
+    mut parser: P,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, Expression<'i>>
+where
+    P: Parser<Input<'i, 't>, Expression<'i>, SyntaxError<'i>>,
+{
+    move |i: Input<'i, 't>| match parser.parse(i) {
+        Err(nom::Err::Error(e)) if i.errors().is_some() => {
+            report(&i, e);
+            Ok((skip_expression(i), Expression::Error(tags::spelling(&i))))
+        }
+        result => result,
+    }
+}
+
+/// Skips a broken expression, up to the `;`, `,` or closing bracket that ends it
+pub(super) fn skip_expression<'i, 't>(i: Input<'i, 't>) -> Input<'i, 't> {
+    let mut depth = 0usize;
+    for (n, t) in i.tokens.iter().enumerate() {
+        match t.kind {
+            TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenBrace => depth += 1,
+            TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::CloseBrace
+                if depth > 0 =>
+            {
+                depth -= 1
+            }
+            TokenKind::CloseParen
+            | TokenKind::CloseBracket
+            | TokenKind::CloseBrace
+            | TokenKind::SemiColon
+            | TokenKind::Comma
+            | TokenKind::Eof => return i.slice(n..),
+            _ => {}
+        }
+    }
+    i.slice(i.tokens.len()..)
+}
+
+/// Skips a broken statement, up to and including the next `;` or block, or up to the
+/// `}` that closes the enclosing block. Always skips at least one token, unless at
+/// the end of the file.
+pub(super) fn skip_statement<'i, 't>(i: Input<'i, 't>) -> Input<'i, 't> {
+    skip(i, |_| false)
+}
+
+/// Skips a broken declaration at file scope, like [`skip_statement`], stopping early
+/// at anything that can only start a new declaration.
+pub(super) fn skip_declaration<'i, 't>(i: Input<'i, 't>) -> Input<'i, 't> {
+    skip(i, |kind| {
+        matches!(
+            kind,
+            TokenKind::Keyword(
+                Keyword::Typedef
+                    | Keyword::Extern
+                    | Keyword::Static
+                    | Keyword::ThreadLocal
+                    | Keyword::Inline
+                    | Keyword::Noreturn
+            )
+        )
+    })
+}
+
+fn skip<'i, 't>(
+    i: Input<'i, 't>,
+    starts_declaration: impl Fn(TokenKind<'i>) -> bool,
+) -> Input<'i, 't> {
+    let mut depth = 0usize;
+    for (n, t) in i.tokens.iter().enumerate() {
+        match t.kind {
+            TokenKind::Eof => return i.slice(n..),
+            kind if n > 0 && depth == 0 && starts_declaration(kind) => return i.slice(n..),
+            TokenKind::SemiColon if depth == 0 => return i.slice(n + 1..),
+            TokenKind::OpenBrace => depth += 1,
+            // A stray `}` is skipped, otherwise it belongs to the enclosing block
+            TokenKind::CloseBrace if depth == 0 => return i.slice(n.max(1)..),
+            TokenKind::CloseBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return i.slice(n + 1..);
+                }
+            }
+            _ => {}
+        }
+    }
+    i.slice(i.tokens.len()..)
+}
@@ -0,0 +1,168 @@
+//! Scopes of ordinary identifiers, to tell typedef names apart from other identifiers.
+//!
+//! Whether `T * x;` declares `x` or multiplies `T` by `x` depends on what `T` names
+//! where it appears, so the parser keeps track of the typedef names declared so far,
+//! along with the variables, functions and enumerators hiding them in inner scopes.
+
+use nom::Parser;
+
+use crate::lexer::token::Ident;
+
+use super::{
+    error::{ParseResult, SyntaxError},
+    Input,
+};
+
+/// Ordinary identifiers declared in each enclosing scope, the file scope first
+#[derive(Debug)]
+pub(crate) struct Scopes<'i> {
+    /// Names declared in each scope, with whether they are typedef names
+    scopes: Vec<Vec<(Ident<'i>, bool)>>,
+}
+impl<'i> Default for Scopes<'i> {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Vec::new()],
+        }
+    }
+}
+impl<'i> Scopes<'i> {
+    /// Whether the innermost declaration of `name` in scope is a typedef
+    pub(crate) fn is_typedef_name(&self, name: Ident<'_>) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(declared, _)| declared.name == name.name)
+            .is_some_and(|&(_, is_typedef)| is_typedef)
+    }
+
+    /// Declares `name` in the innermost scope, hiding any outer declaration
+    pub(crate) fn declare(&mut self, name: Ident<'i>, is_typedef: bool) {
+        self.scopes
+            .last_mut()
+            .expect("the file scope is never left")
+            .push((name, is_typedef));
+    }
+
+    fn enter(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+}
+
+/// Whether `name` names a type where the stream is. Without scopes to look it up in,
+/// no identifier does.
+pub(super) fn is_typedef_name(i: &Input<'_, '_>, name: Ident<'_>) -> bool {
+    i.scopes()
+        .is_some_and(|scopes| scopes.borrow().is_typedef_name(name))
+}
+
+/// Declares `name` in the innermost scope of the stream
+pub(super) fn declare<'i>(i: &Input<'i, '_>, name: Ident<'i>, is_typedef: bool) {
+    if let Some(scopes) = i.scopes() {
+        scopes.borrow_mut().declare(name, is_typedef);
+    }
+}
+
+/// Runs `parser` in a new scope, which ends with it whether it succeeds or not
+pub(super) fn scoped<'i: 't, 't, P, O>(
+    mut parser: P,
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, O>
+where
+    P: Parser<Input<'i, 't>, O, SyntaxError<'i>>,
+{
+    move |i: Input<'i, 't>| {
+        let Some(scopes) = i.scopes() else {
+            return parser.parse(i);
+        };
+        scopes.borrow_mut().enter();
+        let result = parser.parse(i);
+        scopes.borrow_mut().leave();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        parser::{body, parse, Options},
+        tree::{Expression, Statement},
+    };
+
+    #[test]
+    fn test_typedef_names() {
+        let (program, errors) = parse(
+            "typedef int T, *P;
+            void f(int x) { T * a; x * a; (T)(x); (x)(a); P p = (P)0; }",
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let body = body(&program);
+        assert!(matches!(body[0], Statement::VarDecl(_)));
+        assert!(matches!(
+            body[1],
+            Statement::Expression(Expression::Binary { .. })
+        ));
+        assert!(matches!(
+            body[2],
+            Statement::Expression(Expression::Cast { .. })
+        ));
+        assert!(matches!(
+            body[3],
+            Statement::Expression(Expression::Call { .. })
+        ));
+        assert_eq!(body[4].to_string(), "P p = (P)0;\n");
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let (program, errors) = parse(
+            "typedef int T;
+            void f(void) {
+                T * a;
+                {
+                    int T = 2;
+                    T * a;
+                    { typedef char T; T * b; }
+                    T * a;
+                }
+                for (int T = 0; T < 2; T++) T * a;
+                T * c;
+            }
+            void g(int T) { T * a; }
+            void h(void) { enum { T }; T * a; }",
+            Options::default(),
+        );
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+        let is_declaration = |statement: &Statement| matches!(statement, Statement::VarDecl(_));
+
+        let [Statement::Typedef(_), Statement::FuncDecl(f), Statement::FuncDecl(g), Statement::FuncDecl(h)] =
+            program.as_slice()
+        else {
+            panic!("Unexpected program {program:?}")
+        };
+        let body = f.body.as_deref().unwrap_or_default();
+        assert!(is_declaration(&body[0]));
+        let Statement::Compound(ref inner) = body[1] else {
+            panic!("Expected a block, got {:?}", body[1])
+        };
+        let Statement::Compound(ref innermost) = inner.body[2] else {
+            panic!("Expected a block, got {:?}", inner.body[2])
+        };
+        assert!(!is_declaration(&inner.body[1]));
+        assert!(is_declaration(&innermost.body[1]));
+        assert!(!is_declaration(&inner.body[3]));
+        let Statement::For(ref r#for) = body[2] else {
+            panic!("Expected a loop, got {:?}", body[2])
+        };
+        assert!(!is_declaration(&r#for.body[0]));
+        assert!(is_declaration(&body[3]));
+
+        assert!(!is_declaration(&g.body.as_deref().unwrap_or_default()[0]));
+        assert!(!is_declaration(&h.body.as_deref().unwrap_or_default()[1]));
+    }
+}
@@ -0,0 +1,511 @@
+//! Declaration specifiers, the part of a declaration that comes before its
+//! declarators, as in `static const unsigned long`.
+
+use nom::Slice;
+
+use crate::{
+    ast::tree::{
+        AlignmentSpecifier, Attributes, BaseType, DeclSpecifiers, Signedness, StorageClass,
+        TypeQualifiers,
+    },
+    lexer::token::{Keyword, TokenKind},
+};
+
+use super::{
+    attributes, blocks, enums,
+    error::{ParseResult, SyntaxError},
+    expr::parse_conditional_expression,
+    gnu, parse_type_name, records, recovery, scopes, starts_type_name, tags, Input,
+};
+
+/// Type specifier keywords the given one may be combined with
+const fn combines_with(keyword: Keyword) -> &'static [Keyword] {
+    use Keyword::*;
+    match keyword {
+        Char => &[Signed, Unsigned],
+        Short => &[Int, Signed, Unsigned],
+        Int => &[Short, Long, Signed, Unsigned],
+        Long => &[Int, Long, Signed, Unsigned, Double, Complex],
+        Float => &[Complex],
+        Double => &[Long, Complex],
+        Signed | Unsigned => &[Char, Short, Int, Long],
+        Complex => &[Float, Double, Long],
+        _ => &[],
+    }
+}
+
+const fn storage_class(keyword: Keyword) -> Option<StorageClass> {
+    Some(match keyword {
+        Keyword::Typedef => StorageClass::Typedef,
+        Keyword::Extern => StorageClass::Extern,
+        Keyword::Static => StorageClass::Static,
+        Keyword::Auto => StorageClass::Auto,
+        Keyword::Register => StorageClass::Register,
+        _ => return None,
+    })
+}
+
+const fn is_type_specifier(keyword: Keyword) -> bool {
+    use Keyword::*;
+    matches!(
+        keyword,
+        Void | Char | Short | Int | Long | Float | Double | Signed | Unsigned | Bool | Complex
+    )
+}
+
+/// Type specifiers seen so far, validated one by one as they are added
+#[derive(Default)]
+struct TypeSpecifiers<'i> {
+    keywords: Vec<Keyword>,
+    /// A specifier that is not a keyword, such as a typedef name or a `struct`, and
+    /// how to call it in diagnostics
+    other: Option<(BaseType<'i>, String)>,
+}
+impl<'i> TypeSpecifiers<'i> {
+    fn is_empty(&self) -> bool {
+        self.keywords.is_empty() && self.other.is_none()
+    }
+
+    /// The last specifier added, to point at in diagnostics
+    fn previous(&self) -> Option<String> {
+        match self.other {
+            Some((_, ref label)) => Some(label.clone()),
+            None => self.keywords.last().map(ToString::to_string),
+        }
+    }
+
+    /// Adds a specifier that can not be combined with any other
+    fn add_other(&mut self, ty: BaseType<'i>, label: String) -> Result<(), String> {
+        if let Some(previous) = self.previous() {
+            return Err(format!(
+                "cannot combine '{label}' with previous '{previous}'"
+            ));
+        }
+        self.other = Some((ty, label));
+        Ok(())
+    }
+
+    fn has(&self, keyword: Keyword) -> bool {
+        self.keywords.contains(&keyword)
+    }
+
+    fn longs(&self) -> usize {
+        self.keywords
+            .iter()
+            .filter(|&&k| k == Keyword::Long)
+            .count()
+    }
+
+    /// Adds `keyword`, or explains why it can not be combined with the previous ones
+    fn add(&mut self, keyword: Keyword) -> Result<(), String> {
+        if let Some((_, ref label)) = self.other {
+            return Err(format!(
+                "cannot combine '{keyword}' with previous '{label}'"
+            ));
+        }
+        if keyword == Keyword::Long
+            && (self.longs() == 2 || (self.longs() == 1 && self.has(Keyword::Double)))
+        {
+            return Err(format!("'{}' is too long", self.with(keyword)));
+        }
+        if keyword == Keyword::Double && self.longs() == 2 {
+            return Err(format!("'{}' is too long", self.with(keyword)));
+        }
+        for &previous in self.keywords.iter() {
+            if previous == keyword && keyword != Keyword::Long {
+                return Err(format!("duplicate '{keyword}'"));
+            }
+            if !combines_with(previous).contains(&keyword) {
+                return Err(format!(
+                    "cannot combine '{keyword}' with previous '{previous}'"
+                ));
+            }
+        }
+        self.keywords.push(keyword);
+        Ok(())
+    }
+
+    fn with(&self, keyword: Keyword) -> String {
+        self.keywords
+            .iter()
+            .chain(Some(&keyword))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The type these specifiers add up to, or `None` if there were no type specifiers.
+    /// The error comes with the type assumed in its place.
+    fn resolve(self) -> Option<Result<BaseType<'i>, (&'static str, BaseType<'i>)>> {
+        use Keyword::*;
+
+        if let Some((ty, _)) = self.other {
+            return Some(Ok(ty));
+        }
+        if self.keywords.is_empty() {
+            return None;
+        }
+
+        let sign = if self.has(Unsigned) {
+            Signedness::Unsigned
+        } else {
+            Signedness::Signed
+        };
+        let complex = self.has(Complex);
+        let ty = if self.has(Void) {
+            BaseType::Void
+        } else if self.has(Bool) {
+            BaseType::Bool
+        } else if self.has(Char) {
+            let sign = (self.has(Signed) || self.has(Unsigned)).then_some(sign);
+            BaseType::Char(sign)
+        } else if self.has(Float) {
+            BaseType::Float { complex }
+        } else if self.has(Double) && self.longs() == 1 {
+            BaseType::LongDouble { complex }
+        } else if self.has(Double) {
+            BaseType::Double { complex }
+        } else if complex {
+            return Some(Err((
+                "'_Complex' requires 'float', 'double' or 'long double'",
+                BaseType::Double { complex },
+            )));
+        } else if self.has(Short) {
+            BaseType::Short(sign)
+        } else {
+            match self.longs() {
+                0 => BaseType::Int(sign),
+                1 => BaseType::Long(sign),
+                _ => BaseType::LongLong(sign),
+            }
+        };
+        Some(Ok(ty))
+    }
+}
+
+/// Parses the specifiers of a declaration. Fails if there are none at all.
+///
+/// Invalid combinations, such as `short long` or `signed float`, are reported without
+/// stopping the parser.
+pub(super) fn parse_decl_specifiers<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, DeclSpecifiers<'i>> {
+    specifiers(i, true)
+}
+
+/// Parses the specifiers and qualifiers of a type name, which can not have storage
+/// classes nor function specifiers
+pub(super) fn parse_specifier_qualifiers<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, DeclSpecifiers<'i>> {
+    specifiers(i, false)
+}
+
+fn specifiers<'i, 't>(
+    mut i: Input<'i, 't>,
+    is_declaration: bool,
+) -> ParseResult<'i, 't, DeclSpecifiers<'i>> {
+    let start = i;
+    let mut storage: Option<(StorageClass, Input<'i, 't>)> = None;
+    let mut thread_local = false;
+    let mut qualifiers = TypeQualifiers::default();
+    let mut is_inline = false;
+    let mut is_noreturn = false;
+    let mut alignment = Vec::new();
+    let mut attribute_lists = Vec::new();
+    let mut attribute_tokens = 0;
+    let mut types = TypeSpecifiers::default();
+
+    let checkpoint = recovery::checkpoint(&i);
+    while let Some(token) = i.peek(0) {
+        if attributes::starts_attribute(i) {
+            let (rest, Attributes(lists)) = attributes::parse_attributes(i)?;
+            attribute_lists.extend(lists);
+            attribute_tokens += rest.position() - i.position();
+            i = rest;
+            continue;
+        }
+
+        let keyword = match token.kind {
+            TokenKind::Keyword(keyword) => keyword,
+            // After another type specifier, as in `unsigned T`, the name is declared
+            TokenKind::Ident(name) if types.is_empty() && scopes::is_typedef_name(&i, name) => {
+                types.other = Some((BaseType::TypedefName(name), name.to_string()));
+                i = i.slice(1..);
+                continue;
+            }
+            _ => break,
+        };
+
+        if let Some(class) = storage_class(keyword).filter(|_| is_declaration) {
+            match storage {
+                Some((previous, _)) if previous == class => {
+                    recovery::diagnose(&i, SyntaxError::message(i, format!("duplicate '{class}'")))?
+                }
+                Some((previous, _)) => recovery::diagnose(
+                    &i,
+                    SyntaxError::message(
+                        i,
+                        format!("cannot combine '{class}' with previous '{previous}'"),
+                    ),
+                )?,
+                None => storage = Some((class, i)),
+            }
+            i = i.slice(1..);
+            continue;
+        }
+
+        match keyword {
+            Keyword::ThreadLocal | Keyword::Inline | Keyword::Noreturn if !is_declaration => break,
+            Keyword::ThreadLocal => thread_local = true,
+            Keyword::Const => qualifiers.is_const = true,
+            Keyword::Volatile => qualifiers.is_volatile = true,
+            Keyword::Restrict => qualifiers.is_restrict = true,
+            // Followed by a parenthesis, it is a type specifier rather than a qualifier
+            Keyword::Atomic if i.peek(1).is_some_and(|t| t.kind == TokenKind::OpenParen) => {
+                let (rest, ty) = blocks::parens(parse_type_name)(i.slice(1..))?;
+                let ty = BaseType::Atomic(tags::spelling(&i), Box::new(ty));
+                if let Err(message) = types.add_other(ty, "_Atomic".to_string()) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+                i = rest;
+                continue;
+            }
+            Keyword::Atomic => qualifiers.is_atomic = true,
+            Keyword::Inline => is_inline = true,
+            Keyword::Noreturn => is_noreturn = true,
+            Keyword::Struct | Keyword::Union => {
+                let (rest, record) = records::parse_record_specifier(i)?;
+                let label = match record.tag {
+                    Some(tag) => format!("{} {tag}", record.kind),
+                    None => record.kind.to_string(),
+                };
+                if let Err(message) = types.add_other(BaseType::Record(record), label) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+                i = rest;
+                continue;
+            }
+            Keyword::Enum => {
+                let (rest, r#enum) = enums::parse_enum_specifier(i)?;
+                let label = match r#enum.tag {
+                    Some(tag) => format!("enum {tag}"),
+                    None => "enum".to_string(),
+                };
+                if let Err(message) = types.add_other(BaseType::Enum(r#enum), label) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+                i = rest;
+                continue;
+            }
+            Keyword::Typeof => {
+                let (rest, operand) = gnu::parse_typeof(i)?;
+                let ty = BaseType::Typeof(Box::new(operand));
+                if let Err(message) = types.add_other(ty, "typeof".to_string()) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+                i = rest;
+                continue;
+            }
+            Keyword::AutoType if is_declaration => {
+                let label = "__auto_type".to_string();
+                if let Err(message) = types.add_other(BaseType::AutoType, label) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+            }
+            Keyword::Alignas => {
+                let (rest, specifier) = parse_alignment_specifier(i.slice(1..))?;
+                alignment.push(specifier);
+                i = rest;
+                continue;
+            }
+            keyword if is_type_specifier(keyword) => {
+                if let Err(message) = types.add(keyword) {
+                    recovery::diagnose(&i, SyntaxError::message(i, message))?;
+                }
+            }
+            _ => break,
+        }
+        i = i.slice(1..);
+    }
+
+    // Attributes alone start an attributed statement rather than a declaration
+    if i.position() - start.position() == attribute_tokens {
+        recovery::rewind(&i, checkpoint);
+        return Err(nom::Err::Error(SyntaxError::new(i)));
+    }
+
+    if thread_local {
+        if let Some((class, at)) = storage {
+            if !matches!(class, StorageClass::Static | StorageClass::Extern) {
+                recovery::diagnose(
+                    &at,
+                    SyntaxError::message(
+                        at,
+                        format!("'_Thread_local' cannot be combined with '{class}'"),
+                    ),
+                )?;
+            }
+        }
+    }
+
+    let ty = match types.resolve() {
+        Some(Ok(ty)) => ty,
+        Some(Err((message, ty))) => {
+            recovery::diagnose(&start, SyntaxError::message(start, message))?;
+            ty
+        }
+        None => {
+            recovery::diagnose(
+                &i,
+                SyntaxError::message(i, "type specifier missing, defaults to 'int'"),
+            )?;
+            BaseType::Int(Signedness::Signed)
+        }
+    };
+
+    Ok((
+        i,
+        DeclSpecifiers {
+            storage: storage.map(|(class, _)| class),
+            thread_local,
+            qualifiers,
+            is_inline,
+            is_noreturn,
+            alignment,
+            attributes: Attributes(attribute_lists),
+            ty,
+        },
+    ))
+}
+
+/// Parses the qualifiers of a pointer or array declarator, which may be none
+pub(super) fn parse_type_qualifiers<'i, 't>(
+    mut i: Input<'i, 't>,
+) -> ParseResult<'i, 't, TypeQualifiers> {
+    let mut qualifiers = TypeQualifiers::default();
+    while let Some(keyword) = i.peek(0).and_then(|t| t.kind.as_keyword()) {
+        match keyword {
+            Keyword::Const => qualifiers.is_const = true,
+            Keyword::Volatile => qualifiers.is_volatile = true,
+            Keyword::Restrict => qualifiers.is_restrict = true,
+            Keyword::Atomic => qualifiers.is_atomic = true,
+            _ => break,
+        }
+        i = i.slice(1..);
+    }
+    Ok((i, qualifiers))
+}
+
+/// Parses the operand of `_Alignas`, after the keyword
+fn parse_alignment_specifier<'i, 't>(
+    i: Input<'i, 't>,
+) -> ParseResult<'i, 't, AlignmentSpecifier<'i>> {
+    if starts_type_name(i.slice(1..)) {
+        let (rest, ty) = blocks::parens(parse_type_name)(i)?;
+        Ok((rest, AlignmentSpecifier::Type(ty)))
+    } else {
+        let (rest, expr) = blocks::parens(recovery::expression(parse_conditional_expression))(i)?;
+        Ok((rest, AlignmentSpecifier::Expression(expr)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{
+        ast::{
+            parser::{Options, Scopes, State},
+            tree::{BaseType, DeclSpecifiers, Signedness, StorageClass},
+        },
+        lexer::{stream::TokenStream, token::Ident},
+        preprocessor::preprocess,
+    };
+
+    use super::parse_decl_specifiers;
+
+    fn parse(source: &str) -> Result<DeclSpecifiers<'_>, String> {
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(source)).collect();
+        let scopes = RefCell::new(Scopes::default());
+        scopes.borrow_mut().declare(Ident::new("uint32_t"), true);
+        let stream = TokenStream::new(&tokens).with(State::new(Options::default()));
+        parse_decl_specifiers(stream.with_scopes(&scopes))
+            .map(|(_, specifiers)| specifiers)
+            .map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => e.to_string(),
+                nom::Err::Incomplete(_) => unreachable!(),
+            })
+    }
+
+    #[test]
+    fn test_base_types() {
+        use Signedness::*;
+        for (source, ty) in [
+            ("int", BaseType::Int(Signed)),
+            ("unsigned", BaseType::Int(Unsigned)),
+            ("char", BaseType::Char(None)),
+            ("char signed", BaseType::Char(Some(Signed))),
+            ("short int unsigned", BaseType::Short(Unsigned)),
+            ("long unsigned long int", BaseType::LongLong(Unsigned)),
+            ("long double", BaseType::LongDouble { complex: false }),
+            ("double _Complex", BaseType::Double { complex: true }),
+            ("_Bool", BaseType::Bool),
+            ("uint32_t x", BaseType::TypedefName(Ident::new("uint32_t"))),
+        ] {
+            assert_eq!(parse(source).map(|s| s.ty), Ok(ty), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_specifiers() {
+        let specifiers = parse("static const _Thread_local inline volatile long").unwrap();
+        assert_eq!(specifiers.storage, Some(StorageClass::Static));
+        assert!(specifiers.thread_local && specifiers.is_inline);
+        assert!(specifiers.qualifiers.is_const && specifiers.qualifiers.is_volatile);
+        assert_eq!(specifiers.ty, BaseType::Long(Signedness::Signed));
+        assert_eq!(
+            specifiers.to_string(),
+            "static _Thread_local inline const volatile long"
+        );
+
+        let specifiers = parse("_Alignas(16) _Alignas(double) char").unwrap();
+        assert_eq!(specifiers.to_string(), "_Alignas(16) _Alignas(double) char");
+
+        let specifiers = parse("const _Atomic(unsigned long) x").unwrap();
+        assert!(matches!(specifiers.ty, BaseType::Atomic(..)));
+        assert_eq!(specifiers.to_string(), "const _Atomic(unsigned long)");
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        for (source, message) in [
+            ("short long", "cannot combine 'long' with previous 'short'"),
+            (
+                "signed float",
+                "cannot combine 'float' with previous 'signed'",
+            ),
+            ("int int", "duplicate 'int'"),
+            ("long long long", "'long long long' is too long"),
+            (
+                "static extern int",
+                "cannot combine 'extern' with previous 'static'",
+            ),
+            (
+                "_Thread_local auto int",
+                "'_Thread_local' cannot be combined with 'auto'",
+            ),
+            (
+                "_Complex",
+                "'_Complex' requires 'float', 'double' or 'long double'",
+            ),
+            ("const", "type specifier missing, defaults to 'int'"),
+            (
+                "_Atomic(int) long",
+                "cannot combine 'long' with previous '_Atomic'",
+            ),
+        ] {
+            assert_eq!(parse(source), Err(message.to_string()), "{source}");
+        }
+    }
+}
@@ -1,14 +1,22 @@
-use nom::{bytes::complete::take, combinator::verify, IResult};
+use crate::lexer::token::{Keyword, TokenKind};
 
-use crate::lexer::{
-    stream::TokenStream,
-    token::{Keyword, TokenKind},
+use super::{
+    error::{ParseResult, SyntaxError},
+    Input,
 };
 
+/// Takes one token if it is of kind `kind`
+fn token<'i, 't>(kind: TokenKind<'i>, i: Input<'i, 't>) -> ParseResult<'i, 't, Input<'i, 't>> {
+    match i.peek(0) {
+        Some(t) if t.kind == kind => Ok(nom::InputTake::take_split(&i, 1)),
+        _ => Err(nom::Err::Error(SyntaxError::expected(i, kind))),
+    }
+}
+
 macro_rules! def_tag {
     ($v:vis $name:ident => $value:expr) => {
-        $v fn $name<'i, 't>(i: TokenStream<'i, 't>) -> IResult<TokenStream<'i, 't>, TokenStream<'i, 't>> {
-            verify(take(1usize), |t: &TokenStream| t[0] == $value)(i)
+        $v fn $name<'i, 't>(i: Input<'i, 't>) -> ParseResult<'i, 't, Input<'i, 't>> {
+            token($value, i)
         }
     };
 }
@@ -17,32 +25,27 @@ def_tag!(pub(crate) open_paren => TokenKind::OpenParen);
 def_tag!(pub(crate) close_paren => TokenKind::CloseParen);
 def_tag!(pub(crate) open_brace => TokenKind::OpenBrace);
 def_tag!(pub(crate) close_brace => TokenKind::CloseBrace);
-def_tag!(pub(crate) equals => TokenKind::Equals);
 def_tag!(pub(crate) assign => TokenKind::Assign);
 def_tag!(pub(crate) semi_colon => TokenKind::SemiColon);
 def_tag!(pub(crate) comma => TokenKind::Comma);
 def_tag!(pub(crate) star => TokenKind::Star);
+def_tag!(pub(crate) open_bracket => TokenKind::OpenBracket);
+def_tag!(pub(crate) close_bracket => TokenKind::CloseBracket);
+def_tag!(pub(crate) question => TokenKind::Question);
+def_tag!(pub(crate) colon => TokenKind::Colon);
+def_tag!(pub(crate) ellipsis => TokenKind::Ellipsis);
 
-pub(crate) fn eof<'i, 't>(
-    input: TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, TokenStream<'i, 't>> {
-    verify(take(1usize), |t: &TokenStream| t[0] == TokenKind::Eof)(input)
+/// The spelling of the next token, where what starts there is reported
+pub(crate) fn spelling<'i>(i: &Input<'i, '_>) -> &'i str {
+    i.peek(0).map_or("", |t| t.span.get())
+}
+
+pub(crate) fn eof<'i, 't>(input: Input<'i, 't>) -> ParseResult<'i, 't, Input<'i, 't>> {
+    token(TokenKind::Eof, input)
 }
 
 pub(crate) fn keyword<'i: 't, 't>(
     keyword: Keyword,
-) -> impl FnMut(
-    TokenStream<'i, 't>,
-) -> IResult<TokenStream<'i, 't>, (), nom::error::Error<TokenStream<'i, 't>>> {
-    move |i: TokenStream<'i, 't>| {
-        let orig = i;
-        let (i, t) = take(1usize)(i)?;
-        match t[0].kind.as_keyword() {
-            Some(&k) if k == keyword => Ok((i, ())),
-            _ => Err(nom::Err::Error(nom::error::Error::new(
-                orig,
-                nom::error::ErrorKind::Tag,
-            ))),
-        }
-    }
+) -> impl FnMut(Input<'i, 't>) -> ParseResult<'i, 't, ()> {
+    move |i: Input<'i, 't>| token(TokenKind::Keyword(keyword), i).map(|(i, _)| (i, ()))
 }
@@ -2,62 +2,152 @@ use std::fmt::Display;
 
 use crate::lexer::token::Ident;
 
+mod asm;
+mod attributes;
 pub mod control;
+mod declarator;
+mod enums;
 mod expr;
-pub use expr::Expression;
+mod initializer;
+mod records;
+mod specifiers;
+pub use asm::{Asm, AsmOperand, AsmQualifiers};
+pub use attributes::{Attribute, AttributeList, AttributeSyntax, Attributes, KnownAttribute};
+pub use declarator::{ArraySize, Declarator, Parameters};
+pub use enums::{EnumSpecifier, Enumerator};
+pub(crate) use expr::{is_same_type, PRECEDENCE_ASSIGN, PRECEDENCE_CONDITIONAL};
+pub use expr::{
+    AssignOp, BinaryOp, Expression, GenericAssociation, GenericSelection, PostfixOp,
+    StringLiteral, UnaryOp,
+};
+pub use initializer::{DesignatedInitializer, Designator, Initializer};
+pub use records::{MemberDecl, MemberDeclarator, MemberItem, RecordKind, RecordSpecifier};
+pub use specifiers::{
+    AlignmentSpecifier, BaseType, DeclSpecifiers, Signedness, StorageClass, TypeQualifiers,
+    TypeofSpecifier,
+};
 
-#[derive(Debug, PartialEq, Eq, derive_more::From, derive_more::Display)]
-pub enum Declarator<'i> {
-    Ident(Ident<'i>),
-
-    #[display(fmt = "*{}", _0)]
-    Pointer(Box<Declarator<'i>>),
-    /*
-    #[display(fmt = "{}[{}]", _0, _1)]
-    Array(Box<Declarator<'i>>, usize),
-
-    Function(Box<Declarator<'i>>, Vec<Ident<'i>>),
-    */
+/// A type written on its own, as in casts and `sizeof`
+#[derive(Debug, PartialEq, Eq)]
+pub struct TypeName<'i> {
+    /// Never has a storage class nor function specifiers
+    pub specifiers: DeclSpecifiers<'i>,
+    /// Always abstract
+    pub declarator: Declarator<'i>,
+}
+impl<'i> Display for TypeName<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.specifiers, self.declarator)
+    }
 }
 
+/// A function declaration, or its definition when it has a body
 #[derive(Debug, PartialEq, Eq)]
 pub struct FuncDecl<'i> {
-    pub ret: Ident<'i>,
-    pub name: Ident<'i>,
-    pub args: Vec<(Ident<'i>, Declarator<'i>)>,
-    pub body: Vec<Statement<'i>>,
+    pub ret: DeclSpecifiers<'i>,
+    /// Declares the name as a function, as in `*f(int x)` for a function returning a pointer
+    pub declarator: Declarator<'i>,
+    /// Parameter declarations of a K&R definition, as in `int f(a) int a; { ... }`
+    pub declarations: Vec<VarDecl<'i>>,
+    /// `None` for prototypes, as in `int f(void);`
+    pub body: Option<Vec<Statement<'i>>>,
+}
+impl<'i> FuncDecl<'i> {
+    #[must_use]
+    pub fn name(&self) -> Ident<'i> {
+        self.declarator
+            .name()
+            .expect("function declarations are always named")
+    }
+
+    #[must_use]
+    pub fn params(&self) -> &Parameters<'i> {
+        self.declarator
+            .function_params()
+            .expect("function declarations always declare a function")
+    }
+
+    #[must_use]
+    pub fn is_definition(&self) -> bool {
+        self.body.is_some()
+    }
 }
 impl<'i> Display for FuncDecl<'i> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}(", self.ret, self.name)?;
-        for (ty, name) in self.args.iter() {
-            write!(f, "{ty} {name}, ", ty = ty, name = name)?;
+        write!(f, "{} {}", self.ret, self.declarator)?;
+        let Some(ref body) = self.body else {
+            return writeln!(f, ";");
+        };
+        if self.declarations.is_empty() {
+            write!(f, " ")?;
+        } else {
+            writeln!(f)?;
+        }
+        for decl in self.declarations.iter() {
+            write!(f, "{decl}")?;
         }
-        writeln!(f, ") {{")?;
-        for stmt in self.body.iter() {
+        writeln!(f, "{{")?;
+        for stmt in body.iter() {
             writeln!(f, "{stmt} ")?;
         }
         writeln!(f, "}}")
     }
 }
 
+/// One of the declarators of a declaration, with its initializer
+#[derive(Debug, PartialEq, Eq)]
+pub struct InitDeclarator<'i> {
+    pub declarator: Declarator<'i>,
+    pub value: Option<Initializer<'i>>,
+}
+impl<'i> Display for InitDeclarator<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.declarator)?;
+        if let Some(ref value) = self.value {
+            write!(f, " = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `items` separated by commas
+fn write_list<T: Display>(f: &mut std::fmt::Formatter<'_>, items: &[T]) -> std::fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct VarDecl<'i> {
-    pub ty: Ident<'i>,
-    pub name: Declarator<'i>,
-    pub value: Option<Expression<'i>>,
+    pub ty: DeclSpecifiers<'i>,
+    /// Every declarator shares the specifiers, as in `int x, *p;`
+    pub declarators: Vec<InitDeclarator<'i>>,
 }
 impl<'i> VarDecl<'i> {
-    pub fn new<D>(ty: Ident<'i>, name: D, value: Option<Expression<'i>>) -> Self
-    where D: Into<Declarator<'i>> {
-        Self { ty, name: name.into(), value }
+    /// Declaration of a single variable
+    pub fn new<D>(ty: DeclSpecifiers<'i>, name: D, value: Option<Initializer<'i>>) -> Self
+    where
+        D: Into<Declarator<'i>>,
+    {
+        Self {
+            ty,
+            declarators: vec![InitDeclarator {
+                declarator: name.into(),
+                value,
+            }],
+        }
     }
 }
 impl<'i> Display for VarDecl<'i> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{ty} {name}", ty = self.ty, name = self.name)?;
-        if let Some(ref value) = self.value {
-            write!(f, " = {value}")?;
+        write!(f, "{}", self.ty)?;
+        if !self.declarators.is_empty() {
+            write!(f, " ")?;
+            write_list(f, &self.declarators)?;
         }
         writeln!(f, ";")?;
         Ok(())
@@ -66,30 +156,39 @@ impl<'i> Display for VarDecl<'i> {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Typedef<'i> {
-    pub ty: Vec<Ident<'i>>,
-    pub name: Ident<'i>,
+    /// Includes the `typedef` storage class
+    pub ty: DeclSpecifiers<'i>,
+    pub names: Vec<Declarator<'i>>,
 }
 impl<'i> Typedef<'i> {
-    pub fn new(ty: Vec<Ident<'i>>, name: Ident<'i>) -> Self {
-        Self { ty, name }
+    pub fn new(ty: DeclSpecifiers<'i>, names: Vec<Declarator<'i>>) -> Self {
+        Self { ty, names }
     }
 }
 impl<'i> Display for Typedef<'i> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "typedef ")?;
-        for ty in self.ty.iter() {
-            write!(f, "{ty} ")?;
-        }
-        writeln!(f, "{};", self.name)?;
+        write!(f, "{} ", self.ty)?;
+        write_list(f, &self.names)?;
+        writeln!(f, ";")?;
         Ok(())
     }
 }
 
-#[derive(Debug, PartialEq, Eq, derive_more::Display, derive_more::From)]
-#[display(fmt = "{lhs} = {rhs};")]
-pub struct Assignment<'i> {
-    lhs: Ident<'i>,
-    rhs: Expression<'i>,
+/// `_Static_assert(condition, "message");`, checked at compile time
+#[derive(Debug, PartialEq, Eq)]
+pub struct StaticAssert<'i> {
+    pub condition: Expression<'i>,
+    /// String literal, including its quotes. Optional since C23.
+    pub message: Option<&'i str>,
+}
+impl<'i> Display for StaticAssert<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "_Static_assert({}", self.condition)?;
+        if let Some(message) = self.message {
+            write!(f, ", {message}")?;
+        }
+        writeln!(f, ");")
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, derive_more::Display, derive_more::From)]
@@ -97,32 +196,66 @@ pub enum Statement<'i> {
     FuncDecl(FuncDecl<'i>),
     VarDecl(VarDecl<'i>),
     Typedef(Typedef<'i>),
+    StaticAssert(StaticAssert<'i>),
     If(control::If<'i>),
-    Assign(Assignment<'i>),
+    While(control::While<'i>),
+    DoWhile(control::DoWhile<'i>),
+    For(Box<control::For<'i>>),
+    #[display(fmt = "break;")]
+    Break,
+    #[display(fmt = "continue;")]
+    Continue,
+    Switch(control::Switch<'i>),
+    Labeled(control::Labeled<'i>),
+    #[from(ignore)]
+    #[display(fmt = "goto {};", _0)]
+    Goto(Ident<'i>),
+    /// GNU `goto *address;`
+    #[from(ignore)]
+    #[display(fmt = "goto *{};", _0)]
+    ComputedGoto(Expression<'i>),
+    Return(control::Return<'i>),
+    /// `{ ... }` nested in another block, with its own scope
+    Compound(control::Compound<'i>),
+    /// An expression evaluated for its side effects, as in `f(x);`
+    #[display(fmt = "{};", _0)]
+    Expression(Expression<'i>),
+    /// `;` on its own
+    #[display(fmt = ";")]
+    Null,
+    /// GNU inline assembly, as in `asm volatile("nop");`
+    Asm(Box<Asm<'i>>),
+    /// A statement preceded by attributes, as in `[[fallthrough]];`
+    #[from(ignore)]
+    #[display(fmt = "{} {}", _0, _1)]
+    Attributed(Attributes<'i>, Box<Statement<'i>>),
+
+    /// Placeholder for a statement that could not be parsed
+    #[display(fmt = "<error>;")]
+    Error,
 }
 impl<'i> Statement<'i> {
+    /// Definition of a function
     pub const fn new_func_decl(
-        ret: Ident<'i>,
-        name: Ident<'i>,
-        args: Vec<(Ident<'i>, Declarator<'i>)>,
+        ret: DeclSpecifiers<'i>,
+        declarator: Declarator<'i>,
         body: Vec<Statement<'i>>,
     ) -> Self {
         Self::FuncDecl(FuncDecl {
             ret,
-            name,
-            args,
-            body,
+            declarator,
+            declarations: Vec::new(),
+            body: Some(body),
         })
     }
     pub const fn new_var_decl(
-        ty: Ident<'i>,
-        name: Declarator<'i>,
-        value: Option<Expression<'i>>,
+        ty: DeclSpecifiers<'i>,
+        declarators: Vec<InitDeclarator<'i>>,
     ) -> Self {
-        Self::VarDecl(VarDecl { ty, name, value })
+        Self::VarDecl(VarDecl { ty, declarators })
     }
-    pub const fn new_typedef(ty: Vec<Ident<'i>>, name: Ident<'i>) -> Self {
-        Self::Typedef(Typedef { ty, name })
+    pub const fn new_typedef(ty: DeclSpecifiers<'i>, names: Vec<Declarator<'i>>) -> Self {
+        Self::Typedef(Typedef { ty, names })
     }
 
     #[must_use]
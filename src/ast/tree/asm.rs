@@ -0,0 +1,251 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::{write_list, Expression};
+
+/// Qualifiers written after `asm`, in any order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsmQualifiers {
+    /// The statement has side effects besides its outputs, and is never removed
+    pub is_volatile: bool,
+    /// Counted as the smallest possible size when deciding whether to inline
+    pub is_inline: bool,
+    /// The statement may jump to one of its labels
+    pub is_goto: bool,
+}
+impl Display for AsmQualifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let words = [
+            (self.is_volatile, "volatile"),
+            (self.is_inline, "inline"),
+            (self.is_goto, "goto"),
+        ];
+        for (_, word) in words.iter().filter(|(set, _)| *set) {
+            write!(f, " {word}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An output or input operand, as in `[sum] "=r"(x)`
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsmOperand<'i> {
+    /// Name the template refers to the operand by, as in `%[sum]`
+    pub name: Option<Ident<'i>>,
+    /// String literal, including its quotes, such as `"=r"`
+    pub constraint: &'i str,
+    /// An lvalue for outputs
+    pub expr: Expression<'i>,
+}
+impl<'i> Display for AsmOperand<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name {
+            write!(f, "[{name}] ")?;
+        }
+        write!(f, "{}({})", self.constraint, self.expr)
+    }
+}
+
+/// A GNU inline assembly statement, as in `asm volatile("nop")` or
+/// `asm("add %1, %0" : "+r"(x) : "r"(y) : "cc")`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Asm<'i> {
+    pub qualifiers: AsmQualifiers,
+    /// String literals, including their quotes, whose contents are concatenated
+    pub template: Vec<&'i str>,
+    /// Basic asm has no operands, and its template is used as is, without substitution.
+    /// Extended asm has at least one `:`, even if all its sections are empty.
+    pub is_basic: bool,
+    pub outputs: Vec<AsmOperand<'i>>,
+    pub inputs: Vec<AsmOperand<'i>>,
+    /// String literals, including their quotes, such as `"memory"`
+    pub clobbers: Vec<&'i str>,
+    /// Labels of `asm goto`, numbered after the operands
+    pub labels: Vec<Ident<'i>>,
+}
+impl<'i> Asm<'i> {
+    /// The contents of the template, its string literals concatenated, escape sequences
+    /// included
+    #[must_use]
+    pub fn template(&self) -> String {
+        self.template.iter().map(|s| unquote(s)).collect()
+    }
+
+    /// The operand named `name`, numbered as in the template: outputs first, then inputs
+    fn find_operand(&self, name: &str) -> Option<usize> {
+        self.outputs
+            .iter()
+            .chain(self.inputs.iter())
+            .position(|operand| operand.name.is_some_and(|n| n.name == name))
+    }
+
+    /// The text handed to the assembler, with escape sequences decoded and operands
+    /// replaced by what `operand` gives for them.
+    ///
+    /// `operand` is given the number of an operand, outputs first, then inputs, then
+    /// labels, and the modifier letter it was written with, as in `%k0` or `%l[done]`.
+    /// `%%` stands for `%` and `%=` for `unique`, a number unique to this statement in
+    /// the compilation unit. The error explains which `%` is not valid.
+    pub fn substitute(
+        &self,
+        unique: usize,
+        mut operand: impl FnMut(usize, Option<char>) -> String,
+    ) -> Result<String, String> {
+        let template = decode(&self.template());
+        if self.is_basic {
+            return Ok(template);
+        }
+
+        let operands = self.outputs.len() + self.inputs.len();
+        let count = operands + self.labels.len();
+        let mut text = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                text.push(c);
+                continue;
+            }
+            match chars.peek().copied() {
+                Some('%') => {
+                    chars.next();
+                    text.push('%');
+                    continue;
+                }
+                Some('=') => {
+                    chars.next();
+                    text.push_str(&unique.to_string());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let modifier = chars.next_if(char::is_ascii_alphabetic);
+            let number = if chars.next_if_eq(&'[').is_some() {
+                let name: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let label = self.labels.iter().position(|label| label.name == name);
+                match (modifier, label) {
+                    (Some('l'), Some(label)) => operands + label,
+                    _ => self
+                        .find_operand(&name)
+                        .ok_or_else(|| format!("undefined named operand '{name}'"))?,
+                }
+            } else {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(digit);
+                }
+                match digits.parse::<usize>() {
+                    Ok(number) => number,
+                    Err(_) if modifier.is_some() => {
+                        return Err("operand number missing after %-letter".to_string())
+                    }
+                    Err(_) => return Err("invalid %-code".to_string()),
+                }
+            };
+            if number >= count {
+                return Err("operand number out of range".to_string());
+            }
+            text.push_str(&operand(number, modifier));
+        }
+        Ok(text)
+    }
+}
+impl<'i> Display for Asm<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "asm{}(", self.qualifiers)?;
+        for (i, piece) in self.template.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{piece}")?;
+        }
+        if !self.is_basic {
+            // Sections are written up to the last one that is not empty
+            let sections = [
+                !self.outputs.is_empty(),
+                !self.inputs.is_empty(),
+                !self.clobbers.is_empty(),
+                !self.labels.is_empty(),
+            ];
+            let last = sections.iter().rposition(|&set| set).unwrap_or(0);
+            write!(f, " :")?;
+            if !self.outputs.is_empty() {
+                write!(f, " ")?;
+                write_list(f, &self.outputs)?;
+            }
+            if last >= 1 {
+                write!(f, " :")?;
+                if !self.inputs.is_empty() {
+                    write!(f, " ")?;
+                    write_list(f, &self.inputs)?;
+                }
+            }
+            if last >= 2 {
+                write!(f, " :")?;
+                if !self.clobbers.is_empty() {
+                    write!(f, " ")?;
+                    write_list(f, &self.clobbers)?;
+                }
+            }
+            if last >= 3 {
+                write!(f, " : ")?;
+                write_list(f, &self.labels)?;
+            }
+        }
+        write!(f, ");")
+    }
+}
+
+/// The contents of a string literal, without its quotes
+fn unquote(literal: &str) -> &str {
+    literal
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(literal)
+}
+
+/// Decodes the escape sequences of the contents of a string literal
+fn decode(contents: &str) -> String {
+    let mut decoded = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        let Some(escaped) = chars.next() else {
+            break;
+        };
+        match escaped {
+            'n' => decoded.push('\n'),
+            't' => decoded.push('\t'),
+            'r' => decoded.push('\r'),
+            'a' => decoded.push('\x07'),
+            'b' => decoded.push('\x08'),
+            'f' => decoded.push('\x0c'),
+            'v' => decoded.push('\x0b'),
+            '0'..='7' => {
+                let mut value = escaped.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    match chars.next_if(|c| c.is_digit(8)) {
+                        Some(digit) => value = value * 8 + digit.to_digit(8).unwrap_or_default(),
+                        None => break,
+                    }
+                }
+                decoded.extend(char::from_u32(value));
+            }
+            'x' => {
+                let mut value: u32 = 0;
+                while let Some(digit) = chars.next_if(char::is_ascii_hexdigit) {
+                    value = value
+                        .saturating_mul(16)
+                        .saturating_add(digit.to_digit(16).unwrap_or_default());
+                }
+                decoded.extend(char::from_u32(value));
+            }
+            other => decoded.push(other),
+        }
+    }
+    decoded
+}
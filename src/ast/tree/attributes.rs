@@ -0,0 +1,164 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::Expression;
+
+/// Attributes the compiler acts on. Others are kept in the tree but ignored, as
+/// `[[maybe_unused]]` is since no unused declaration is warned about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum KnownAttribute {
+    /// GNU `packed`, members of the record are not padded
+    #[display(fmt = "packed")]
+    Packed,
+    /// GNU `aligned(n)`, or `aligned` for the largest alignment of the target
+    #[display(fmt = "aligned")]
+    Aligned,
+    /// `[[noreturn]]` or GNU `noreturn`
+    #[display(fmt = "noreturn")]
+    Noreturn,
+    /// `[[deprecated]]` or GNU `deprecated`, with an optional message
+    #[display(fmt = "deprecated")]
+    Deprecated,
+    /// `[[nodiscard]]` or GNU `warn_unused_result`, with an optional message
+    #[display(fmt = "nodiscard")]
+    Nodiscard,
+    /// `[[fallthrough]]` or GNU `fallthrough`, only allowed on an empty statement
+    #[display(fmt = "fallthrough")]
+    Fallthrough,
+}
+impl KnownAttribute {
+    /// Whether the attribute may be given arguments, as in `aligned(8)`
+    #[must_use]
+    pub const fn takes_arguments(self) -> bool {
+        matches!(self, Self::Aligned | Self::Deprecated | Self::Nodiscard)
+    }
+}
+
+/// A single attribute, as in `gnu::aligned(8)` or `format(printf, 1, 2)`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Attribute<'i> {
+    /// The vendor of a standard attribute, as in `gnu::packed`
+    pub prefix: Option<Ident<'i>>,
+    pub name: Ident<'i>,
+    /// `None` without parenthesis, which is not the same as empty ones
+    pub args: Option<Vec<Expression<'i>>>,
+}
+impl<'i> Attribute<'i> {
+    /// The name without the underscores of GNU spellings such as `__packed__`
+    #[must_use]
+    pub fn plain_name(&self) -> &'i str {
+        let name = self.name.name;
+        name.strip_prefix("__")
+            .and_then(|name| name.strip_suffix("__"))
+            .unwrap_or(name)
+    }
+
+    /// The attribute this is, if the compiler knows it. GNU spellings such as
+    /// `__packed__` are the same as the plain ones.
+    #[must_use]
+    pub fn kind(&self) -> Option<KnownAttribute> {
+        let name = self.plain_name();
+        let is_gnu = match self.prefix {
+            None => true,
+            Some(prefix) => matches!(prefix.name, "gnu" | "__gnu__"),
+        };
+        Some(match name {
+            "packed" if is_gnu => KnownAttribute::Packed,
+            "aligned" if is_gnu => KnownAttribute::Aligned,
+            "noreturn" | "_Noreturn" => KnownAttribute::Noreturn,
+            "deprecated" => KnownAttribute::Deprecated,
+            "warn_unused_result" if is_gnu => KnownAttribute::Nodiscard,
+            "nodiscard" if self.prefix.is_none() => KnownAttribute::Nodiscard,
+            "fallthrough" => KnownAttribute::Fallthrough,
+            _ => return None,
+        })
+    }
+}
+impl<'i> Display for Attribute<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(prefix) = self.prefix {
+            write!(f, "{prefix}::")?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(ref args) = self.args {
+            write!(f, "(")?;
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{arg}")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// How a list of attributes is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeSyntax {
+    /// C23 `[[a, b]]`
+    Standard,
+    /// GNU `__attribute__((a, b))`
+    Gnu,
+}
+
+/// One `[[...]]` or `__attribute__((...))`
+#[derive(Debug, PartialEq, Eq)]
+pub struct AttributeList<'i> {
+    pub syntax: AttributeSyntax,
+    pub attributes: Vec<Attribute<'i>>,
+}
+impl<'i> Display for AttributeList<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (open, close) = match self.syntax {
+            AttributeSyntax::Standard => ("[[", "]]"),
+            AttributeSyntax::Gnu => ("__attribute__((", "))"),
+        };
+        write!(f, "{open}")?;
+        for (i, attribute) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{attribute}")?;
+        }
+        write!(f, "{close}")
+    }
+}
+
+/// The attribute lists written in one position, in order. Usually empty.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Attributes<'i>(pub Vec<AttributeList<'i>>);
+impl<'i> Attributes<'i> {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Attribute<'i>> {
+        self.0.iter().flat_map(|list| list.attributes.iter())
+    }
+
+    /// The last attribute of the given kind, which overrides the previous ones
+    #[must_use]
+    pub fn find(&self, kind: KnownAttribute) -> Option<&Attribute<'i>> {
+        self.iter().filter(|a| a.kind() == Some(kind)).last()
+    }
+
+    #[must_use]
+    pub fn has(&self, kind: KnownAttribute) -> bool {
+        self.find(kind).is_some()
+    }
+}
+impl<'i> Display for Attributes<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, list) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{list}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::Display;
 
-use super::{Expression, Statement};
+use crate::lexer::token::Ident;
+
+use super::{Expression, Statement, VarDecl};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct If<'i> {
@@ -26,3 +28,146 @@ impl<'i> Display for If<'i> {
         Ok(())
     }
 }
+
+/// Writes `{ body }`
+fn write_body(f: &mut std::fmt::Formatter<'_>, body: &[Statement<'_>]) -> std::fmt::Result {
+    write!(f, "{{")?;
+    for stmt in body.iter() {
+        write!(f, "{stmt}")?;
+    }
+    write!(f, "}}")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct While<'i> {
+    pub condition: Expression<'i>,
+    pub body: Vec<Statement<'i>>,
+}
+impl<'i> Display for While<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "while({}) ", self.condition)?;
+        write_body(f, &self.body)?;
+        writeln!(f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DoWhile<'i> {
+    pub body: Vec<Statement<'i>>,
+    pub condition: Expression<'i>,
+}
+impl<'i> Display for DoWhile<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "do ")?;
+        write_body(f, &self.body)?;
+        writeln!(f, " while({});", self.condition)
+    }
+}
+
+/// First clause of a `for` loop
+#[derive(Debug, PartialEq, Eq)]
+pub enum ForInit<'i> {
+    /// Variables scoped to the loop, as in `for (int i = 0; ...)`
+    Declaration(VarDecl<'i>),
+    Expression(Expression<'i>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct For<'i> {
+    pub init: Option<ForInit<'i>>,
+    /// A missing condition is always true
+    pub condition: Option<Expression<'i>>,
+    pub step: Option<Expression<'i>>,
+    pub body: Vec<Statement<'i>>,
+}
+impl<'i> Display for For<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "for(")?;
+        match self.init {
+            // Declarations are displayed as statements, `;` and new line included
+            Some(ForInit::Declaration(ref decl)) => write!(f, "{}", decl.to_string().trim_end())?,
+            Some(ForInit::Expression(ref expr)) => write!(f, "{expr};")?,
+            None => write!(f, ";")?,
+        }
+        if let Some(ref condition) = self.condition {
+            write!(f, " {condition}")?;
+        }
+        write!(f, ";")?;
+        if let Some(ref step) = self.step {
+            write!(f, " {step}")?;
+        }
+        write!(f, ") ")?;
+        write_body(f, &self.body)?;
+        writeln!(f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Switch<'i> {
+    pub condition: Expression<'i>,
+    pub body: Vec<Statement<'i>>,
+}
+impl<'i> Display for Switch<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "switch({}) ", self.condition)?;
+        write_body(f, &self.body)?;
+        writeln!(f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Label<'i> {
+    /// Target of a `goto`
+    Named(Ident<'i>),
+    /// `case value:`, or the GNU range `case value ... end:`
+    Case {
+        value: Expression<'i>,
+        end: Option<Box<Expression<'i>>>,
+    },
+    Default,
+}
+impl<'i> Display for Label<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, "{name}"),
+            Self::Case { value, end: None } => write!(f, "case {value}"),
+            Self::Case {
+                value,
+                end: Some(end),
+            } => write!(f, "case {value} ... {end}"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, derive_more::Display)]
+#[display(fmt = "{label}: {body}")]
+pub struct Labeled<'i> {
+    pub label: Label<'i>,
+    pub body: Box<Statement<'i>>,
+}
+
+/// `return;` or `return value;`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Return<'i> {
+    pub value: Option<Expression<'i>>,
+}
+impl<'i> Display for Return<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Some(ref value) => write!(f, "return {value};"),
+            None => write!(f, "return;"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Compound<'i> {
+    pub body: Vec<Statement<'i>>,
+}
+impl<'i> Display for Compound<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_body(f, &self.body)?;
+        writeln!(f)
+    }
+}
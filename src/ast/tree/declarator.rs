@@ -0,0 +1,308 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::{Attributes, DeclSpecifiers, Expression, TypeQualifiers};
+
+/// Size of an array declarator
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArraySize<'i> {
+    /// `[]`
+    Unspecified,
+    /// `[*]`, a variable length array of unspecified size, only allowed in prototypes
+    Variable,
+    Expression(Box<Expression<'i>>),
+}
+impl<'i> Display for ArraySize<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unspecified => Ok(()),
+            Self::Variable => write!(f, "*"),
+            Self::Expression(expr) => write!(f, "{expr}"),
+        }
+    }
+}
+
+/// Parameters of a function declarator
+#[derive(Debug, PartialEq, Eq)]
+pub enum Parameters<'i> {
+    /// `()`, which declares no parameters since C23 and unknown ones before
+    Unspecified,
+    /// `(void)` or a list of parameter declarations, which may be unnamed
+    Prototype {
+        params: Vec<(DeclSpecifiers<'i>, Declarator<'i>)>,
+        /// Ends with `...`
+        is_variadic: bool,
+    },
+    /// `(a, b)`, the identifier list of a K&R definition
+    Identifiers(Vec<Ident<'i>>),
+}
+impl<'i> Parameters<'i> {
+    /// The parameter declarations, empty unless this is a prototype
+    #[must_use]
+    pub fn declarations(&self) -> &[(DeclSpecifiers<'i>, Declarator<'i>)] {
+        match self {
+            Self::Prototype { params, .. } => params,
+            Self::Unspecified | Self::Identifiers(_) => &[],
+        }
+    }
+}
+impl<'i> Display for Parameters<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unspecified => write!(f, "()"),
+            Self::Prototype {
+                params,
+                is_variadic: false,
+            } if params.is_empty() => write!(f, "(void)"),
+            Self::Prototype {
+                params,
+                is_variadic,
+            } => {
+                write!(f, "(")?;
+                for (i, (ty, declarator)) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if declarator.name().is_some() {
+                        write!(f, "{ty} {declarator}")?;
+                    } else {
+                        write!(f, "{ty}{declarator}")?;
+                    }
+                }
+                match (is_variadic, params.is_empty()) {
+                    (true, true) => write!(f, "...")?,
+                    (true, false) => write!(f, ", ...")?,
+                    (false, _) => {}
+                }
+                write!(f, ")")
+            }
+            Self::Identifiers(names) => {
+                write!(f, "(")?;
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// The part of a declaration that names something and wraps its type in pointers,
+/// arrays and functions.
+///
+/// Declarators nest inside out: in `*p[3]` the array is the innermost one, since `p`
+/// is an array of pointers.
+#[derive(Debug, PartialEq, Eq, derive_more::From)]
+pub enum Declarator<'i> {
+    Ident(Ident<'i>),
+
+    /// The missing name of an abstract declarator, as in `(int *)` or an unnamed parameter
+    #[from(ignore)]
+    Abstract,
+
+    #[from(ignore)]
+    Pointer {
+        qualifiers: TypeQualifiers,
+        inner: Box<Declarator<'i>>,
+    },
+    /// `inner[size]`. Only parameters may have qualifiers or `static`, as in `[static const 4]`
+    #[from(ignore)]
+    Array {
+        inner: Box<Declarator<'i>>,
+        size: ArraySize<'i>,
+        qualifiers: TypeQualifiers,
+        is_static: bool,
+    },
+    #[from(ignore)]
+    Function {
+        inner: Box<Declarator<'i>>,
+        params: Parameters<'i>,
+    },
+    /// Attributes appertaining to what `inner` declares: the entity after its name, as
+    /// in `x [[maybe_unused]]`, or the type after a `*`, `]` or `)`
+    #[from(ignore)]
+    Attributed {
+        inner: Box<Declarator<'i>>,
+        attributes: Attributes<'i>,
+    },
+}
+impl<'i> Declarator<'i> {
+    pub fn new_pointer(inner: Declarator<'i>) -> Self {
+        Self::Pointer {
+            qualifiers: TypeQualifiers::default(),
+            inner: Box::new(inner),
+        }
+    }
+
+    /// The declared name, `None` for abstract declarators
+    #[must_use]
+    pub fn name(&self) -> Option<Ident<'i>> {
+        match self {
+            Self::Ident(ident) => Some(*ident),
+            Self::Abstract => None,
+            Self::Pointer { inner, .. }
+            | Self::Array { inner, .. }
+            | Self::Function { inner, .. }
+            | Self::Attributed { inner, .. } => inner.name(),
+        }
+    }
+
+    /// Parameters of the function the name is declared as, if it is declared as one
+    #[must_use]
+    pub fn function_params(&self) -> Option<&Parameters<'i>> {
+        match self {
+            Self::Function { inner, params } if inner.is_name() => Some(params),
+            Self::Ident(_) | Self::Abstract => None,
+            Self::Pointer { inner, .. }
+            | Self::Array { inner, .. }
+            | Self::Function { inner, .. }
+            | Self::Attributed { inner, .. } => inner.function_params(),
+        }
+    }
+
+    /// Whether this is only a name, or no name at all, with attributes or not
+    pub(crate) fn is_name(&self) -> bool {
+        match self {
+            Self::Ident(_) | Self::Abstract => true,
+            Self::Attributed { inner, .. } => inner.is_name(),
+            Self::Pointer { .. } | Self::Array { .. } | Self::Function { .. } => false,
+        }
+    }
+
+    /// The attributes of the declared entity and of its derived types, outermost first
+    pub fn attributes(&self) -> impl Iterator<Item = &Attributes<'i>> {
+        let mut next = Some(self);
+        std::iter::from_fn(move || loop {
+            let (inner, attributes) = match next? {
+                Self::Ident(_) | Self::Abstract => (None, None),
+                Self::Pointer { inner, .. }
+                | Self::Array { inner, .. }
+                | Self::Function { inner, .. } => (Some(&**inner), None),
+                Self::Attributed { inner, attributes } => (Some(&**inner), Some(attributes)),
+            };
+            next = inner;
+            if attributes.is_some() || next.is_none() {
+                return attributes;
+            }
+        })
+    }
+
+    /// Adjusts the declarator of a parameter: a parameter declared as an array is a
+    /// pointer, qualified by the qualifiers in the brackets, and one declared as a
+    /// function is a pointer to that function
+    #[must_use]
+    pub fn adjust_parameter(self) -> Self {
+        match self {
+            Self::Array {
+                inner, qualifiers, ..
+            } if inner.is_name() => Self::Pointer { qualifiers, inner },
+            Self::Function { inner, params } if inner.is_name() => Self::Function {
+                inner: Box::new(Self::new_pointer(*inner)),
+                params,
+            },
+            Self::Pointer { qualifiers, inner } => Self::Pointer {
+                qualifiers,
+                inner: Box::new(inner.adjust_parameter()),
+            },
+            Self::Array {
+                inner,
+                size,
+                qualifiers,
+                is_static,
+            } => Self::Array {
+                inner: Box::new(inner.adjust_parameter()),
+                size,
+                qualifiers,
+                is_static,
+            },
+            Self::Function { inner, params } => Self::Function {
+                inner: Box::new(inner.adjust_parameter()),
+                params,
+            },
+            Self::Attributed { inner, attributes } => Self::Attributed {
+                inner: Box::new(inner.adjust_parameter()),
+                attributes,
+            },
+            Self::Ident(_) | Self::Abstract => self,
+        }
+    }
+}
+
+/// Displays a declarator as the operand of an array or function declarator, in
+/// parenthesis if it is a pointer
+struct Operand<'d, 'i>(&'d Declarator<'i>);
+impl Display for Operand<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Declarator::Pointer { .. } | Declarator::Attributed { .. } = self.0 {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// Writes `*`, followed by its attributes and qualifiers, and then by `inner`
+fn write_pointer(
+    f: &mut std::fmt::Formatter<'_>,
+    attributes: Option<&Attributes<'_>>,
+    qualifiers: TypeQualifiers,
+    inner: &Declarator<'_>,
+) -> std::fmt::Result {
+    write!(f, "*")?;
+    let mut words = Vec::new();
+    if let Some(attributes) = attributes {
+        words.push(attributes.to_string());
+    }
+    if !qualifiers.is_empty() {
+        words.push(qualifiers.to_string());
+    }
+    if !words.is_empty() {
+        write!(f, "{}", words.join(" "))?;
+        if *inner != Declarator::Abstract {
+            write!(f, " ")?;
+        }
+    }
+    write!(f, "{inner}")
+}
+
+impl<'i> Display for Declarator<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(ident) => write!(f, "{ident}"),
+            Self::Abstract => Ok(()),
+            Self::Pointer { qualifiers, inner } => write_pointer(f, None, *qualifiers, inner),
+            Self::Array {
+                inner,
+                size,
+                qualifiers,
+                is_static,
+            } => {
+                let mut words = Vec::new();
+                if *is_static {
+                    words.push("static".to_string());
+                }
+                if !qualifiers.is_empty() {
+                    words.push(qualifiers.to_string());
+                }
+                if *size != ArraySize::Unspecified {
+                    words.push(size.to_string());
+                }
+                write!(f, "{}[{}]", Operand(inner), words.join(" "))
+            }
+            Self::Function { inner, params } => write!(f, "{}{params}", Operand(inner)),
+            Self::Attributed { inner, attributes } => match **inner {
+                Self::Abstract => write!(f, " {attributes}"),
+                // Attributes of a pointer come right after its `*`
+                Self::Pointer {
+                    qualifiers,
+                    ref inner,
+                } => write_pointer(f, Some(attributes), qualifiers, inner),
+                _ => write!(f, "{inner} {attributes}"),
+            },
+        }
+    }
+}
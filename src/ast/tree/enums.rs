@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::{Attributes, DeclSpecifiers, Expression};
+
+/// An `enum` type specifier
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnumSpecifier<'i> {
+    /// Attributes after the keyword, and GNU ones after the enumerators
+    pub attributes: Attributes<'i>,
+    pub tag: Option<Ident<'i>>,
+    /// C23 fixed underlying type, as in `enum E : unsigned char`
+    pub underlying: Option<Box<DeclSpecifiers<'i>>>,
+    /// `None` when the enum is not defined here, as in `enum color c;`
+    pub enumerators: Option<Vec<Enumerator<'i>>>,
+}
+impl<'i> EnumSpecifier<'i> {
+    #[must_use]
+    pub fn is_definition(&self) -> bool {
+        self.enumerators.is_some()
+    }
+}
+impl<'i> Display for EnumSpecifier<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "enum")?;
+        if !self.attributes.is_empty() {
+            write!(f, " {}", self.attributes)?;
+        }
+        if let Some(tag) = self.tag {
+            write!(f, " {tag}")?;
+        }
+        if let Some(ref underlying) = self.underlying {
+            write!(f, " : {underlying}")?;
+        }
+        if let Some(ref enumerators) = self.enumerators {
+            write!(f, " {{ ")?;
+            for (i, enumerator) in enumerators.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{enumerator}")?;
+            }
+            write!(f, " }}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Enumerator<'i> {
+    pub name: Ident<'i>,
+    /// Attributes after the name, as in `OLD [[deprecated]] = 1`
+    pub attributes: Attributes<'i>,
+    pub value: Option<Expression<'i>>,
+}
+impl<'i> Display for Enumerator<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.attributes.is_empty() {
+            write!(f, " {}", self.attributes)?;
+        }
+        if let Some(ref value) = self.value {
+            write!(f, " = {value}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,445 @@
+use std::fmt::Display;
+
 use crate::lexer::token::{Ident, Literal};
 
-#[derive(Debug, PartialEq, Eq, derive_more::Display, derive_more::From)]
+use super::{
+    initializer::{self, DesignatedInitializer},
+    Designator, Statement, TypeName,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum UnaryOp {
+    #[display(fmt = "&")]
+    AddressOf,
+    #[display(fmt = "*")]
+    Deref,
+    #[display(fmt = "+")]
+    Plus,
+    #[display(fmt = "-")]
+    Minus,
+    #[display(fmt = "~")]
+    BitNot,
+    #[display(fmt = "!")]
+    Not,
+    #[display(fmt = "++")]
+    PreIncrement,
+    #[display(fmt = "--")]
+    PreDecrement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum PostfixOp {
+    #[display(fmt = "++")]
+    Increment,
+    #[display(fmt = "--")]
+    Decrement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum BinaryOp {
+    #[display(fmt = "*")]
+    Mul,
+    #[display(fmt = "/")]
+    Div,
+    #[display(fmt = "%")]
+    Mod,
+    #[display(fmt = "+")]
+    Add,
+    #[display(fmt = "-")]
+    Sub,
+    #[display(fmt = "<<")]
+    Shl,
+    #[display(fmt = ">>")]
+    Shr,
+    #[display(fmt = "<")]
+    Less,
+    #[display(fmt = ">")]
+    Greater,
+    #[display(fmt = "<=")]
+    LessEquals,
+    #[display(fmt = ">=")]
+    GreaterEquals,
+    #[display(fmt = "==")]
+    Equals,
+    #[display(fmt = "!=")]
+    NotEquals,
+    #[display(fmt = "&")]
+    BitAnd,
+    #[display(fmt = "^")]
+    BitXor,
+    #[display(fmt = "|")]
+    BitOr,
+    #[display(fmt = "&&")]
+    And,
+    #[display(fmt = "||")]
+    Or,
+    #[display(fmt = ",")]
+    Comma,
+}
+impl BinaryOp {
+    /// Binding power, higher binds tighter
+    #[must_use]
+    pub const fn precedence(&self) -> u8 {
+        match self {
+            Self::Comma => 1,
+            Self::Or => 4,
+            Self::And => 5,
+            Self::BitOr => 6,
+            Self::BitXor => 7,
+            Self::BitAnd => 8,
+            Self::Equals | Self::NotEquals => 9,
+            Self::Less | Self::Greater | Self::LessEquals | Self::GreaterEquals => 10,
+            Self::Shl | Self::Shr => 11,
+            Self::Add | Self::Sub => 12,
+            Self::Mul | Self::Div | Self::Mod => 13,
+        }
+    }
+}
+
+/// `=` or a compound assignment. `None` is plain assignment, otherwise `lhs op= rhs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssignOp(pub Option<BinaryOp>);
+impl Display for AssignOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(op) => write!(f, "{op}="),
+            None => write!(f, "="),
+        }
+    }
+}
+
+pub(crate) const PRECEDENCE_ASSIGN: u8 = 2;
+pub(crate) const PRECEDENCE_CONDITIONAL: u8 = 3;
+pub(crate) const PRECEDENCE_UNARY: u8 = 14;
+pub(crate) const PRECEDENCE_POSTFIX: u8 = 15;
+
+#[derive(Debug, PartialEq, Eq, derive_more::From)]
 pub enum Expression<'i> {
-    #[display(fmt = "{lhs} == {rhs}")]
-    Equals {
+    /// A constant other than a string literal
+    Literal(Literal<'i>),
+    #[from(ignore)]
+    String(StringLiteral<'i>),
+    Ident(Ident<'i>),
+
+    #[from(ignore)]
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expression<'i>>,
+    },
+    #[from(ignore)]
+    Postfix {
+        op: PostfixOp,
+        operand: Box<Expression<'i>>,
+    },
+    #[from(ignore)]
+    Binary {
+        op: BinaryOp,
         lhs: Box<Expression<'i>>,
         rhs: Box<Expression<'i>>,
     },
-    Literal(Literal),
-    Ident(Ident<'i>),
+    #[from(ignore)]
+    Assign {
+        op: AssignOp,
+        lhs: Box<Expression<'i>>,
+        rhs: Box<Expression<'i>>,
+    },
+    #[from(ignore)]
+    Conditional {
+        condition: Box<Expression<'i>>,
+        then: Box<Expression<'i>>,
+        r#else: Box<Expression<'i>>,
+    },
+    #[from(ignore)]
+    Cast {
+        ty: TypeName<'i>,
+        expr: Box<Expression<'i>>,
+    },
+    /// `(type){ ... }`
+    #[from(ignore)]
+    CompoundLiteral {
+        /// The opening parenthesis, where problems with the literal are reported
+        paren: &'i str,
+        ty: Box<TypeName<'i>>,
+        init: Vec<DesignatedInitializer<'i>>,
+    },
+    #[from(ignore)]
+    SizeofExpr(Box<Expression<'i>>),
+    /// `sizeof(type)`, with the keyword as written
+    #[from(ignore)]
+    SizeofType(&'i str, TypeName<'i>),
+    /// `_Alignof(type)`, with the keyword as written, which may be `alignof`
+    #[from(ignore)]
+    AlignofType(&'i str, TypeName<'i>),
+    /// `_Generic(controlling, type: value, default: value)`
+    #[from(ignore)]
+    Generic(GenericSelection<'i>),
+    #[from(ignore)]
+    Call {
+        callee: Box<Expression<'i>>,
+        args: Vec<Expression<'i>>,
+    },
+    #[from(ignore)]
+    Index {
+        array: Box<Expression<'i>>,
+        index: Box<Expression<'i>>,
+    },
+    /// `object.member`, or `object->member` when `arrow` is set
+    #[from(ignore)]
+    Member {
+        object: Box<Expression<'i>>,
+        member: Ident<'i>,
+        arrow: bool,
+    },
+    /// GNU address of a label, as in `&&label`
+    #[from(ignore)]
+    LabelAddress(Ident<'i>),
+    /// GNU statement expression, as in `({ int y = x; y * y; })`, whose value is that of
+    /// its last statement if it is an expression. Its opening parenthesis comes first.
+    #[from(ignore)]
+    Statement(&'i str, Vec<Statement<'i>>),
+    /// `__builtin_offsetof(type, member)`, where the member may be nested, as in
+    /// `a.b[2]`
+    #[from(ignore)]
+    Offsetof {
+        ty: Box<TypeName<'i>>,
+        member: Ident<'i>,
+        designators: Vec<Designator<'i>>,
+    },
+    /// `__builtin_types_compatible_p(a, b)`, 1 if both types are the same regardless
+    /// of their top level qualifiers. The keyword comes first.
+    #[from(ignore)]
+    TypesCompatible(&'i str, Box<TypeName<'i>>, Box<TypeName<'i>>),
+
+    /// Placeholder for an expression that could not be parsed, with the spelling of
+    /// the token it starts at
+    #[from(ignore)]
+    Error(&'i str),
 }
 impl<'i> Expression<'i> {
     pub fn new_equals(expr: (Expression<'i>, Expression<'i>)) -> Self {
-        Self::Equals {
-            lhs: Box::new(expr.0),
-            rhs: Box::new(expr.1),
+        Self::new_binary(BinaryOp::Equals, expr.0, expr.1)
+    }
+
+    pub fn new_binary(op: BinaryOp, lhs: Expression<'i>, rhs: Expression<'i>) -> Self {
+        Self::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn new_unary(op: UnaryOp, operand: Expression<'i>) -> Self {
+        Self::Unary {
+            op,
+            operand: Box::new(operand),
+        }
+    }
+
+    pub fn new_assign(op: AssignOp, lhs: Expression<'i>, rhs: Expression<'i>) -> Self {
+        Self::Assign {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    /// Binding power of the outermost operator, used to know where parenthesis are needed
+    #[must_use]
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary { op, .. } => op.precedence(),
+            Self::Assign { .. } => PRECEDENCE_ASSIGN,
+            Self::Conditional { .. } => PRECEDENCE_CONDITIONAL,
+            Self::Unary { .. }
+            | Self::Cast { .. }
+            | Self::SizeofExpr(_)
+            | Self::SizeofType(..)
+            | Self::AlignofType(..)
+            | Self::LabelAddress(_) => PRECEDENCE_UNARY,
+            Self::Postfix { .. }
+            | Self::Call { .. }
+            | Self::Index { .. }
+            | Self::Member { .. }
+            | Self::CompoundLiteral { .. } => PRECEDENCE_POSTFIX,
+            Self::Literal(_)
+            | Self::String(_)
+            | Self::Ident(_)
+            | Self::Generic(_)
+            | Self::Statement(..)
+            | Self::Offsetof { .. }
+            | Self::TypesCompatible(..)
+            | Self::Error(_) => u8::MAX,
+        }
+    }
+}
+
+/// Displays `expr`, wrapped in parenthesis if it binds looser than `precedence`
+struct Operand<'e, 'i>(&'e Expression<'i>, u8);
+impl Display for Operand<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.precedence() < self.1 {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl<'i> Display for Expression<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prec = self.precedence();
+        match self {
+            Self::Literal(literal) => write!(f, "{literal}"),
+            Self::String(string) => write!(f, "{string}"),
+            Self::Ident(ident) => write!(f, "{ident}"),
+            Self::Unary { op, operand } => {
+                // Keep `- -x` from turning into `--x`
+                let sep = match **operand {
+                    Self::Unary { op: inner, .. }
+                        if op
+                            .to_string()
+                            .chars()
+                            .all(|c| inner.to_string().starts_with(c)) =>
+                    {
+                        " "
+                    }
+                    _ => "",
+                };
+                write!(f, "{op}{sep}{}", Operand(operand, prec))
+            }
+            Self::Postfix { op, operand } => write!(f, "{}{op}", Operand(operand, prec)),
+            Self::Binary {
+                op: BinaryOp::Comma,
+                lhs,
+                rhs,
+            } => write!(f, "{}, {}", Operand(lhs, prec), Operand(rhs, prec + 1)),
+            Self::Binary { op, lhs, rhs } => {
+                write!(f, "{} {op} {}", Operand(lhs, prec), Operand(rhs, prec + 1))
+            }
+            // The left hand side is a unary expression in the grammar
+            Self::Assign { op, lhs, rhs } => write!(
+                f,
+                "{} {op} {}",
+                Operand(lhs, PRECEDENCE_UNARY),
+                Operand(rhs, prec)
+            ),
+            Self::Conditional {
+                condition,
+                then,
+                r#else,
+            } => write!(
+                f,
+                "{} ? {then} : {}",
+                Operand(condition, prec + 1),
+                Operand(r#else, prec)
+            ),
+            Self::Cast { ty, expr } => write!(f, "({ty}){}", Operand(expr, prec)),
+            Self::CompoundLiteral { ty, init, .. } => {
+                write!(f, "({ty})")?;
+                initializer::write_list(f, init)
+            }
+            Self::SizeofExpr(expr) => write!(f, "sizeof {}", Operand(expr, prec)),
+            Self::SizeofType(_, ty) => write!(f, "sizeof({ty})"),
+            Self::AlignofType(_, ty) => write!(f, "_Alignof({ty})"),
+            Self::Generic(generic) => write!(f, "{generic}"),
+            Self::Call { callee, args } => {
+                write!(f, "{}(", Operand(callee, prec))?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", Operand(arg, PRECEDENCE_ASSIGN))?;
+                }
+                write!(f, ")")
+            }
+            Self::Index { array, index } => write!(f, "{}[{index}]", Operand(array, prec)),
+            Self::Member {
+                object,
+                member,
+                arrow,
+            } => {
+                let op = if *arrow { "->" } else { "." };
+                write!(f, "{}{op}{member}", Operand(object, prec))
+            }
+            Self::LabelAddress(label) => write!(f, "&&{label}"),
+            Self::Statement(_, body) => {
+                write!(f, "({{")?;
+                for stmt in body.iter() {
+                    write!(f, "{stmt}")?;
+                }
+                write!(f, "}})")
+            }
+            Self::Offsetof {
+                ty,
+                member,
+                designators,
+            } => {
+                write!(f, "__builtin_offsetof({ty}, {member}")?;
+                for designator in designators.iter() {
+                    write!(f, "{designator}")?;
+                }
+                write!(f, ")")
+            }
+            Self::TypesCompatible(_, a, b) => {
+                write!(f, "__builtin_types_compatible_p({a}, {b})")
+            }
+            Self::Error(_) => write!(f, "<error>"),
+        }
+    }
+}
+
+/// String literals next to each other, which make a single one, as in `"a" "b"`
+#[derive(Debug, PartialEq, Eq)]
+pub struct StringLiteral<'i> {
+    /// Each literal, including its prefix and quotes
+    pub pieces: Vec<&'i str>,
+}
+impl<'i> Display for StringLiteral<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pieces.join(" "))
+    }
+}
+
+/// Operands of `_Generic`, which evaluates to the association matching the type of
+/// the controlling expression
+#[derive(Debug, PartialEq, Eq)]
+pub struct GenericSelection<'i> {
+    /// Only its type matters, it is not evaluated
+    pub controlling: Box<Expression<'i>>,
+    pub associations: Vec<GenericAssociation<'i>>,
+}
+impl<'i> Display for GenericSelection<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "_Generic({}",
+            Operand(&self.controlling, PRECEDENCE_ASSIGN)
+        )?;
+        for association in self.associations.iter() {
+            write!(f, ", {association}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Whether two type names name the same type, regardless of how their specifiers were
+/// spelled and of their top level qualifiers
+pub(crate) fn is_same_type(a: &TypeName<'_>, b: &TypeName<'_>) -> bool {
+    a.specifiers.ty == b.specifiers.ty && a.declarator == b.declarator
+}
+
+/// `type: value`, or `default: value` when `ty` is `None`
+#[derive(Debug, PartialEq, Eq)]
+pub struct GenericAssociation<'i> {
+    pub ty: Option<TypeName<'i>>,
+    pub value: Expression<'i>,
+}
+impl<'i> Display for GenericAssociation<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ty {
+            Some(ref ty) => write!(f, "{ty}: ")?,
+            None => write!(f, "default: ")?,
         }
+        write!(f, "{}", Operand(&self.value, PRECEDENCE_ASSIGN))
     }
 }
@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::Expression;
+
+/// Initial value of a variable, or the contents of a compound literal
+#[derive(Debug, PartialEq, Eq)]
+pub enum Initializer<'i> {
+    Expression(Expression<'i>),
+    /// `{ ... }`, nested as written. Elided braces, as in `int a[2][2] = { 1, 2, 3, 4 }`,
+    /// are only known once the type being initialized is.
+    List(Vec<DesignatedInitializer<'i>>),
+}
+impl<'i> Display for Initializer<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Expression(expr) => write!(f, "{expr}"),
+            Self::List(list) => write_list(f, list),
+        }
+    }
+}
+
+/// Writes `{ a, b }`, or `{}` for the C23 empty initializer
+pub(super) fn write_list(
+    f: &mut std::fmt::Formatter<'_>,
+    list: &[DesignatedInitializer<'_>],
+) -> std::fmt::Result {
+    if list.is_empty() {
+        return write!(f, "{{}}");
+    }
+    write!(f, "{{ ")?;
+    for (i, item) in list.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    write!(f, " }}")
+}
+
+/// An element of an initializer list, as in `.pos.x = 1` or `2`
+#[derive(Debug, PartialEq, Eq)]
+pub struct DesignatedInitializer<'i> {
+    /// Empty when the element initializes the next member or index
+    pub designators: Vec<Designator<'i>>,
+    pub value: Initializer<'i>,
+}
+impl<'i> Display for DesignatedInitializer<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for designator in self.designators.iter() {
+            write!(f, "{designator}")?;
+        }
+        if !self.designators.is_empty() {
+            write!(f, " = ")?;
+        }
+        write!(f, "{}", self.value)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Designator<'i> {
+    /// `.member`
+    Member(Ident<'i>),
+    /// `[index]`
+    Index(Expression<'i>),
+    /// GNU `[first ... last]`, both included
+    Range(Expression<'i>, Expression<'i>),
+}
+impl<'i> Display for Designator<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Member(name) => write!(f, ".{name}"),
+            Self::Index(index) => write!(f, "[{index}]"),
+            Self::Range(first, last) => write!(f, "[{first} ... {last}]"),
+        }
+    }
+}
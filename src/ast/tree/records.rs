@@ -0,0 +1,113 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::{Attributes, DeclSpecifiers, Declarator, Expression, StaticAssert};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum RecordKind {
+    #[display(fmt = "struct")]
+    Struct,
+    #[display(fmt = "union")]
+    Union,
+}
+
+/// A `struct` or `union` type specifier
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecordSpecifier<'i> {
+    pub kind: RecordKind,
+    /// Attributes after the keyword, and GNU ones after the members
+    pub attributes: Attributes<'i>,
+    /// `None` for anonymous records, as in `struct { int x; } point;`
+    pub tag: Option<Ident<'i>>,
+    /// `None` when the record is not defined here, as in `struct node *next;` or the
+    /// forward declaration `struct node;`
+    pub members: Option<Vec<MemberItem<'i>>>,
+}
+impl<'i> RecordSpecifier<'i> {
+    #[must_use]
+    pub fn is_definition(&self) -> bool {
+        self.members.is_some()
+    }
+}
+impl<'i> Display for RecordSpecifier<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.attributes.is_empty() {
+            write!(f, " {}", self.attributes)?;
+        }
+        if let Some(tag) = self.tag {
+            write!(f, " {tag}")?;
+        }
+        if let Some(ref members) = self.members {
+            write!(f, " {{ ")?;
+            for member in members.iter() {
+                write!(f, "{member} ")?;
+            }
+            write!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An item of the body of a record
+#[derive(Debug, PartialEq, Eq, derive_more::From)]
+pub enum MemberItem<'i> {
+    Declaration(MemberDecl<'i>),
+    /// Declares no member
+    StaticAssert(StaticAssert<'i>),
+}
+impl<'i> MemberItem<'i> {
+    #[must_use]
+    pub fn declaration(&self) -> Option<&MemberDecl<'i>> {
+        match self {
+            MemberItem::Declaration(member) => Some(member),
+            MemberItem::StaticAssert(_) => None,
+        }
+    }
+}
+impl<'i> Display for MemberItem<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemberItem::Declaration(member) => write!(f, "{member}"),
+            // Without the line break that ends the statement
+            MemberItem::StaticAssert(assert) => write!(f, "{}", assert.to_string().trim_end()),
+        }
+    }
+}
+
+/// A declaration inside a record, such as `unsigned flag : 1, *p;`
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemberDecl<'i> {
+    /// Never has a storage class nor function specifiers
+    pub ty: DeclSpecifiers<'i>,
+    /// Empty for anonymous structs and unions, whose members belong to the enclosing record
+    pub declarators: Vec<MemberDeclarator<'i>>,
+}
+impl<'i> Display for MemberDecl<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ty)?;
+        for (i, declarator) in self.declarators.iter().enumerate() {
+            let sep = if i > 0 { "," } else { "" };
+            write!(f, "{sep} {declarator}")?;
+        }
+        write!(f, ";")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemberDeclarator<'i> {
+    /// Abstract for unnamed bit-fields, as in `int : 4;`
+    pub declarator: Declarator<'i>,
+    /// Width of a bit-field
+    pub width: Option<Expression<'i>>,
+}
+impl<'i> Display for MemberDeclarator<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.width {
+            Some(ref width) if self.declarator == Declarator::Abstract => write!(f, ": {width}"),
+            Some(ref width) => write!(f, "{} : {width}", self.declarator),
+            None => write!(f, "{}", self.declarator),
+        }
+    }
+}
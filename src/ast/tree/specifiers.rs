@@ -0,0 +1,221 @@
+use std::fmt::Display;
+
+use crate::lexer::token::Ident;
+
+use super::{Attributes, EnumSpecifier, Expression, RecordSpecifier, TypeName};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum StorageClass {
+    #[display(fmt = "typedef")]
+    Typedef,
+    #[display(fmt = "extern")]
+    Extern,
+    #[display(fmt = "static")]
+    Static,
+    #[display(fmt = "auto")]
+    Auto,
+    #[display(fmt = "register")]
+    Register,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TypeQualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+    pub is_atomic: bool,
+}
+impl TypeQualifiers {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+impl Display for TypeQualifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let words = [
+            (self.is_const, "const"),
+            (self.is_volatile, "volatile"),
+            (self.is_restrict, "restrict"),
+            (self.is_atomic, "_Atomic"),
+        ];
+        let mut first = true;
+        for (_, word) in words.iter().filter(|(set, _)| *set) {
+            if !first {
+                write!(f, " ")?;
+            }
+            write!(f, "{word}")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum Signedness {
+    #[display(fmt = "signed")]
+    Signed,
+    #[display(fmt = "unsigned")]
+    Unsigned,
+}
+
+/// The type the type specifiers of a declaration add up to, such as `unsigned long`
+#[derive(Debug, PartialEq, Eq)]
+pub enum BaseType<'i> {
+    Void,
+    Bool,
+    /// Plain `char` has no signedness, its sign is implementation defined
+    Char(Option<Signedness>),
+    Short(Signedness),
+    Int(Signedness),
+    Long(Signedness),
+    LongLong(Signedness),
+    Float {
+        complex: bool,
+    },
+    Double {
+        complex: bool,
+    },
+    LongDouble {
+        complex: bool,
+    },
+    TypedefName(Ident<'i>),
+    Record(RecordSpecifier<'i>),
+    Enum(EnumSpecifier<'i>),
+    /// GNU `typeof(x)`, standard since C23
+    Typeof(Box<TypeofSpecifier<'i>>),
+    /// `_Atomic(type-name)`, the keyword as written and the type made atomic
+    Atomic(&'i str, Box<TypeName<'i>>),
+    /// GNU `__auto_type`, the type of the initializer of the declared variable
+    AutoType,
+}
+impl<'i> Display for BaseType<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn int(f: &mut std::fmt::Formatter<'_>, sign: Signedness, name: &str) -> std::fmt::Result {
+            match sign {
+                Signedness::Signed => write!(f, "{name}"),
+                Signedness::Unsigned => write!(f, "unsigned {name}"),
+            }
+        }
+        fn float(f: &mut std::fmt::Formatter<'_>, complex: bool, name: &str) -> std::fmt::Result {
+            if complex {
+                write!(f, "_Complex ")?;
+            }
+            write!(f, "{name}")
+        }
+
+        match *self {
+            Self::Record(ref record) => write!(f, "{record}"),
+            Self::Enum(ref r#enum) => write!(f, "{enum}"),
+            Self::Void => write!(f, "void"),
+            Self::Bool => write!(f, "_Bool"),
+            Self::Char(None) => write!(f, "char"),
+            Self::Char(Some(sign)) => write!(f, "{sign} char"),
+            Self::Short(sign) => int(f, sign, "short"),
+            Self::Int(sign) => int(f, sign, "int"),
+            Self::Long(sign) => int(f, sign, "long"),
+            Self::LongLong(sign) => int(f, sign, "long long"),
+            Self::Float { complex } => float(f, complex, "float"),
+            Self::Double { complex } => float(f, complex, "double"),
+            Self::LongDouble { complex } => float(f, complex, "long double"),
+            Self::TypedefName(name) => write!(f, "{name}"),
+            Self::Typeof(ref operand) => write!(f, "{operand}"),
+            Self::Atomic(_, ref ty) => write!(f, "_Atomic({ty})"),
+            Self::AutoType => write!(f, "__auto_type"),
+        }
+    }
+}
+
+/// Operand of `_Alignas`
+#[derive(Debug, PartialEq, Eq)]
+pub enum AlignmentSpecifier<'i> {
+    Type(TypeName<'i>),
+    Expression(Expression<'i>),
+}
+impl<'i> Display for AlignmentSpecifier<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Type(ty) => write!(f, "_Alignas({ty})"),
+            Self::Expression(expr) => write!(f, "_Alignas({expr})"),
+        }
+    }
+}
+
+/// Operand of `typeof`, the type of an expression or a type name
+#[derive(Debug, PartialEq, Eq)]
+pub enum TypeofSpecifier<'i> {
+    /// Not evaluated, only its type matters
+    Expression(Expression<'i>),
+    Type(TypeName<'i>),
+}
+impl<'i> Display for TypeofSpecifier<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Expression(expr) => write!(f, "typeof({expr})"),
+            Self::Type(ty) => write!(f, "typeof({ty})"),
+        }
+    }
+}
+
+/// Everything that comes before the declarators of a declaration,
+/// as in `static const unsigned long`
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeclSpecifiers<'i> {
+    pub storage: Option<StorageClass>,
+    /// `_Thread_local`, which can be combined with `static` and `extern`
+    pub thread_local: bool,
+    pub qualifiers: TypeQualifiers,
+    pub is_inline: bool,
+    pub is_noreturn: bool,
+    pub alignment: Vec<AlignmentSpecifier<'i>>,
+    /// Attributes before and among the specifiers. Those at the start of a declaration
+    /// apply to everything it declares.
+    pub attributes: Attributes<'i>,
+    pub ty: BaseType<'i>,
+}
+impl<'i> DeclSpecifiers<'i> {
+    #[must_use]
+    pub fn is_typedef(&self) -> bool {
+        self.storage == Some(StorageClass::Typedef)
+    }
+}
+impl<'i> From<BaseType<'i>> for DeclSpecifiers<'i> {
+    fn from(ty: BaseType<'i>) -> Self {
+        Self {
+            storage: None,
+            thread_local: false,
+            qualifiers: TypeQualifiers::default(),
+            is_inline: false,
+            is_noreturn: false,
+            alignment: Vec::new(),
+            attributes: Attributes::default(),
+            ty,
+        }
+    }
+}
+impl<'i> Display for DeclSpecifiers<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.attributes.is_empty() {
+            write!(f, "{} ", self.attributes)?;
+        }
+        if let Some(storage) = self.storage {
+            write!(f, "{storage} ")?;
+        }
+        if self.thread_local {
+            write!(f, "_Thread_local ")?;
+        }
+        if self.is_inline {
+            write!(f, "inline ")?;
+        }
+        if self.is_noreturn {
+            write!(f, "_Noreturn ")?;
+        }
+        for alignment in self.alignment.iter() {
+            write!(f, "{alignment} ")?;
+        }
+        if !self.qualifiers.is_empty() {
+            write!(f, "{} ", self.qualifiers)?;
+        }
+        write!(f, "{}", self.ty)
+    }
+}
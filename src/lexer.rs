@@ -2,13 +2,14 @@ pub(crate) mod parsers;
 pub(crate) mod stream;
 pub(crate) mod token;
 
-pub use parsers::parse_tokens;
+pub use parsers::{parse_tokens, parse_tokens_lossless, LosslessTokenIter};
 pub use stream::TokenStream;
+pub use token::{Token, TokenKind, Trivia};
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        lexer::token::{Ident, Token, TokenKind},
+        lexer::token::{Ident, Invalid, Keyword, Literal, Token, TokenKind},
         span::Span,
     };
 
@@ -23,6 +24,101 @@ mod tests {
         assert_eq!(token, ident.kind);
     }
 
+    #[test]
+    fn test_parse_literals() {
+        const SOURCE: &str = "42 0x1Fu 017 10ul 1.5 .5e-3f 0x1p+4 'a' '\\'' \"s\"";
+
+        let kinds: Vec<_> = crate::lexer::parse_tokens(crate::preprocessor::preprocess(SOURCE))
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                Literal::Int("42").into(),
+                Literal::Int("0x1Fu").into(),
+                Literal::Int("017").into(),
+                Literal::Int("10ul").into(),
+                Literal::Float("1.5").into(),
+                Literal::Float(".5e-3f").into(),
+                Literal::Float("0x1p+4").into(),
+                Literal::Char("'a'").into(),
+                Literal::Char("'\\''").into(),
+                Literal::String("\"s\"").into(),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        const SOURCE: &str = "99999999999999999999 0x 08 1.5 @ L\"w\" u8'c'";
+
+        let kinds: Vec<_> = crate::lexer::parse_tokens(crate::preprocessor::preprocess(SOURCE))
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                Invalid::Number("99999999999999999999").into(),
+                Invalid::Number("0x").into(),
+                Invalid::Number("08").into(),
+                Literal::Float("1.5").into(),
+                Invalid::Stray('@').into(),
+                Literal::String("L\"w\"").into(),
+                Literal::Char("u8'c'").into(),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lossless_trivia() {
+        const SOURCE: &str = "int x; // the x\\\nstill x\n\n/* y */ int y;";
+
+        let tokens: Vec<_> = crate::lexer::parse_tokens_lossless(SOURCE).collect();
+        let int = &tokens[0];
+        assert_eq!(int.kind, TokenKind::Keyword(Keyword::Int));
+        assert!(int.leading().is_empty());
+        assert_eq!(int.trailing().get(), " ");
+
+        let semi_colon = &tokens[2];
+        assert_eq!(semi_colon.kind, TokenKind::SemiColon);
+        assert_eq!(semi_colon.trailing().get(), " // the x\\\nstill x");
+
+        let int = &tokens[3];
+        assert_eq!(int.leading().get(), "\n\n/* y */ ");
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn test_lossless_directives() {
+        const SOURCE: &str = "#define X 1 + \\\n  2\nint x = X;\n";
+
+        let tokens: Vec<_> = crate::lexer::parse_tokens_lossless(SOURCE).collect();
+        assert_eq!(tokens[0].kind, TokenKind::Keyword(Keyword::Int));
+        assert_eq!(tokens[0].leading().get(), "#define X 1 + \\\n  2\n");
+        assert_eq!(tokens[3].kind, TokenKind::Ident(Ident::new("X")));
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let examples = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/example"))
+            .expect("Could not read example directory");
+
+        for entry in examples {
+            let path = entry.expect("Could not read example").path();
+            let source = std::fs::read_to_string(&path).expect("Could not read example");
+
+            let mut concat = String::with_capacity(source.len());
+            for token in crate::lexer::parse_tokens_lossless(&source) {
+                concat.push_str(token.leading().get());
+                concat.push_str(token.span().get());
+                concat.push_str(token.trailing().get());
+            }
+            assert_eq!(concat, source, "{} did not round-trip", path.display());
+        }
+    }
+
     /*
     #[test]
     fn test_parse_many_idents() {
@@ -196,3 +292,14 @@ mod tests {
         assert_eq!(program, PARSED);
     } */
 }
+#[test]
+fn zz_sizes() {
+    eprintln!(
+        "lit {} tk {} otk {} span {} err {}",
+        std::mem::size_of::<token::Literal>(),
+        std::mem::size_of::<token::TokenKind>(),
+        std::mem::size_of::<Option<token::TokenKind>>(),
+        std::mem::size_of::<crate::span::Span>(),
+        std::mem::size_of::<crate::ast::SyntaxError>()
+    );
+}
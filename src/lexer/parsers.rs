@@ -1,29 +1,134 @@
-use crate::{preprocessor::SpanType, span::Span};
+use std::iter::Peekable;
 
-use super::token::{Ident, Keyword, Literal, Token, TokenKind};
+use crate::{
+    preprocessor::SpanType,
+    span::Span,
+    standard::{Dialect, Standard},
+};
 
-fn parse_token<'i>(span: &Span<'i, SpanType>) -> TokenKind<'i> {
+use super::token::{Ident, Invalid, Keyword, Literal, Token, TokenKind, Trivia};
+
+/// The kind of the token `span`, whose spelling names a keyword only if `dialect` has it
+fn parse_token<'i>(span: &Span<'i, SpanType>, dialect: Dialect) -> TokenKind<'i> {
     match span.get() {
         "typedef" => TokenKind::Keyword(Keyword::Typedef),
         "if" => TokenKind::Keyword(Keyword::If),
         "else" => TokenKind::Keyword(Keyword::Else),
+        "sizeof" => TokenKind::Keyword(Keyword::Sizeof),
+        "static" => TokenKind::Keyword(Keyword::Static),
+        "extern" => TokenKind::Keyword(Keyword::Extern),
+        "auto" => TokenKind::Keyword(Keyword::Auto),
+        "register" => TokenKind::Keyword(Keyword::Register),
+        "_Thread_local" => TokenKind::Keyword(Keyword::ThreadLocal),
+        "const" => TokenKind::Keyword(Keyword::Const),
+        "volatile" | "__volatile__" | "__volatile" => TokenKind::Keyword(Keyword::Volatile),
+        "restrict" => TokenKind::Keyword(Keyword::Restrict),
+        "_Atomic" => TokenKind::Keyword(Keyword::Atomic),
+        "inline" | "__inline__" | "__inline" => TokenKind::Keyword(Keyword::Inline),
+        "_Noreturn" => TokenKind::Keyword(Keyword::Noreturn),
+        "_Alignas" => TokenKind::Keyword(Keyword::Alignas),
+        "_Alignof" => TokenKind::Keyword(Keyword::Alignof),
+        "_Static_assert" => TokenKind::Keyword(Keyword::StaticAssert),
+        "_Generic" => TokenKind::Keyword(Keyword::Generic),
+        "__attribute__" | "__attribute" => TokenKind::Keyword(Keyword::Attribute),
+        // Plain `typeof` is not reserved before C23, as GCC only takes it in GNU dialects
+        "typeof" if dialect.gnu_extensions || dialect.standard >= Standard::C23 => {
+            TokenKind::Keyword(Keyword::Typeof)
+        }
+        "__typeof__" | "__typeof" => TokenKind::Keyword(Keyword::Typeof),
+        "__auto_type" => TokenKind::Keyword(Keyword::AutoType),
+        "__extension__" => TokenKind::Keyword(Keyword::Extension),
+        "__builtin_offsetof" => TokenKind::Keyword(Keyword::BuiltinOffsetof),
+        "__builtin_types_compatible_p" => TokenKind::Keyword(Keyword::BuiltinTypesCompatible),
+        // No revision of ISO C reserves plain `asm`
+        "asm" if dialect.gnu_extensions => TokenKind::Keyword(Keyword::Asm),
+        "__asm__" | "__asm" => TokenKind::Keyword(Keyword::Asm),
+        "void" => TokenKind::Keyword(Keyword::Void),
+        "char" => TokenKind::Keyword(Keyword::Char),
+        "short" => TokenKind::Keyword(Keyword::Short),
+        "int" => TokenKind::Keyword(Keyword::Int),
+        "long" => TokenKind::Keyword(Keyword::Long),
+        "float" => TokenKind::Keyword(Keyword::Float),
+        "double" => TokenKind::Keyword(Keyword::Double),
+        "signed" => TokenKind::Keyword(Keyword::Signed),
+        "unsigned" => TokenKind::Keyword(Keyword::Unsigned),
+        "_Bool" => TokenKind::Keyword(Keyword::Bool),
+        "_Complex" => TokenKind::Keyword(Keyword::Complex),
+        "struct" => TokenKind::Keyword(Keyword::Struct),
+        "union" => TokenKind::Keyword(Keyword::Union),
+        "enum" => TokenKind::Keyword(Keyword::Enum),
+        "while" => TokenKind::Keyword(Keyword::While),
+        "do" => TokenKind::Keyword(Keyword::Do),
+        "for" => TokenKind::Keyword(Keyword::For),
+        "break" => TokenKind::Keyword(Keyword::Break),
+        "continue" => TokenKind::Keyword(Keyword::Continue),
+        "switch" => TokenKind::Keyword(Keyword::Switch),
+        "case" => TokenKind::Keyword(Keyword::Case),
+        "default" => TokenKind::Keyword(Keyword::Default),
+        "goto" => TokenKind::Keyword(Keyword::Goto),
+        "return" => TokenKind::Keyword(Keyword::Return),
 
         ident if span.extra.is_identifier() => TokenKind::Ident(Ident::new(ident)),
-        literal if span.extra.is_literal_num() => {
-            TokenKind::Literal(Literal::Int(literal.parse().unwrap()))
-        }
+        literal if span.extra.is_literal_num() => match Literal::parse_number(literal) {
+            Some(literal) => TokenKind::Literal(literal),
+            None => TokenKind::Invalid(Invalid::Number(literal)),
+        },
+        string if span.extra.is_string() => TokenKind::Literal(Literal::String(string)),
+        char if span.extra.is_char() => TokenKind::Literal(Literal::Char(char)),
 
         "(" => TokenKind::OpenParen,
         ")" => TokenKind::CloseParen,
         "{" => TokenKind::OpenBrace,
         "}" => TokenKind::CloseBrace,
+        "[" => TokenKind::OpenBracket,
+        "]" => TokenKind::CloseBracket,
         "==" => TokenKind::Equals,
         "=" => TokenKind::Assign,
         ";" => TokenKind::SemiColon,
         "," => TokenKind::Comma,
         "*" => TokenKind::Star,
+        "." => TokenKind::Dot,
+        "->" => TokenKind::Arrow,
+        "++" => TokenKind::Increment,
+        "--" => TokenKind::Decrement,
+        "&" => TokenKind::Ampersand,
+        "+" => TokenKind::Plus,
+        "-" => TokenKind::Minus,
+        "~" => TokenKind::Tilde,
+        "!" => TokenKind::Bang,
+        "/" => TokenKind::Slash,
+        "%" => TokenKind::Percent,
+        "<<" => TokenKind::ShiftLeft,
+        ">>" => TokenKind::ShiftRight,
+        "<" => TokenKind::Less,
+        ">" => TokenKind::Greater,
+        "<=" => TokenKind::LessEquals,
+        ">=" => TokenKind::GreaterEquals,
+        "!=" => TokenKind::NotEquals,
+        "^" => TokenKind::Caret,
+        "|" => TokenKind::Pipe,
+        "&&" => TokenKind::LogicalAnd,
+        "||" => TokenKind::LogicalOr,
+        "?" => TokenKind::Question,
+        ":" => TokenKind::Colon,
+        "..." => TokenKind::Ellipsis,
+        "*=" => TokenKind::MulAssign,
+        "/=" => TokenKind::DivAssign,
+        "%=" => TokenKind::ModAssign,
+        "+=" => TokenKind::AddAssign,
+        "-=" => TokenKind::SubAssign,
+        "<<=" => TokenKind::ShlAssign,
+        ">>=" => TokenKind::ShrAssign,
+        "&=" => TokenKind::AndAssign,
+        "^=" => TokenKind::XorAssign,
+        "|=" => TokenKind::OrAssign,
+        "#" => TokenKind::Hash,
+        "##" => TokenKind::HashHash,
 
         _ if span.extra.is_eof() => TokenKind::Eof,
+        char if span.extra.is_invalid() => {
+            TokenKind::Invalid(Invalid::Stray(char.chars().next().unwrap_or_default()))
+        }
 
         _ => unreachable!("{span} of type '{:?}'", span.extra),
     }
@@ -34,6 +139,18 @@ where
     I: Iterator<Item = Span<'i, SpanType>>,
 {
     iter: I,
+    dialect: Dialect,
+}
+impl<'i, I> TokenIter<'i, I>
+where
+    I: Iterator<Item = Span<'i, SpanType>>,
+{
+    /// Reads the keywords of `dialect`, such as `typeof` in GNU C, instead of those of
+    /// the default one
+    #[must_use]
+    pub fn with_dialect(self, dialect: Dialect) -> Self {
+        Self { dialect, ..self }
+    }
 }
 
 impl<'i, I> Iterator for TokenIter<'i, I>
@@ -44,8 +161,8 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter
-            .find(|s| !s.extra.is_whitespace())
-            .map(|span| Token::new(parse_token(&span), span.with(())))
+            .find(|s| !s.extra.is_trivia())
+            .map(|span| Token::new(parse_token(&span, self.dialect), span.with(())))
     }
 }
 
@@ -53,5 +170,106 @@ pub fn parse_tokens<'i, I>(source: I) -> TokenIter<'i, I>
 where
     I: Iterator<Item = Span<'i, SpanType>>,
 {
-    TokenIter { iter: source }
+    TokenIter {
+        iter: source,
+        dialect: Dialect::default(),
+    }
+}
+
+/// Token iterator that keeps every byte of the source.
+///
+/// Whitespace, comments and line continuations are attached to the tokens as
+/// [`Trivia`]. A token's trailing trivia runs until the end of its line, everything
+/// else is leading trivia of the next token. Preprocessor directives are not executed,
+/// instead they are kept as leading trivia too, so concatenating the leading trivia, the
+/// token and the trailing trivia of every token yields back the original source.
+pub struct LosslessTokenIter<'i> {
+    source: &'i str,
+    iter: Peekable<crate::preprocessor::PreprocessorTokenIter<'i>>,
+    /// Where the trivia of the next token starts
+    position: usize,
+    /// Whether only trivia has been found since the last newline
+    at_line_start: bool,
+    done: bool,
+}
+impl<'i> LosslessTokenIter<'i> {
+    fn is_newline(span: &Span<'i, SpanType>) -> bool {
+        span.extra.is_whitespace() && (*span == "\n" || *span == "\r\n")
+    }
+
+    /// Takes a whole directive, up to the newline that ends it
+    fn take_directive(&mut self) {
+        while self
+            .iter
+            .next_if(|s| !s.extra.is_eof() && !Self::is_newline(s))
+            .is_some()
+        {}
+    }
+
+    /// Takes trivia until a token is found, returning that token
+    fn take_leading(&mut self) -> Option<Span<'i, SpanType>> {
+        loop {
+            let span = self.iter.next()?;
+            if Self::is_newline(&span) {
+                self.at_line_start = true;
+            } else if span.extra.is_trivia() {
+            } else if self.at_line_start && span == "#" {
+                self.take_directive();
+            } else {
+                self.at_line_start = false;
+                return Some(span);
+            }
+        }
+    }
+
+    /// Takes trivia until the end of the line, returning where it stopped
+    fn take_trailing(&mut self, from: usize) -> usize {
+        let mut end = from;
+        while let Some(span) = self
+            .iter
+            .next_if(|s| s.extra.is_trivia() && !Self::is_newline(s))
+        {
+            end = span.end();
+        }
+        end
+    }
+}
+impl<'i> Iterator for LosslessTokenIter<'i> {
+    type Item = Token<'i>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let span = self.take_leading()?;
+        let leading = Trivia::new(Span::new(self.source, self.position, span.start()));
+        let trailing_end = if span.extra.is_eof() {
+            self.done = true;
+            span.end()
+        } else {
+            self.take_trailing(span.end())
+        };
+        let trailing = Trivia::new(Span::new(self.source, span.end(), trailing_end));
+        self.position = trailing_end;
+
+        Some(Token::new_with_trivia(
+            parse_token(&span, Dialect::default()),
+            span.with(()),
+            leading,
+            trailing,
+        ))
+    }
+}
+
+/// Tokenizes `source` without running the preprocessor, keeping all trivia.
+/// See [`LosslessTokenIter`].
+pub fn parse_tokens_lossless(source: &str) -> LosslessTokenIter<'_> {
+    LosslessTokenIter {
+        source,
+        iter: crate::preprocessor::parse_preprocessor(source).peekable(),
+        position: 0,
+        at_line_start: true,
+        done: false,
+    }
 }
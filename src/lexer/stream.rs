@@ -1,21 +1,76 @@
 use std::ops::{Index, Range, RangeFrom, RangeFull, RangeTo};
 
-use nom::{InputIter, InputLength, InputTake, Needed, Slice};
+use nom::{FindSubstring, InputIter, InputLength, InputTake, Needed, Offset, Slice};
 
-use super::token::Token;
+use super::token::{Token, TokenKind};
 
+/// A slice of tokens that remembers where it starts in the original token vector.
+/// `extra` is carried along every subslice, as the parser does with its
+/// configuration and state.
 #[derive(Clone, Copy, Debug)]
-pub struct TokenStream<'i, 't> {
+pub struct TokenStream<'i, 't, X = ()> {
     pub(crate) tokens: &'t [Token<'i>],
+    /// Index of `tokens[0]` in the original token vector
+    offset: usize,
+    pub extra: X,
 }
 
 impl<'i, 't> TokenStream<'i, 't> {
     pub const fn new(tokens: &'t [Token<'i>]) -> Self {
-        Self { tokens }
+        Self {
+            tokens,
+            offset: 0,
+            extra: (),
+        }
+    }
+}
+
+impl<'i, 't, X> TokenStream<'i, 't, X> {
+    /// The same tokens carrying `extra` instead
+    #[must_use]
+    pub fn with<Y>(self, extra: Y) -> TokenStream<'i, 't, Y> {
+        TokenStream {
+            tokens: self.tokens,
+            offset: self.offset,
+            extra,
+        }
+    }
+
+    fn subslice(&self, tokens: &'t [Token<'i>], start: usize) -> Self
+    where
+        X: Copy,
+    {
+        Self {
+            tokens,
+            offset: self.offset + start,
+            extra: self.extra,
+        }
+    }
+
+    /// Index of the first token of this stream in the original token vector
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the `n`th token of the stream without consuming it
+    #[inline]
+    #[must_use]
+    pub fn peek(&self, n: usize) -> Option<&'t Token<'i>> {
+        self.tokens.get(n)
+    }
+
+    pub const fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
     }
 }
 
-impl<'i, 't> Index<usize> for TokenStream<'i, 't> {
+impl<'i, 't, X> Index<usize> for TokenStream<'i, 't, X> {
     type Output = Token<'i>;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -23,23 +78,22 @@ impl<'i, 't> Index<usize> for TokenStream<'i, 't> {
     }
 }
 
-impl<'i, 't> InputLength for TokenStream<'i, 't> {
+impl<'i, 't, X> InputLength for TokenStream<'i, 't, X> {
     fn input_len(&self) -> usize {
         self.tokens.len()
     }
 }
 
-impl<'i: 't, 't> InputTake for TokenStream<'i, 't> {
+impl<'i: 't, 't, X: Copy> InputTake for TokenStream<'i, 't, X> {
     fn take(&self, count: usize) -> Self {
-        Self::new(&self.tokens[..count])
+        self.slice(..count)
     }
 
     fn take_split(&self, count: usize) -> (Self, Self) {
-        let (first, second) = self.tokens.split_at(count);
-        (Self::new(second), Self::new(first))
+        (self.slice(count..), self.slice(..count))
     }
 }
-impl<'i, 't> InputIter for TokenStream<'i, 't> {
+impl<'i, 't, X> InputIter for TokenStream<'i, 't, X> {
     type Item = &'t Token<'i>;
 
     type Iter = std::iter::Enumerate<std::slice::Iter<'t, Token<'i>>>;
@@ -70,23 +124,108 @@ impl<'i, 't> InputIter for TokenStream<'i, 't> {
     }
 }
 
-impl Slice<Range<usize>> for TokenStream<'_, '_> {
-    fn slice(&self, _range: Range<usize>) -> Self {
-        todo!()
+impl<X> Offset for TokenStream<'_, '_, X> {
+    fn offset(&self, second: &Self) -> usize {
+        second.offset - self.offset
     }
 }
-impl Slice<RangeFrom<usize>> for TokenStream<'_, '_> {
-    fn slice(&self, _range: RangeFrom<usize>) -> Self {
-        todo!()
+
+/// A single token kind is a "substring" of length 1, which lets it be used as the
+/// needle of `take_until`.
+impl InputLength for TokenKind<'_> {
+    fn input_len(&self) -> usize {
+        1
+    }
+}
+
+impl<'i, X> FindSubstring<TokenKind<'i>> for TokenStream<'i, '_, X> {
+    fn find_substring(&self, substr: TokenKind<'i>) -> Option<usize> {
+        self.tokens.iter().position(|t| t.kind == substr)
+    }
+}
+
+impl<X: Copy> Slice<Range<usize>> for TokenStream<'_, '_, X> {
+    fn slice(&self, range: Range<usize>) -> Self {
+        self.subslice(&self.tokens[range.clone()], range.start)
     }
 }
-impl Slice<RangeTo<usize>> for TokenStream<'_, '_> {
-    fn slice(&self, _range: RangeTo<usize>) -> Self {
-        todo!()
+impl<X: Copy> Slice<RangeFrom<usize>> for TokenStream<'_, '_, X> {
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        self.subslice(&self.tokens[range.clone()], range.start)
     }
 }
-impl Slice<RangeFull> for TokenStream<'_, '_> {
+impl<X: Copy> Slice<RangeTo<usize>> for TokenStream<'_, '_, X> {
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.subslice(&self.tokens[range], 0)
+    }
+}
+impl<X: Copy> Slice<RangeFull> for TokenStream<'_, '_, X> {
     fn slice(&self, _range: RangeFull) -> Self {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nom::{
+        bytes::complete::{take, take_until},
+        combinator::{consumed, recognize},
+        sequence::pair,
+        IResult, InputTake,
+    };
+
+    use crate::{
+        lexer::{
+            stream::TokenStream,
+            token::{Ident, TokenKind},
+        },
+        preprocessor::preprocess,
+    };
+
+    #[test]
+    fn test_take_split_keeps_position() {
+        const SOURCE: &str = "a b c d";
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
+        let stream = TokenStream::new(&tokens);
+
+        let (rest, taken) = stream.take_split(1);
+        assert_eq!(taken.position(), 0);
+        assert_eq!(rest.position(), 1);
+
+        let (rest, taken) = rest.take_split(2);
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken.position(), 1);
+        assert_eq!(rest.position(), 3);
+        assert_eq!(*rest.peek(0).unwrap(), TokenKind::Ident(Ident::new("d")));
+        assert_eq!(*rest.peek(1).unwrap(), TokenKind::Eof);
+        assert!(rest.peek(2).is_none());
+    }
+
+    #[test]
+    fn test_nom_combinators() {
+        fn two<'i, 't>(
+            i: TokenStream<'i, 't>,
+        ) -> IResult<TokenStream<'i, 't>, TokenStream<'i, 't>> {
+            recognize(pair(take(1usize), take(1usize)))(i)
+        }
+
+        const SOURCE: &str = "a b ; c";
+        let tokens: Vec<_> = crate::lexer::parse_tokens(preprocess(SOURCE)).collect();
+        let stream = TokenStream::new(&tokens);
+
+        let (rest, recognized) = two(stream).expect("Could not recognize");
+        assert_eq!(recognized.len(), 2);
+        assert_eq!(rest.position(), 2);
+
+        let (_, (consumed, _)) = consumed(take(1usize))(rest)
+            .map_err(|_: nom::Err<()>| ())
+            .unwrap();
+        assert_eq!(consumed.position(), 2);
+        assert_eq!(consumed[0], TokenKind::SemiColon);
+
+        let (rest, until): (_, TokenStream) =
+            take_until::<_, _, ()>(TokenKind::Eof)(stream).unwrap();
+        assert_eq!(until.len(), 4);
+        assert_eq!(rest.position(), 4);
+    }
+}
@@ -10,6 +10,147 @@ pub enum Keyword {
 
     #[display(fmt = "else")]
     Else,
+
+    #[display(fmt = "sizeof")]
+    Sizeof,
+
+    #[display(fmt = "static")]
+    Static,
+
+    #[display(fmt = "extern")]
+    Extern,
+
+    #[display(fmt = "auto")]
+    Auto,
+
+    #[display(fmt = "register")]
+    Register,
+
+    #[display(fmt = "_Thread_local")]
+    ThreadLocal,
+
+    #[display(fmt = "const")]
+    Const,
+
+    #[display(fmt = "volatile")]
+    Volatile,
+
+    #[display(fmt = "restrict")]
+    Restrict,
+
+    #[display(fmt = "_Atomic")]
+    Atomic,
+
+    #[display(fmt = "inline")]
+    Inline,
+
+    #[display(fmt = "_Noreturn")]
+    Noreturn,
+
+    #[display(fmt = "_Alignas")]
+    Alignas,
+
+    #[display(fmt = "_Alignof")]
+    Alignof,
+
+    #[display(fmt = "_Static_assert")]
+    StaticAssert,
+
+    #[display(fmt = "_Generic")]
+    Generic,
+
+    #[display(fmt = "__attribute__")]
+    Attribute,
+
+    #[display(fmt = "typeof")]
+    Typeof,
+
+    #[display(fmt = "__auto_type")]
+    AutoType,
+
+    #[display(fmt = "__extension__")]
+    Extension,
+
+    #[display(fmt = "__builtin_offsetof")]
+    BuiltinOffsetof,
+
+    #[display(fmt = "__builtin_types_compatible_p")]
+    BuiltinTypesCompatible,
+
+    #[display(fmt = "asm")]
+    Asm,
+
+    #[display(fmt = "void")]
+    Void,
+
+    #[display(fmt = "char")]
+    Char,
+
+    #[display(fmt = "short")]
+    Short,
+
+    #[display(fmt = "int")]
+    Int,
+
+    #[display(fmt = "long")]
+    Long,
+
+    #[display(fmt = "float")]
+    Float,
+
+    #[display(fmt = "double")]
+    Double,
+
+    #[display(fmt = "signed")]
+    Signed,
+
+    #[display(fmt = "unsigned")]
+    Unsigned,
+
+    #[display(fmt = "_Bool")]
+    Bool,
+
+    #[display(fmt = "_Complex")]
+    Complex,
+
+    #[display(fmt = "struct")]
+    Struct,
+
+    #[display(fmt = "union")]
+    Union,
+
+    #[display(fmt = "enum")]
+    Enum,
+
+    #[display(fmt = "while")]
+    While,
+
+    #[display(fmt = "do")]
+    Do,
+
+    #[display(fmt = "for")]
+    For,
+
+    #[display(fmt = "break")]
+    Break,
+
+    #[display(fmt = "continue")]
+    Continue,
+
+    #[display(fmt = "switch")]
+    Switch,
+
+    #[display(fmt = "case")]
+    Case,
+
+    #[display(fmt = "default")]
+    Default,
+
+    #[display(fmt = "goto")]
+    Goto,
+
+    #[display(fmt = "return")]
+    Return,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display)]
@@ -23,16 +164,126 @@ impl<'i> Ident<'i> {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum IntSuffix {
+    #[default]
+    #[display(fmt = "")]
+    None,
+    #[display(fmt = "u")]
+    Unsigned,
+    #[display(fmt = "l")]
+    Long,
+    #[display(fmt = "ul")]
+    UnsignedLong,
+    #[display(fmt = "ll")]
+    LongLong,
+    #[display(fmt = "ull")]
+    UnsignedLongLong,
+}
+impl IntSuffix {
+    fn parse(suffix: &str) -> Option<Self> {
+        match suffix.to_ascii_lowercase().as_str() {
+            "" => Some(Self::None),
+            "u" => Some(Self::Unsigned),
+            "l" => Some(Self::Long),
+            "ul" | "lu" => Some(Self::UnsignedLong),
+            "ll" => Some(Self::LongLong),
+            "ull" | "llu" => Some(Self::UnsignedLongLong),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Self::Unsigned | Self::UnsignedLong | Self::UnsignedLongLong
+        )
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display)]
-pub enum Literal {
-    Int(i64),
+pub enum Literal<'i> {
+    /// Integer constant, as written
+    Int(&'i str),
+
+    /// Floating constant, as written
+    Float(&'i str),
+
+    /// Character constant, including its quotes
+    Char(&'i str),
+
+    /// String literal, including its quotes
+    String(&'i str),
+}
+impl<'i> Literal<'i> {
+    /// Parses a preprocessing number into an integer or floating constant
+    pub(crate) fn parse_number(number: &'i str) -> Option<Self> {
+        let lower = number.to_ascii_lowercase();
+        let is_hex = lower.starts_with("0x");
+        let is_float = lower.contains('.')
+            || (is_hex && lower.contains('p'))
+            || (!is_hex && lower.contains('e'));
+        if is_float {
+            return Some(Self::Float(number));
+        }
+        int_value(number)?;
+        Some(Self::Int(number))
+    }
+}
+
+/// The value and suffix of an integer constant, written as in `0x1Fu`
+pub(crate) fn int_value(number: &str) -> Option<(u64, IntSuffix)> {
+    let lower = number.to_ascii_lowercase();
+    let (radix, digits) = if lower.starts_with("0x") {
+        (16, &number[2..])
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        (2, &number[number.len() - binary.len()..])
+    } else if number.starts_with('0') && number.len() > 1 {
+        (8, &number[1..])
+    } else {
+        (10, number)
+    };
+    let suffix_start = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    let (digits, suffix) = digits.split_at(suffix_start);
+    let value = if digits.is_empty() && radix == 8 {
+        0
+    } else {
+        u64::from_str_radix(digits, radix).ok()?
+    };
+    Some((value, IntSuffix::parse(suffix)?))
+}
+
+/// Source text that makes no valid token, kept for the parser to report
+#[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display)]
+pub enum Invalid<'i> {
+    /// A character that starts no token, such as `@`
+    #[display(fmt = "{}", _0)]
+    Stray(char),
+    /// A preprocessing number that is no integer or floating constant, as `08` or `0x`,
+    /// or whose value no integer type holds
+    #[display(fmt = "{}", _0)]
+    Number(&'i str),
+}
+impl<'i> Invalid<'i> {
+    /// Why the token is invalid
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self {
+            Self::Stray(char) => format!("stray '{char}' in program"),
+            Self::Number(number) => format!("invalid numeric constant '{number}'"),
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display, derive_more::From)]
 pub enum TokenKind<'i> {
     Keyword(Keyword),
     Ident(Ident<'i>),
-    Literal(Literal),
+    Literal(Literal<'i>),
+    Invalid(Invalid<'i>),
 
     #[display(fmt = "(")]
     OpenParen,
@@ -61,6 +312,123 @@ pub enum TokenKind<'i> {
     #[display(fmt = "*")]
     Star,
 
+    #[display(fmt = "[")]
+    OpenBracket,
+
+    #[display(fmt = "]")]
+    CloseBracket,
+
+    #[display(fmt = ".")]
+    Dot,
+
+    #[display(fmt = "->")]
+    Arrow,
+
+    #[display(fmt = "++")]
+    Increment,
+
+    #[display(fmt = "--")]
+    Decrement,
+
+    #[display(fmt = "&")]
+    Ampersand,
+
+    #[display(fmt = "+")]
+    Plus,
+
+    #[display(fmt = "-")]
+    Minus,
+
+    #[display(fmt = "~")]
+    Tilde,
+
+    #[display(fmt = "!")]
+    Bang,
+
+    #[display(fmt = "/")]
+    Slash,
+
+    #[display(fmt = "%")]
+    Percent,
+
+    #[display(fmt = "<<")]
+    ShiftLeft,
+
+    #[display(fmt = ">>")]
+    ShiftRight,
+
+    #[display(fmt = "<")]
+    Less,
+
+    #[display(fmt = ">")]
+    Greater,
+
+    #[display(fmt = "<=")]
+    LessEquals,
+
+    #[display(fmt = ">=")]
+    GreaterEquals,
+
+    #[display(fmt = "!=")]
+    NotEquals,
+
+    #[display(fmt = "^")]
+    Caret,
+
+    #[display(fmt = "|")]
+    Pipe,
+
+    #[display(fmt = "&&")]
+    LogicalAnd,
+
+    #[display(fmt = "||")]
+    LogicalOr,
+
+    #[display(fmt = "?")]
+    Question,
+
+    #[display(fmt = ":")]
+    Colon,
+
+    #[display(fmt = "...")]
+    Ellipsis,
+
+    #[display(fmt = "*=")]
+    MulAssign,
+
+    #[display(fmt = "/=")]
+    DivAssign,
+
+    #[display(fmt = "%=")]
+    ModAssign,
+
+    #[display(fmt = "+=")]
+    AddAssign,
+
+    #[display(fmt = "-=")]
+    SubAssign,
+
+    #[display(fmt = "<<=")]
+    ShlAssign,
+
+    #[display(fmt = ">>=")]
+    ShrAssign,
+
+    #[display(fmt = "&=")]
+    AndAssign,
+
+    #[display(fmt = "^=")]
+    XorAssign,
+
+    #[display(fmt = "|=")]
+    OrAssign,
+
+    #[display(fmt = "#")]
+    Hash,
+
+    #[display(fmt = "##")]
+    HashHash,
+
     #[display(fmt = "$")]
     Eof,
 }
@@ -84,7 +452,7 @@ impl<'i> TokenKind<'i> {
     }
 
     #[must_use]
-    pub fn as_literal(&self) -> Option<&Literal> {
+    pub fn as_literal(&self) -> Option<&Literal<'i>> {
         if let Self::Literal(v) = self {
             Some(v)
         } else {
@@ -93,14 +461,97 @@ impl<'i> TokenKind<'i> {
     }
 }
 
+/// Source text that carries no meaning for the parser: whitespace, newlines,
+/// comments, line continuations and, in lossless mode, preprocessor directives.
+///
+/// Trivia is always a contiguous slice of the original source.
+#[derive(Debug, Clone, Copy)]
+pub struct Trivia<'i> {
+    span: Span<'i>,
+}
+impl<'i> Trivia<'i> {
+    pub(crate) const fn new(span: Span<'i>) -> Self {
+        Self { span }
+    }
+
+    /// Empty trivia positioned at `at`
+    pub(crate) fn empty(input: &'i str, at: usize) -> Self {
+        Self::new(Span::new(input, at, at))
+    }
+
+    pub fn get(&self) -> &'i str {
+        self.span.get()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn span(&self) -> Span<'i> {
+        self.span
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.span.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Token<'i> {
     pub(crate) kind: TokenKind<'i>,
     pub(crate) span: Span<'i>,
+    pub(crate) leading: Trivia<'i>,
+    pub(crate) trailing: Trivia<'i>,
 }
 impl<'i> Token<'i> {
-    pub const fn new(kind: TokenKind<'i>, span: Span<'i>) -> Self {
-        Self { kind, span }
+    pub fn new(kind: TokenKind<'i>, span: Span<'i>) -> Self {
+        let leading = Trivia::empty(span.input(), span.start());
+        let trailing = Trivia::empty(span.input(), span.end());
+        Self::new_with_trivia(kind, span, leading, trailing)
+    }
+
+    pub const fn new_with_trivia(
+        kind: TokenKind<'i>,
+        span: Span<'i>,
+        leading: Trivia<'i>,
+        trailing: Trivia<'i>,
+    ) -> Self {
+        Self {
+            kind,
+            span,
+            leading,
+            trailing,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> TokenKind<'i> {
+        self.kind
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn span(&self) -> Span<'i> {
+        self.span
+    }
+
+    /// Trivia found before this token. Always empty outside of lossless mode.
+    #[inline]
+    #[must_use]
+    pub fn leading(&self) -> Trivia<'i> {
+        self.leading
+    }
+
+    /// Trivia found after this token, up to (but not including) the end of the line.
+    /// Always empty outside of lossless mode.
+    #[inline]
+    #[must_use]
+    pub fn trailing(&self) -> Trivia<'i> {
+        self.trailing
+    }
+}
+impl<'i> std::fmt::Display for Token<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
     }
 }
 impl<'i> PartialEq<TokenKind<'i>> for Token<'i> {
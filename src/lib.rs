@@ -1,7 +1,10 @@
 pub mod ast;
 pub mod lexer;
 pub mod preprocessor;
+pub mod sema;
 pub mod span;
+pub mod standard;
+pub mod target;
 
 pub use preprocessor::{PreprocessorExecutor, preprocess};
 
@@ -1,7 +1,11 @@
 use std::path::PathBuf;
 
 use clap::Parser;
-use rcc::ast::parse_stream;
+use rcc::{
+    ast::{parse_stream, Options, Severity},
+    standard::Dialect,
+    target::Target,
+};
 
 #[derive(Parser)]
 struct Args {
@@ -18,26 +22,102 @@ struct Args {
 
     #[clap(short, long)]
     compile: bool,
+
+    /// Revision of the C standard, such as `c89` or `c11`, or its GNU dialect, such as
+    /// `gnu11`
+    #[clap(long, default_value_t)]
+    std: Dialect,
+
+    /// Warn about GNU extensions when they are not part of the selected dialect
+    #[clap(long)]
+    pedantic: bool,
+
+    /// Like `--pedantic`, but report GNU extensions as errors
+    #[clap(long)]
+    pedantic_errors: bool,
+
+    /// Target whose ABI lays types out, such as `x86_64` or `i386`, or a target triple
+    #[clap(long, default_value_t)]
+    target: Target,
+
+    /// Print the layout of every struct and union
+    #[clap(long = "fdump-record-layouts")]
+    dump_record_layouts: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    // Accept the GCC spellings `-std=c89`, `-pedantic` and `-fdump-record-layouts` along
+    // with `--std=c89`, `--pedantic` and `--fdump-record-layouts`
+    let args = Args::parse_from(std::env::args_os().map(|arg| match arg.to_str() {
+        Some(option @ ("-pedantic" | "-pedantic-errors" | "-fdump-record-layouts")) => {
+            format!("-{option}").into()
+        }
+        Some(option) if option.starts_with("-std=") => format!("-{option}").into(),
+        _ => arg,
+    }));
 
-    let file = std::fs::File::open(args.path)?;
+    let file = std::fs::File::open(&args.path)?;
 
     let content = unsafe { memmap2::Mmap::map(&file)? };
     let content = std::str::from_utf8(&content)?;
 
-    let parsed = rcc::lexer::parsers::parse_program(content);
-    let stream = rcc::lexer::stream::TokenStream::new(&parsed);
+    let mut preprocessor = rcc::preprocess(content);
+    let parsed: Vec<_> = rcc::lexer::parse_tokens(preprocessor.by_ref())
+        .with_dialect(args.std)
+        .collect();
+    let pedantic = if args.pedantic_errors {
+        Some(Severity::Error)
+    } else if args.pedantic {
+        Some(Severity::Warning)
+    } else {
+        None
+    };
+    let options = Options {
+        standard: args.std.standard,
+        gnu_extensions: args.std.gnu_extensions,
+        pedantic,
+    };
 
     for token in &parsed {
         print!("{} ", token);
     }
     println!();
 
-    let program = parse_stream(stream);
-    for node in program {
+    let (program, errors) = parse_stream(rcc::lexer::TokenStream::new(&parsed), options);
+    for e in &errors {
+        let (line, column) = e.span().map_or((0, 0), |s| s.line_col());
+        let severity = e.severity();
+        eprintln!("{}:{line}:{column}: {severity}: {e}", args.path.display());
+    }
+    let resolution = rcc::sema::resolve(&program, args.std.standard);
+    let mut types = rcc::sema::TypeContext::new().with_target(args.target);
+    let mut typing = rcc::sema::check(&program, &resolution, &mut types);
+    typing.fold(&resolution, &mut types);
+    if args.dump_record_layouts {
+        for record in types.laid_out() {
+            print!("\n{}", types.dump_layout(record));
+        }
+    }
+    let diagnostics = preprocessor
+        .diagnostics()
+        .iter()
+        .chain(resolution.diagnostics())
+        .chain(typing.diagnostics());
+    for d in diagnostics.clone() {
+        let (line, column) = d.line_col(content).unwrap_or((0, 0));
+        eprintln!(
+            "{}:{line}:{column}: {}: {d}",
+            args.path.display(),
+            d.severity
+        );
+    }
+    let is_error = |severity| severity == Severity::Error;
+    if errors.iter().any(|e| is_error(e.severity()))
+        || diagnostics.clone().any(|d| is_error(d.severity))
+    {
+        std::process::exit(1);
+    }
+    for node in program.iter() {
         println!("{node} ");
     }
 
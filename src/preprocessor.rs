@@ -4,14 +4,17 @@ mod executor;
 mod iterator;
 mod parser;
 
-pub(crate) use executor::Executor;
+pub use executor::Executor as PreprocessorExecutor;
+pub(crate) use iterator::PreprocessorTokenIter;
 pub(crate) use parser::SpanType;
 
-pub fn preprocess<'i>(source: &'i str) -> executor::Executor<'i, iterator::PreprocessorTokenIter> {
+pub fn preprocess<'i>(
+    source: &'i str,
+) -> executor::Executor<'i, iterator::PreprocessorTokenIter<'i>> {
     execute(parse_preprocessor(source))
 }
 
-pub(crate) fn parse_preprocessor(source: &str) -> iterator::PreprocessorTokenIter {
+pub(crate) fn parse_preprocessor(source: &str) -> iterator::PreprocessorTokenIter<'_> {
     iterator::PreprocessorTokenIter::new(source)
 }
 pub(crate) fn execute<'i, I>(iter: I) -> executor::Executor<'i, I>
@@ -1,8 +1,9 @@
+mod condition;
 mod r#macro;
 
 use std::collections::HashMap;
 
-use crate::{preprocessor::SpanType, span::Span};
+use crate::{lexer::token::Ident, preprocessor::SpanType, sema::Diagnostic, span::Span};
 
 pub use r#macro::Macro;
 
@@ -13,6 +14,9 @@ where
     iter: I,
     defines: HashMap<&'i str, Macro<'i>>,
     r#macro: Vec<Span<'i, SpanType>>,
+    /// Conditional groups being included, whose `#endif` is still ahead
+    depth: usize,
+    diagnostics: Vec<Diagnostic<'i>>,
 }
 impl<'i, I> Executor<'i, I>
 where
@@ -23,6 +27,206 @@ where
             iter,
             defines: Default::default(),
             r#macro: Vec::with_capacity(0),
+            depth: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Problems found in the directives run so far
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic<'i>] {
+        &self.diagnostics
+    }
+
+    /// The name of the directive after a `#`, or `None` for the null directive
+    fn directive(&mut self) -> Option<Span<'i, SpanType>> {
+        for span in self.iter.by_ref() {
+            if span == "\n" || span.extra.is_eof() {
+                self.r#macro.extend(span.extra.is_eof().then_some(span));
+                return None;
+            } else if !span.extra.is_trivia() {
+                return Some(span);
+            }
+        }
+        None
+    }
+
+    /// The tokens up to the end of the directive's line, without trivia
+    fn line(&mut self) -> Vec<Span<'i, SpanType>> {
+        let mut tokens = Vec::new();
+        for span in self.iter.by_ref() {
+            if span == "\n" {
+                break;
+            } else if span.extra.is_eof() {
+                self.r#macro.push(span);
+                break;
+            } else if !span.extra.is_trivia() {
+                tokens.push(span);
+            }
+        }
+        tokens
+    }
+
+    /// The macro name an `#ifdef`, `#ifndef` or `#undef` directive names
+    fn name(&mut self, directive: Span<'i, SpanType>) -> &'i str {
+        match self.line().first() {
+            Some(name) if name.extra.is_identifier() => name.get(),
+            _ => panic!("macro name missing in #{}", directive.get()),
+        }
+    }
+
+    /// Whether the macro an `#ifdef`, `#ifndef`, `#elifdef` or `#elifndef` directive
+    /// names is defined
+    fn is_defined(&mut self, directive: Span<'i, SpanType>) -> bool {
+        let name = self.name(directive);
+        self.defines.contains_key(name)
+    }
+
+    /// Whether the condition of an `#if` or `#elif` holds. Conditions that cannot be
+    /// evaluated are reported at the directive, and do not hold.
+    fn condition(&mut self, directive: Span<'i, SpanType>) -> bool {
+        let line = self.line();
+        let tokens = self.expand(line);
+        condition::evaluate(&tokens).unwrap_or_else(|error| {
+            let at = Ident::new(directive.get());
+            self.diagnostics.push(Diagnostic::error(at, error));
+            false
+        })
+    }
+
+    /// Replaces `defined` operators and macros in a condition. Names are not expanded
+    /// again inside their own expansion.
+    fn expand(&self, line: Vec<Span<'i, SpanType>>) -> Vec<Span<'i, SpanType>> {
+        let mut pending: Vec<_> = line.into_iter().rev().collect();
+        let mut tokens = Vec::new();
+        // Each macro being expanded, with the number of pending tokens past its body
+        let mut expanding: Vec<(&str, usize)> = Vec::new();
+        while let Some(token) = pending.pop() {
+            while expanding
+                .last()
+                .is_some_and(|&(_, base)| base > pending.len())
+            {
+                expanding.pop();
+            }
+            if token == "defined" {
+                let mut name = pending.pop();
+                let is_parenthesized = name.is_some_and(|name| name == "(");
+                if is_parenthesized {
+                    name = pending.pop();
+                }
+                let name = name
+                    .filter(|name| name.extra.is_identifier())
+                    .expect("macro name missing after 'defined'");
+                if is_parenthesized && pending.pop().is_none_or(|close| close != ")") {
+                    panic!("missing ')' after 'defined'");
+                }
+                let value = if self.defines.contains_key(name.get()) {
+                    "1"
+                } else {
+                    "0"
+                };
+                tokens.push(Span::new_with(value, 0, 1, SpanType::LiteralNum));
+                continue;
+            }
+            let Some(r#macro) = self
+                .defines
+                .get(token.get())
+                .filter(|_| !expanding.iter().any(|&(name, _)| token == name))
+            else {
+                tokens.push(token);
+                continue;
+            };
+            let base = pending.len();
+            if r#macro.is_function_like() {
+                if pending.last().is_none_or(|open| *open != "(") {
+                    tokens.push(token);
+                    continue;
+                }
+                pending.pop();
+                let mut arguments = Vec::with_capacity(r#macro.args());
+                let mut argument = Vec::new();
+                let mut nesting = 0;
+                loop {
+                    let span = pending
+                        .pop()
+                        .unwrap_or_else(|| panic!("unterminated call of macro {token}"));
+                    if span == ")" && nesting == 0 {
+                        arguments.push(argument);
+                        break;
+                    } else if span == "," && nesting == 0 {
+                        arguments.push(std::mem::take(&mut argument));
+                        continue;
+                    } else if span == "(" {
+                        nesting += 1;
+                    } else if span == ")" {
+                        nesting -= 1;
+                    }
+                    argument.push(span);
+                }
+                let base = pending.len();
+                pending.extend(
+                    r#macro
+                        .apply(arguments)
+                        .into_iter()
+                        .filter(|span| !span.extra.is_trivia()),
+                );
+                expanding.push((r#macro.name(), base));
+            } else {
+                pending.extend(
+                    r#macro
+                        .value()
+                        .into_iter()
+                        .rev()
+                        .filter(|span| !span.extra.is_trivia()),
+                );
+                expanding.push((r#macro.name(), base));
+            }
+        }
+        tokens
+    }
+
+    /// Skips the rest of a conditional group, up to the `#elif` or `#else` of the group
+    /// to include if `can_include`, or else to the `#endif`. Returns whether a group was
+    /// found to include, the conditional then still being open.
+    fn skip(&mut self, can_include: bool) -> bool {
+        let mut nesting = 0usize;
+        while let Some(span) = self.iter.next() {
+            if span.extra.is_eof() {
+                break;
+            } else if span != "#" || !span.is_at_line_start() {
+                continue;
+            }
+            let Some(directive) = self.directive() else {
+                continue;
+            };
+            match directive.get() {
+                "if" | "ifdef" | "ifndef" => nesting += 1,
+                "endif" if nesting == 0 => {
+                    self.line();
+                    return false;
+                }
+                "endif" => nesting -= 1,
+                "else" if nesting == 0 && can_include => {
+                    self.line();
+                    return true;
+                }
+                "elif" if nesting == 0 && can_include && self.condition(directive) => return true,
+                "elifdef" | "elifndef" if nesting == 0 && can_include => {
+                    let is_defined = self.is_defined(directive);
+                    if is_defined == (directive == "elifdef") {
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        panic!("unterminated conditional directive");
+    }
+
+    /// Enters a conditional whose first group is included if `is_included`
+    fn conditional(&mut self, is_included: bool) {
+        if is_included || self.skip(true) {
+            self.depth += 1;
         }
     }
 }
@@ -38,12 +242,53 @@ where
         }
         while let Some(span) = self.iter.next() {
             if span == "#" {
-                let macro_type = self.iter.next().unwrap();
-                if macro_type == "define" {
-                    let mac = Macro::new_from(self);
-                    self.defines.insert(mac.name(), mac);
-                } else {
-                    todo!("preprocessor directive {macro_type} not implemented");
+                if let Some(directive) = self.directive() {
+                    match directive.get() {
+                        "define" => {
+                            // Taken up to the end of the line first, so that the end of file
+                            // ending it is kept
+                            let mut line = Vec::new();
+                            while let Some(span) = self.next() {
+                                if span == "\n" {
+                                    break;
+                                } else if span.extra.is_eof() {
+                                    self.r#macro.push(span);
+                                    break;
+                                }
+                                line.push(span);
+                            }
+                            let mac = Macro::new_from(&mut line.into_iter());
+                            self.defines.insert(mac.name(), mac);
+                        }
+                        "undef" => {
+                            let name = self.name(directive);
+                            self.defines.remove(name);
+                        }
+                        "if" => {
+                            let is_included = self.condition(directive);
+                            self.conditional(is_included);
+                        }
+                        "ifdef" | "ifndef" => {
+                            let is_defined = self.is_defined(directive);
+                            self.conditional(is_defined == (directive == "ifdef"));
+                        }
+                        // The group before was included, so the rest of the conditional is not
+                        "elif" | "elifdef" | "elifndef" | "else" => {
+                            assert!(self.depth > 0, "#{} without #if", directive.get());
+                            self.skip(false);
+                            self.depth -= 1;
+                        }
+                        "endif" => {
+                            assert!(self.depth > 0, "#endif without #if");
+                            self.line();
+                            self.depth -= 1;
+                        }
+                        _ => todo!("preprocessor directive {directive} not implemented"),
+                    }
+                }
+                // The end of file ending the directive
+                if let Some(eof) = self.r#macro.pop() {
+                    return Some(eof);
                 }
             } else if let Some(r#macro) = self.defines.get(span.get()) {
                 if r#macro.is_function_like() {
@@ -51,7 +296,7 @@ where
                     let mut argument = Vec::new();
 
                     for span in self.iter.by_ref() {
-                        if span.extra.is_whitespace() {
+                        if span.extra.is_trivia() {
                             continue;
                         } else if span == "(" {
                             break;
@@ -89,3 +334,103 @@ where
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocessor::preprocess;
+
+    /// The identifiers left in `source` once preprocessed
+    fn identifiers(source: &str) -> Vec<&str> {
+        preprocess(source)
+            .filter(|span| span.extra.is_identifier())
+            .map(|span| span.get())
+            .collect()
+    }
+
+    #[test]
+    fn test_conditionals() {
+        const SOURCE: &str = "#define A 2
+#define F(x, y) ((x) * (y))
+#if F(A, 3) == 6 && defined(A) && !defined B
+yes1
+#elif 1
+no1
+#else
+no2
+#endif
+#ifdef B
+no3
+#  if 1 / 0
+no4
+#  endif
+#elif -1 < 0u
+no5
+#elifndef C
+yes2
+#else
+no6
+#endif
+#undef A
+#if 0 && 1 / 0 || !defined A
+yes3
+#endif
+";
+        assert_eq!(identifiers(SOURCE), ["yes1", "yes2", "yes3"]);
+    }
+
+    #[test]
+    fn test_condition_values() {
+        const SOURCE: &str = "#define SELF SELF + 1
+#if SELF && 'ab' == 24930 && 0x8000000000000000 > 0 && (-1 >> 1) == -1
+yes1
+#endif
+#if (1 ? -1 : 0u) > 0 && -1 > 0u && undefined == 0
+yes2
+#endif
+";
+        assert_eq!(identifiers(SOURCE), ["yes1", "yes2"]);
+    }
+
+    #[test]
+    fn test_condition_errors() {
+        let source = "#if 1 / 0
+no1
+#elif 1 +
+no2
+#else
+yes1
+#endif
+#define LAST yes2
+LAST";
+        let mut preprocessor = preprocess(source);
+        let identifiers: Vec<_> = preprocessor
+            .by_ref()
+            .filter(|span| span.extra.is_identifier())
+            .map(|span| span.get())
+            .collect();
+        assert_eq!(identifiers, ["yes1", "yes2"]);
+        let diagnostics: Vec<_> = preprocessor
+            .diagnostics()
+            .iter()
+            .map(|d| (d.line_col(source), d.to_string()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                (Some((1, 2)), "division by zero is undefined".to_string()),
+                (Some((3, 2)), "expected value in expression".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directive_at_end_of_file() {
+        for source in ["x\n#define A 1", "#if 1\nx\n#endif", "x\n#"] {
+            let spans: Vec<_> = preprocess(source)
+                .filter(|span| !span.extra.is_trivia())
+                .collect();
+            assert_eq!(spans.len(), 2, "{source:?} gave {spans:?}");
+            assert!(spans[1].extra.is_eof(), "{source:?} lost its end of file");
+        }
+    }
+}
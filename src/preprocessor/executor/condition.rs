@@ -0,0 +1,222 @@
+//! Conditions of `#if` and `#elif`, integer constant expressions over the tokens of the
+//! directive once macros and `defined` are replaced, in which every value has type
+//! `intmax_t` or `uintmax_t`.
+
+use crate::{
+    ast::tree::{BinaryOp, UnaryOp},
+    lexer::token::{int_value, Literal},
+    preprocessor::SpanType,
+    sema::{constant, IntKind},
+    span::Span,
+    target::Target,
+};
+
+/// `intmax_t` is `long long` on every target, so its width is the same on all of them
+const TARGET: &Target = &Target::X86_64;
+
+/// Whether the condition made of `tokens` holds
+pub(super) fn evaluate(tokens: &[Span<'_, SpanType>]) -> Result<bool, String> {
+    let mut condition = Condition {
+        tokens,
+        position: 0,
+    };
+    let value = condition.conditional(true)?;
+    match condition.tokens.get(condition.position) {
+        Some(token) => Err(format!(
+            "token is not a valid binary operator in a preprocessor subexpression: '{}'",
+            token.get()
+        )),
+        None => Ok(value.value != 0),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Value {
+    value: i128,
+    is_unsigned: bool,
+}
+impl Value {
+    const fn int(value: i128) -> Self {
+        Self {
+            value,
+            is_unsigned: false,
+        }
+    }
+
+    fn kind(is_unsigned: bool) -> IntKind {
+        if is_unsigned {
+            IntKind::ULongLong
+        } else {
+            IntKind::LongLong
+        }
+    }
+}
+
+fn binary_op(token: &str) -> Option<BinaryOp> {
+    Some(match token {
+        "*" => BinaryOp::Mul,
+        "/" => BinaryOp::Div,
+        "%" => BinaryOp::Mod,
+        "+" => BinaryOp::Add,
+        "-" => BinaryOp::Sub,
+        "<<" => BinaryOp::Shl,
+        ">>" => BinaryOp::Shr,
+        "<" => BinaryOp::Less,
+        ">" => BinaryOp::Greater,
+        "<=" => BinaryOp::LessEquals,
+        ">=" => BinaryOp::GreaterEquals,
+        "==" => BinaryOp::Equals,
+        "!=" => BinaryOp::NotEquals,
+        "&" => BinaryOp::BitAnd,
+        "^" => BinaryOp::BitXor,
+        "|" => BinaryOp::BitOr,
+        "&&" => BinaryOp::And,
+        "||" => BinaryOp::Or,
+        _ => return None,
+    })
+}
+
+/// A recursive descent over the tokens. Operands that are not evaluated, behind `&&`,
+/// `||` or `?:`, may divide by zero or overflow freely.
+struct Condition<'t, 'i> {
+    tokens: &'t [Span<'i, SpanType>],
+    position: usize,
+}
+impl<'t, 'i> Condition<'t, 'i> {
+    fn peek(&self) -> Option<&'i str> {
+        self.tokens.get(self.position).map(Span::get)
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.peek() != Some(token) {
+            return Err(format!("expected '{token}' in preprocessor expression"));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn conditional(&mut self, is_evaluated: bool) -> Result<Value, String> {
+        let condition = self.binary(BinaryOp::Or.precedence(), is_evaluated)?;
+        if self.peek() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let is_true = condition.value != 0;
+        let then = self.conditional(is_evaluated && is_true)?;
+        self.expect(":")?;
+        let r#else = self.conditional(is_evaluated && !is_true)?;
+        // Both operands are brought to their common type
+        let is_unsigned = then.is_unsigned || r#else.is_unsigned;
+        let chosen = if is_true { then } else { r#else };
+        Ok(Value {
+            value: constant::wrap(chosen.value, Value::kind(is_unsigned), TARGET),
+            is_unsigned,
+        })
+    }
+
+    fn binary(&mut self, min_precedence: u8, is_evaluated: bool) -> Result<Value, String> {
+        let mut lhs = self.unary(is_evaluated)?;
+        while let Some(op) = self.peek().and_then(binary_op) {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let is_rhs_evaluated = is_evaluated
+                && match op {
+                    BinaryOp::And => lhs.value != 0,
+                    BinaryOp::Or => lhs.value == 0,
+                    _ => true,
+                };
+            let rhs = self.binary(precedence + 1, is_rhs_evaluated)?;
+            lhs = apply(op, lhs, rhs, is_rhs_evaluated)?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self, is_evaluated: bool) -> Result<Value, String> {
+        let Some(token) = self.tokens.get(self.position) else {
+            return Err("expected value in expression".to_owned());
+        };
+        self.position += 1;
+        let op = match token.get() {
+            "+" => UnaryOp::Plus,
+            "-" => UnaryOp::Minus,
+            "~" => UnaryOp::BitNot,
+            "!" => UnaryOp::Not,
+            "(" => {
+                let value = self.conditional(is_evaluated)?;
+                self.expect(")")?;
+                return Ok(value);
+            }
+            _ => return primary(token),
+        };
+        let operand = self.unary(is_evaluated)?;
+        let is_unsigned = operand.is_unsigned && op != UnaryOp::Not;
+        match constant::unary(op, operand.value, Value::kind(operand.is_unsigned), TARGET) {
+            Ok(value) => Ok(Value { value, is_unsigned }),
+            Err(_) if !is_evaluated => Ok(Value::int(0)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
+
+fn primary(token: &Span<'_, SpanType>) -> Result<Value, String> {
+    match token.extra {
+        SpanType::LiteralNum => match Literal::parse_number(token.get()) {
+            Some(Literal::Int(number)) => {
+                let (value, suffix) = int_value(number).unwrap_or_default();
+                Ok(Value {
+                    value: i128::from(value),
+                    is_unsigned: suffix.is_unsigned() || value > i64::MAX as u64,
+                })
+            }
+            Some(_) => Err("floating point literal in preprocessor expression".to_owned()),
+            None => Err(format!("invalid integer constant '{}'", token.get())),
+        },
+        SpanType::Char => constant::char_value(token.get(), TARGET)
+            .map(Value::int)
+            .ok_or_else(|| format!("invalid character constant {}", token.get())),
+        // C23 keeps its boolean constants, every other name left is zero
+        SpanType::Identifier => Ok(Value::int(i128::from(token == &"true"))),
+        _ => Err(format!(
+            "invalid token at start of a preprocessor expression: '{}'",
+            token.get()
+        )),
+    }
+}
+
+/// Applies `op` after the usual arithmetic conversions, where an unsigned operand makes
+/// both unsigned
+fn apply(op: BinaryOp, lhs: Value, rhs: Value, is_evaluated: bool) -> Result<Value, String> {
+    let is_unsigned = match op {
+        BinaryOp::Shl | BinaryOp::Shr => lhs.is_unsigned,
+        BinaryOp::And | BinaryOp::Or => false,
+        _ => lhs.is_unsigned || rhs.is_unsigned,
+    };
+    let kind = Value::kind(is_unsigned);
+    let (lhs, rhs) = match op {
+        BinaryOp::Shl | BinaryOp::Shr => (lhs.value, rhs.value),
+        _ => (
+            constant::wrap(lhs.value, kind, TARGET),
+            constant::wrap(rhs.value, kind, TARGET),
+        ),
+    };
+    let is_comparison = matches!(
+        op,
+        BinaryOp::Less
+            | BinaryOp::Greater
+            | BinaryOp::LessEquals
+            | BinaryOp::GreaterEquals
+            | BinaryOp::Equals
+            | BinaryOp::NotEquals
+    );
+    match constant::binary(op, lhs, rhs, kind, TARGET) {
+        Ok(value) => Ok(Value {
+            value,
+            is_unsigned: is_unsigned && !is_comparison,
+        }),
+        Err(_) if !is_evaluated => Ok(Value::int(0)),
+        Err(error) => Err(error.to_string()),
+    }
+}
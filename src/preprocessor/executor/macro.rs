@@ -11,7 +11,7 @@ impl<'i> Macro<'i> {
     pub(crate) fn new_from(iter: &mut impl Iterator<Item = Span<'i, SpanType>>) -> Self {
         // iter.next(); // skip whitespace
         let name = iter
-            .find(|s| !s.extra.is_whitespace())
+            .find(|s| !s.extra.is_trivia())
             .filter(|s| s.extra.is_identifier())
             .expect("Expected identifier");
         let mut args;
@@ -19,12 +19,12 @@ impl<'i> Macro<'i> {
 
         // Does it have arguments?
 
-        match iter.find(|s| !s.extra.is_whitespace()) {
+        match iter.find(|s| !s.extra.is_trivia()) {
             Some(span) if span == "(" => {
                 args = Vec::new();
 
                 // take arguments
-                while let Some(span) = iter.find(|s| !s.extra.is_whitespace()) {
+                while let Some(span) = iter.find(|s| !s.extra.is_trivia()) {
                     match span {
                         span if span == ")" => {
                             break;
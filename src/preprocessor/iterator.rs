@@ -1,6 +1,6 @@
 use crate::span::Span;
 
-use super::{SpanType, parser::take_preprocessing_seq};
+use super::{parser::take_preprocessing_seq, SpanType};
 
 pub struct PreprocessorTokenIter<'i> {
     source: &'i str,
@@ -1,39 +1,121 @@
 use crate::span::Span;
 
+/// Punctuators sorted so that the longest candidate is always tried first.
+const PUNCTUATORS: &[&str] = &[
+    "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=",
+    "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "[", "]", "(", ")", "{", "}", ".", "&", "*",
+    "+", "-", "~", "!", "/", "%", "<", ">", "^", "|", "?", ":", ";", "=", ",", "#",
+];
+
 fn take_escape_seq<I>(iter: &mut I)
 where
     I: Iterator<Item = (usize, char)>,
 {
-    let (_, c) = iter.next().unwrap();
-    match c {
-        '\\' | '\n' => {}
-        _ => panic!("invalid escape sequence"),
+    if iter.next().is_none() {
+        panic!("invalid escape sequence");
     }
 }
 
-fn take_string<I>(iter: &mut I) -> usize
+/// Takes a string literal or character constant, ending in `quote`
+fn take_quoted<I>(iter: &mut I, quote: char) -> usize
 where
     I: Iterator<Item = (usize, char)>,
 {
     loop {
-        let (i, c) = iter.next().unwrap();
+        let (i, c) = iter.next().expect("unterminated literal");
         match c {
             '\\' => take_escape_seq(iter),
-            '"' => break i,
-            '\n' => panic!("unterminated string literal"),
+            c if c == quote => break i + 1,
+            '\n' => panic!("unterminated literal"),
             _ => {}
         }
     }
 }
 
+/// Takes a preprocessing number, which is a superset of integer and floating constants
+fn take_number(source: &str, start: usize) -> usize {
+    let mut end = start;
+    let mut prev = '\0';
+    for c in source[start..].chars() {
+        let is_exponent_sign =
+            (c == '+' || c == '-') && matches!(prev, 'e' | 'E' | 'p' | 'P');
+        if crate::is_valid_for_ident(c) || c == '.' || is_exponent_sign {
+            end += c.len_utf8();
+            prev = c;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// Returns the length of the encoding prefix at the start of `s`, as `L` or `u8`, if a
+/// string literal or character constant follows it
+fn encoding_prefix_len(s: &str) -> Option<usize> {
+    ["u8", "u", "U", "L"]
+        .into_iter()
+        .find(|prefix| {
+            s.strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with(['"', '\'']))
+        })
+        .map(str::len)
+}
+
+/// Returns the length of the newline at the start of `s`, if there is one.
+fn newline_len(s: &str) -> Option<usize> {
+    if s.starts_with('\n') {
+        Some(1)
+    } else if s.starts_with("\r\n") {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the line continuation (`\` followed by a newline)
+/// at the start of `s`, if there is one.
+fn line_continuation_len(s: &str) -> Option<usize> {
+    s.strip_prefix('\\')
+        .and_then(newline_len)
+        .map(|len| len + 1)
+}
+
+/// Takes a `// ...` comment, honoring line continuations. Stops before the newline.
+fn take_line_comment(source: &str, start: usize) -> usize {
+    let mut end = start + 2;
+    while end < source.len() {
+        let rest = &source[end..];
+        if let Some(len) = line_continuation_len(rest) {
+            end += len;
+        } else if newline_len(rest).is_some() {
+            break;
+        } else {
+            end += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    end
+}
+
+/// Takes a `/* ... */` comment.
+fn take_block_comment(source: &str, start: usize) -> usize {
+    match source[start + 2..].find("*/") {
+        Some(end) => start + 2 + end + 2,
+        None => panic!("unterminated comment"),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, derive_more::IsVariant)]
 pub enum SpanType {
+    /// Blanks, or a single newline
     Whitespace,
 
     Eof,
 
     Comment,
 
+    /// A backslash immediately followed by a newline
+    LineContinuation,
+
     /// Literal values such as numbers
     /// except string
     LiteralNum,
@@ -41,6 +123,9 @@ pub enum SpanType {
     /// String literal
     String,
 
+    /// Character constant
+    Char,
+
     /// Includes identifiers and keywords
     Identifier,
 
@@ -48,66 +133,119 @@ pub enum SpanType {
 
     Operator,
 
+    /// A character that starts no preprocessing token, such as `@` or a `\` that does
+    /// not end a line
+    Invalid,
+
     #[default]
     None,
 }
+impl SpanType {
+    /// Whether the span carries no meaning for the parser (whitespace, newlines,
+    /// comments and line continuations)
+    #[must_use]
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace | Self::Comment | Self::LineContinuation
+        )
+    }
+}
 
 /// Takes a source string and an offset, then returns the next span that is a valid
 /// preprocessing token.
-pub(crate) fn take_preprocessing_seq(source: &str, start: usize) -> Option<Span<SpanType>> {
-    let mut iter = source.char_indices().skip(start).peekable();
+pub(crate) fn take_preprocessing_seq(source: &str, start: usize) -> Option<Span<'_, SpanType>> {
+    let rest = source.get(start..)?;
+    let mut iter = rest.char_indices().map(|(i, c)| (start + i, c)).peekable();
     let (index, char) = iter.next()?;
-    let next = iter.peek().map(|&c| c.1);
     match char {
-        // Combine operators
-        '+' | '-' | '<' | '>' | '=' => {
-            if let Some(next) = next {
-                if next == char || next == '=' {
-                    return Some(Span::new_with(source, start, index + 2, SpanType::Operator));
-                } else {
-                    return Some(Span::new_with(source, start, index + 1, SpanType::Operator));
-                }
-            } else {
-                return Some(Span::new(source, start, index + 1));
-            }
+        // Comments
+        '/' if rest.starts_with("//") => Some(Span::new_with(
+            source,
+            start,
+            take_line_comment(source, start),
+            SpanType::Comment,
+        )),
+        '/' if rest.starts_with("/*") => Some(Span::new_with(
+            source,
+            start,
+            take_block_comment(source, start),
+            SpanType::Comment,
+        )),
+        '"' => {
+            let end = take_quoted(&mut iter, '"');
+            Some(Span::new_with(source, start, end, SpanType::String))
         }
-        // Punctuation
-        '(' | ')' | '{' | '}' | ',' | '#' | '?' | ':' | ';' => {
-            return Some(Span::new_with(
-                source,
-                index,
-                index + 1,
-                SpanType::Punctuation,
-            ));
+        '\'' => {
+            let end = take_quoted(&mut iter, '\'');
+            Some(Span::new_with(source, start, end, SpanType::Char))
         }
-        '"' => {
-            let end = take_string(&mut iter);
-            return Some(Span::new_with(source, start, end, SpanType::String));
+        c if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) =>
+        {
+            Some(Span::new_with(
+                source,
+                start,
+                take_number(source, start),
+                SpanType::LiteralNum,
+            ))
         }
+        '\\' => match line_continuation_len(rest) {
+            Some(len) => Some(Span::new_with(
+                source,
+                start,
+                start + len,
+                SpanType::LineContinuation,
+            )),
+            None => Some(Span::new_with(source, start, start + 1, SpanType::Invalid)),
+        },
+        // Newlines are kept on their own so directives can find where they end
+        _ if newline_len(rest).is_some() => Some(Span::new_with(
+            source,
+            start,
+            start + newline_len(rest).unwrap(),
+            SpanType::Whitespace,
+        )),
         c if c.is_ascii_whitespace() => {
-            if let Some((next, _)) = iter.find(|&(_, c)| !c.is_ascii_whitespace()) {
-                return Some(Span::new_with(source, start, next, SpanType::Whitespace));
-            } else {
-                return Some(Span::new_remaining_with(
-                    source,
-                    start,
-                    SpanType::Whitespace,
-                ));
-            }
+            let end = iter
+                .find(|&(i, c)| !c.is_ascii_whitespace() || newline_len(&source[i..]).is_some())
+                .map_or(source.len(), |(next, _)| next);
+            Some(Span::new_with(source, start, end, SpanType::Whitespace))
         }
-        c if crate::is_valid_for_ident(c) => {
-            if let Some((next, _)) = iter.find(|&(_, c)| !crate::is_valid_for_ident(c)) {
-                return Some(Span::new_with(source, start, next, SpanType::Identifier));
+        // The encoding prefix of a string literal or character constant, as in `L"wide"`
+        'L' | 'u' | 'U' if encoding_prefix_len(rest).is_some() => {
+            let len = encoding_prefix_len(rest).unwrap_or_default();
+            let quote = rest[len..].chars().next().unwrap_or_default();
+            let mut iter = iter.skip_while(|&(i, _)| i <= start + len);
+            let end = take_quoted(&mut iter, quote);
+            let ty = if quote == '"' {
+                SpanType::String
             } else {
-                return Some(Span::new_remaining_with(
-                    source,
-                    start,
-                    SpanType::Identifier,
-                ));
-            }
+                SpanType::Char
+            };
+            Some(Span::new_with(source, start, end, ty))
         }
-        x => {
-            todo!("Found char {x}")
+        c if crate::is_valid_for_ident(c) => {
+            let end = iter
+                .find(|&(_, c)| !crate::is_valid_for_ident(c))
+                .map_or(source.len(), |(next, _)| next);
+            Some(Span::new_with(source, start, end, SpanType::Identifier))
         }
+        _ => match PUNCTUATORS.iter().find(|p| rest.starts_with(**p)) {
+            Some(punct) => {
+                let ty = match *punct {
+                    "(" | ")" | "{" | "}" | "[" | "]" | "," | "#" | "##" | "?" | ":" | ";"
+                    | "..." => SpanType::Punctuation,
+                    _ => SpanType::Operator,
+                };
+                Some(Span::new_with(source, index, index + punct.len(), ty))
+            }
+            None => Some(Span::new_with(
+                source,
+                index,
+                index + char.len_utf8(),
+                SpanType::Invalid,
+            )),
+        },
     }
 }
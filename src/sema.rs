@@ -0,0 +1,68 @@
+//! Semantic analysis of the tree built by the parser: which declaration every name
+//! refers to, then the type of every expression, and the value of those that are
+//! constant.
+
+mod check;
+pub(crate) mod constant;
+mod resolve;
+mod symbols;
+mod typed;
+mod types;
+
+use std::fmt::Display;
+
+use crate::{ast::Severity, lexer::token::Ident, span::Span};
+
+pub use check::{check, Typing};
+pub use constant::{Base, ConstError, Constant, Evaluator};
+pub use resolve::{resolve, Resolution};
+pub use symbols::{Declaration, Linkage, Namespace, ScopeKind, Symbol, SymbolId, SymbolKind, Tag};
+pub use typed::{CastKind, ExprKind, TypedExpr, ValueCategory};
+pub use types::{
+    ArrayLength, DisplayType, DumpLayout, Enum, EnumId, FloatKind, IntKind, Member, Record,
+    RecordId, RecordLayout, Type, TypeContext, TypeId,
+};
+
+/// A problem with a program that parses, reported at the name it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic<'i> {
+    pub severity: Severity,
+    pub message: String,
+    at: Ident<'i>,
+}
+impl<'i> Diagnostic<'i> {
+    pub(crate) fn error(at: Ident<'i>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            at,
+        }
+    }
+
+    pub(crate) fn warning(at: Ident<'i>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            at,
+        }
+    }
+
+    /// The name the diagnostic is about
+    #[must_use]
+    pub fn at(&self) -> Ident<'i> {
+        self.at
+    }
+
+    /// Line and column of the name in `source`, the text the program was parsed from.
+    /// `None` if the name does not point into it.
+    #[must_use]
+    pub fn line_col(&self, source: &str) -> Option<(usize, usize)> {
+        let offset = (self.at.name.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+        (offset < source.len()).then(|| Span::<()>::new(source, offset, offset).line_col())
+    }
+}
+impl<'i> Display for Diagnostic<'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
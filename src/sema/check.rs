@@ -0,0 +1,2159 @@
+//! Type checking: gives every expression a type and a value category, makes the
+//! conversions C applies implicitly explicit, and reports operands that do not fit
+//! their operators.
+
+mod convert;
+mod expr;
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        tree::{
+            control::{self, ForInit, Label},
+            AlignmentSpecifier, ArraySize, Attribute, Attributes, BaseType, DeclSpecifiers,
+            Declarator, DesignatedInitializer, Designator, EnumSpecifier, Expression, FuncDecl,
+            Initializer, KnownAttribute, MemberItem, Parameters, RecordKind, Statement,
+            StaticAssert, StorageClass, TypeName, TypeofSpecifier, VarDecl,
+        },
+        Severity,
+    },
+    lexer::token::{Ident, Literal},
+    standard::Standard,
+};
+
+use super::{
+    constant::{self, ConstError, Evaluator},
+    resolve,
+    typed::TypedExpr,
+    ArrayLength, Declaration, Diagnostic, IntKind, Linkage, Resolution, SymbolId, SymbolKind, Type,
+    TypeContext, TypeId,
+};
+
+/// The types of the full expressions of a program
+#[derive(Debug, Default)]
+pub struct Typing<'a, 'i> {
+    /// Expressions are told apart by where they are in the tree, as names are
+    expressions: HashMap<*const Expression<'i>, TypedExpr<'a, 'i>>,
+    diagnostics: Vec<Diagnostic<'i>>,
+}
+impl<'a, 'i> Typing<'a, 'i> {
+    /// The typed version of `expr`, borrowed from the checked tree, when it is a full
+    /// expression: a statement, a condition, an initializer, a returned value or an
+    /// array length. Its value is converted as the context it appears in requires.
+    #[must_use]
+    pub fn expression(&self, expr: &Expression<'i>) -> Option<&TypedExpr<'a, 'i>> {
+        self.expressions.get(&(expr as *const _))
+    }
+
+    /// Invalid operands and conversions, in the order they appear
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic<'i>] {
+        &self.diagnostics
+    }
+
+    /// Replaces the constant subexpressions of every full expression with their values,
+    /// as in `x * (2 + 3)` which becomes `x * 5`
+    pub fn fold(&mut self, resolution: &Resolution<'a, 'i>, types: &mut TypeContext<'a, 'i>) {
+        let mut evaluator = Evaluator::new(resolution, types);
+        for typed in self.expressions.values_mut() {
+            evaluator.fold(typed);
+        }
+    }
+}
+
+/// Labels and `goto`s of a function, along with the variably modified declarations in
+/// scope where they appear
+#[derive(Default)]
+struct Jumps<'i> {
+    /// Names of the variably modified declarations, in order
+    variables: Vec<Ident<'i>>,
+    /// Indices in `variables` of the declarations in scope
+    scope: Vec<usize>,
+    labels: Vec<(Ident<'i>, Vec<usize>)>,
+    gotos: Vec<(Ident<'i>, Vec<usize>)>,
+}
+
+/// The `case` values of a switch seen so far
+struct Cases {
+    /// Promoted type of the controlling expression, which the values are converted to.
+    /// `None` if it is not an integer.
+    kind: Option<IntKind>,
+    /// Inclusive ranges of values, one value for a plain `case`
+    ranges: Vec<(i128, i128)>,
+}
+
+/// Where the items of an aggregate whose braces are elided start
+#[derive(Clone, Copy)]
+enum Start<'a, 'i> {
+    /// At the next item of the list
+    Next,
+    /// At the first scalar of the aggregate, which this expression initializes
+    Scalar(&'a Expression<'i>),
+    /// At the member or element the rest of the designators of the next item name
+    Designated(&'a [Designator<'i>]),
+}
+
+struct Checker<'c, 'a, 'i> {
+    resolution: &'c Resolution<'a, 'i>,
+    types: &'c mut TypeContext<'a, 'i>,
+    typing: Typing<'a, 'i>,
+    /// Name and return type of the function being checked
+    function: Option<(Ident<'i>, TypeId)>,
+    /// Whether the function being checked is declared never to return
+    is_noreturn: bool,
+    /// Whether the initializer being checked is that of an object with static storage
+    /// duration, whose values must be constant
+    is_static: bool,
+    /// Whether a `case` or `default` label comes right after the statement being
+    /// checked, as it must after a fallthrough annotation
+    is_label_next: bool,
+    /// Labels and `goto`s of the function being checked
+    jumps: Jumps<'i>,
+    /// Values of the switch being checked, `None` outside of any
+    cases: Option<Cases>,
+    /// Type of the objects and functions with linkage declared so far, as their
+    /// declarations complete it
+    declared: HashMap<SymbolId, TypeId>,
+}
+
+/// Checks the types of `program`, whose names are resolved by `resolution`, building
+/// them in `types`
+#[must_use]
+pub fn check<'a, 'i: 'a>(
+    program: &'a [Statement<'i>],
+    resolution: &Resolution<'a, 'i>,
+    types: &mut TypeContext<'a, 'i>,
+) -> Typing<'a, 'i> {
+    let mut checker = Checker::new(resolution, types);
+    checker.statements(program);
+    checker.typing
+}
+
+/// The type of `expr` as the operand of `typeof`, without reporting its problems
+pub(crate) fn expression_type<'a, 'i: 'a>(
+    resolution: &Resolution<'a, 'i>,
+    types: &mut TypeContext<'a, 'i>,
+    expr: &'a Expression<'i>,
+) -> TypeId {
+    Checker::new(resolution, types).expr(expr).ty
+}
+
+/// `expr` typed on its own as a value, without reporting its problems, as an integer
+/// constant expression in a type
+pub(crate) fn typed_expression<'a, 'i: 'a>(
+    resolution: &Resolution<'a, 'i>,
+    types: &mut TypeContext<'a, 'i>,
+    expr: &'a Expression<'i>,
+) -> TypedExpr<'a, 'i> {
+    Checker::new(resolution, types).value(expr)
+}
+
+/// The message given to a `deprecated` or `nodiscard` attribute, as it ends the
+/// diagnostics about it
+fn attribute_message(attribute: &Attribute<'_>) -> String {
+    let Some([Expression::String(ref message)]) = attribute.args.as_deref() else {
+        return String::new();
+    };
+    let text: String = message
+        .pieces
+        .iter()
+        .map(|piece| {
+            // Without the quotes and encoding prefix
+            piece
+                .split_once('"')
+                .and_then(|(_, text)| text.strip_suffix('"'))
+                .unwrap_or(piece)
+        })
+        .collect();
+    format!(": {text}")
+}
+
+/// The first expression of an initializer, where problems with it are reported
+fn first_expression<'a, 'i>(value: &'a Initializer<'i>) -> Option<&'a Expression<'i>> {
+    match value {
+        Initializer::Expression(expr) => Some(expr),
+        Initializer::List(items) => items.iter().find_map(|item| first_expression(&item.value)),
+    }
+}
+
+/// The first name or constant in `expr`, or the keyword or parenthesis it starts with,
+/// where problems with it are reported
+fn location<'i>(expr: &Expression<'i>) -> Ident<'i> {
+    match expr {
+        Expression::Literal(
+            Literal::Int(s) | Literal::Float(s) | Literal::Char(s) | Literal::String(s),
+        ) => Ident::new(s),
+        Expression::String(string) => Ident::new(string.pieces[0]),
+        Expression::Ident(name)
+        | Expression::LabelAddress(name)
+        | Expression::Offsetof { member: name, .. } => *name,
+        Expression::Unary { operand, .. }
+        | Expression::Postfix { operand, .. }
+        | Expression::SizeofExpr(operand)
+        | Expression::Cast { expr: operand, .. } => location(operand),
+        Expression::Binary { lhs, .. } | Expression::Assign { lhs, .. } => location(lhs),
+        Expression::Conditional { condition, .. } => location(condition),
+        Expression::Call { callee, .. } => location(callee),
+        Expression::Index { array, .. } => location(array),
+        Expression::Member { object, .. } => location(object),
+        Expression::Generic(generic) => location(&generic.controlling),
+        Expression::CompoundLiteral { paren: s, .. }
+        | Expression::SizeofType(s, _)
+        | Expression::AlignofType(s, _)
+        | Expression::Statement(s, _)
+        | Expression::TypesCompatible(s, ..)
+        | Expression::Error(s) => Ident::new(s),
+    }
+}
+
+impl<'c, 'a, 'i: 'a> Checker<'c, 'a, 'i> {
+    fn new(resolution: &'c Resolution<'a, 'i>, types: &'c mut TypeContext<'a, 'i>) -> Self {
+        Self {
+            resolution,
+            types,
+            typing: Typing::default(),
+            function: None,
+            is_noreturn: false,
+            is_static: false,
+            is_label_next: false,
+            jumps: Jumps::default(),
+            cases: None,
+            declared: HashMap::new(),
+        }
+    }
+
+    /// Reports a problem with `expr`, at its [`location`]
+    fn diagnose(&mut self, severity: Severity, expr: &Expression<'i>, message: String) {
+        self.diagnose_at(severity, location(expr), message);
+    }
+
+    /// Reports a problem at `at`
+    fn diagnose_at(&mut self, severity: Severity, at: Ident<'i>, message: String) {
+        let diagnostic = match severity {
+            Severity::Error => Diagnostic::error(at, message),
+            Severity::Warning => Diagnostic::warning(at, message),
+        };
+        self.typing.diagnostics.push(diagnostic);
+    }
+
+    fn error(&mut self, expr: &Expression<'i>, message: String) {
+        self.diagnose(Severity::Error, expr, message);
+    }
+
+    fn warning(&mut self, expr: &Expression<'i>, message: String) {
+        self.diagnose(Severity::Warning, expr, message);
+    }
+
+    /// `ty` as written in diagnostics
+    fn display(&self, ty: TypeId) -> String {
+        self.types.display(ty).to_string()
+    }
+
+    /// Whether `ty` could not be determined, in which case the problem is already
+    /// reported
+    fn is_error(&self, ty: TypeId) -> bool {
+        *self.types.get(self.types.unqualified(ty).0) == Type::Error
+    }
+
+    /// Types a full expression and records it, with its value converted as in
+    /// `x + 1`
+    fn full_expression(&mut self, expr: &'a Expression<'i>) -> TypeId {
+        self.full(expr, |checker| checker.value(expr))
+    }
+
+    /// Records the full expression `expr` as typed by `check`, which reports the
+    /// problems of its operands, along with the undefined operations of its constant
+    /// parts
+    fn full(
+        &mut self,
+        expr: &'a Expression<'i>,
+        check: impl FnOnce(&mut Self) -> TypedExpr<'a, 'i>,
+    ) -> TypeId {
+        let typed = check(self);
+        for error in Evaluator::new(self.resolution, self.types).undefined(&typed) {
+            self.warning(expr, error.to_string());
+        }
+        let ty = typed.ty;
+        self.typing.expressions.insert(expr as *const _, typed);
+        ty
+    }
+
+    /// Checks the controlling expression of `if` and loops, which is compared to zero
+    fn condition(&mut self, expr: &'a Expression<'i>) {
+        let ty = self.full_expression(expr);
+        if !self.is_error(ty) && !self.types.is_scalar(ty) {
+            let message = format!(
+                "statement requires expression of scalar type ('{}' invalid)",
+                self.display(ty)
+            );
+            self.error(expr, message);
+        }
+    }
+
+    /// Checks an expression that must have an integer type, such as the controlling
+    /// expression of `switch` or the length of an array
+    fn integer(&mut self, expr: &'a Expression<'i>, what: &str) {
+        let ty = self.full_expression(expr);
+        if !self.is_error(ty) && !self.types.is_integer(ty) {
+            let message = format!("{what} has non-integer type '{}'", self.display(ty));
+            self.error(expr, message);
+        }
+    }
+
+    /// Checks an integer constant expression, such as the width of a bit-field, and
+    /// gives its value
+    fn integer_constant(&mut self, expr: &'a Expression<'i>, what: &str) -> Option<i128> {
+        self.integer(expr, what);
+        let typed = self.typing.expression(expr)?;
+        if !self.types.is_integer(typed.ty) {
+            return None;
+        }
+        match Evaluator::new(self.resolution, self.types).integer(typed) {
+            Ok(value) => Some(value),
+            // Undefined operations are reported already, as warnings
+            Err(_) => {
+                let message = "expression is not an integer constant expression";
+                self.error(expr, message.to_string());
+                None
+            }
+        }
+    }
+
+    /// Reports the initializer `expr` of an object with static storage duration when
+    /// its value is not known before the program runs
+    fn static_value(&mut self, expr: &'a Expression<'i>) {
+        if !self.is_static {
+            return;
+        }
+        let Some(typed) = self.typing.expression(expr) else {
+            return;
+        };
+        // Aggregates are only checked element by element
+        if !self.types.is_scalar(typed.ty) {
+            return;
+        }
+        if let Err(ConstError::NotConstant) =
+            Evaluator::new(self.resolution, self.types).evaluate(typed)
+        {
+            let message = "initializer element is not a compile-time constant";
+            self.error(expr, message.to_string());
+        }
+    }
+
+    fn statements(&mut self, body: &'a [Statement<'i>]) {
+        let is_label = |next: &Statement<'_>| {
+            matches!(
+                next,
+                Statement::Labeled(control::Labeled {
+                    label: Label::Case { .. } | Label::Default,
+                    ..
+                })
+            )
+        };
+        // What follows the last statement is what follows the whole body
+        let is_label_after = self.is_label_next;
+        // The declarations of a block go out of scope at its end
+        let depth = self.jumps.scope.len();
+        for (n, statement) in body.iter().enumerate() {
+            let is_label_next = body.get(n + 1).map_or(is_label_after, is_label);
+            self.is_label_next = is_label_next;
+            self.statement(statement);
+            // The statement labels are attached to
+            let mut inner = statement;
+            while let Statement::Labeled(ref labeled) = *inner {
+                inner = &labeled.body;
+            }
+            let Statement::Attributed(ref attributes, _) = *inner else {
+                continue;
+            };
+            if let Some(fallthrough) = attributes.find(KnownAttribute::Fallthrough) {
+                if !is_label_next {
+                    let message = "fallthrough annotation does not directly precede switch label";
+                    self.diagnose_at(Severity::Error, fallthrough.name, message.to_string());
+                }
+            }
+        }
+        self.is_label_next = is_label_after;
+        self.jumps.scope.truncate(depth);
+    }
+
+    /// Checks the body of a loop or of a `switch`, after which no label comes
+    fn enclosed(&mut self, body: &'a [Statement<'i>]) {
+        let is_label_next = std::mem::replace(&mut self.is_label_next, false);
+        self.statements(body);
+        self.is_label_next = is_label_next;
+    }
+
+    fn statement(&mut self, statement: &'a Statement<'i>) {
+        match statement {
+            Statement::FuncDecl(func) => self.function(func),
+            Statement::VarDecl(decl) => self.var_decl(decl),
+            Statement::Typedef(typedef) => {
+                self.specifiers(&typedef.ty);
+                for declarator in typedef.names.iter() {
+                    self.declarator(declarator);
+                    self.variably_modified(Declaration {
+                        specifiers: &typedef.ty,
+                        declarator,
+                    });
+                }
+            }
+            Statement::StaticAssert(assert) => self.static_assert(assert),
+            Statement::If(r#if) => {
+                self.condition(&r#if.condition);
+                self.statements(&r#if.body);
+                if let Some(ref else_body) = r#if.else_body {
+                    self.statements(else_body);
+                }
+            }
+            Statement::While(r#while) => {
+                self.condition(&r#while.condition);
+                self.enclosed(&r#while.body);
+            }
+            Statement::DoWhile(do_while) => {
+                self.enclosed(&do_while.body);
+                self.condition(&do_while.condition);
+            }
+            Statement::For(r#for) => {
+                let depth = self.jumps.scope.len();
+                match r#for.init {
+                    Some(ForInit::Declaration(ref decl)) => self.var_decl(decl),
+                    Some(ForInit::Expression(ref expr)) => {
+                        self.full_expression(expr);
+                    }
+                    None => {}
+                }
+                if let Some(ref condition) = r#for.condition {
+                    self.condition(condition);
+                }
+                if let Some(ref step) = r#for.step {
+                    self.full_expression(step);
+                }
+                self.enclosed(&r#for.body);
+                self.jumps.scope.truncate(depth);
+            }
+            Statement::Switch(switch) => {
+                let ty = self.full_expression(&switch.condition);
+                if !self.is_error(ty) && !self.types.is_integer(ty) {
+                    let message = format!(
+                        "statement requires expression of integer type ('{}' invalid)",
+                        self.display(ty)
+                    );
+                    self.error(&switch.condition, message);
+                }
+                let cases = Cases {
+                    kind: self.types.int_kind(ty).map(IntKind::promoted),
+                    ranges: Vec::new(),
+                };
+                let outer = self.cases.replace(cases);
+                self.enclosed(&switch.body);
+                self.cases = outer;
+            }
+            Statement::Labeled(labeled) => {
+                match labeled.label {
+                    Label::Case { ref value, ref end } => {
+                        let low = self.integer_constant(value, "case value");
+                        let high = match end {
+                            Some(end) => self.integer_constant(end, "case value"),
+                            None => low,
+                        };
+                        if let (Some(low), Some(high)) = (low, high) {
+                            self.case(value, low, high);
+                        }
+                    }
+                    Label::Named(name) => {
+                        let scope = self.jumps.scope.clone();
+                        self.jumps.labels.push((name, scope));
+                    }
+                    Label::Default => {}
+                }
+                self.statement(&labeled.body);
+            }
+            Statement::ComputedGoto(expr) => {
+                self.full_expression(expr);
+            }
+            Statement::Expression(expr) => {
+                self.full_expression(expr);
+                self.discarded(expr);
+            }
+            Statement::Return(r#return) => self.r#return(r#return.value.as_ref()),
+            Statement::Compound(compound) => self.statements(&compound.body),
+            Statement::Asm(asm) => {
+                for operand in asm.outputs.iter() {
+                    self.full(&operand.expr, |checker| {
+                        let typed = checker.expr(&operand.expr);
+                        checker.assignable(&operand.expr, &typed);
+                        typed
+                    });
+                }
+                for operand in asm.inputs.iter() {
+                    self.full_expression(&operand.expr);
+                }
+                for &label in asm.labels.iter() {
+                    let scope = self.jumps.scope.clone();
+                    self.jumps.gotos.push((label, scope));
+                }
+            }
+            Statement::Attributed(_, statement) => self.statement(statement),
+            Statement::Goto(label) => {
+                let scope = self.jumps.scope.clone();
+                self.jumps.gotos.push((*label, scope));
+            }
+            Statement::Break | Statement::Continue | Statement::Null | Statement::Error => {}
+        }
+    }
+
+    fn function(&mut self, func: &'a FuncDecl<'i>) {
+        self.specifiers(&func.ret);
+        self.declarator(&func.declarator);
+        let declaration = Declaration {
+            specifiers: &func.ret,
+            declarator: &func.declarator,
+        };
+        self.redeclaration(declaration);
+        let Some(ref body) = func.body else {
+            return;
+        };
+        let ty = self.types.declaration(self.resolution, declaration);
+        let ret = match *self.types.get(ty) {
+            Type::Function { ret, .. } => ret,
+            _ => self.types.intern(Type::Error),
+        };
+        for decl in func.declarations.iter() {
+            self.var_decl(decl);
+        }
+        let symbol = resolve::declared_name(&func.declarator)
+            .and_then(|name| self.resolution.binding(name))
+            .map(|id| self.resolution.symbol(id));
+        let is_noreturn = func.ret.is_noreturn
+            || symbol.is_some_and(|symbol| symbol.attribute(KnownAttribute::Noreturn).is_some());
+        let outer = self.function.replace((func.name(), ret));
+        let was_noreturn = std::mem::replace(&mut self.is_noreturn, is_noreturn);
+        let outer_jumps = std::mem::take(&mut self.jumps);
+        self.statements(body);
+        let jumps = std::mem::replace(&mut self.jumps, outer_jumps);
+        self.jumps_into_scopes(jumps);
+        self.function = outer;
+        self.is_noreturn = was_noreturn;
+    }
+
+    /// Reports the `goto`s of a function that jump into the scope of a variably
+    /// modified declaration. Those to labels that do not exist are reported by the
+    /// parser.
+    fn jumps_into_scopes(&mut self, jumps: Jumps<'i>) {
+        for (name, scope) in jumps.gotos.iter() {
+            let Some((_, label_scope)) = jumps.labels.iter().find(|(label, _)| label == name)
+            else {
+                continue;
+            };
+            if let Some(&variable) = label_scope.iter().find(|v| !scope.contains(v)) {
+                let message = format!(
+                    "jump into the scope of variable length array '{}'",
+                    jumps.variables[variable]
+                );
+                self.diagnose_at(Severity::Error, *name, message);
+            }
+        }
+    }
+
+    /// Reports a declaration of an object or function with linkage whose type is not
+    /// compatible with that of its previous declarations, as in `int x; long x;`
+    fn redeclaration(&mut self, declaration: Declaration<'a, 'i>) {
+        let Some(name) = resolve::declared_name(declaration.declarator) else {
+            return;
+        };
+        let Some(id) = self.resolution.binding(name) else {
+            return;
+        };
+        let symbol = self.resolution.symbol(id);
+        let is_function = declaration.declarator.function_params().is_some();
+        // Other kinds of redeclarations are reported with the names
+        let is_same_kind = match symbol.kind {
+            SymbolKind::Object(_) => !is_function,
+            SymbolKind::Function(_) => is_function,
+            _ => false,
+        };
+        if !is_same_kind || symbol.linkage == Linkage::None {
+            return;
+        }
+        let ty = self.types.declaration(self.resolution, declaration);
+        let Some(previous) = self.declared.insert(id, ty) else {
+            return;
+        };
+        if !self.types.is_compatible(previous, ty) {
+            self.declared.insert(id, previous);
+            let message = format!("conflicting types for '{name}'");
+            self.diagnose_at(Severity::Error, *name, message);
+            return;
+        }
+        // Later declarations may leave out what earlier ones tell
+        let is_vaguer = match *self.types.get(ty) {
+            Type::Array { length, .. } => length == ArrayLength::Incomplete,
+            Type::Function { ref params, .. } => params.is_none(),
+            _ => false,
+        };
+        if is_vaguer {
+            self.declared.insert(id, previous);
+        }
+    }
+
+    /// Keeps track of a declaration in a function body when it is variably modified,
+    /// since `goto` must not jump into its scope
+    fn variably_modified(&mut self, declaration: Declaration<'a, 'i>) {
+        if self.function.is_none() {
+            return;
+        }
+        let Some(&name) = resolve::declared_name(declaration.declarator) else {
+            return;
+        };
+        let ty = self.types.declaration(self.resolution, declaration);
+        if self.types.is_variably_modified(ty) {
+            self.jumps.scope.push(self.jumps.variables.len());
+            self.jumps.variables.push(name);
+        }
+    }
+
+    /// Reports a `case` whose values, from `low` to `high`, are already those of
+    /// another `case` of the switch, once converted to the type of its controlling
+    /// expression
+    fn case(&mut self, value: &Expression<'i>, low: i128, high: i128) {
+        let target = self.types.target();
+        // Outside of a switch, which the parser reports
+        let Some(ref mut cases) = self.cases else {
+            return;
+        };
+        let (low, high) = match cases.kind {
+            Some(kind) => (
+                constant::wrap(low, kind, target),
+                constant::wrap(high, kind, target),
+            ),
+            None => (low, high),
+        };
+        if cases.ranges.iter().any(|&(l, h)| low <= h && l <= high) {
+            self.error(value, format!("duplicate case value '{low}'"));
+        } else {
+            cases.ranges.push((low, high));
+        }
+    }
+
+    /// Warns about the value of a call discarded by an expression statement, when the
+    /// function is declared `nodiscard`
+    fn discarded(&mut self, expr: &Expression<'i>) {
+        let Expression::Call { ref callee, .. } = *expr else {
+            return;
+        };
+        let Expression::Ident(ref name) = **callee else {
+            return;
+        };
+        let attribute = self.resolution.binding(name).and_then(|id| {
+            self.resolution
+                .symbol(id)
+                .attribute(KnownAttribute::Nodiscard)
+        });
+        if let Some(attribute) = attribute {
+            let message = format!(
+                "ignoring return value of function declared with '{}' attribute{}",
+                attribute.plain_name(),
+                attribute_message(attribute)
+            );
+            self.warning(expr, message);
+        }
+    }
+
+    /// Warns about a use of `name`, which refers to `id`, when it is declared
+    /// `deprecated`
+    fn deprecated(&mut self, name: &Ident<'i>, id: SymbolId) {
+        let symbol = self.resolution.symbol(id);
+        if let Some(attribute) = symbol.attribute(KnownAttribute::Deprecated) {
+            let message = format!("'{name}' is deprecated{}", attribute_message(attribute));
+            self.warning(&Expression::Ident(*name), message);
+        }
+    }
+
+    fn r#return(&mut self, value: Option<&'a Expression<'i>>) {
+        let Some((name, ret)) = self.function else {
+            return;
+        };
+        if self.is_noreturn {
+            let message = format!("function '{name}' declared 'noreturn' should not return");
+            let at = value.map_or(name, location);
+            self.diagnose_at(Severity::Warning, at, message);
+        }
+        let is_void = *self.types.get(self.types.unqualified(ret).0) == Type::Void;
+        let Some(value) = value else {
+            if !is_void && !self.is_error(ret) {
+                let message = format!("non-void function '{name}' should return a value");
+                self.diagnose_at(Severity::Warning, name, message);
+            }
+            return;
+        };
+        self.full(value, |checker| {
+            let typed = checker.value(value);
+            if !is_void {
+                return checker.assign(convert::Conversion::Returning, ret, typed, value);
+            }
+            if !matches!(checker.types.get(typed.ty), Type::Void | Type::Error) {
+                let message = format!("void function '{name}' should not return a value");
+                checker.warning(value, message);
+            }
+            typed
+        });
+    }
+
+    fn static_assert(&mut self, assert: &'a StaticAssert<'i>) {
+        let value = self.integer_constant(&assert.condition, "static assertion expression");
+        if value == Some(0) {
+            let problem = match assert.message {
+                Some(message) => format!("static assertion failed: {message}"),
+                None => "static assertion failed".to_string(),
+            };
+            self.error(&assert.condition, problem);
+        }
+    }
+
+    fn var_decl(&mut self, decl: &'a VarDecl<'i>) {
+        self.specifiers(&decl.ty);
+        for init in decl.declarators.iter() {
+            self.declarator(&init.declarator);
+            let declaration = Declaration {
+                specifiers: &decl.ty,
+                declarator: &init.declarator,
+            };
+            self.redeclaration(declaration);
+            self.variably_modified(declaration);
+            let Some(ref value) = init.value else {
+                continue;
+            };
+            // Objects declared outside of functions are static too
+            self.is_static =
+                self.function.is_none() || decl.ty.storage == Some(StorageClass::Static);
+            if decl.ty.ty == BaseType::AutoType {
+                self.auto_type(&init.declarator, value);
+            } else {
+                let ty = self.types.declaration(self.resolution, declaration);
+                let completed = self.initializer(ty, value);
+                // Arrays of unknown length take that of their initializer
+                if completed != ty {
+                    let name = resolve::declared_name(&init.declarator);
+                    if let Some(symbol) = name.and_then(|name| self.resolution.binding(name)) {
+                        self.types.infer(symbol, completed);
+                    }
+                }
+            }
+            self.is_static = false;
+        }
+    }
+
+    /// Gives the variable declared with `__auto_type` the type of its initializer
+    fn auto_type(&mut self, declarator: &'a Declarator<'i>, value: &'a Initializer<'i>) {
+        let Initializer::Expression(ref expr) = *value else {
+            let ty = self.types.intern(Type::Error);
+            self.initializer(ty, value);
+            return;
+        };
+        let ty = self.full_expression(expr);
+        self.static_value(expr);
+        let symbol = match declarator {
+            Declarator::Ident(name) => self.resolution.binding(name),
+            _ => None,
+        };
+        if let Some(symbol) = symbol {
+            self.types.infer(symbol, ty);
+        }
+    }
+
+    /// Checks the expressions of declaration specifiers: those of `typeof`, alignments,
+    /// and the members and enumerators they define
+    fn specifiers(&mut self, specifiers: &'a DeclSpecifiers<'i>) {
+        self.attributes(&specifiers.attributes);
+        for alignment in specifiers.alignment.iter() {
+            match alignment {
+                AlignmentSpecifier::Type(ty) => self.type_name(ty),
+                AlignmentSpecifier::Expression(expr) => {
+                    let alignment = self.integer_constant(expr, "alignment");
+                    if alignment
+                        .is_some_and(|alignment| alignment < 0 || alignment.count_ones() > 1)
+                    {
+                        self.error(expr, "requested alignment is not a power of 2".to_string());
+                    }
+                }
+            }
+        }
+        match specifiers.ty {
+            BaseType::TypedefName(ref name) => {
+                if let Some(id) = self.resolution.binding(name) {
+                    self.deprecated(name, id);
+                }
+            }
+            BaseType::Typeof(ref operand) => match **operand {
+                TypeofSpecifier::Expression(ref expr) => {
+                    // Not evaluated, so its value is not converted
+                    self.full(expr, |checker| checker.expr(expr));
+                }
+                TypeofSpecifier::Type(ref ty) => self.type_name(ty),
+            },
+            BaseType::Atomic(keyword, ref ty) => {
+                self.type_name(ty);
+                let ty = self.types.type_name(self.resolution, ty);
+                let kind = match *self.types.get(ty) {
+                    Type::Array { .. } => "array",
+                    Type::Function { .. } => "function",
+                    Type::Qualified { qualifiers, .. } if qualifiers.is_atomic => "atomic",
+                    Type::Qualified { .. } => "qualified",
+                    _ => return,
+                };
+                let message = format!(
+                    "_Atomic cannot be applied to {kind} type '{}'",
+                    self.display(ty)
+                );
+                self.diagnose_at(Severity::Error, Ident::new(keyword), message);
+            }
+            BaseType::Record(ref record) => {
+                self.attributes(&record.attributes);
+                // Lays the record out, even if nothing is declared with it
+                if record.is_definition() {
+                    self.types.specifiers(self.resolution, specifiers);
+                }
+                for item in record.members.iter().flatten() {
+                    let member = match item {
+                        MemberItem::Declaration(member) => member,
+                        MemberItem::StaticAssert(assert) => {
+                            self.static_assert(assert);
+                            continue;
+                        }
+                    };
+                    self.specifiers(&member.ty);
+                    for declarator in member.declarators.iter() {
+                        self.declarator(&declarator.declarator);
+                        if let Some(ref width) = declarator.width {
+                            let declaration = Declaration {
+                                specifiers: &member.ty,
+                                declarator: &declarator.declarator,
+                            };
+                            self.bit_field(declaration, width);
+                        }
+                    }
+                }
+            }
+            BaseType::Enum(ref specifier) => self.enumeration(specifier),
+            _ => {}
+        }
+    }
+
+    /// Checks the values of the enumerators against the fixed underlying type of the
+    /// enum, or against `int` before C23
+    fn enumeration(&mut self, specifier: &'a EnumSpecifier<'i>) {
+        let fixed = specifier.underlying.as_ref().map(|underlying| {
+            self.specifiers(underlying);
+            let ty = self.types.specifiers(self.resolution, underlying);
+            match *self.types.get(self.types.unqualified(ty).0) {
+                Type::Int(kind) => Some((kind, ty)),
+                _ => None,
+            }
+        });
+        let is_int_only = self.resolution.standard() < Standard::C23;
+        // The value of an enumerator without one of its own
+        let mut next = Some(0);
+        for enumerator in specifier.enumerators.iter().flatten() {
+            let value = match enumerator.value {
+                Some(ref value) => self.integer_constant(value, "enumerator value"),
+                None => next,
+            };
+            next = value.and_then(|value| value.checked_add(1));
+            let Some(value) = value else {
+                continue;
+            };
+            // Problems with the value of an enumerator are reported at its name
+            let at = enumerator.name;
+            match fixed {
+                Some(Some((kind, ty)))
+                    if constant::wrap(value, kind, self.types.target()) != value =>
+                {
+                    let ty = self.display(ty);
+                    let message = match enumerator.value {
+                        Some(_) => format!(
+                            "enumerator value is not representable in the underlying type '{ty}'"
+                        ),
+                        None => format!(
+                            "enumerator value {value} is not representable in the underlying type '{ty}'"
+                        ),
+                    };
+                    self.diagnose_at(Severity::Error, at, message);
+                }
+                // Counting past the largest `int`
+                None if is_int_only
+                    && enumerator.value.is_none()
+                    && value == i128::from(i32::MAX) + 1 =>
+                {
+                    let message = "overflow in enumeration values".to_string();
+                    self.diagnose_at(Severity::Error, at, message);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks the lengths of arrays and the parameters of a declarator
+    fn declarator(&mut self, declarator: &'a Declarator<'i>) {
+        match declarator {
+            Declarator::Ident(_) | Declarator::Abstract => {}
+            Declarator::Pointer { inner, .. } => self.declarator(inner),
+            Declarator::Attributed { inner, attributes } => {
+                self.attributes(attributes);
+                self.declarator(inner);
+            }
+            Declarator::Array { inner, size, .. } => {
+                if let ArraySize::Expression(size) = size {
+                    self.integer(size, "size of array");
+                    // Sizes that are not constant are those of variable length arrays
+                    let length = self
+                        .typing
+                        .expression(size)
+                        .map(|typed| Evaluator::new(self.resolution, self.types).integer(typed));
+                    if let Some(Ok(length)) = length {
+                        if length < 0 {
+                            self.error(size, "array size is negative".to_string());
+                        }
+                    }
+                }
+                self.declarator(inner);
+            }
+            Declarator::Function { inner, params } => {
+                if let Parameters::Prototype { params, .. } = params {
+                    for (specifiers, declarator) in params.iter() {
+                        self.specifiers(specifiers);
+                        self.declarator(declarator);
+                    }
+                }
+                self.declarator(inner);
+            }
+        }
+    }
+
+    /// Checks the arguments of the attributes that ask for an alignment
+    fn attributes(&mut self, attributes: &'a Attributes<'i>) {
+        for attribute in attributes.iter() {
+            let Some([ref value]) = attribute.args.as_deref() else {
+                continue;
+            };
+            if attribute.kind() != Some(KnownAttribute::Aligned) {
+                continue;
+            }
+            let alignment = self.types.constant(self.resolution, value);
+            if alignment.is_some_and(|alignment| alignment <= 0 || alignment.count_ones() > 1) {
+                let message = "requested alignment is not a positive power of 2";
+                self.error(value, message.to_string());
+            }
+        }
+    }
+
+    /// Checks the width of a bit-field, which must fit in the type of the member
+    fn bit_field(&mut self, declaration: Declaration<'a, 'i>, width: &'a Expression<'i>) {
+        let Some(value) = self.integer_constant(width, "bit-field width") else {
+            return;
+        };
+        let name = declaration.declarator.name();
+        let member = match name {
+            Some(name) => format!("bit-field '{name}'"),
+            None => "anonymous bit-field".to_string(),
+        };
+        let ty = self.types.declaration(self.resolution, declaration);
+        let target = self.types.target();
+        let bits = self.types.int_kind(ty).map(|kind| target.int_bits(kind));
+        let message = if value < 0 {
+            format!("{member} has negative width ({value})")
+        } else if value == 0 && name.is_some() {
+            format!("named {member} has zero width")
+        } else {
+            match bits {
+                Some(bits) if value > i128::from(bits) => format!(
+                    "width of {member} ({value} bits) exceeds the width of its type ({bits} bits)"
+                ),
+                _ => return,
+            }
+        };
+        self.error(width, message);
+    }
+
+    /// The type written in a cast, `sizeof` or a compound literal, after checking its
+    /// expressions
+    fn type_name(&mut self, ty: &'a TypeName<'i>) {
+        self.specifiers(&ty.specifiers);
+        self.declarator(&ty.declarator);
+    }
+
+    /// Checks the initializer of an object of type `ty`, and gives that type completed
+    /// by it, as an array of unknown length is by its elements
+    fn initializer(&mut self, ty: TypeId, value: &'a Initializer<'i>) -> TypeId {
+        match value {
+            Initializer::Expression(expr) => self.initializer_expression(ty, expr),
+            Initializer::List(items) => self.initializer_list(ty, items),
+        }
+    }
+
+    fn initializer_expression(&mut self, ty: TypeId, expr: &'a Expression<'i>) -> TypeId {
+        let (unqualified, _) = self.types.unqualified(ty);
+        match *self.types.get(unqualified) {
+            Type::Array { element, length } => {
+                let typed = self.full(expr, |checker| {
+                    let typed = checker.expr(expr);
+                    let is_string = matches!(expr, Expression::String(_));
+                    let is_text = checker.types.is_integer(element);
+                    if !(checker.is_error(typed.ty) || is_string && is_text) {
+                        let message =
+                            "array initializer must be an initializer list or string literal";
+                        checker.error(expr, message.to_string());
+                    }
+                    typed
+                });
+                let Type::Array {
+                    length: ArrayLength::Fixed(string),
+                    ..
+                } = *self.types.get(typed)
+                else {
+                    return ty;
+                };
+                // Only the terminating null character may be left out
+                if matches!(length, ArrayLength::Fixed(length) if string - 1 > length) {
+                    let message = "initializer-string for char array is too long";
+                    self.warning(expr, message.to_string());
+                }
+                self.completed(ty, string)
+            }
+            _ => {
+                self.full(expr, |checker| {
+                    let typed = checker.value(expr);
+                    checker.assign(convert::Conversion::Initializing, ty, typed, expr)
+                });
+                self.static_value(expr);
+                ty
+            }
+        }
+    }
+
+    /// `ty` with `length` elements if it is an array of unknown length
+    fn completed(&mut self, ty: TypeId, length: u64) -> TypeId {
+        let (unqualified, qualifiers) = self.types.unqualified(ty);
+        let Type::Array {
+            element,
+            length: ArrayLength::Incomplete,
+        } = *self.types.get(unqualified)
+        else {
+            return ty;
+        };
+        let length = ArrayLength::Fixed(length);
+        let array = self.types.intern(Type::Array { element, length });
+        self.types.qualified(array, qualifiers)
+    }
+
+    /// Checks the elements of `{ ... }` against the members or elements of `ty` they
+    /// initialize
+    pub(super) fn initializer_list(
+        &mut self,
+        ty: TypeId,
+        items: &'a [DesignatedInitializer<'i>],
+    ) -> TypeId {
+        let length = self.aggregate(ty, items, &mut 0, true, Start::Next);
+        self.completed(ty, length)
+    }
+
+    /// Checks the items of a list from `position` against the members or elements of
+    /// `ty` they initialize, and gives the number of elements initialized when `ty` is
+    /// an array. A list in braces takes all of its items, while an aggregate whose
+    /// braces are elided ends once it is full, or at a designator of the enclosing list.
+    fn aggregate(
+        &mut self,
+        ty: TypeId,
+        items: &'a [DesignatedInitializer<'i>],
+        position: &mut usize,
+        is_braced: bool,
+        start: Start<'a, 'i>,
+    ) -> u64 {
+        let (unqualified, _) = self.types.unqualified(ty);
+        // The member or element the next item initializes
+        let mut next = 0;
+        let mut designated = None;
+        match start {
+            Start::Next => {}
+            Start::Scalar(expr) => {
+                if let Some(target) = self.element(unqualified, &mut next) {
+                    if self.is_aggregate(target) && !self.is_whole(target, expr) {
+                        self.aggregate(target, items, position, false, start);
+                    }
+                }
+            }
+            Start::Designated(designators) => designated = Some(designators),
+        }
+        let mut length = next;
+        let mut is_excess = false;
+        while let Some(item) = items.get(*position) {
+            // The designators of the first item may be followed partly already
+            let (designators, is_start) = match designated.take() {
+                Some(designators) => (designators, true),
+                None => (item.designators.as_slice(), false),
+            };
+            let target = if let Some((designator, rest)) = designators.split_first() {
+                if !(is_braced || is_start) {
+                    break;
+                }
+                let target = self.designated(unqualified, designator, &mut next);
+                length = length.max(next);
+                match target {
+                    // The items after it continue in the aggregate it names, up to the end
+                    // of that aggregate
+                    Some(target) if !rest.is_empty() && self.is_aggregate(target) => {
+                        let start = Start::Designated(rest);
+                        self.aggregate(target, items, position, false, start);
+                        continue;
+                    }
+                    Some(target) => rest.iter().try_fold(target, |ty, designator| {
+                        self.designated(ty, designator, &mut 0)
+                    }),
+                    None => None,
+                }
+            } else {
+                let target = self.element(unqualified, &mut next);
+                if target.is_none() && !is_braced {
+                    break;
+                }
+                target
+            };
+            *position += 1;
+            length = length.max(next);
+            if target.is_none() && item.designators.is_empty() && !is_excess {
+                is_excess = true;
+                self.excess(unqualified, &item.value);
+            }
+            match (target, &item.value) {
+                (Some(target), Initializer::Expression(expr)) if self.is_aggregate(target) => {
+                    if !self.elided(target, expr) {
+                        let start = Start::Scalar(expr);
+                        self.aggregate(target, items, position, false, start);
+                    }
+                }
+                (Some(target), value) => {
+                    self.initializer(target, value);
+                }
+                (None, value) => self.loose_initializer(value),
+            }
+        }
+        length
+    }
+
+    /// The member or element of `ty` that the item at `next` initializes, moving `next`
+    /// past it. `None` once `ty` is full. A scalar is its own single element.
+    fn element(&self, ty: TypeId, next: &mut u64) -> Option<TypeId> {
+        match *self.types.get(ty) {
+            Type::Array { element, length } => {
+                if matches!(length, ArrayLength::Fixed(length) if *next >= length) {
+                    return None;
+                }
+                *next += 1;
+                Some(element)
+            }
+            Type::Record(id) => {
+                let record = self.types.record(id);
+                let members = record.members.as_deref().unwrap_or_default();
+                // Unnamed bit-fields are skipped
+                let (n, member) = members
+                    .iter()
+                    .enumerate()
+                    .skip(*next as usize)
+                    .find(|(_, m)| m.name.is_some() || m.width.is_none())?;
+                if record.kind == RecordKind::Union && n > 0 {
+                    return None;
+                }
+                *next = n as u64 + 1;
+                Some(member.ty)
+            }
+            Type::Error => None,
+            _ if *next == 0 => {
+                *next = 1;
+                Some(ty)
+            }
+            _ => None,
+        }
+    }
+
+    /// The member or element of `ty` that `designator` names, moving `next` past it
+    fn designated(
+        &mut self,
+        ty: TypeId,
+        designator: &'a Designator<'i>,
+        next: &mut u64,
+    ) -> Option<TypeId> {
+        let (unqualified, _) = self.types.unqualified(ty);
+        let (first, last) = match designator {
+            Designator::Member(name) => {
+                let problem = match *self.types.get(unqualified) {
+                    Type::Record(id) => match self.types.find_member(id, name.name) {
+                        Some((path, ty)) => {
+                            *next = path[0] as u64 + 1;
+                            return Some(ty);
+                        }
+                        None => format!(
+                            "field designator '{name}' does not refer to any field in type '{}'",
+                            self.display(unqualified)
+                        ),
+                    },
+                    Type::Error => return None,
+                    _ => format!(
+                        "field designator cannot initialize a non-struct, non-union type '{}'",
+                        self.display(unqualified)
+                    ),
+                };
+                self.error(&Expression::Ident(*name), problem);
+                return None;
+            }
+            Designator::Index(index) => (index, index),
+            Designator::Range(first, last) => (first, last),
+        };
+        let values = [first, last].map(|index| self.integer_constant(index, "array designator"));
+        let Type::Array { element, length } = *self.types.get(unqualified) else {
+            if !self.is_error(unqualified) {
+                let message = format!(
+                    "array designator cannot initialize non-array type '{}'",
+                    self.display(unqualified)
+                );
+                self.error(first, message);
+            }
+            return None;
+        };
+        for (value, index) in values.into_iter().zip([first, last]) {
+            let problem = match (value?, length) {
+                (value, _) if value < 0 => format!("array designator value '{value}' is negative"),
+                (value, ArrayLength::Fixed(length)) if value >= i128::from(length) => {
+                    format!("array designator index ({value}) exceeds array bounds ({length})")
+                }
+                _ => continue,
+            };
+            self.error(index, problem);
+            return None;
+        }
+        *next = values[1]? as u64 + 1;
+        Some(element)
+    }
+
+    /// Reports an item of a list past the end of the object of type `ty` it initializes
+    fn excess(&mut self, ty: TypeId, value: &'a Initializer<'i>) {
+        let what = match *self.types.get(ty) {
+            Type::Array { .. } => "array",
+            Type::Record(id) if self.types.record(id).kind == RecordKind::Union => "union",
+            Type::Record(_) => "struct",
+            Type::Error => return,
+            _ => "scalar",
+        };
+        if let Some(expr) = first_expression(value) {
+            self.warning(expr, format!("excess elements in {what} initializer"));
+        }
+    }
+
+    /// Whether values of `ty` are initialized with lists
+    fn is_aggregate(&self, ty: TypeId) -> bool {
+        matches!(
+            self.types.get(self.types.unqualified(ty).0),
+            Type::Array { .. } | Type::Record(_)
+        )
+    }
+
+    /// Whether `expr`, once typed, initializes the whole aggregate `target` rather than
+    /// its first scalar: a string does so for an array of characters, and a struct or
+    /// union for one of a compatible type
+    fn is_whole(&self, target: TypeId, expr: &Expression<'i>) -> bool {
+        let (target, _) = self.types.unqualified(target);
+        if let Type::Array { element, .. } = *self.types.get(target) {
+            return matches!(expr, Expression::String(_)) && self.types.is_integer(element);
+        }
+        self.typing.expression(expr).is_some_and(|typed| {
+            let (ty, _) = self.types.unqualified(typed.ty);
+            self.types.is_compatible(ty, target)
+        })
+    }
+
+    /// Checks an expression that initializes an aggregate, either as a whole, as with a
+    /// struct value or a string, or as its first scalar with the braces around it
+    /// elided. Gives whether it initializes the whole aggregate.
+    fn elided(&mut self, target: TypeId, expr: &'a Expression<'i>) -> bool {
+        if matches!(expr, Expression::String(_)) && self.is_whole(target, expr) {
+            self.initializer_expression(target, expr);
+            return true;
+        }
+        self.full(expr, |checker| {
+            let typed = checker.value(expr);
+            let (ty, _) = checker.types.unqualified(typed.ty);
+            if checker.types.is_compatible(ty, target) {
+                checker.assign(convert::Conversion::Initializing, target, typed, expr)
+            } else {
+                typed
+            }
+        });
+        self.static_value(expr);
+        self.is_whole(target, expr)
+    }
+
+    /// Types the expressions of an initializer whose target is not followed
+    fn loose_initializer(&mut self, value: &'a Initializer<'i>) {
+        match value {
+            Initializer::Expression(expr) => {
+                self.full_expression(expr);
+            }
+            Initializer::List(items) => {
+                for item in items {
+                    self.loose_initializer(&item.value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parse_valid,
+            tree::{Initializer, Statement},
+            Severity,
+        },
+        sema::{resolve, CastKind, ExprKind, TypeContext, ValueCategory},
+        standard::Standard,
+        target::Target,
+    };
+
+    use super::check;
+
+    /// The diagnostics of `source`, with their severities
+    fn diagnostics(source: &str) -> Vec<(Severity, String)> {
+        target_diagnostics(source, Target::default())
+    }
+
+    /// The diagnostics of `source` when compiled for `target`
+    fn target_diagnostics(source: &str, target: Target) -> Vec<(Severity, String)> {
+        let program = parse_valid(source);
+        let resolution = resolve(&program, Standard::C17);
+        assert!(resolution.diagnostics().is_empty());
+        let mut types = TypeContext::new().with_target(target);
+        let typing = check(&program, &resolution, &mut types);
+        let diagnostics = typing.diagnostics();
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.to_string()))
+            .collect()
+    }
+
+    /// The line and column of the diagnostics of `source`, with their messages
+    fn positions(source: &str) -> Vec<((usize, usize), String)> {
+        let program = parse_valid(source);
+        let resolution = resolve(&program, Standard::C17);
+        let mut types = TypeContext::new();
+        let typing = check(&program, &resolution, &mut types);
+        typing
+            .diagnostics()
+            .iter()
+            .map(|d| (d.line_col(source).unwrap(), d.to_string()))
+            .collect()
+    }
+
+    /// The types of the initializers of the variables declared in a function whose body
+    /// is `source`, which need not be constant
+    fn initializer_types(source: &str) -> Vec<String> {
+        let source = format!("void test(void) {{ {source} }}");
+        let program = parse_valid(&source);
+        let resolution = resolve(&program, Standard::C17);
+        let mut types = TypeContext::new();
+        let typing = check(&program, &resolution, &mut types);
+        assert!(
+            typing.diagnostics().is_empty(),
+            "{:?}",
+            typing.diagnostics()
+        );
+        let Statement::FuncDecl(ref test) = program[0] else {
+            panic!("Expected a function")
+        };
+        test.body
+            .iter()
+            .flatten()
+            .filter_map(|statement| match statement {
+                Statement::VarDecl(decl) => decl.declarators[0].value.as_ref(),
+                _ => None,
+            })
+            .map(|value| match value {
+                Initializer::Expression(expr) => {
+                    let typed = typing.expression(expr).expect("Expected a type");
+                    types.display(typed.ty).to_string()
+                }
+                Initializer::List(_) => "{}".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expression_types() {
+        let types = initializer_types(
+            "char c; short s; unsigned u; long l; float f; double d;
+            int *p; const int ci; int a[3]; struct pt { int x; const int y; } pt;
+            int f0(void);
+            __auto_type r1 = c + s;
+            __auto_type r2 = u + 1;
+            __auto_type r3 = u + l;
+            __auto_type r4 = f + c;
+            __auto_type r5 = d * f;
+            __auto_type r6 = c << l;
+            __auto_type r7 = -c;
+            __auto_type r8 = p - p;
+            __auto_type r9 = p + 1;
+            __auto_type r10 = a;
+            __auto_type r11 = &a[1];
+            __auto_type r12 = c < d;
+            __auto_type r13 = sizeof a;
+            __auto_type r14 = 'a';
+            __auto_type r15 = 1 ? s : l;
+            __auto_type r16 = &ci;
+            __auto_type r17 = f0;
+            __auto_type r18 = pt.y;
+            __auto_type r19 = 2147483648 + 4000000000u;
+            __auto_type r20 = _Generic(ci, int: 1, default: 2.0);
+            __auto_type r21 = (typeof(a) *)0;
+            __auto_type r22 = (typeof(\"abc\") *)0;
+            long r23 = c;
+            __auto_type r24 = (typeof(\"ab\" \"cd\") *)0;
+            __auto_type r25 = (typeof(u8\"a\" L\"bc\") *)0;",
+        );
+        assert_eq!(
+            types,
+            [
+                "int",
+                "unsigned int",
+                "long",
+                "float",
+                "double",
+                "int",
+                "int",
+                "long",
+                "int *",
+                "int *",
+                "int *",
+                "int",
+                "unsigned long",
+                "int",
+                "long",
+                "const int *",
+                "int (*)(void)",
+                "int",
+                "long",
+                "int",
+                "int (*)[3]",
+                "char (*)[4]",
+                "long",
+                "char (*)[5]",
+                "int (*)[4]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_implicit_casts() {
+        let program = parse_valid("void f(long n, ...) { char c; int a[2]; f(c, a, 1.0f); }");
+        let resolution = resolve(&program, Standard::C17);
+        let mut types = TypeContext::new();
+        let typing = check(&program, &resolution, &mut types);
+        let Statement::FuncDecl(ref func) = program[0] else {
+            panic!("Expected a function")
+        };
+        let Some(Statement::Expression(ref call)) = func.body.as_ref().and_then(|b| b.last())
+        else {
+            panic!("Expected a call")
+        };
+        let typed = typing.expression(call).expect("Expected a type");
+        assert_eq!(typed.category, ValueCategory::RValue);
+        let ExprKind::Call {
+            ref callee,
+            ref args,
+        } = typed.kind
+        else {
+            panic!("Expected a call")
+        };
+        let casts = |mut expr: &crate::sema::TypedExpr<'_, '_>| {
+            let mut casts = Vec::new();
+            while let ExprKind::ImplicitCast { kind, ref operand } = expr.kind {
+                casts.push((kind, types.display(expr.ty).to_string()));
+                expr = operand;
+            }
+            casts
+        };
+        assert_eq!(
+            casts(callee),
+            [(
+                CastKind::FunctionToPointer,
+                "void (*)(long, ...)".to_string()
+            )]
+        );
+        let args: Vec<_> = args.iter().map(casts).collect();
+        assert_eq!(
+            args,
+            [
+                vec![
+                    (CastKind::IntegralCast, "long".to_string()),
+                    (CastKind::LValueToRValue, "char".to_string()),
+                ],
+                vec![(CastKind::ArrayToPointer, "int *".to_string())],
+                vec![(CastKind::FloatingCast, "double".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_operands() {
+        let messages = diagnostics(
+            "struct s { int x; } s;
+            void h(int, int);
+            void f(int n) {
+                int *p; long *q; const int c = 1; int a[2]; const int *k;
+                s + 1;
+                *n;
+                &3;
+                n(1);
+                n[1];
+                n.x;
+                n->x;
+                p->x;
+                s.y;
+                c = 2;
+                a = p;
+                n + 1 = 3;
+                p = q;
+                p = k;
+                p = n;
+                n = p;
+                s = n;
+                p = 0;
+                f(1, 2);
+                h(1);
+                if (s) {}
+                sizeof(void (void));
+                _Generic(n, long: 1);
+            }
+            int g(void) { return; }",
+        );
+        let error = |message: &str| (Severity::Error, message.to_string());
+        let warning = |message: &str| (Severity::Warning, message.to_string());
+        assert_eq!(
+            messages,
+            [
+                error("invalid operands to binary expression ('struct s' and 'int')"),
+                error("indirection requires pointer operand ('int' invalid)"),
+                error("cannot take the address of an rvalue of type 'int'"),
+                error("called object type 'int' is not a function or function pointer"),
+                error("subscripted value is not an array, pointer, or vector"),
+                error("member reference base type 'int' is not a structure or union"),
+                error("member reference type 'int' is not a pointer"),
+                error("member reference base type 'int' is not a structure or union"),
+                error("no member named 'y' in 'struct s'"),
+                error("cannot assign to variable 'c' with const-qualified type 'const int'"),
+                error("array type 'int[2]' is not assignable"),
+                error("expression is not assignable"),
+                warning("incompatible pointer types assigning to 'int *' from 'long *'"),
+                warning("assigning to 'int *' from 'const int *' discards qualifiers"),
+                error("incompatible integer to pointer conversion assigning to 'int *' from 'int'"),
+                error("incompatible pointer to integer conversion assigning to 'int' from 'int *'"),
+                error("assigning to 'struct s' from incompatible type 'int'"),
+                error("too many arguments to function call, expected 1, have 2"),
+                error("too few arguments to function call, expected 2, have 1"),
+                error("statement requires expression of scalar type ('struct s' invalid)"),
+                error("invalid application of 'sizeof' to a function type"),
+                error("controlling expression type 'int' not compatible with any generic association type"),
+                warning("non-void function 'g' should return a value"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_initializers() {
+        let messages = diagnostics(
+            "struct s { int x; char *name; struct { int a, b; } inner; } v = {
+                1, \"one\", 2, 3
+            };
+            struct s w = { .name = 0, .inner = { 1, 2 }, .z = 3 };
+            int a[] = { 1, 2, [4] = 5 };
+            char text[] = \"text\";
+            int b[2] = 3;
+            int *p = 1.0;
+            __auto_type q = &v;
+            int x = q->x;
+            int y = q;",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Error,
+                    "field designator 'z' does not refer to any field in type 'struct s'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "array initializer must be an initializer list or string literal".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "initializing 'int *' with an expression of incompatible type 'double'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "initializer element is not a compile-time constant".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "incompatible pointer to integer conversion initializing 'int' with an expression of type 'struct s *'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "initializer element is not a compile-time constant".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_initializer_lengths() {
+        let messages = diagnostics(
+            "int a[] = { 1, 2, 3 };
+            char s[] = \"hello\";
+            int m[][2] = { 1, 2, 3, 4, 5 };
+            struct { int x, y; } points[] = { { 1, 2 }, 3, 4, [4] = { 5 } };
+            _Static_assert(sizeof a == 12, \"a\");
+            _Static_assert(sizeof(a) / sizeof(a[0]) == 3, \"count\");
+            _Static_assert(sizeof s == 6, \"s\");
+            _Static_assert(sizeof m == 24, \"m\");
+            _Static_assert(sizeof points == 40, \"points\");
+            int b[2] = { 1, 2, 3 };
+            char t[2] = \"abc\";
+            char u[3] = \"abc\";
+            struct { int x; } v = { .x.a = 2 };
+            struct { int x; } w = { 1, 2 };
+            int c[2] = { [2] = 1 };
+            struct { int t[3]; int k; } d = { .t[1] = 4, 5, 6 };
+            struct o { struct { int x, y, z; } in; int after; } e = { .in.x = 1, 2, 3 };
+            struct o f = { .in.y = 1, 2, 3, 4 };
+            int g[][3] = { [1][1] = 4, 5, 6 };
+            _Static_assert(sizeof g == 36, \"g\");",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Warning,
+                    "excess elements in array initializer".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "initializer-string for char array is too long".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "field designator cannot initialize a non-struct, non-union type 'int'"
+                        .to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "excess elements in struct initializer".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "array designator index (2) exceeds array bounds (2)".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "excess elements in struct initializer".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let messages = diagnostics(
+            "[[nodiscard]] int f(void);
+            int f(void) { return 1; }
+            int g(void) __attribute__((warn_unused_result));
+            [[nodiscard(\"check it\")]] int h(void);
+            [[deprecated]] int old;
+            [[deprecated(\"use int\")]] typedef long word;
+            _Noreturn void fail(void) { return; }
+            [[noreturn]] void stop(int n) { if (n) return; for (;;) {} }
+            int use(int n) {
+                f();
+                (void)f();
+                int x = f() + g();
+                g();
+                h();
+                word w = old;
+                switch (n) {
+                case 0:
+                    n++;
+                    [[fallthrough]];
+                case 1:
+                    [[fallthrough]];
+                    n--;
+                default:
+                    break;
+                }
+                return x + w;
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Warning,
+                    "function 'fail' declared 'noreturn' should not return".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "function 'stop' declared 'noreturn' should not return".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "ignoring return value of function declared with 'nodiscard' attribute"
+                        .to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "ignoring return value of function declared with 'warn_unused_result' attribute"
+                        .to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "ignoring return value of function declared with 'nodiscard' attribute: check it"
+                        .to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "'word' is deprecated: use int".to_string()
+                ),
+                (Severity::Warning, "'old' is deprecated".to_string()),
+                (
+                    Severity::Error,
+                    "fallthrough annotation does not directly precede switch label".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallthrough() {
+        let messages = positions(
+            "void f(int n) {
+                switch (n) {
+                case 0:
+                    if (n) { n++; [[fallthrough]]; }
+                case 1:
+                    [[fallthrough]];
+                    n--;
+                    while (n) { [[fallthrough]]; }
+                case 2:
+                    n++;
+                    __attribute__((fallthrough));
+                }
+            }",
+        );
+        let message = "fallthrough annotation does not directly precede switch label";
+        assert_eq!(
+            messages,
+            [
+                ((6, 23), message.to_string()),
+                ((8, 35), message.to_string()),
+                ((11, 36), message.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_atomic_specifier() {
+        let messages = positions(
+            "_Atomic(int) ai;
+            _Atomic(const int) ci;
+            typedef int A[2];
+            _Atomic(A) aa;
+            _Atomic(_Atomic int) nested;",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    (2, 13),
+                    "_Atomic cannot be applied to qualified type 'const int'".to_string()
+                ),
+                (
+                    (4, 13),
+                    "_Atomic cannot be applied to array type 'int[2]'".to_string()
+                ),
+                (
+                    (5, 13),
+                    "_Atomic cannot be applied to atomic type '_Atomic int'".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_static_assertions() {
+        let messages = diagnostics(
+            "_Static_assert(-1 < 0u, \"converted\");
+            _Static_assert(1u - 2 == 4294967295u, \"wraps\");
+            _Static_assert(1 == 2, \"one is not two\");
+            struct s { _Static_assert(0, \"in a struct\"); int a; };
+            void f(void) { _Static_assert(0, \"in a block\"); }",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Error,
+                    "static assertion failed: \"converted\"".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "static assertion failed: \"one is not two\"".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "static assertion failed: \"in a struct\"".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "static assertion failed: \"in a block\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        let messages = positions(
+            "enum { N = 4 };
+            void f(int n) {
+                goto done;
+                int a[sizeof(int)];
+                int b[N];
+                done:;
+                goto inside;
+                if (n) {
+                    int buf[n];
+                    inside:;
+                    goto inside;
+                }
+                {
+                    typedef int row[n];
+                    row *rows;
+                    { nested:; }
+                }
+                goto nested;
+                for (int (*p)[n] = 0; n; ) { loop:; }
+                goto loop;
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    (7, 22),
+                    "jump into the scope of variable length array 'buf'".to_string()
+                ),
+                (
+                    (18, 22),
+                    "jump into the scope of variable length array 'row'".to_string()
+                ),
+                (
+                    (20, 22),
+                    "jump into the scope of variable length array 'p'".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_cases() {
+        let messages = positions(
+            "enum { N = 4 };
+            void f(int i, unsigned char c, long l) {
+                switch (i) { case N: case 4: break; }
+                switch (i) { case -1: case 0xFFFFFFFF: break; }
+                switch (i) { case 1: case 2 - 1: default: break; }
+                switch (i) { case 0 ... 5: case 6 ... 9: case 3: break; }
+                switch (c) { case 255: case -1: break; }
+                switch (l) { case -1: case 0xFFFFFFFF: break; }
+                switch (i) { case 1: switch (c) { case 1: break; } }
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                ((3, 43), "duplicate case value '4'".to_string()),
+                ((4, 44), "duplicate case value '-1'".to_string()),
+                ((5, 43), "duplicate case value '1'".to_string()),
+                ((6, 63), "duplicate case value '3'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alignment_attributes() {
+        let messages = positions(
+            "int a __attribute__((aligned(sizeof(int) - 1)));
+            struct s { char c; } __attribute__((aligned(sizeof(int) * 2)));
+            struct t { int x __attribute__((aligned(0))); };
+            int *__attribute__((aligned(16))) p;",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    (1, 30),
+                    "requested alignment is not a positive power of 2".to_string()
+                ),
+                (
+                    (3, 53),
+                    "requested alignment is not a positive power of 2".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicting_types() {
+        let messages = positions(
+            "int x; long x;
+            int f(void); char *f(void);
+            int g(); int g(int a) { return a; }
+            int a[]; int a[3]; extern int a[4];
+            static int h(int); static int h(int n) { return n; }
+            void k(void) { extern long x; extern int a[3]; }",
+        );
+        assert_eq!(
+            messages,
+            [
+                ((1, 13), "conflicting types for 'x'".to_string()),
+                ((2, 32), "conflicting types for 'f'".to_string()),
+                ((4, 43), "conflicting types for 'a'".to_string()),
+                ((6, 40), "conflicting types for 'x'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_locations() {
+        // Expressions without a name are reported at their first token
+        let messages = positions(
+            "_Static_assert(1 / 0, \"m\");
+            typedef int T[-1];
+            int f(void) {
+                1 = 2;
+                return 1 / 0;
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                ((1, 16), "division by zero is undefined".to_string()),
+                (
+                    (1, 16),
+                    "expression is not an integer constant expression".to_string()
+                ),
+                ((2, 28), "array size is negative".to_string()),
+                ((4, 17), "expression is not assignable".to_string()),
+                ((5, 24), "division by zero is undefined".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enumerator_ranges() {
+        let messages = diagnostics(
+            "enum small : unsigned char { A = 255, B };
+            enum tiny : unsigned char { C = 300, D = -1 };
+            typedef signed char s8;
+            enum named : s8 { E = 127, F };
+            enum big { G = 2147483647, H };
+            enum wide { I = 4294967295, J };
+            _Static_assert(A == 255 && J == 4294967296, \"values\");
+            enum color { RED, GREEN = 5, BLUE, ALIAS = RED, LAST = BLUE * 2 + 1, };
+            _Static_assert(BLUE == 6 && ALIAS == 0 && LAST == 13, \"numbering\");",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Error,
+                    "enumerator value 256 is not representable in the underlying type 'unsigned char'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "enumerator value is not representable in the underlying type 'unsigned char'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "enumerator value is not representable in the underlying type 'unsigned char'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "enumerator value 128 is not representable in the underlying type 'signed char'"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "overflow in enumeration values".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constant_expressions() {
+        let messages = diagnostics(
+            "enum e { A = 1, B, C = B * 4 };
+            int big = 2147483647 + 1;
+            unsigned wraps = 4294967295u + 1;
+            int shifted = 1 << 32;
+            int g;
+            int *p = &g + C;
+            int bad = g;
+            struct bits { int a : 33; unsigned b : 3; };
+            _Static_assert(C == 8, \"C is 8\");
+            _Static_assert(__builtin_types_compatible_p(const int, int), \"qualifiers\");
+            _Static_assert(!__builtin_types_compatible_p(int, long), \"long\");
+            _Static_assert(sizeof(long) == 4, \"long is 4 bytes\");
+            int negative[-1];
+            void f(int n) {
+                switch (n) { case A + 1: case n: break; }
+                int x = 0 && 1 / 0;
+                int y = n / 0;
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Warning,
+                    "overflow in expression; result is -2147483648 with type 'int'".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "shift count >= width of type".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "initializer element is not a compile-time constant".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "width of bit-field 'a' (33 bits) exceeds the width of its type (32 bits)"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "static assertion failed: \"long is 4 bytes\"".to_string()
+                ),
+                (Severity::Error, "array size is negative".to_string()),
+                (
+                    Severity::Error,
+                    "expression is not an integer constant expression".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "division by zero is undefined".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        let messages = diagnostics(
+            "int i = (int)2.0;
+            unsigned u = (unsigned)3.9;
+            long l = (long)-1.5;
+            unsigned char c = (unsigned char)255.5;
+            int big = (int)3e9;
+            unsigned negative = (unsigned)-1.0;
+            void f(int n) {
+                switch (n) { case (int)2.0: case (long)1.5: break; }
+            }",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Warning,
+                    "implicit conversion of out of range value from 'double' to 'int' is undefined"
+                        .to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "implicit conversion of out of range value from 'double' to 'unsigned int' \
+                     is undefined"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_constants() {
+        let messages = diagnostics(
+            "struct point { char tag; int x, y; };
+            struct point origin;
+            _Static_assert(sizeof(struct point) == 12, \"size\");
+            _Static_assert(_Alignof(struct point) == 4, \"alignment\");
+            _Static_assert(__builtin_offsetof(struct point, y) == 8, \"offset\");
+            int *y = &origin.y;
+            unsigned long x = (unsigned long)&((struct point *)0)->x;
+            _Static_assert(sizeof(struct point) == 9, \"packed\");
+            struct pair { int a, b; _Static_assert(sizeof(int) == 4, \"int\"); };
+            struct wide { long l; _Static_assert(sizeof(long) == 4, \"member\"); };",
+        );
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Error,
+                    "static assertion failed: \"packed\"".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "static assertion failed: \"member\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_widths() {
+        const SOURCE: &str = "_Static_assert((unsigned long)-1 == 4294967295u, \"ulong\");
+            _Static_assert(sizeof(2147483648) == 8, \"literal\");
+            int conversion[-1L > 0u ? 1 : -1];
+            struct b { long x : 40; };";
+        let messages = |target| -> Vec<_> {
+            target_diagnostics(SOURCE, target)
+                .into_iter()
+                .map(|(_, message)| message)
+                .collect()
+        };
+        assert_eq!(
+            messages(Target::I386),
+            ["width of bit-field 'x' (40 bits) exceeds the width of its type (32 bits)"]
+        );
+        assert_eq!(
+            messages(Target::X86_64),
+            [
+                "static assertion failed: \"ulong\"",
+                "array size is negative",
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! The conversions C applies on its own: integer promotions, the usual arithmetic
+//! conversions, and conversions as if by assignment.
+
+use crate::ast::tree::Expression;
+
+use super::{
+    super::{
+        constant::Evaluator,
+        typed::{CastKind, ExprKind, TypedExpr, ValueCategory},
+        FloatKind, IntKind, Type, TypeId,
+    },
+    Checker,
+};
+
+/// Where a value is converted as if by assignment, which words the diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Conversion {
+    Assigning,
+    Initializing,
+    /// To a parameter, in a call
+    Passing,
+    Returning,
+}
+impl Conversion {
+    /// Describes converting `source` to `target`, as in "assigning to 'int *' from
+    /// 'long *'"
+    fn describe(self, target: &str, source: &str, is_incompatible: bool) -> String {
+        let incompatible = if is_incompatible { "incompatible " } else { "" };
+        match self {
+            Self::Assigning if is_incompatible => {
+                format!("assigning to '{target}' from incompatible type '{source}'")
+            }
+            Self::Assigning => format!("assigning to '{target}' from '{source}'"),
+            Self::Initializing => {
+                format!(
+                    "initializing '{target}' with an expression of {incompatible}type '{source}'"
+                )
+            }
+            Self::Passing => {
+                format!("passing '{source}' to parameter of {incompatible}type '{target}'")
+            }
+            Self::Returning => format!(
+                "returning '{source}' from a function with {incompatible}result type '{target}'"
+            ),
+        }
+    }
+}
+
+impl<'c, 'a, 'i: 'a> Checker<'c, 'a, 'i> {
+    /// Wraps `operand` in a conversion to `ty`
+    pub(super) fn implicit(
+        &self,
+        kind: CastKind,
+        operand: TypedExpr<'a, 'i>,
+        ty: TypeId,
+    ) -> TypedExpr<'a, 'i> {
+        TypedExpr {
+            kind: ExprKind::ImplicitCast {
+                kind,
+                operand: Box::new(operand),
+            },
+            ty,
+            category: ValueCategory::RValue,
+        }
+    }
+
+    /// The conversion from a value of type `from` to `to`, both scalar or `void`
+    fn cast_kind(&self, from: TypeId, to: TypeId) -> Option<CastKind> {
+        let is_float = |ty| {
+            matches!(
+                self.types.get(self.types.unqualified(ty).0),
+                Type::Float { .. }
+            )
+        };
+        let is_pointer = |ty| self.types.pointee(ty).is_some();
+        let kind = if *self.types.get(to) == Type::Void {
+            CastKind::ToVoid
+        } else if self.types.int_kind(to) == Some(IntKind::Bool) && self.types.is_scalar(from) {
+            CastKind::ToBool
+        } else if self.types.is_integer(from) {
+            if self.types.is_integer(to) {
+                CastKind::IntegralCast
+            } else if is_float(to) {
+                CastKind::IntegralToFloating
+            } else if is_pointer(to) {
+                CastKind::IntegralToPointer
+            } else {
+                return None;
+            }
+        } else if is_float(from) {
+            if self.types.is_integer(to) {
+                CastKind::FloatingToIntegral
+            } else if is_float(to) {
+                CastKind::FloatingCast
+            } else {
+                return None;
+            }
+        } else if is_pointer(from) {
+            if is_pointer(to) {
+                CastKind::PointerCast
+            } else if self.types.is_integer(to) {
+                CastKind::PointerToIntegral
+            } else {
+                return None;
+            }
+        } else {
+            return None;
+        };
+        Some(kind)
+    }
+
+    /// Converts a value to `to`, leaving it as is when it already has this type or
+    /// cannot be converted
+    pub(super) fn convert(&mut self, value: TypedExpr<'a, 'i>, to: TypeId) -> TypedExpr<'a, 'i> {
+        let (to, _) = self.types.unqualified(to);
+        if value.ty == to || self.is_error(value.ty) || self.is_error(to) {
+            return value;
+        }
+        match self.cast_kind(value.ty, to) {
+            Some(kind) => self.implicit(kind, value, to),
+            None => value,
+        }
+    }
+
+    /// Applies the integer promotions to a value: integers of lower rank than `int`
+    /// become `int`, and enums their underlying type
+    pub(super) fn promote(&mut self, value: TypedExpr<'a, 'i>) -> TypedExpr<'a, 'i> {
+        match self.types.int_kind(value.ty) {
+            Some(kind) => {
+                let promoted = self.types.int(kind.promoted());
+                self.convert(value, promoted)
+            }
+            None => value,
+        }
+    }
+
+    /// Applies the default argument promotions, for arguments without a parameter:
+    /// `float` becomes `double`
+    pub(super) fn promote_argument(&mut self, value: TypedExpr<'a, 'i>) -> TypedExpr<'a, 'i> {
+        match *self.types.get(value.ty) {
+            Type::Float {
+                kind: FloatKind::Float,
+                is_complex,
+            } => {
+                let double = self.types.intern(Type::Float {
+                    kind: FloatKind::Double,
+                    is_complex,
+                });
+                self.convert(value, double)
+            }
+            _ => self.promote(value),
+        }
+    }
+
+    /// The type the usual arithmetic conversions bring two arithmetic types to
+    pub(super) fn common_type(&mut self, a: TypeId, b: TypeId) -> TypeId {
+        let float = |ty| match *self.types.get(self.types.unqualified(ty).0) {
+            Type::Float { kind, is_complex } => Some((kind, is_complex)),
+            _ => None,
+        };
+        match (float(a), float(b)) {
+            (None, None) => {}
+            (a, b) => {
+                let floats = a.into_iter().chain(b);
+                let kind = floats.clone().map(|(kind, _)| kind).max();
+                let is_complex = floats.clone().any(|(_, is_complex)| is_complex);
+                return self.types.intern(Type::Float {
+                    kind: kind.unwrap_or(FloatKind::Double),
+                    is_complex,
+                });
+            }
+        }
+        let (Some(a), Some(b)) = (self.types.int_kind(a), self.types.int_kind(b)) else {
+            return self.types.intern(Type::Error);
+        };
+        let (a, b) = (a.promoted(), b.promoted());
+        let (high, low) = if a.rank() >= b.rank() { (a, b) } else { (b, a) };
+        let kind = if a.is_signed() == b.is_signed() || !high.is_signed() {
+            high
+        } else if self.types.target().int_bits(high) > self.types.target().int_bits(low) {
+            // The signed type can represent every value of the unsigned one
+            high
+        } else {
+            high.to_unsigned()
+        };
+        self.types.int(kind)
+    }
+
+    /// Applies the usual arithmetic conversions to the operands of a binary operator,
+    /// which must both be arithmetic
+    pub(super) fn arithmetic(
+        &mut self,
+        lhs: TypedExpr<'a, 'i>,
+        rhs: TypedExpr<'a, 'i>,
+    ) -> (TypedExpr<'a, 'i>, TypedExpr<'a, 'i>, TypeId) {
+        let ty = self.common_type(lhs.ty, rhs.ty);
+        let lhs = self.convert(lhs, ty);
+        let rhs = self.convert(rhs, ty);
+        (lhs, rhs, ty)
+    }
+
+    /// Whether `expr`, typed as `typed`, is a null pointer constant: an integer constant
+    /// expression of value zero, or one cast to `void *`
+    pub(super) fn is_null_constant(
+        &mut self,
+        expr: &Expression<'i>,
+        typed: &TypedExpr<'a, 'i>,
+    ) -> bool {
+        if self.types.is_integer(typed.ty) {
+            return Evaluator::new(self.resolution, self.types).integer(typed) == Ok(0);
+        }
+        let is_void_pointer = self
+            .types
+            .pointee(typed.ty)
+            .is_some_and(|to| *self.types.get(to) == Type::Void);
+        match (expr, &typed.kind) {
+            (Expression::Cast { expr, .. }, ExprKind::Cast(operand)) if is_void_pointer => {
+                self.is_null_constant(expr, operand)
+            }
+            _ => false,
+        }
+    }
+
+    /// Converts `value`, the value of `expr`, to `target` as if by assignment, and
+    /// reports the conversions that are not allowed or lose qualifiers
+    pub(super) fn assign(
+        &mut self,
+        context: Conversion,
+        target: TypeId,
+        value: TypedExpr<'a, 'i>,
+        expr: &Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let (target, _) = self.types.unqualified(target);
+        let source = value.ty;
+        if self.is_error(target) || self.is_error(source) {
+            return value;
+        }
+        let describe = |checker: &Self, is_incompatible| {
+            let (target, source) = (checker.display(target), checker.display(source));
+            context.describe(&target, &source, is_incompatible)
+        };
+
+        let is_bool = self.types.int_kind(target) == Some(IntKind::Bool);
+        if self.types.is_arithmetic(target) && self.types.is_arithmetic(source)
+            || is_bool && self.types.is_scalar(source)
+        {
+            return self.convert(value, target);
+        }
+        if self.types.is_compatible(target, source) {
+            return self.convert(value, target);
+        }
+        match (self.types.pointee(target), self.types.pointee(source)) {
+            (Some(to), Some(from)) => {
+                let ((to, to_qualifiers), (from, from_qualifiers)) =
+                    (self.types.unqualified(to), self.types.unqualified(from));
+                let is_void = |ty| *self.types.get(ty) == Type::Void;
+                let is_function = |ty| matches!(self.types.get(ty), Type::Function { .. });
+                let loses = (from_qualifiers.is_const && !to_qualifiers.is_const)
+                    || (from_qualifiers.is_volatile && !to_qualifiers.is_volatile)
+                    || (from_qualifiers.is_restrict && !to_qualifiers.is_restrict);
+                let is_compatible = (is_void(to) && !is_function(from))
+                    || (is_void(from) && !is_function(to))
+                    || self.types.is_compatible(to, from);
+                if !is_compatible {
+                    let message = format!("incompatible pointer types {}", describe(self, false));
+                    self.warning(expr, message);
+                } else if loses {
+                    let message = format!("{} discards qualifiers", describe(self, false));
+                    self.warning(expr, message);
+                }
+                self.convert(value, target)
+            }
+            (Some(_), None) if self.is_null_constant(expr, &value) => {
+                self.implicit(CastKind::NullToPointer, value, target)
+            }
+            (Some(_), None) if self.types.is_integer(source) => {
+                let message = format!(
+                    "incompatible integer to pointer conversion {}",
+                    describe(self, false)
+                );
+                self.error(expr, message);
+                self.implicit(CastKind::IntegralToPointer, value, target)
+            }
+            (None, Some(_)) if self.types.is_integer(target) => {
+                let message = format!(
+                    "incompatible pointer to integer conversion {}",
+                    describe(self, false)
+                );
+                self.error(expr, message);
+                self.implicit(CastKind::PointerToIntegral, value, target)
+            }
+            _ => {
+                let message = describe(self, true);
+                self.error(expr, message);
+                value
+            }
+        }
+    }
+}
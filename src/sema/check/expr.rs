@@ -0,0 +1,1173 @@
+//! Types of expressions, operator by operator.
+
+use crate::{
+    ast::tree::{
+        AssignOp, BinaryOp, Designator, Expression, GenericSelection, Statement, StringLiteral,
+        TypeName, TypeQualifiers, UnaryOp,
+    },
+    lexer::token::{int_value, Ident, IntSuffix, Literal},
+    sema::{SymbolId, SymbolKind},
+    target::Target,
+};
+
+use super::{
+    super::{
+        constant::literal_parts,
+        typed::{CastKind, ExprKind, TypedExpr, ValueCategory},
+        ArrayLength, FloatKind, IntKind, Type, TypeId,
+    },
+    convert::Conversion,
+    Checker,
+};
+
+/// Identifiers every function can use without declaring them, naming it
+const PREDEFINED: &[&str] = &["__func__", "__FUNCTION__", "__PRETTY_FUNCTION__"];
+
+/// The type of an integer constant: the first of the types its suffix allows that can
+/// represent its value on `target`
+fn int_literal(value: u64, suffix: IntSuffix, target: &Target) -> IntKind {
+    let fits = |kind: IntKind| {
+        let bits = target.int_bits(kind) - u32::from(kind.is_signed());
+        bits >= 64 || value < 1 << bits
+    };
+    let candidates: &[IntKind] = match suffix {
+        IntSuffix::None => &[IntKind::Int, IntKind::Long, IntKind::LongLong],
+        IntSuffix::Unsigned => &[IntKind::UInt, IntKind::ULong, IntKind::ULongLong],
+        IntSuffix::Long => &[IntKind::Long, IntKind::LongLong],
+        IntSuffix::UnsignedLong => &[IntKind::ULong, IntKind::ULongLong],
+        IntSuffix::LongLong => &[IntKind::LongLong],
+        IntSuffix::UnsignedLongLong => &[IntKind::ULongLong],
+    };
+    let unsigned = match suffix {
+        IntSuffix::LongLong | IntSuffix::UnsignedLongLong => IntKind::ULongLong,
+        _ if fits(IntKind::ULong) => IntKind::ULong,
+        _ => IntKind::ULongLong,
+    };
+    candidates
+        .iter()
+        .copied()
+        .find(|&kind| fits(kind))
+        .unwrap_or(unsigned)
+}
+
+/// Number of elements of the array a string literal is, its terminating null included.
+/// Escape sequences are one element, other characters as many as their encoding takes.
+fn string_length(contents: &str, is_wide: bool) -> u64 {
+    let encoded = |c: char| if is_wide { 1 } else { c.len_utf8() as u64 };
+    let mut length = 1;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            length += encoded(c);
+            continue;
+        }
+        match chars.next() {
+            Some('x') => while chars.next_if(char::is_ascii_hexdigit).is_some() {},
+            Some('0'..='7') => {
+                for _ in 0..2 {
+                    chars.next_if(|c| c.is_digit(8));
+                }
+            }
+            Some(escape @ ('u' | 'U')) => {
+                let digits = if escape == 'u' { 4 } else { 8 };
+                let code: String = (0..digits).filter_map(|_| chars.next()).collect();
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or_default();
+                length += encoded(c);
+                continue;
+            }
+            _ => {}
+        }
+        length += 1;
+    }
+    length
+}
+
+impl<'c, 'a, 'i: 'a> Checker<'c, 'a, 'i> {
+    fn typed(kind: ExprKind<'a, 'i>, ty: TypeId, category: ValueCategory) -> TypedExpr<'a, 'i> {
+        TypedExpr { kind, ty, category }
+    }
+
+    /// An expression whose problem is already reported
+    pub(super) fn invalid(&mut self) -> TypedExpr<'a, 'i> {
+        let ty = self.types.intern(Type::Error);
+        Self::typed(ExprKind::Error, ty, ValueCategory::RValue)
+    }
+
+    fn rvalue(&mut self, kind: ExprKind<'a, 'i>, ty: Type) -> TypedExpr<'a, 'i> {
+        let ty = self.types.intern(ty);
+        Self::typed(kind, ty, ValueCategory::RValue)
+    }
+
+    /// Types `expr` and converts it to the value it designates, as the operands of most
+    /// operators are: arrays and functions decay to pointers, and lvalues are read
+    pub(super) fn value(&mut self, expr: &'a Expression<'i>) -> TypedExpr<'a, 'i> {
+        let typed = self.expr(expr);
+        self.decay(typed)
+    }
+
+    /// Converts an expression to the value it designates
+    pub(super) fn decay(&mut self, typed: TypedExpr<'a, 'i>) -> TypedExpr<'a, 'i> {
+        if self.is_error(typed.ty) {
+            return typed;
+        }
+        if let Type::Array { element, .. } = *self.types.get(typed.ty) {
+            let pointer = self.types.pointer(element);
+            return self.implicit(CastKind::ArrayToPointer, typed, pointer);
+        }
+        match typed.category {
+            ValueCategory::Function => {
+                let pointer = self.types.pointer(typed.ty);
+                self.implicit(CastKind::FunctionToPointer, typed, pointer)
+            }
+            ValueCategory::LValue => {
+                let (ty, _) = self.types.unqualified(typed.ty);
+                self.implicit(CastKind::LValueToRValue, typed, ty)
+            }
+            ValueCategory::RValue => typed,
+        }
+    }
+
+    /// Types `expr` as written, before it is converted to its value, as the operand of
+    /// `sizeof`, `&` or `typeof`
+    pub(super) fn expr(&mut self, expr: &'a Expression<'i>) -> TypedExpr<'a, 'i> {
+        match expr {
+            Expression::Literal(literal) => self.literal(literal),
+            Expression::String(string) => self.string(expr, string),
+            Expression::Ident(name) => self.name(name),
+            Expression::Unary { op, operand } => self.unary(expr, *op, operand),
+            Expression::Postfix { op, operand } => {
+                let operand = self.expr(operand);
+                match self.increment(expr, operand) {
+                    Some((operand, ty)) => {
+                        let kind = ExprKind::Postfix {
+                            op: *op,
+                            operand: Box::new(operand),
+                        };
+                        Self::typed(kind, ty, ValueCategory::RValue)
+                    }
+                    None => self.invalid(),
+                }
+            }
+            Expression::Binary { op, lhs, rhs } => {
+                let (lhs_value, rhs_value) = (self.value(lhs), self.value(rhs));
+                self.binary(expr, *op, lhs_value, rhs_value, rhs)
+            }
+            Expression::Assign { op, lhs, rhs } => self.assignment(expr, *op, lhs, rhs),
+            Expression::Conditional {
+                condition,
+                then,
+                r#else,
+            } => self.conditional(expr, condition, then, r#else),
+            Expression::Cast { ty, expr: operand } => {
+                self.type_name(ty);
+                let target = self.types.type_name(self.resolution, ty);
+                let operand = self.value(operand);
+                self.cast(expr, target, operand)
+            }
+            Expression::CompoundLiteral { ty, init, .. } => {
+                self.type_name(ty);
+                let target = self.types.type_name(self.resolution, ty);
+                let target = self.initializer_list(target, init);
+                Self::typed(
+                    ExprKind::CompoundLiteral(init),
+                    target,
+                    ValueCategory::LValue,
+                )
+            }
+            Expression::SizeofExpr(operand) => {
+                let operand = self.expr(operand);
+                self.size_of(expr, operand.ty, "sizeof")
+            }
+            Expression::SizeofType(_, ty) => {
+                self.type_name(ty);
+                let ty = self.types.type_name(self.resolution, ty);
+                self.size_of(expr, ty, "sizeof")
+            }
+            Expression::AlignofType(_, ty) => {
+                self.type_name(ty);
+                let ty = self.types.type_name(self.resolution, ty);
+                self.size_of(expr, ty, "alignof")
+            }
+            Expression::Generic(generic) => self.generic(expr, generic),
+            Expression::Call { callee, args } => self.call(expr, callee, args),
+            Expression::Index { array, index } => {
+                let (array, index) = (self.value(array), self.value(index));
+                self.index(expr, array, index)
+            }
+            Expression::Member {
+                object,
+                member,
+                arrow,
+            } => self.member(expr, object, member, *arrow),
+            Expression::LabelAddress(label) => {
+                let void = self.types.intern(Type::Void);
+                let label = self.resolution.binding(label);
+                self.rvalue(ExprKind::LabelAddress(label), Type::Pointer(void))
+            }
+            Expression::Statement(_, body) => {
+                self.statements(body);
+                // The value is that of the last statement, already typed
+                let ty = match body.last() {
+                    Some(Statement::Expression(last)) => {
+                        self.typing.expression(last).map(|typed| typed.ty)
+                    }
+                    _ => None,
+                };
+                let ty = ty.unwrap_or_else(|| self.types.intern(Type::Void));
+                Self::typed(ExprKind::Statement(body), ty, ValueCategory::RValue)
+            }
+            Expression::Offsetof {
+                ty,
+                member,
+                designators,
+            } => self.offset_of(expr, ty, member, designators),
+            Expression::TypesCompatible(_, a, b) => {
+                self.type_name(a);
+                self.type_name(b);
+                let a = self.types.type_name(self.resolution, a);
+                let b = self.types.type_name(self.resolution, b);
+                let (a, b) = (self.types.unqualified(a).0, self.types.unqualified(b).0);
+                let is_compatible = self.types.is_compatible(a, b);
+                self.rvalue(
+                    ExprKind::TypesCompatible(is_compatible),
+                    Type::Int(IntKind::Int),
+                )
+            }
+            Expression::Error(_) => self.invalid(),
+        }
+    }
+
+    fn literal(&mut self, literal: &'a Literal<'i>) -> TypedExpr<'a, 'i> {
+        let kind = ExprKind::Literal(literal);
+        match *literal {
+            Literal::Int(number) => {
+                let Some((value, suffix)) = int_value(number) else {
+                    return self.invalid();
+                };
+                let ty = int_literal(value, suffix, self.types.target());
+                self.rvalue(kind, Type::Int(ty))
+            }
+            Literal::Float(number) => {
+                let float = match number.chars().last().map(|c| c.to_ascii_lowercase()) {
+                    Some('f') => FloatKind::Float,
+                    Some('l') => FloatKind::LongDouble,
+                    _ => FloatKind::Double,
+                };
+                let float = Type::Float {
+                    kind: float,
+                    is_complex: false,
+                };
+                self.rvalue(kind, float)
+            }
+            Literal::Char(char) => {
+                let (element, _) = literal_parts(char);
+                // Character constants are `int`, unless they have a prefix
+                let element = if element == IntKind::Char {
+                    IntKind::Int
+                } else {
+                    element
+                };
+                self.rvalue(kind, Type::Int(element))
+            }
+            Literal::String(_) => unreachable!("string literals are parsed as Expression::String"),
+        }
+    }
+
+    /// String literals, concatenated. A prefix applies to the pieces without one.
+    fn string(
+        &mut self,
+        expr: &'a Expression<'i>,
+        string: &'a StringLiteral<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let pieces: Vec<_> = string
+            .pieces
+            .iter()
+            .map(|piece| literal_parts(piece))
+            .collect();
+        let mut element = IntKind::Char;
+        for &(kind, _) in &pieces {
+            if kind == IntKind::Char || kind == element {
+                continue;
+            }
+            if element != IntKind::Char {
+                self.error(
+                    expr,
+                    "unsupported non-standard concatenation of string literals".to_string(),
+                );
+                return self.invalid();
+            }
+            element = kind;
+        }
+        // Every piece but the last loses its terminating null character
+        let length = pieces
+            .iter()
+            .map(|(_, contents)| string_length(contents, element != IntKind::Char) - 1)
+            .sum::<u64>()
+            + 1;
+        let element = self.types.int(element);
+        let array = self.types.intern(Type::Array {
+            element,
+            length: ArrayLength::Fixed(length),
+        });
+        Self::typed(ExprKind::String(string), array, ValueCategory::LValue)
+    }
+
+    /// An identifier used as an expression
+    fn name(&mut self, name: &'a Ident<'i>) -> TypedExpr<'a, 'i> {
+        let Some(id) = self.resolution.binding(name) else {
+            return match self.function {
+                Some((function, _)) if PREDEFINED.contains(&name.name) => {
+                    // `static const char __func__[] = "name";`
+                    let char = self.types.int(IntKind::Char);
+                    let qualifiers = TypeQualifiers {
+                        is_const: true,
+                        ..TypeQualifiers::default()
+                    };
+                    let char = self.types.qualified(char, qualifiers);
+                    let length = ArrayLength::Fixed(function.name.len() as u64 + 1);
+                    let array = self.types.intern(Type::Array {
+                        element: char,
+                        length,
+                    });
+                    Self::typed(ExprKind::FunctionName(name), array, ValueCategory::LValue)
+                }
+                // Undeclared names are reported by name resolution, and builtins are
+                // not known
+                _ => self.invalid(),
+            };
+        };
+        self.deprecated(name, id);
+        self.symbol(id)
+    }
+
+    fn symbol(&mut self, id: SymbolId) -> TypedExpr<'a, 'i> {
+        let category = match self.resolution.symbol(id).kind {
+            SymbolKind::Object(_) | SymbolKind::Parameter(_) => ValueCategory::LValue,
+            SymbolKind::Function(_) | SymbolKind::ImplicitFunction => ValueCategory::Function,
+            SymbolKind::Enumerator { .. } => ValueCategory::RValue,
+            SymbolKind::Typedef(_) | SymbolKind::Tag(_) | SymbolKind::Label => {
+                return self.invalid()
+            }
+        };
+        match self.types.symbol(self.resolution, id) {
+            Some(ty) => Self::typed(ExprKind::Symbol(id), ty, category),
+            None => self.invalid(),
+        }
+    }
+
+    /// Reports that `typed`, the operand of an assignment or increment, cannot be
+    /// modified. Returns whether it can.
+    pub(super) fn assignable(&mut self, expr: &Expression<'i>, typed: &TypedExpr<'a, 'i>) -> bool {
+        if self.is_error(typed.ty) {
+            return false;
+        }
+        let message = if typed.category != ValueCategory::LValue {
+            "expression is not assignable".to_string()
+        } else if matches!(self.types.get(typed.ty), Type::Array { .. }) {
+            format!("array type '{}' is not assignable", self.display(typed.ty))
+        } else if self.types.unqualified(typed.ty).1.is_const {
+            match typed.kind {
+                ExprKind::Symbol(id) => format!(
+                    "cannot assign to variable '{}' with const-qualified type '{}'",
+                    self.resolution.symbol(id).name,
+                    self.display(typed.ty)
+                ),
+                _ => "read-only variable is not assignable".to_string(),
+            }
+        } else {
+            return true;
+        };
+        self.error(expr, message);
+        false
+    }
+
+    fn unary(
+        &mut self,
+        expr: &'a Expression<'i>,
+        op: UnaryOp,
+        operand: &'a Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let unary = |operand, ty, category| {
+            let kind = ExprKind::Unary {
+                op,
+                operand: Box::new(operand),
+            };
+            Self::typed(kind, ty, category)
+        };
+        match op {
+            UnaryOp::AddressOf => {
+                let operand = self.expr(operand);
+                if self.is_error(operand.ty) {
+                    return operand;
+                }
+                if operand.category == ValueCategory::RValue {
+                    let message = format!(
+                        "cannot take the address of an rvalue of type '{}'",
+                        self.display(operand.ty)
+                    );
+                    self.error(expr, message);
+                    return self.invalid();
+                }
+                let pointer = self.types.pointer(operand.ty);
+                unary(operand, pointer, ValueCategory::RValue)
+            }
+            UnaryOp::Deref => {
+                let operand = self.value(operand);
+                if self.is_error(operand.ty) {
+                    return operand;
+                }
+                let Some(to) = self.types.pointee(operand.ty) else {
+                    let message = format!(
+                        "indirection requires pointer operand ('{}' invalid)",
+                        self.display(operand.ty)
+                    );
+                    self.error(expr, message);
+                    return self.invalid();
+                };
+                let category = match self.types.get(to) {
+                    Type::Function { .. } => ValueCategory::Function,
+                    _ => ValueCategory::LValue,
+                };
+                unary(operand, to, category)
+            }
+            UnaryOp::Plus | UnaryOp::Minus | UnaryOp::BitNot | UnaryOp::Not => {
+                let operand = self.value(operand);
+                if self.is_error(operand.ty) {
+                    return operand;
+                }
+                let is_valid = match op {
+                    UnaryOp::BitNot => self.types.is_integer(operand.ty),
+                    UnaryOp::Not => self.types.is_scalar(operand.ty),
+                    _ => self.types.is_arithmetic(operand.ty),
+                };
+                if !is_valid {
+                    let message = format!(
+                        "invalid argument type '{}' to unary expression",
+                        self.display(operand.ty)
+                    );
+                    self.error(expr, message);
+                    return self.invalid();
+                }
+                if op == UnaryOp::Not {
+                    let int = self.types.int(IntKind::Int);
+                    return unary(operand, int, ValueCategory::RValue);
+                }
+                let operand = self.promote(operand);
+                let ty = operand.ty;
+                unary(operand, ty, ValueCategory::RValue)
+            }
+            UnaryOp::PreIncrement | UnaryOp::PreDecrement => {
+                let operand = self.expr(operand);
+                match self.increment(expr, operand) {
+                    Some((operand, ty)) => unary(operand, ty, ValueCategory::RValue),
+                    None => self.invalid(),
+                }
+            }
+        }
+    }
+
+    /// Checks the operand of `++` or `--`, which must be a modifiable lvalue of real or
+    /// pointer type, and gives the type of the result
+    fn increment(
+        &mut self,
+        expr: &Expression<'i>,
+        operand: TypedExpr<'a, 'i>,
+    ) -> Option<(TypedExpr<'a, 'i>, TypeId)> {
+        if !self.assignable(expr, &operand) {
+            return None;
+        }
+        let (ty, _) = self.types.unqualified(operand.ty);
+        if !self.types.is_scalar(ty) {
+            let message = format!(
+                "cannot increment value of type '{}'",
+                self.display(operand.ty)
+            );
+            self.error(expr, message);
+            return None;
+        }
+        Some((operand, ty))
+    }
+
+    fn invalid_operands(&mut self, expr: &Expression<'i>, lhs: TypeId, rhs: TypeId) {
+        let message = format!(
+            "invalid operands to binary expression ('{}' and '{}')",
+            self.display(lhs),
+            self.display(rhs)
+        );
+        self.error(expr, message);
+    }
+
+    /// Types a binary operation on two values. `rhs_expr` is the right operand as
+    /// written, which may be a null pointer constant.
+    fn binary(
+        &mut self,
+        expr: &Expression<'i>,
+        op: BinaryOp,
+        lhs: TypedExpr<'a, 'i>,
+        rhs: TypedExpr<'a, 'i>,
+        rhs_expr: &Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        if self.is_error(lhs.ty) || self.is_error(rhs.ty) {
+            return self.invalid();
+        }
+        let binary = |lhs, rhs, ty| {
+            let kind = ExprKind::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            Self::typed(kind, ty, ValueCategory::RValue)
+        };
+        let int = self.types.int(IntKind::Int);
+        let types = &*self.types;
+        let (is_arithmetic, is_integer) = (
+            types.is_arithmetic(lhs.ty) && types.is_arithmetic(rhs.ty),
+            types.is_integer(lhs.ty) && types.is_integer(rhs.ty),
+        );
+        let (lhs_pointee, rhs_pointee) = (types.pointee(lhs.ty), types.pointee(rhs.ty));
+        match op {
+            BinaryOp::Comma => {
+                let ty = rhs.ty;
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::And | BinaryOp::Or if types.is_scalar(lhs.ty) && types.is_scalar(rhs.ty) => {
+                return binary(lhs, rhs, int);
+            }
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Add | BinaryOp::Sub if is_arithmetic => {
+                let (lhs, rhs, ty) = self.arithmetic(lhs, rhs);
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::Mod | BinaryOp::BitAnd | BinaryOp::BitXor | BinaryOp::BitOr if is_integer => {
+                let (lhs, rhs, ty) = self.arithmetic(lhs, rhs);
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::Shl | BinaryOp::Shr if is_integer => {
+                let (lhs, rhs) = (self.promote(lhs), self.promote(rhs));
+                let ty = lhs.ty;
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::Add | BinaryOp::Sub if lhs_pointee.is_some() && types.is_integer(rhs.ty) => {
+                let (ty, rhs) = (lhs.ty, self.promote(rhs));
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::Add if rhs_pointee.is_some() && types.is_integer(lhs.ty) => {
+                let (ty, lhs) = (rhs.ty, self.promote(lhs));
+                return binary(lhs, rhs, ty);
+            }
+            BinaryOp::Sub => {
+                if let (Some(a), Some(b)) = (lhs_pointee, rhs_pointee) {
+                    let (a, b) = (types.unqualified(a).0, types.unqualified(b).0);
+                    if types.is_compatible(a, b) {
+                        let ptrdiff = self.types.int(IntKind::Long);
+                        return binary(lhs, rhs, ptrdiff);
+                    }
+                }
+            }
+            BinaryOp::Less
+            | BinaryOp::Greater
+            | BinaryOp::LessEquals
+            | BinaryOp::GreaterEquals
+            | BinaryOp::Equals
+            | BinaryOp::NotEquals => return self.comparison(expr, op, lhs, rhs, rhs_expr),
+            _ => {}
+        }
+        self.invalid_operands(expr, lhs.ty, rhs.ty);
+        self.invalid()
+    }
+
+    /// Types a relational or equality operation, which compares arithmetic values or
+    /// pointers
+    fn comparison(
+        &mut self,
+        expr: &Expression<'i>,
+        op: BinaryOp,
+        lhs: TypedExpr<'a, 'i>,
+        rhs: TypedExpr<'a, 'i>,
+        rhs_expr: &Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let int = self.types.int(IntKind::Int);
+        let binary = |lhs, rhs| {
+            let kind = ExprKind::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            Self::typed(kind, int, ValueCategory::RValue)
+        };
+        let is_equality = matches!(op, BinaryOp::Equals | BinaryOp::NotEquals);
+        if self.types.is_arithmetic(lhs.ty) && self.types.is_arithmetic(rhs.ty) {
+            let (lhs, rhs, _) = self.arithmetic(lhs, rhs);
+            return binary(lhs, rhs);
+        }
+        match (self.types.pointee(lhs.ty), self.types.pointee(rhs.ty)) {
+            (Some(a), Some(b)) => {
+                let (a, b) = (self.types.unqualified(a).0, self.types.unqualified(b).0);
+                let is_void = |ty| *self.types.get(ty) == Type::Void;
+                if !(self.types.is_compatible(a, b) || is_equality && (is_void(a) || is_void(b))) {
+                    let message = format!(
+                        "comparison of distinct pointer types ('{}' and '{}')",
+                        self.display(lhs.ty),
+                        self.display(rhs.ty)
+                    );
+                    self.warning(expr, message);
+                }
+                binary(lhs, rhs)
+            }
+            (Some(_), None) if self.types.is_integer(rhs.ty) => {
+                if is_equality && self.is_null_constant(rhs_expr, &rhs) {
+                    let ty = lhs.ty;
+                    let rhs = self.implicit(CastKind::NullToPointer, rhs, ty);
+                    return binary(lhs, rhs);
+                }
+                self.pointer_integer_comparison(expr, lhs.ty, rhs.ty);
+                let ty = lhs.ty;
+                let rhs = self.implicit(CastKind::IntegralToPointer, rhs, ty);
+                binary(lhs, rhs)
+            }
+            (None, Some(_)) if self.types.is_integer(lhs.ty) => {
+                // Null pointer constants on the left are only literals
+                let is_null = is_equality
+                    && matches!(lhs.kind, ExprKind::Literal(Literal::Int(number))
+                        if int_value(number).is_some_and(|(value, _)| value == 0));
+                if !is_null {
+                    self.pointer_integer_comparison(expr, lhs.ty, rhs.ty);
+                }
+                let kind = if is_null {
+                    CastKind::NullToPointer
+                } else {
+                    CastKind::IntegralToPointer
+                };
+                let ty = rhs.ty;
+                let lhs = self.implicit(kind, lhs, ty);
+                binary(lhs, rhs)
+            }
+            _ => {
+                self.invalid_operands(expr, lhs.ty, rhs.ty);
+                self.invalid()
+            }
+        }
+    }
+
+    fn pointer_integer_comparison(&mut self, expr: &Expression<'i>, lhs: TypeId, rhs: TypeId) {
+        let message = format!(
+            "comparison between pointer and integer ('{}' and '{}')",
+            self.display(lhs),
+            self.display(rhs)
+        );
+        self.warning(expr, message);
+    }
+
+    fn assignment(
+        &mut self,
+        expr: &'a Expression<'i>,
+        op: AssignOp,
+        lhs_expr: &'a Expression<'i>,
+        rhs_expr: &'a Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let lhs = self.expr(lhs_expr);
+        let rhs = self.value(rhs_expr);
+        if !self.assignable(lhs_expr, &lhs) || self.is_error(rhs.ty) {
+            return self.invalid();
+        }
+        let (ty, _) = self.types.unqualified(lhs.ty);
+        let assign = |lhs, rhs| {
+            let kind = ExprKind::Assign {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            Self::typed(kind, ty, ValueCategory::RValue)
+        };
+        let Some(binary) = op.0 else {
+            let rhs = self.assign(Conversion::Assigning, ty, rhs, rhs_expr);
+            return assign(lhs, rhs);
+        };
+
+        // `lhs op= rhs` is `lhs = lhs op rhs`, with `lhs` evaluated once
+        let is_pointer_arithmetic = matches!(binary, BinaryOp::Add | BinaryOp::Sub)
+            && self.types.pointee(ty).is_some()
+            && self.types.is_integer(rhs.ty);
+        let is_valid = match binary {
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Add | BinaryOp::Sub => {
+                is_pointer_arithmetic
+                    || self.types.is_arithmetic(ty) && self.types.is_arithmetic(rhs.ty)
+            }
+            _ => self.types.is_integer(ty) && self.types.is_integer(rhs.ty),
+        };
+        if !is_valid {
+            self.invalid_operands(expr, lhs.ty, rhs.ty);
+            return self.invalid();
+        }
+        let rhs = match binary {
+            _ if is_pointer_arithmetic => self.promote(rhs),
+            BinaryOp::Shl | BinaryOp::Shr => self.promote(rhs),
+            _ => {
+                let common = self.common_type(ty, rhs.ty);
+                self.convert(rhs, common)
+            }
+        };
+        assign(lhs, rhs)
+    }
+
+    fn conditional(
+        &mut self,
+        expr: &'a Expression<'i>,
+        condition: &'a Expression<'i>,
+        then_expr: &'a Expression<'i>,
+        else_expr: &'a Expression<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let condition = self.value(condition);
+        let then = self.value(then_expr);
+        let r#else = self.value(else_expr);
+        if self.is_error(condition.ty) || self.is_error(then.ty) || self.is_error(r#else.ty) {
+            return self.invalid();
+        }
+        if !self.types.is_scalar(condition.ty) {
+            let message = format!(
+                "used type '{}' where arithmetic or pointer type is required",
+                self.display(condition.ty)
+            );
+            self.error(expr, message);
+            return self.invalid();
+        }
+        let conditional = |condition, then, r#else, ty| {
+            let kind = ExprKind::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                r#else: Box::new(r#else),
+            };
+            Self::typed(kind, ty, ValueCategory::RValue)
+        };
+
+        if self.types.is_arithmetic(then.ty) && self.types.is_arithmetic(r#else.ty) {
+            let (then, r#else, ty) = self.arithmetic(then, r#else);
+            return conditional(condition, then, r#else, ty);
+        }
+        if self.types.is_compatible(then.ty, r#else.ty) {
+            let ty = then.ty;
+            return conditional(condition, then, r#else, ty);
+        }
+        let (then_pointee, else_pointee) =
+            (self.types.pointee(then.ty), self.types.pointee(r#else.ty));
+        match (then_pointee, else_pointee) {
+            (Some(_), None) if self.is_null_constant(else_expr, &r#else) => {
+                let ty = then.ty;
+                let r#else = self.implicit(CastKind::NullToPointer, r#else, ty);
+                conditional(condition, then, r#else, ty)
+            }
+            (None, Some(_)) if self.is_null_constant(then_expr, &then) => {
+                let ty = r#else.ty;
+                let then = self.implicit(CastKind::NullToPointer, then, ty);
+                conditional(condition, then, r#else, ty)
+            }
+            (Some(a), Some(b)) => {
+                // The result points to the qualifiers of both
+                let ((a, a_qualifiers), (b, b_qualifiers)) =
+                    (self.types.unqualified(a), self.types.unqualified(b));
+                let qualifiers = TypeQualifiers {
+                    is_const: a_qualifiers.is_const || b_qualifiers.is_const,
+                    is_volatile: a_qualifiers.is_volatile || b_qualifiers.is_volatile,
+                    is_restrict: a_qualifiers.is_restrict || b_qualifiers.is_restrict,
+                    is_atomic: a_qualifiers.is_atomic || b_qualifiers.is_atomic,
+                };
+                let void = self.types.intern(Type::Void);
+                let pointee = if self.types.is_compatible(a, b) {
+                    a
+                } else if a == void || b == void {
+                    void
+                } else {
+                    let message = format!(
+                        "pointer type mismatch ('{}' and '{}')",
+                        self.display(then.ty),
+                        self.display(r#else.ty)
+                    );
+                    self.warning(expr, message);
+                    void
+                };
+                let pointee = self.types.qualified(pointee, qualifiers);
+                let ty = self.types.pointer(pointee);
+                let then = self.convert(then, ty);
+                let r#else = self.convert(r#else, ty);
+                conditional(condition, then, r#else, ty)
+            }
+            (Some(_), None) | (None, Some(_))
+                if self.types.is_integer(then.ty) || self.types.is_integer(r#else.ty) =>
+            {
+                let message = format!(
+                    "pointer/integer type mismatch in conditional expression ('{}' and '{}')",
+                    self.display(then.ty),
+                    self.display(r#else.ty)
+                );
+                self.warning(expr, message);
+                let ty = if then_pointee.is_some() {
+                    then.ty
+                } else {
+                    r#else.ty
+                };
+                let then = self.convert(then, ty);
+                let r#else = self.convert(r#else, ty);
+                conditional(condition, then, r#else, ty)
+            }
+            _ => {
+                let message = format!(
+                    "incompatible operand types ('{}' and '{}')",
+                    self.display(then.ty),
+                    self.display(r#else.ty)
+                );
+                self.error(expr, message);
+                self.invalid()
+            }
+        }
+    }
+
+    /// A cast written in the source, which converts between scalar types or to `void`
+    fn cast(
+        &mut self,
+        expr: &Expression<'i>,
+        target: TypeId,
+        operand: TypedExpr<'a, 'i>,
+    ) -> TypedExpr<'a, 'i> {
+        if self.is_error(target) || self.is_error(operand.ty) {
+            return self.invalid();
+        }
+        let (target, _) = self.types.unqualified(target);
+        let is_void = *self.types.get(target) == Type::Void;
+        let is_float = |ty| matches!(self.types.get(ty), Type::Float { .. });
+        let message = if is_void {
+            None
+        } else if !self.types.is_scalar(target) {
+            Some(format!(
+                "used type '{}' where arithmetic or pointer type is required",
+                self.display(target)
+            ))
+        } else if !self.types.is_scalar(operand.ty) {
+            Some(format!(
+                "operand of type '{}' where arithmetic or pointer type is required",
+                self.display(operand.ty)
+            ))
+        } else if self.types.pointee(target).is_some() && is_float(operand.ty) {
+            Some(format!(
+                "operand of type '{}' cannot be cast to a pointer type",
+                self.display(operand.ty)
+            ))
+        } else if self.types.pointee(operand.ty).is_some() && is_float(target) {
+            Some(format!(
+                "pointer cannot be cast to type '{}'",
+                self.display(target)
+            ))
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            self.error(expr, message);
+            return self.invalid();
+        }
+        Self::typed(
+            ExprKind::Cast(Box::new(operand)),
+            target,
+            ValueCategory::RValue,
+        )
+    }
+
+    /// `sizeof` or `_Alignof` of `ty`, which must be a complete object type
+    fn size_of(&mut self, expr: &Expression<'i>, ty: TypeId, operator: &str) -> TypedExpr<'a, 'i> {
+        if self.is_error(ty) {
+            return self.invalid();
+        }
+        let (unqualified, _) = self.types.unqualified(ty);
+        let problem = match self.types.get(unqualified) {
+            Type::Function { .. } => Some("a function type".to_string()),
+            // GNU extension, where `void` has size 1
+            Type::Void => None,
+            _ if !self.types.is_complete(ty) => {
+                Some(format!("an incomplete type '{}'", self.display(ty)))
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            self.error(
+                expr,
+                format!("invalid application of '{operator}' to {problem}"),
+            );
+            return self.invalid();
+        }
+        let kind = if operator == "sizeof" {
+            ExprKind::Sizeof(ty)
+        } else {
+            ExprKind::Alignof(ty)
+        };
+        self.rvalue(kind, Type::Int(IntKind::ULong))
+    }
+
+    /// `_Generic`, whose value is that of the association matching the type of the
+    /// controlling expression after lvalue conversion
+    fn generic(
+        &mut self,
+        expr: &'a Expression<'i>,
+        generic: &'a GenericSelection<'i>,
+    ) -> TypedExpr<'a, 'i> {
+        let controlling = self.value(&generic.controlling);
+        let mut selected = None;
+        let mut default = None;
+        for association in generic.associations.iter() {
+            let value = self.expr(&association.value);
+            let Some(ref ty) = association.ty else {
+                default = Some(value);
+                continue;
+            };
+            self.type_name(ty);
+            let ty = self.types.type_name(self.resolution, ty);
+            if selected.is_none() && self.types.is_compatible(ty, controlling.ty) {
+                selected = Some(value);
+            }
+        }
+        if self.is_error(controlling.ty) {
+            return self.invalid();
+        }
+        match selected.or(default) {
+            Some(value) => value,
+            None => {
+                let message = format!(
+                    "controlling expression type '{}' not compatible with any generic association type",
+                    self.display(controlling.ty)
+                );
+                self.error(expr, message);
+                self.invalid()
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        expr: &'a Expression<'i>,
+        callee_expr: &'a Expression<'i>,
+        args: &'a [Expression<'i>],
+    ) -> TypedExpr<'a, 'i> {
+        let callee = self.value(callee_expr);
+        let values: Vec<_> = args.iter().map(|arg| self.value(arg)).collect();
+        if self.is_error(callee.ty) {
+            return self.invalid();
+        }
+        let function = self
+            .types
+            .pointee(callee.ty)
+            .map(|to| self.types.get(to).clone());
+        let Some(Type::Function {
+            ret,
+            params,
+            is_variadic,
+        }) = function
+        else {
+            let message = format!(
+                "called object type '{}' is not a function or function pointer",
+                self.display(callee.ty)
+            );
+            self.error(expr, message);
+            return self.invalid();
+        };
+
+        if let Some(ref params) = params {
+            let (expected, have) = (params.len(), args.len());
+            let problem = if have < expected {
+                Some("too few")
+            } else if have > expected && !is_variadic {
+                Some("too many")
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                let at_least = if is_variadic { "at least " } else { "" };
+                let message = format!(
+                    "{problem} arguments to function call, expected {at_least}{expected}, have {have}"
+                );
+                self.error(expr, message);
+                return self.invalid();
+            }
+        }
+        let params = params.unwrap_or_default();
+        let args = values
+            .into_iter()
+            .zip(args)
+            .enumerate()
+            .map(|(n, (value, arg))| match params.get(n) {
+                Some(&param) => self.assign(Conversion::Passing, param, value, arg),
+                None => self.promote_argument(value),
+            })
+            .collect();
+        let (ret, _) = self.types.unqualified(ret);
+        let kind = ExprKind::Call {
+            callee: Box::new(callee),
+            args,
+        };
+        Self::typed(kind, ret, ValueCategory::RValue)
+    }
+
+    /// `array[index]`, which is `*(array + index)`, so either operand may be the pointer
+    fn index(
+        &mut self,
+        expr: &Expression<'i>,
+        array: TypedExpr<'a, 'i>,
+        index: TypedExpr<'a, 'i>,
+    ) -> TypedExpr<'a, 'i> {
+        if self.is_error(array.ty) || self.is_error(index.ty) {
+            return self.invalid();
+        }
+        let (array, index) = match (self.types.pointee(array.ty), self.types.pointee(index.ty)) {
+            (None, Some(_)) => (index, array),
+            _ => (array, index),
+        };
+        let Some(element) = self.types.pointee(array.ty) else {
+            self.error(
+                expr,
+                "subscripted value is not an array, pointer, or vector".to_string(),
+            );
+            return self.invalid();
+        };
+        if !self.types.is_integer(index.ty) {
+            self.error(expr, "array subscript is not an integer".to_string());
+            return self.invalid();
+        }
+        let index = self.promote(index);
+        let kind = ExprKind::Index {
+            array: Box::new(array),
+            index: Box::new(index),
+        };
+        Self::typed(kind, element, ValueCategory::LValue)
+    }
+
+    /// `object.member` or `object->member`, whose type has the qualifiers of the object
+    fn member(
+        &mut self,
+        expr: &'a Expression<'i>,
+        object: &'a Expression<'i>,
+        member: &'a Ident<'i>,
+        arrow: bool,
+    ) -> TypedExpr<'a, 'i> {
+        let object = if arrow {
+            self.value(object)
+        } else {
+            self.expr(object)
+        };
+        if self.is_error(object.ty) {
+            return object;
+        }
+        let record = if arrow {
+            let Some(to) = self.types.pointee(object.ty) else {
+                let message = format!(
+                    "member reference type '{}' is not a pointer",
+                    self.display(object.ty)
+                );
+                self.error(expr, message);
+                return self.invalid();
+            };
+            to
+        } else {
+            let is_record_pointer = self.types.pointee(object.ty).is_some_and(|to| {
+                matches!(
+                    self.types.get(self.types.unqualified(to).0),
+                    Type::Record(_)
+                )
+            });
+            if is_record_pointer {
+                let message = format!(
+                    "member reference type '{}' is a pointer; did you mean to use '->'?",
+                    self.display(object.ty)
+                );
+                self.error(expr, message);
+                return self.invalid();
+            }
+            object.ty
+        };
+        let (unqualified, qualifiers) = self.types.unqualified(record);
+        let Type::Record(id) = *self.types.get(unqualified) else {
+            let message = format!(
+                "member reference base type '{}' is not a structure or union",
+                self.display(record)
+            );
+            self.error(expr, message);
+            return self.invalid();
+        };
+        if !self.types.is_complete(unqualified) {
+            let message = format!("incomplete definition of type '{}'", self.display(record));
+            self.error(expr, message);
+            return self.invalid();
+        }
+        let Some((path, ty)) = self.types.find_member(id, member.name) else {
+            let message = format!(
+                "no member named '{member}' in '{}'",
+                self.display(unqualified)
+            );
+            self.error(expr, message);
+            return self.invalid();
+        };
+        let ty = self.types.qualified(ty, qualifiers);
+        let category = if arrow {
+            ValueCategory::LValue
+        } else {
+            object.category
+        };
+        let kind = ExprKind::Member {
+            object: Box::new(object),
+            path,
+            arrow,
+        };
+        Self::typed(kind, ty, category)
+    }
+
+    /// `__builtin_offsetof(type, member)`, where the member may be nested
+    fn offset_of(
+        &mut self,
+        expr: &Expression<'i>,
+        ty: &'a TypeName<'i>,
+        member: &'a Ident<'i>,
+        designators: &'a [Designator<'i>],
+    ) -> TypedExpr<'a, 'i> {
+        self.type_name(ty);
+        let record = self.types.type_name(self.resolution, ty);
+        let mut current = record;
+        let names = std::iter::once(Ok(member)).chain(designators.iter().map(|d| match d {
+            Designator::Member(name) => Ok(name),
+            other => Err(other),
+        }));
+        for designator in names {
+            if self.is_error(current) {
+                return self.invalid();
+            }
+            let (unqualified, _) = self.types.unqualified(current);
+            match (designator, self.types.get(unqualified).clone()) {
+                (Ok(name), Type::Record(id)) => match self.types.find_member(id, name.name) {
+                    Some((_, ty)) => current = ty,
+                    None => {
+                        let message = format!(
+                            "no member named '{name}' in '{}'",
+                            self.display(unqualified)
+                        );
+                        self.error(expr, message);
+                        return self.invalid();
+                    }
+                },
+                (Err(Designator::Index(index)), Type::Array { element, .. }) => {
+                    self.integer(index, "array index");
+                    current = element;
+                }
+                _ => {
+                    let message = format!(
+                        "offsetof requires struct, union, or array type, '{}' invalid",
+                        self.display(current)
+                    );
+                    self.error(expr, message);
+                    return self.invalid();
+                }
+            }
+        }
+        let kind = ExprKind::Offsetof {
+            ty: record,
+            member: *member,
+            designators,
+        };
+        self.rvalue(kind, Type::Int(IntKind::ULong))
+    }
+}
@@ -0,0 +1,951 @@
+//! Evaluation of constant expressions over the typed tree, with the semantics C gives
+//! each type: unsigned arithmetic wraps around, while signed overflow, division by
+//! zero and shifts out of range are undefined, and reported.
+
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use crate::{
+    ast::tree::{BinaryOp, Designator, StorageClass, StringLiteral, UnaryOp},
+    lexer::token::{int_value, Ident, Literal},
+    target::Target,
+};
+
+use super::{
+    typed::{CastKind, ExprKind, TypedExpr, ValueCategory},
+    FloatKind, IntKind, Resolution, ScopeKind, SymbolId, SymbolKind, Type, TypeContext, TypeId,
+};
+
+/// What an address constant points into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base<'a, 'i> {
+    /// An object with static storage duration, or a function
+    Symbol(SymbolId),
+    String(&'a StringLiteral<'i>),
+    /// A label whose address is taken, as in `&&done`
+    Label(SymbolId),
+}
+
+/// The value of a constant expression
+#[derive(Debug, Clone, Copy)]
+pub enum Constant<'a, 'i> {
+    /// An integer, within the range of its type, or a pointer that is only a number, as
+    /// a null pointer
+    Int(i128),
+    /// Rounded to its type
+    Float(f64),
+    /// An address known once the program is linked, `offset` bytes past `base`, as in
+    /// `&table[2]`
+    Address { base: Base<'a, 'i>, offset: i128 },
+}
+impl<'a, 'i> PartialEq for Constant<'a, 'i> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a == b,
+            // By representation, so that every value is equal to itself
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (
+                Self::Address { base, offset },
+                Self::Address {
+                    base: other_base,
+                    offset: other_offset,
+                },
+            ) => base == other_base && offset == other_offset,
+            _ => false,
+        }
+    }
+}
+impl<'a, 'i> Eq for Constant<'a, 'i> {}
+
+/// Why an expression has no constant value
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum ConstError {
+    /// Reads an object, calls a function, or has side effects
+    #[display(fmt = "expression is not a constant")]
+    NotConstant,
+    #[display(fmt = "overflow in expression; result is {value} with type '{ty}'")]
+    Overflow { value: i128, ty: IntKind },
+    #[display(fmt = "division by zero is undefined")]
+    DivisionByZero,
+    #[display(fmt = "remainder by zero is undefined")]
+    RemainderByZero,
+    #[display(fmt = "shift count is negative")]
+    NegativeShift,
+    #[display(fmt = "shift count >= width of type")]
+    LargeShift,
+    #[display(fmt = "shifting a negative signed value is undefined")]
+    NegativeShifted,
+    #[display(
+        fmt = "implicit conversion of out of range value from '{from}' to '{to}' is undefined"
+    )]
+    OutOfRange { from: String, to: String },
+}
+impl ConstError {
+    /// Whether the expression does something undefined, rather than something that is
+    /// only not constant
+    #[must_use]
+    pub fn is_undefined(&self) -> bool {
+        *self != Self::NotConstant
+    }
+}
+
+/// `value` converted to the integer type `kind` of `target`, modulo the range of the
+/// type for signed types too, as GCC does
+pub(crate) fn wrap(value: i128, kind: IntKind, target: &Target) -> i128 {
+    if kind == IntKind::Bool {
+        return i128::from(value != 0);
+    }
+    let modulus = 1 << target.int_bits(kind);
+    let value = value.rem_euclid(modulus);
+    if kind.is_signed() && value >= modulus / 2 {
+        value - modulus
+    } else {
+        value
+    }
+}
+
+/// The exact result of an operation done in `kind`, brought into its range: unsigned
+/// results wrap around, signed ones overflow
+fn checked(value: i128, kind: IntKind, target: &Target) -> Result<i128, ConstError> {
+    let wrapped = wrap(value, kind, target);
+    if kind.is_signed() && wrapped != value {
+        return Err(ConstError::Overflow {
+            value: wrapped,
+            ty: kind,
+        });
+    }
+    Ok(wrapped)
+}
+
+/// Applies `op` to two integers of type `kind`, its operands after the usual arithmetic
+/// conversions, or the promoted left operand for shifts
+pub(crate) fn binary(
+    op: BinaryOp,
+    lhs: i128,
+    rhs: i128,
+    kind: IntKind,
+    target: &Target,
+) -> Result<i128, ConstError> {
+    let bits = i128::from(target.int_bits(kind));
+    let value = match op {
+        // Only the product of 64-bit unsigned values may not fit, which wraps around
+        BinaryOp::Mul => lhs
+            .checked_mul(rhs)
+            .unwrap_or_else(|| (lhs as u128).wrapping_mul(rhs as u128) as i128),
+        BinaryOp::Div if rhs == 0 => return Err(ConstError::DivisionByZero),
+        BinaryOp::Mod if rhs == 0 => return Err(ConstError::RemainderByZero),
+        // Both truncate towards zero, and `INT_MIN / -1` overflows
+        BinaryOp::Div => lhs / rhs,
+        BinaryOp::Mod => {
+            checked(lhs / rhs, kind, target)?;
+            lhs % rhs
+        }
+        BinaryOp::Add => lhs + rhs,
+        BinaryOp::Sub => lhs - rhs,
+        BinaryOp::Shl | BinaryOp::Shr if rhs < 0 => return Err(ConstError::NegativeShift),
+        BinaryOp::Shl | BinaryOp::Shr if rhs >= bits => return Err(ConstError::LargeShift),
+        BinaryOp::Shl if lhs < 0 && kind.is_signed() => return Err(ConstError::NegativeShifted),
+        BinaryOp::Shl => {
+            let value = ((lhs as u128) << rhs) as i128;
+            // Shifting a one into the sign bit is accepted, as by GCC and Clang
+            if kind.is_signed() && value >> bits != 0 {
+                return Err(ConstError::Overflow {
+                    value: wrap(value, kind, target),
+                    ty: kind,
+                });
+            }
+            return Ok(wrap(value, kind, target));
+        }
+        // Arithmetic for negative values
+        BinaryOp::Shr => lhs >> rhs,
+        BinaryOp::Less => return Ok(i128::from(lhs < rhs)),
+        BinaryOp::Greater => return Ok(i128::from(lhs > rhs)),
+        BinaryOp::LessEquals => return Ok(i128::from(lhs <= rhs)),
+        BinaryOp::GreaterEquals => return Ok(i128::from(lhs >= rhs)),
+        BinaryOp::Equals => return Ok(i128::from(lhs == rhs)),
+        BinaryOp::NotEquals => return Ok(i128::from(lhs != rhs)),
+        BinaryOp::BitAnd => lhs & rhs,
+        BinaryOp::BitXor => lhs ^ rhs,
+        BinaryOp::BitOr => lhs | rhs,
+        BinaryOp::And => return Ok(i128::from(lhs != 0 && rhs != 0)),
+        BinaryOp::Or => return Ok(i128::from(lhs != 0 || rhs != 0)),
+        BinaryOp::Comma => return Err(ConstError::NotConstant),
+    };
+    checked(value, kind, target)
+}
+
+/// Applies `op` to an integer of type `kind`, its promoted type
+pub(crate) fn unary(
+    op: UnaryOp,
+    operand: i128,
+    kind: IntKind,
+    target: &Target,
+) -> Result<i128, ConstError> {
+    match op {
+        UnaryOp::Plus => Ok(operand),
+        UnaryOp::Minus => checked(-operand, kind, target),
+        UnaryOp::BitNot => Ok(wrap(!operand, kind, target)),
+        UnaryOp::Not => Ok(i128::from(operand == 0)),
+        UnaryOp::AddressOf | UnaryOp::Deref | UnaryOp::PreIncrement | UnaryOp::PreDecrement => {
+            Err(ConstError::NotConstant)
+        }
+    }
+}
+
+/// The element type of a string or character literal, from its prefix, and its
+/// contents without the prefix nor the quotes
+pub(crate) fn literal_parts(literal: &str) -> (IntKind, &str) {
+    let (kind, rest) = if let Some(rest) = literal.strip_prefix("u8") {
+        (IntKind::Char, rest)
+    } else if let Some(rest) = literal.strip_prefix('u') {
+        (IntKind::UShort, rest)
+    } else if let Some(rest) = literal.strip_prefix('U') {
+        (IntKind::UInt, rest)
+    } else if let Some(rest) = literal.strip_prefix('L') {
+        (IntKind::Int, rest)
+    } else {
+        (IntKind::Char, literal)
+    };
+    (
+        kind,
+        rest.get(1..rest.len().saturating_sub(1)).unwrap_or(""),
+    )
+}
+
+/// The code of the character or escape sequence at the start of `chars`
+fn character(chars: &mut Peekable<Chars<'_>>) -> Option<u32> {
+    let c = chars.next()?;
+    if c != '\\' {
+        return Some(u32::from(c));
+    }
+    // Up to `count` digits, after those already in `code`
+    let digits = |chars: &mut Peekable<Chars<'_>>, radix, count, mut code: u32| {
+        for _ in 0..count {
+            let Some(digit) = chars.peek().and_then(|c| c.to_digit(radix)) else {
+                break;
+            };
+            chars.next();
+            code = code.wrapping_mul(radix).wrapping_add(digit);
+        }
+        code
+    };
+    Some(match chars.next()? {
+        'n' => 0x0a,
+        't' => 0x09,
+        'r' => 0x0d,
+        'a' => 0x07,
+        'b' => 0x08,
+        'f' => 0x0c,
+        'v' => 0x0b,
+        // GNU escape for the escape character
+        'e' | 'E' => 0x1b,
+        'x' => digits(chars, 16, usize::MAX, 0),
+        'u' => digits(chars, 16, 4, 0),
+        'U' => digits(chars, 16, 8, 0),
+        c @ '0'..='7' => digits(chars, 8, 2, c.to_digit(8)?),
+        c => u32::from(c),
+    })
+}
+
+/// The value of a character constant, written with its prefix and quotes. Plain ones
+/// are values of `char`, signed here, and those with several characters are combined
+/// byte by byte, as GCC does.
+pub(crate) fn char_value(literal: &str, target: &Target) -> Option<i128> {
+    let (kind, contents) = literal_parts(literal);
+    let mut chars = contents.chars().peekable();
+    let first = character(&mut chars)?;
+    if kind != IntKind::Char {
+        return Some(wrap(i128::from(first), kind, target));
+    }
+    let mut value = wrap(i128::from(first), IntKind::Char, target);
+    while chars.peek().is_some() {
+        let next = wrap(i128::from(character(&mut chars)?), IntKind::UChar, target);
+        value = wrap((value << 8) | next, IntKind::Int, target);
+    }
+    Some(value)
+}
+
+/// The value of a floating constant as written, in decimal or in hexadecimal, before it
+/// is rounded to its type
+pub(crate) fn float_value(literal: &str) -> Option<f64> {
+    let number = literal.trim_end_matches(['f', 'F', 'l', 'L']);
+    let Some(hex) = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    else {
+        return number.parse().ok();
+    };
+    let (mantissa, exponent) = hex.split_once(['p', 'P'])?;
+    let exponent: i32 = exponent.parse().ok()?;
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut value = 0.0;
+    for digit in whole.chars().chain(fraction.chars()) {
+        value = value * 16.0 + f64::from(digit.to_digit(16)?);
+    }
+    let fraction = i32::try_from(fraction.len()).ok()?;
+    Some(value * 2f64.powi(exponent - 4 * fraction))
+}
+
+/// `value` rounded to the floating type `kind`. `long double` is only as precise as
+/// `double` here.
+fn round(value: f64, kind: FloatKind) -> f64 {
+    match kind {
+        FloatKind::Float => f64::from(value as f32),
+        FloatKind::Double | FloatKind::LongDouble => value,
+    }
+}
+
+/// Whether a constant compares unequal to zero, as conditions do. Addresses are never
+/// null.
+fn truth(value: Constant<'_, '_>) -> bool {
+    match value {
+        Constant::Int(value) => value != 0,
+        Constant::Float(value) => value != 0.0,
+        Constant::Address { .. } => true,
+    }
+}
+
+/// Computes the values of constant expressions, with the types and symbols of the
+/// program they are part of
+pub struct Evaluator<'c, 'a, 'i> {
+    resolution: &'c Resolution<'a, 'i>,
+    types: &'c mut TypeContext<'a, 'i>,
+}
+impl<'c, 'a, 'i: 'a> Evaluator<'c, 'a, 'i> {
+    pub fn new(resolution: &'c Resolution<'a, 'i>, types: &'c mut TypeContext<'a, 'i>) -> Self {
+        Self { resolution, types }
+    }
+
+    /// The value of an integer constant expression, such as the length of an array or
+    /// the value of a `case` label
+    pub fn integer(&mut self, expr: &TypedExpr<'a, 'i>) -> Result<i128, ConstError> {
+        if !self.types.is_integer(expr.ty) {
+            return Err(ConstError::NotConstant);
+        }
+        match self.evaluate(expr)? {
+            Constant::Int(value) => Ok(value),
+            Constant::Float(_) | Constant::Address { .. } => Err(ConstError::NotConstant),
+        }
+    }
+
+    /// The value of a constant expression, which may also be a floating value or an
+    /// address, as the initializers of objects with static storage duration
+    pub fn evaluate(&mut self, expr: &TypedExpr<'a, 'i>) -> Result<Constant<'a, 'i>, ConstError> {
+        match expr.kind {
+            ExprKind::Constant(value) => Ok(value),
+            ExprKind::Literal(literal) => self.literal(literal, expr.ty),
+            ExprKind::Symbol(id) => match self.resolution.symbol(id).kind {
+                SymbolKind::Enumerator { .. } => self
+                    .types
+                    .enumerator(self.resolution, id)
+                    .map(Constant::Int)
+                    .ok_or(ConstError::NotConstant),
+                _ => Err(ConstError::NotConstant),
+            },
+            ExprKind::Unary {
+                op: UnaryOp::AddressOf,
+                ref operand,
+            } => self.address(operand),
+            ExprKind::Unary { op, ref operand } => match self.evaluate(operand)? {
+                Constant::Int(value) => {
+                    let kind = self.int_kind(expr.ty)?;
+                    unary(op, value, kind, self.types.target()).map(Constant::Int)
+                }
+                Constant::Float(value) => match op {
+                    UnaryOp::Plus => Ok(Constant::Float(value)),
+                    UnaryOp::Minus => Ok(Constant::Float(-value)),
+                    UnaryOp::Not => Ok(Constant::Int(i128::from(value == 0.0))),
+                    _ => Err(ConstError::NotConstant),
+                },
+                Constant::Address { .. } if op == UnaryOp::Not => Ok(Constant::Int(0)),
+                Constant::Address { .. } => Err(ConstError::NotConstant),
+            },
+            ExprKind::Binary {
+                op,
+                ref lhs,
+                ref rhs,
+            } => self.binary(expr.ty, op, lhs, rhs),
+            // Only the operand that is chosen is evaluated
+            ExprKind::Conditional {
+                ref condition,
+                ref then,
+                ref r#else,
+            } => {
+                if truth(self.evaluate(condition)?) {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(r#else)
+                }
+            }
+            ExprKind::Cast(ref operand) => {
+                let value = self.evaluate(operand)?;
+                self.convert(value, operand.ty, expr.ty)
+            }
+            ExprKind::ImplicitCast { kind, ref operand } => match kind {
+                CastKind::LValueToRValue => Err(ConstError::NotConstant),
+                CastKind::ArrayToPointer | CastKind::FunctionToPointer => self.address(operand),
+                CastKind::NullToPointer => Ok(Constant::Int(0)),
+                _ => {
+                    let value = self.evaluate(operand)?;
+                    self.convert(value, operand.ty, expr.ty)
+                }
+            },
+            ExprKind::Sizeof(ty) => {
+                let size = self.types.size_of(ty).ok_or(ConstError::NotConstant)?;
+                Ok(Constant::Int(i128::from(size)))
+            }
+            ExprKind::Alignof(ty) => {
+                let align = self.types.align_of(ty).ok_or(ConstError::NotConstant)?;
+                Ok(Constant::Int(i128::from(align)))
+            }
+            ExprKind::LabelAddress(Some(id)) => Ok(Constant::Address {
+                base: Base::Label(id),
+                offset: 0,
+            }),
+            ExprKind::TypesCompatible(is_compatible) => {
+                Ok(Constant::Int(i128::from(is_compatible)))
+            }
+            ExprKind::Offsetof {
+                ty,
+                member,
+                designators,
+            } => Ok(Constant::Int(self.offset_of(ty, member, designators)?)),
+            // Only its address is constant
+            ExprKind::String(_)
+            | ExprKind::FunctionName(_)
+            | ExprKind::Postfix { .. }
+            | ExprKind::Assign { .. }
+            | ExprKind::CompoundLiteral(_)
+            | ExprKind::Call { .. }
+            | ExprKind::Index { .. }
+            | ExprKind::Member { .. }
+            | ExprKind::LabelAddress(None)
+            | ExprKind::Statement(_)
+            | ExprKind::Error => Err(ConstError::NotConstant),
+        }
+    }
+
+    /// The undefined operations in the constant subexpressions of `expr`, whether it is
+    /// constant itself or not, innermost first
+    pub fn undefined(&mut self, expr: &TypedExpr<'a, 'i>) -> Vec<ConstError> {
+        let mut found = Vec::new();
+        self.find_undefined(expr, &mut found);
+        found
+    }
+
+    fn find_undefined(&mut self, expr: &TypedExpr<'a, 'i>, found: &mut Vec<ConstError>) {
+        let Err(error) = self.evaluate(expr) else {
+            return;
+        };
+        let before = found.len();
+        for operand in expr.operands() {
+            self.find_undefined(operand, found);
+        }
+        if found.len() == before && error.is_undefined() {
+            found.push(error);
+        } else if found.len() == before {
+            // Dividing by a constant zero is undefined whatever the dividend
+            if let ExprKind::Binary {
+                op: op @ (BinaryOp::Div | BinaryOp::Mod),
+                ref rhs,
+                ..
+            } = expr.kind
+            {
+                let is_integer = self.types.int_kind(expr.ty).is_some();
+                if is_integer && self.evaluate(rhs) == Ok(Constant::Int(0)) {
+                    found.push(if op == BinaryOp::Div {
+                        ConstError::DivisionByZero
+                    } else {
+                        ConstError::RemainderByZero
+                    });
+                }
+            }
+        }
+    }
+
+    /// Replaces the constant subexpressions of `expr` with their values. Those whose
+    /// operations are undefined are left as they are written.
+    pub fn fold(&mut self, expr: &mut TypedExpr<'a, 'i>) {
+        for operand in expr.operands_mut() {
+            self.fold(operand);
+        }
+        let is_folded = matches!(expr.kind, ExprKind::Literal(_) | ExprKind::Constant(_));
+        if is_folded || expr.category != ValueCategory::RValue {
+            return;
+        }
+        if let Ok(value) = self.evaluate(expr) {
+            expr.kind = ExprKind::Constant(value);
+        }
+    }
+
+    /// `address` brought into the range of pointers, as wide as `unsigned long`
+    fn address_wrap(&self, address: i128) -> i128 {
+        wrap(address, IntKind::ULong, self.types.target())
+    }
+
+    fn int_kind(&self, ty: TypeId) -> Result<IntKind, ConstError> {
+        self.types.int_kind(ty).ok_or(ConstError::NotConstant)
+    }
+
+    fn float_kind(&self, ty: TypeId) -> Result<FloatKind, ConstError> {
+        match *self.types.get(self.types.unqualified(ty).0) {
+            Type::Float {
+                kind,
+                is_complex: false,
+            } => Ok(kind),
+            _ => Err(ConstError::NotConstant),
+        }
+    }
+
+    /// The type of the member `name` of the struct or union `ty`, and its offset in
+    /// bytes
+    fn member(&self, ty: TypeId, name: &str) -> Result<(TypeId, i128), ConstError> {
+        let Type::Record(id) = *self.types.get(self.types.unqualified(ty).0) else {
+            return Err(ConstError::NotConstant);
+        };
+        let (path, member) = self
+            .types
+            .find_member(id, name)
+            .ok_or(ConstError::NotConstant)?;
+        let offset = self
+            .types
+            .offset_of(id, &path)
+            .ok_or(ConstError::NotConstant)?;
+        Ok((member, i128::from(offset)))
+    }
+
+    /// The offset in bytes `__builtin_offsetof(ty, member designators)` gives
+    fn offset_of(
+        &mut self,
+        ty: TypeId,
+        member: Ident<'i>,
+        designators: &'a [Designator<'i>],
+    ) -> Result<i128, ConstError> {
+        let (mut current, mut offset) = self.member(ty, member.name)?;
+        for designator in designators {
+            let delta = match *designator {
+                Designator::Member(name) => {
+                    let (member, delta) = self.member(current, name.name)?;
+                    current = member;
+                    delta
+                }
+                Designator::Index(ref index) => {
+                    let (unqualified, _) = self.types.unqualified(current);
+                    let Type::Array { element, .. } = *self.types.get(unqualified) else {
+                        return Err(ConstError::NotConstant);
+                    };
+                    let index = self
+                        .types
+                        .constant(self.resolution, index)
+                        .ok_or(ConstError::NotConstant)?;
+                    let size = self.types.size_of(element).ok_or(ConstError::NotConstant)?;
+                    current = element;
+                    index * i128::from(size)
+                }
+                Designator::Range(..) => return Err(ConstError::NotConstant),
+            };
+            offset += delta;
+        }
+        Ok(offset)
+    }
+
+    fn literal(
+        &self,
+        literal: &'a Literal<'i>,
+        ty: TypeId,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        match *literal {
+            Literal::Int(number) => int_value(number)
+                .map(|(value, _)| Constant::Int(i128::from(value)))
+                .ok_or(ConstError::NotConstant),
+            Literal::Float(number) => {
+                let value = float_value(number).ok_or(ConstError::NotConstant)?;
+                Ok(Constant::Float(round(value, self.float_kind(ty)?)))
+            }
+            Literal::Char(char) => char_value(char, self.types.target())
+                .map(Constant::Int)
+                .ok_or(ConstError::NotConstant),
+            Literal::String(_) => Err(ConstError::NotConstant),
+        }
+    }
+
+    /// Whether the object or function `id` has an address known before the program runs
+    fn is_static(&self, id: SymbolId) -> bool {
+        let symbol = self.resolution.symbol(id);
+        match symbol.kind {
+            SymbolKind::Function(_) | SymbolKind::ImplicitFunction => true,
+            SymbolKind::Object(declaration) => {
+                let specifiers = declaration.specifiers;
+                let is_static = symbol.scope == ScopeKind::File
+                    || matches!(
+                        specifiers.storage,
+                        Some(StorageClass::Static | StorageClass::Extern)
+                    );
+                // Every thread has its own
+                is_static && !specifiers.thread_local
+            }
+            _ => false,
+        }
+    }
+
+    /// The address of the object or function `expr` designates, when it is a constant
+    fn address(&mut self, expr: &TypedExpr<'a, 'i>) -> Result<Constant<'a, 'i>, ConstError> {
+        match expr.kind {
+            ExprKind::Symbol(id) if self.is_static(id) => Ok(Constant::Address {
+                base: Base::Symbol(id),
+                offset: 0,
+            }),
+            ExprKind::String(string) => Ok(Constant::Address {
+                base: Base::String(string),
+                offset: 0,
+            }),
+            ExprKind::Unary {
+                op: UnaryOp::Deref,
+                ref operand,
+            } => self.evaluate(operand),
+            ExprKind::Index {
+                ref array,
+                ref index,
+            } => {
+                let pointer = self.evaluate(array)?;
+                let index = self.integer(index)?;
+                self.offset(pointer, index, expr.ty)
+            }
+            // Also `&((struct s *)0)->member`, the offset of the member
+            ExprKind::Member {
+                ref object,
+                ref path,
+                arrow,
+            } => {
+                let (pointer, record) = if arrow {
+                    let record = self.types.pointee(object.ty);
+                    (self.evaluate(object)?, record)
+                } else {
+                    (self.address(object)?, Some(object.ty))
+                };
+                let record = record.map(|record| self.types.get(self.types.unqualified(record).0));
+                let Some(&Type::Record(id)) = record else {
+                    return Err(ConstError::NotConstant);
+                };
+                let offset = self
+                    .types
+                    .offset_of(id, path)
+                    .ok_or(ConstError::NotConstant)?;
+                self.displace(pointer, i128::from(offset))
+            }
+            _ => Err(ConstError::NotConstant),
+        }
+    }
+
+    /// `pointer` moved by `count` elements of type `pointee`
+    fn offset(
+        &self,
+        pointer: Constant<'a, 'i>,
+        count: i128,
+        pointee: TypeId,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        let size = self.types.size_of(pointee).ok_or(ConstError::NotConstant)?;
+        let delta = count
+            .checked_mul(i128::from(size))
+            .ok_or(ConstError::NotConstant)?;
+        self.displace(pointer, delta)
+    }
+
+    /// `pointer` moved by `delta` bytes
+    fn displace(
+        &self,
+        pointer: Constant<'a, 'i>,
+        delta: i128,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        match pointer {
+            Constant::Int(address) => Ok(Constant::Int(self.address_wrap(address + delta))),
+            Constant::Address { base, offset } => Ok(Constant::Address {
+                base,
+                offset: offset + delta,
+            }),
+            Constant::Float(_) => Err(ConstError::NotConstant),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ty: TypeId,
+        op: BinaryOp,
+        lhs: &TypedExpr<'a, 'i>,
+        rhs: &TypedExpr<'a, 'i>,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        match op {
+            // The right operand is only evaluated when the left one does not decide
+            BinaryOp::And | BinaryOp::Or => {
+                let is_or = op == BinaryOp::Or;
+                if truth(self.evaluate(lhs)?) == is_or {
+                    return Ok(Constant::Int(i128::from(is_or)));
+                }
+                return Ok(Constant::Int(i128::from(truth(self.evaluate(rhs)?))));
+            }
+            BinaryOp::Comma => return Err(ConstError::NotConstant),
+            _ => {}
+        }
+        let (left, right) = (self.evaluate(lhs)?, self.evaluate(rhs)?);
+        let integer = |value| match value {
+            Constant::Int(value) => Ok(value),
+            Constant::Float(_) | Constant::Address { .. } => Err(ConstError::NotConstant),
+        };
+        match (self.types.pointee(lhs.ty), self.types.pointee(rhs.ty)) {
+            (Some(to), None) if matches!(op, BinaryOp::Add | BinaryOp::Sub) => {
+                let count = integer(right)?;
+                let count = if op == BinaryOp::Sub { -count } else { count };
+                self.offset(left, count, to)
+            }
+            (None, Some(to)) if op == BinaryOp::Add => self.offset(right, integer(left)?, to),
+            (Some(to), Some(_)) if op == BinaryOp::Sub => {
+                let size = self.types.size_of(to).ok_or(ConstError::NotConstant)?;
+                let difference = match (left, right) {
+                    (Constant::Int(a), Constant::Int(b)) => a - b,
+                    (
+                        Constant::Address { base, offset },
+                        Constant::Address {
+                            base: other_base,
+                            offset: other_offset,
+                        },
+                    ) if base == other_base => offset - other_offset,
+                    _ => return Err(ConstError::NotConstant),
+                };
+                match i128::from(size) {
+                    0 => Err(ConstError::NotConstant),
+                    size => Ok(Constant::Int(difference / size)),
+                }
+            }
+            (Some(_), _) | (_, Some(_)) => Self::compare_pointers(op, left, right),
+            (None, None) => match (left, right) {
+                (Constant::Int(a), Constant::Int(b)) => {
+                    let kind = self.int_kind(lhs.ty)?;
+                    binary(op, a, b, kind, self.types.target()).map(Constant::Int)
+                }
+                (Constant::Float(a), Constant::Float(b)) => self.float_binary(ty, op, a, b),
+                _ => Err(ConstError::NotConstant),
+            },
+        }
+    }
+
+    /// Compares two pointers, which are constant when they point into the same object,
+    /// or when an address is compared to a null pointer
+    fn compare_pointers(
+        op: BinaryOp,
+        left: Constant<'a, 'i>,
+        right: Constant<'a, 'i>,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        let is_equality = matches!(op, BinaryOp::Equals | BinaryOp::NotEquals);
+        let ordering = match (left, right) {
+            (Constant::Int(a), Constant::Int(b)) => a.cmp(&b),
+            (
+                Constant::Address { base, offset },
+                Constant::Address {
+                    base: other_base,
+                    offset: other_offset,
+                },
+            ) if base == other_base => offset.cmp(&other_offset),
+            // Only unequal, as no object is at address zero
+            (Constant::Address { .. }, Constant::Int(0))
+            | (Constant::Int(0), Constant::Address { .. })
+                if is_equality =>
+            {
+                Ordering::Greater
+            }
+            _ => return Err(ConstError::NotConstant),
+        };
+        let result = match op {
+            BinaryOp::Less => ordering.is_lt(),
+            BinaryOp::Greater => ordering.is_gt(),
+            BinaryOp::LessEquals => ordering.is_le(),
+            BinaryOp::GreaterEquals => ordering.is_ge(),
+            BinaryOp::Equals => ordering.is_eq(),
+            BinaryOp::NotEquals => ordering.is_ne(),
+            _ => return Err(ConstError::NotConstant),
+        };
+        Ok(Constant::Int(i128::from(result)))
+    }
+
+    /// Applies `op` to two floating values, giving a value of type `ty`
+    fn float_binary(
+        &self,
+        ty: TypeId,
+        op: BinaryOp,
+        lhs: f64,
+        rhs: f64,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        let value = match op {
+            BinaryOp::Mul => lhs * rhs,
+            BinaryOp::Div => lhs / rhs,
+            BinaryOp::Add => lhs + rhs,
+            BinaryOp::Sub => lhs - rhs,
+            BinaryOp::Less => return Ok(Constant::Int(i128::from(lhs < rhs))),
+            BinaryOp::Greater => return Ok(Constant::Int(i128::from(lhs > rhs))),
+            BinaryOp::LessEquals => return Ok(Constant::Int(i128::from(lhs <= rhs))),
+            BinaryOp::GreaterEquals => return Ok(Constant::Int(i128::from(lhs >= rhs))),
+            BinaryOp::Equals => return Ok(Constant::Int(i128::from(lhs == rhs))),
+            BinaryOp::NotEquals => return Ok(Constant::Int(i128::from(lhs != rhs))),
+            _ => return Err(ConstError::NotConstant),
+        };
+        Ok(Constant::Float(round(value, self.float_kind(ty)?)))
+    }
+
+    /// Converts `value`, of type `from`, to `to`
+    fn convert(
+        &self,
+        value: Constant<'a, 'i>,
+        from: TypeId,
+        to: TypeId,
+    ) -> Result<Constant<'a, 'i>, ConstError> {
+        if let Some(kind) = self.types.int_kind(to) {
+            return match value {
+                _ if kind == IntKind::Bool => Ok(Constant::Int(i128::from(truth(value)))),
+                Constant::Int(value) => Ok(Constant::Int(wrap(value, kind, self.types.target()))),
+                Constant::Float(value) => {
+                    // Truncated, and undefined when out of range
+                    let value = value.trunc();
+                    let bits = self.types.target().int_bits(kind);
+                    let (min, max) = if kind.is_signed() {
+                        (-(1i128 << (bits - 1)), 1i128 << (bits - 1))
+                    } else {
+                        (0, 1i128 << bits)
+                    };
+                    if value >= min as f64 && value < max as f64 {
+                        Ok(Constant::Int(value as i128))
+                    } else {
+                        Err(ConstError::OutOfRange {
+                            from: self.types.display(from).to_string(),
+                            to: self.types.display(to).to_string(),
+                        })
+                    }
+                }
+                Constant::Address { .. } => Err(ConstError::NotConstant),
+            };
+        }
+        match *self.types.get(self.types.unqualified(to).0) {
+            Type::Float {
+                kind,
+                is_complex: false,
+            } => match value {
+                Constant::Int(value) => Ok(Constant::Float(round(value as f64, kind))),
+                Constant::Float(value) => Ok(Constant::Float(round(value, kind))),
+                Constant::Address { .. } => Err(ConstError::NotConstant),
+            },
+            Type::Pointer(_) => match value {
+                Constant::Int(value) => Ok(Constant::Int(self.address_wrap(value))),
+                Constant::Address { .. } => Ok(value),
+                Constant::Float(_) => Err(ConstError::NotConstant),
+            },
+            _ => Err(ConstError::NotConstant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::tree::{BinaryOp, UnaryOp};
+
+    use super::{binary, char_value, float_value, unary, wrap, ConstError};
+    use crate::{sema::IntKind, target::Target};
+
+    const LP64: Target = Target::X86_64;
+
+    #[test]
+    fn test_integer_semantics() {
+        assert_eq!(wrap(256, IntKind::UChar, &LP64), 0);
+        assert_eq!(wrap(128, IntKind::SChar, &LP64), -128);
+        assert_eq!(wrap(-1, IntKind::UInt, &LP64), 0xffff_ffff);
+        assert_eq!(wrap(2, IntKind::Bool, &LP64), 1);
+        assert_eq!(wrap(-1, IntKind::ULong, &Target::I386), 0xffff_ffff);
+
+        assert_eq!(
+            binary(BinaryOp::Add, 0xffff_ffff, 1, IntKind::UInt, &LP64),
+            Ok(0)
+        );
+        assert_eq!(
+            binary(BinaryOp::Add, i128::from(i32::MAX), 1, IntKind::Int, &LP64),
+            Err(ConstError::Overflow {
+                value: i128::from(i32::MIN),
+                ty: IntKind::Int
+            })
+        );
+        assert_eq!(
+            binary(BinaryOp::Div, i128::from(i32::MIN), -1, IntKind::Int, &LP64),
+            Err(ConstError::Overflow {
+                value: i128::from(i32::MIN),
+                ty: IntKind::Int
+            })
+        );
+        assert_eq!(binary(BinaryOp::Div, -7, 2, IntKind::Int, &LP64), Ok(-3));
+        assert_eq!(binary(BinaryOp::Mod, -7, 2, IntKind::Int, &LP64), Ok(-1));
+        assert_eq!(
+            binary(BinaryOp::Mod, 1, 0, IntKind::Int, &LP64),
+            Err(ConstError::RemainderByZero)
+        );
+        assert_eq!(
+            binary(
+                BinaryOp::Mul,
+                i128::from(u64::MAX),
+                2,
+                IntKind::ULong,
+                &LP64
+            ),
+            Ok(i128::from(u64::MAX - 1))
+        );
+        assert_eq!(
+            unary(UnaryOp::Minus, 1, IntKind::UInt, &LP64).map(|value| wrap(
+                value,
+                IntKind::UInt,
+                &LP64
+            )),
+            Ok(0xffff_ffff)
+        );
+    }
+
+    #[test]
+    fn test_shifts() {
+        assert_eq!(
+            binary(BinaryOp::Shl, 1, 31, IntKind::Int, &LP64),
+            Ok(i128::from(i32::MIN))
+        );
+        assert_eq!(
+            binary(BinaryOp::Shl, 1, 63, IntKind::ULong, &LP64),
+            Ok(1 << 63)
+        );
+        assert_eq!(
+            binary(BinaryOp::Shl, 1, 32, IntKind::ULong, &Target::I386),
+            Err(ConstError::LargeShift)
+        );
+        assert_eq!(binary(BinaryOp::Shr, -8, 1, IntKind::Int, &LP64), Ok(-4));
+        assert_eq!(
+            binary(BinaryOp::Shl, 3, 31, IntKind::Int, &LP64),
+            Err(ConstError::Overflow {
+                value: i128::from(i32::MIN),
+                ty: IntKind::Int
+            })
+        );
+        assert_eq!(
+            binary(BinaryOp::Shl, 1, 32, IntKind::Int, &LP64),
+            Err(ConstError::LargeShift)
+        );
+        assert_eq!(
+            binary(BinaryOp::Shr, 1, -1, IntKind::Int, &LP64),
+            Err(ConstError::NegativeShift)
+        );
+        assert_eq!(
+            binary(BinaryOp::Shl, -1, 1, IntKind::Int, &LP64),
+            Err(ConstError::NegativeShifted)
+        );
+    }
+
+    #[test]
+    fn test_literal_values() {
+        assert_eq!(char_value("'a'", &LP64), Some(97));
+        assert_eq!(char_value("'\\377'", &LP64), Some(-1));
+        assert_eq!(char_value("'\\x41'", &LP64), Some(65));
+        assert_eq!(char_value("'ab'", &LP64), Some(0x6162));
+        assert_eq!(char_value("L'\\xffff'", &LP64), Some(0xffff));
+        assert_eq!(float_value("1.5e2"), Some(150.0));
+        assert_eq!(float_value("0x1.8p1"), Some(3.0));
+        assert_eq!(float_value("2.5f"), Some(2.5));
+    }
+}
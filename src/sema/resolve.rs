@@ -0,0 +1,1057 @@
+//! Name resolution: binds every name in the program to the symbol it declares or
+//! refers to, following the scopes and namespaces of C.
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::tree::{
+        control::{ForInit, Label},
+        AlignmentSpecifier, ArraySize, BaseType, DeclSpecifiers, Declarator, DesignatedInitializer,
+        Designator, EnumSpecifier, Expression, FuncDecl, Initializer, MemberItem, Parameters,
+        RecordSpecifier, Statement, StorageClass, TypeName, TypeofSpecifier, VarDecl,
+    },
+    lexer::token::Ident,
+    standard::Standard,
+};
+
+use super::{
+    symbols::{Declaration, Linkage, Namespace, ScopeKind, Symbol, SymbolId, SymbolKind, Tag},
+    Diagnostic,
+};
+
+/// Identifiers every function can use without declaring them
+const PREDEFINED: &[&str] = &["__func__", "__FUNCTION__", "__PRETTY_FUNCTION__"];
+
+/// The symbols of a program and the names bound to them
+#[derive(Debug, Default)]
+pub struct Resolution<'a, 'i> {
+    symbols: Vec<Symbol<'a, 'i>>,
+    /// Names are told apart by where they are in the tree, since the same spelling may
+    /// refer to different symbols, even from the same macro expansion
+    bindings: HashMap<*const Ident<'i>, SymbolId>,
+    diagnostics: Vec<Diagnostic<'i>>,
+    standard: Standard,
+}
+impl<'a, 'i> Resolution<'a, 'i> {
+    /// The revision of C the program is written in
+    #[must_use]
+    pub fn standard(&self) -> Standard {
+        self.standard
+    }
+
+    #[must_use]
+    pub fn symbol(&self, id: SymbolId) -> &Symbol<'a, 'i> {
+        &self.symbols[id.0]
+    }
+
+    /// All the symbols, in the order of their first declaration
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol<'a, 'i>)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(n, s)| (SymbolId(n), s))
+    }
+
+    /// The symbol `name` declares or refers to. Members are not bound, nor names that
+    /// are not declared.
+    ///
+    /// Names are looked up by address, so `name` must be borrowed from the resolved
+    /// tree: a copy of it, even of the same identifier, is bound to nothing.
+    #[must_use]
+    pub(crate) fn binding(&self, name: &Ident<'i>) -> Option<SymbolId> {
+        self.bindings.get(&(name as *const _)).copied()
+    }
+
+    /// Undeclared names and declarations that conflict, in the order they appear
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic<'i>] {
+        &self.diagnostics
+    }
+}
+
+/// Names declared directly in one scope
+struct Scope<'i> {
+    kind: ScopeKind,
+    names: HashMap<(Namespace, &'i str), SymbolId>,
+}
+
+struct Resolver<'a, 'i> {
+    /// Innermost last, starting with the file scope
+    scopes: Vec<Scope<'i>>,
+    resolution: Resolution<'a, 'i>,
+}
+
+/// Resolves the names of `program`, written in `standard`
+#[must_use]
+pub fn resolve<'a, 'i>(program: &'a [Statement<'i>], standard: Standard) -> Resolution<'a, 'i> {
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        resolution: Resolution {
+            standard,
+            ..Resolution::default()
+        },
+    };
+    resolver.enter(ScopeKind::File);
+    resolver.statements(program);
+    resolver.resolution
+}
+
+/// The name a declarator declares, borrowed from it
+pub(super) fn declared_name<'a, 'i>(declarator: &'a Declarator<'i>) -> Option<&'a Ident<'i>> {
+    match declarator {
+        Declarator::Ident(ident) => Some(ident),
+        Declarator::Abstract => None,
+        Declarator::Pointer { inner, .. }
+        | Declarator::Array { inner, .. }
+        | Declarator::Function { inner, .. }
+        | Declarator::Attributed { inner, .. } => declared_name(inner),
+    }
+}
+
+/// Names of the members of a struct or union, including those of its anonymous
+/// struct and union members
+fn member_names<'a, 'i>(members: &'a [MemberItem<'i>], names: &mut Vec<&'a Ident<'i>>) {
+    for member in members.iter().filter_map(MemberItem::declaration) {
+        if let BaseType::Record(RecordSpecifier {
+            tag: None,
+            members: Some(ref inner),
+            ..
+        }) = member.ty.ty
+        {
+            if member.declarators.is_empty() {
+                member_names(inner, names);
+            }
+        }
+        names.extend(
+            member
+                .declarators
+                .iter()
+                .filter_map(|d| declared_name(&d.declarator)),
+        );
+    }
+}
+
+impl<'a, 'i: 'a> Resolver<'a, 'i> {
+    fn enter(&mut self, kind: ScopeKind) {
+        self.scopes.push(Scope {
+            kind,
+            names: HashMap::new(),
+        });
+    }
+
+    fn leave(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn scope_kind(&self) -> ScopeKind {
+        self.scopes
+            .last()
+            .map_or(ScopeKind::File, |scope| scope.kind)
+    }
+
+    fn error(&mut self, at: Ident<'i>, message: String) {
+        self.resolution
+            .diagnostics
+            .push(Diagnostic::error(at, message));
+    }
+
+    fn symbol_mut(&mut self, id: SymbolId) -> &mut Symbol<'a, 'i> {
+        &mut self.resolution.symbols[id.0]
+    }
+
+    fn bind(&mut self, name: &'a Ident<'i>, id: SymbolId) {
+        self.resolution.bindings.insert(name as *const _, id);
+    }
+
+    /// Binds `name` as a new declaration of the symbol `id`
+    fn redeclare(&mut self, name: &'a Ident<'i>, id: SymbolId) {
+        self.symbol_mut(id).declarations.push(name);
+        self.bind(name, id);
+    }
+
+    /// Declares a new symbol named `name` in the current scope
+    fn insert(
+        &mut self,
+        name: &'a Ident<'i>,
+        kind: SymbolKind<'a, 'i>,
+        linkage: Linkage,
+        is_defined: bool,
+    ) -> SymbolId {
+        let id = SymbolId(self.resolution.symbols.len());
+        self.resolution.symbols.push(Symbol {
+            name: *name,
+            kind,
+            linkage,
+            scope: self.scope_kind(),
+            is_defined,
+            declarations: vec![name],
+            attributes: kind.attributes().collect(),
+        });
+        self.bind(name, id);
+        self.declare_in_scope(kind.namespace(), name, id);
+        id
+    }
+
+    fn declare_in_scope(&mut self, namespace: Namespace, name: &Ident<'i>, id: SymbolId) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.names.insert((namespace, name.name), id);
+        }
+    }
+
+    /// The symbol `name` refers to in the current scope
+    fn lookup(&self, namespace: Namespace, name: &Ident<'i>) -> Option<SymbolId> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.names.get(&(namespace, name.name)).copied())
+    }
+
+    /// The symbol `name` is declared as in the current scope itself
+    fn lookup_current(&self, namespace: Namespace, name: &Ident<'i>) -> Option<SymbolId> {
+        let scope = self.scopes.last()?;
+        scope.names.get(&(namespace, name.name)).copied()
+    }
+
+    /// Declares `name` in the ordinary namespace, or redeclares the symbol it already
+    /// refers to, and reports the declarations that conflict with the previous ones
+    fn declare(
+        &mut self,
+        name: &'a Ident<'i>,
+        kind: SymbolKind<'a, 'i>,
+        storage: Option<StorageClass>,
+        is_definition: bool,
+    ) {
+        let scope = self.scope_kind();
+        let visible = self
+            .lookup(Namespace::Ordinary, name)
+            .filter(|&id| self.resolution.symbol(id).linkage != Linkage::None);
+        // `extern` takes the linkage of the declaration it refers to, if any
+        let inherited = visible.map_or(Linkage::External, |id| self.resolution.symbol(id).linkage);
+        let is_function = matches!(kind, SymbolKind::Function(_) | SymbolKind::ImplicitFunction);
+        let linkage = match (kind, storage) {
+            (SymbolKind::Object(_), Some(StorageClass::Static)) if scope != ScopeKind::File => {
+                Linkage::None
+            }
+            (SymbolKind::Object(_) | SymbolKind::Function(_), Some(StorageClass::Static)) => {
+                Linkage::Internal
+            }
+            (SymbolKind::Object(_), None) if scope == ScopeKind::File => Linkage::External,
+            (SymbolKind::Object(_), Some(StorageClass::Extern)) => inherited,
+            _ if is_function => inherited,
+            _ => Linkage::None,
+        };
+
+        let Some(previous) = self.lookup_current(Namespace::Ordinary, name) else {
+            match visible {
+                // A block scope `extern` declaration of something declared outside
+                Some(id) if linkage != Linkage::None => {
+                    if !self.resolution.symbol(id).kind.is_same_kind(&kind) {
+                        let message =
+                            format!("redefinition of '{name}' as different kind of symbol");
+                        self.error(*name, message);
+                    }
+                    self.redeclare(name, id);
+                    self.symbol_mut(id).attributes.extend(kind.attributes());
+                    self.declare_in_scope(Namespace::Ordinary, name, id);
+                }
+                _ => {
+                    self.insert(name, kind, linkage, is_definition);
+                }
+            }
+            return;
+        };
+
+        let symbol = self.resolution.symbol(previous);
+        let problem = if !symbol.kind.is_same_kind(&kind) {
+            Some(format!(
+                "redefinition of '{name}' as different kind of symbol"
+            ))
+        } else {
+            match (symbol.linkage, linkage) {
+                (Linkage::None, Linkage::None) => match (symbol.kind, kind) {
+                    (SymbolKind::Typedef(old), SymbolKind::Typedef(new))
+                        if old.specifiers.ty == new.specifiers.ty
+                            && old.specifiers.qualifiers == new.specifiers.qualifiers
+                            && old.declarator == new.declarator =>
+                    {
+                        None
+                    }
+                    (SymbolKind::Typedef(_), _) => Some(format!(
+                        "typedef redefinition of '{name}' with different types"
+                    )),
+                    (SymbolKind::Parameter(_), SymbolKind::Parameter(_)) => {
+                        Some(format!("redefinition of parameter '{name}'"))
+                    }
+                    (SymbolKind::Enumerator { .. }, _) => {
+                        Some(format!("redefinition of enumerator '{name}'"))
+                    }
+                    _ => Some(format!("redefinition of '{name}'")),
+                },
+                (Linkage::None, _) => Some(format!(
+                    "extern declaration of '{name}' follows non-extern declaration"
+                )),
+                (_, Linkage::None) => Some(format!(
+                    "non-extern declaration of '{name}' follows extern declaration"
+                )),
+                (Linkage::External, Linkage::Internal) => Some(format!(
+                    "static declaration of '{name}' follows non-static declaration"
+                )),
+                (Linkage::Internal, Linkage::External) => Some(format!(
+                    "non-static declaration of '{name}' follows static declaration"
+                )),
+                _ if symbol.is_defined && is_definition => {
+                    Some(format!("redefinition of '{name}'"))
+                }
+                _ => None,
+            }
+        };
+
+        self.redeclare(name, previous);
+        self.symbol_mut(previous)
+            .attributes
+            .extend(kind.attributes());
+        match problem {
+            Some(problem) => self.error(*name, problem),
+            None if is_definition => {
+                let symbol = self.symbol_mut(previous);
+                symbol.is_defined = true;
+                symbol.kind = kind;
+            }
+            None => {}
+        }
+    }
+
+    /// Resolves the tag of a struct, union or enum specifier, which declares it in the
+    /// current scope when it defines it or declares nothing else, as in `struct s;`
+    fn tag(
+        &mut self,
+        name: &'a Ident<'i>,
+        tag: Tag<'a, 'i>,
+        is_definition: bool,
+        is_forward: bool,
+    ) {
+        let kind = SymbolKind::Tag(tag);
+        let previous = if is_definition || is_forward {
+            self.lookup_current(Namespace::Tag, name)
+        } else {
+            self.lookup(Namespace::Tag, name)
+        };
+        let Some(previous) = previous else {
+            self.insert(name, kind, Linkage::None, is_definition);
+            return;
+        };
+
+        self.redeclare(name, previous);
+        let symbol = self.resolution.symbol(previous);
+        if !symbol.kind.is_same_kind(&kind) {
+            let message =
+                format!("use of '{name}' with tag type that does not match previous declaration");
+            self.error(*name, message);
+        } else if is_definition && symbol.is_defined {
+            let message = format!("redefinition of '{} {name}'", tag.keyword());
+            self.error(*name, message);
+        } else if is_definition {
+            let symbol = self.symbol_mut(previous);
+            symbol.is_defined = true;
+            symbol.kind = kind;
+        }
+    }
+
+    /// Binds a use of an ordinary identifier, as in an expression
+    fn use_name(&mut self, name: &'a Ident<'i>) {
+        match self.lookup(Namespace::Ordinary, name) {
+            Some(id) => self.bind(name, id),
+            None if name.name.starts_with("__builtin_") || PREDEFINED.contains(&name.name) => {}
+            None => self.error(*name, format!("use of undeclared identifier '{name}'")),
+        }
+    }
+
+    /// Binds the name of a function called as in `f(x)`, which C89 implicitly declares
+    /// if it is not declared
+    fn use_callee(&mut self, name: &'a Ident<'i>) {
+        if self.lookup(Namespace::Ordinary, name).is_some() || name.name.starts_with("__builtin_") {
+            return self.use_name(name);
+        }
+        if self.resolution.standard >= Standard::C99 {
+            let message = format!(
+                "call to undeclared function '{name}'; ISO C99 and later do not support implicit function declarations"
+            );
+            self.error(*name, message);
+        }
+        self.declare(
+            name,
+            SymbolKind::ImplicitFunction,
+            Some(StorageClass::Extern),
+            false,
+        );
+    }
+
+    /// Binds the target of a `goto`. Labels that do not exist are reported by the parser.
+    fn use_label(&mut self, name: &'a Ident<'i>) {
+        if let Some(id) = self.lookup(Namespace::Label, name) {
+            self.bind(name, id);
+        }
+    }
+
+    /// Declares the labels of a function body, before walking it since `goto` may jump
+    /// forward
+    fn labels(&mut self, body: &'a [Statement<'i>]) {
+        for statement in body {
+            match statement {
+                Statement::Labeled(labeled) => {
+                    if let Label::Named(ref name) = labeled.label {
+                        if self.lookup_current(Namespace::Label, name).is_none() {
+                            self.insert(name, SymbolKind::Label, Linkage::None, true);
+                        }
+                    }
+                    self.labels(std::slice::from_ref(&labeled.body));
+                }
+                Statement::If(r#if) => {
+                    self.labels(&r#if.body);
+                    self.labels(r#if.else_body.as_deref().unwrap_or_default());
+                }
+                Statement::While(r#while) => self.labels(&r#while.body),
+                Statement::DoWhile(do_while) => self.labels(&do_while.body),
+                Statement::For(r#for) => self.labels(&r#for.body),
+                Statement::Switch(switch) => self.labels(&switch.body),
+                Statement::Compound(compound) => self.labels(&compound.body),
+                Statement::Attributed(_, statement) => {
+                    self.labels(std::slice::from_ref(statement));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn statements(&mut self, body: &'a [Statement<'i>]) {
+        for statement in body {
+            self.statement(statement);
+        }
+    }
+
+    /// Walks statements in a scope of their own
+    fn block(&mut self, body: &'a [Statement<'i>]) {
+        self.enter(ScopeKind::Block);
+        self.statements(body);
+        self.leave();
+    }
+
+    fn statement(&mut self, statement: &'a Statement<'i>) {
+        match statement {
+            Statement::FuncDecl(func) => self.function(func),
+            Statement::VarDecl(decl) => self.var_decl(decl),
+            Statement::Typedef(typedef) => {
+                self.specifiers(&typedef.ty, false);
+                for declarator in typedef.names.iter() {
+                    self.declarator(declarator, None);
+                    if let Some(name) = declared_name(declarator) {
+                        let declaration = Declaration {
+                            specifiers: &typedef.ty,
+                            declarator,
+                        };
+                        self.declare(name, SymbolKind::Typedef(declaration), None, true);
+                    }
+                }
+            }
+            Statement::StaticAssert(assert) => self.expression(&assert.condition),
+            Statement::If(r#if) => {
+                self.expression(&r#if.condition);
+                self.block(&r#if.body);
+                if let Some(ref else_body) = r#if.else_body {
+                    self.block(else_body);
+                }
+            }
+            Statement::While(r#while) => {
+                self.expression(&r#while.condition);
+                self.block(&r#while.body);
+            }
+            Statement::DoWhile(do_while) => {
+                self.block(&do_while.body);
+                self.expression(&do_while.condition);
+            }
+            Statement::For(r#for) => {
+                self.enter(ScopeKind::Block);
+                match r#for.init {
+                    Some(ForInit::Declaration(ref decl)) => self.var_decl(decl),
+                    Some(ForInit::Expression(ref expr)) => self.expression(expr),
+                    None => {}
+                }
+                for expr in r#for.condition.iter().chain(r#for.step.iter()) {
+                    self.expression(expr);
+                }
+                self.block(&r#for.body);
+                self.leave();
+            }
+            Statement::Switch(switch) => {
+                self.expression(&switch.condition);
+                self.block(&switch.body);
+            }
+            Statement::Labeled(labeled) => {
+                match labeled.label {
+                    Label::Named(ref name) => self.use_label(name),
+                    Label::Case { ref value, ref end } => {
+                        self.expression(value);
+                        if let Some(end) = end {
+                            self.expression(end);
+                        }
+                    }
+                    Label::Default => {}
+                }
+                self.statement(&labeled.body);
+            }
+            Statement::Goto(label) => self.use_label(label),
+            Statement::ComputedGoto(expr) | Statement::Expression(expr) => self.expression(expr),
+            Statement::Return(r#return) => {
+                if let Some(ref value) = r#return.value {
+                    self.expression(value);
+                }
+            }
+            Statement::Compound(compound) => self.block(&compound.body),
+            Statement::Asm(asm) => {
+                for operand in asm.outputs.iter().chain(asm.inputs.iter()) {
+                    self.expression(&operand.expr);
+                }
+                for label in asm.labels.iter() {
+                    self.use_label(label);
+                }
+            }
+            Statement::Attributed(_, statement) => self.statement(statement),
+            Statement::Break | Statement::Continue | Statement::Null | Statement::Error => {}
+        }
+    }
+
+    fn function(&mut self, func: &'a FuncDecl<'i>) {
+        self.specifiers(&func.ret, false);
+        let name = declared_name(&func.declarator);
+        let declaration = Declaration {
+            specifiers: &func.ret,
+            declarator: &func.declarator,
+        };
+        let Some(ref body) = func.body else {
+            self.declarator(&func.declarator, None);
+            if let Some(name) = name {
+                self.declare(
+                    name,
+                    SymbolKind::Function(declaration),
+                    func.ret.storage,
+                    false,
+                );
+            }
+            return;
+        };
+
+        // The function can call itself from its body
+        if let Some(name) = name {
+            self.declare(
+                name,
+                SymbolKind::Function(declaration),
+                func.ret.storage,
+                true,
+            );
+        }
+        self.enter(ScopeKind::Function);
+        self.declarator(&func.declarator, func.declarator.function_params());
+        for decl in func.declarations.iter() {
+            self.specifiers(&decl.ty, false);
+            for init in decl.declarators.iter() {
+                self.declarator(&init.declarator, None);
+                if let Some(name) = declared_name(&init.declarator) {
+                    let declaration = Declaration {
+                        specifiers: &decl.ty,
+                        declarator: &init.declarator,
+                    };
+                    self.declare_parameter(name, declaration);
+                }
+            }
+        }
+        self.labels(body);
+        self.statements(body);
+        self.leave();
+    }
+
+    /// Declares the type of a parameter of a K&R definition, as `a` in
+    /// `int f(a) int a; { ... }`
+    fn declare_parameter(&mut self, name: &'a Ident<'i>, declaration: Declaration<'a, 'i>) {
+        let Some(previous) = self.lookup_current(Namespace::Ordinary, name) else {
+            return self.error(*name, format!("parameter named '{name}' is missing"));
+        };
+        self.redeclare(name, previous);
+        match self.resolution.symbol(previous).kind {
+            SymbolKind::Parameter(None) => {
+                self.symbol_mut(previous).kind = SymbolKind::Parameter(Some(declaration));
+            }
+            _ => self.error(*name, format!("redefinition of parameter '{name}'")),
+        }
+    }
+
+    fn var_decl(&mut self, decl: &'a VarDecl<'i>) {
+        self.specifiers(&decl.ty, decl.declarators.is_empty());
+        for init in decl.declarators.iter() {
+            self.declarator(&init.declarator, None);
+            if let Some(name) = declared_name(&init.declarator) {
+                let declaration = Declaration {
+                    specifiers: &decl.ty,
+                    declarator: &init.declarator,
+                };
+                let is_function = init.declarator.function_params().is_some();
+                let (kind, is_definition) = if is_function {
+                    (SymbolKind::Function(declaration), false)
+                } else {
+                    // Without an initializer, declarations at file scope are tentative
+                    // definitions, which may be repeated
+                    let is_definition = match (self.scope_kind(), decl.ty.storage) {
+                        (ScopeKind::File, _) | (_, Some(StorageClass::Extern)) => {
+                            init.value.is_some()
+                        }
+                        _ => true,
+                    };
+                    (SymbolKind::Object(declaration), is_definition)
+                };
+                self.declare(name, kind, decl.ty.storage, is_definition);
+            }
+            if let Some(ref value) = init.value {
+                self.initializer(value);
+            }
+        }
+    }
+
+    /// Walks the specifiers of a declaration. `is_forward` is set when the declaration
+    /// has no declarators, as in `struct s;`.
+    fn specifiers(&mut self, specifiers: &'a DeclSpecifiers<'i>, is_forward: bool) {
+        for alignment in specifiers.alignment.iter() {
+            match alignment {
+                AlignmentSpecifier::Type(ty) => self.type_name(ty),
+                AlignmentSpecifier::Expression(expr) => self.expression(expr),
+            }
+        }
+        match specifiers.ty {
+            BaseType::TypedefName(ref name) => self.use_name(name),
+            BaseType::Record(ref record) => self.record(record, is_forward),
+            BaseType::Enum(ref specifier) => self.enumeration(specifier, is_forward),
+            BaseType::Typeof(ref operand) => match **operand {
+                TypeofSpecifier::Expression(ref expr) => self.expression(expr),
+                TypeofSpecifier::Type(ref ty) => self.type_name(ty),
+            },
+            BaseType::Atomic(_, ref ty) => self.type_name(ty),
+            _ => {}
+        }
+    }
+
+    fn record(&mut self, record: &'a RecordSpecifier<'i>, is_forward: bool) {
+        if let Some(ref tag) = record.tag {
+            self.tag(tag, Tag::Record(record), record.is_definition(), is_forward);
+        }
+        let Some(ref members) = record.members else {
+            return;
+        };
+        for item in members.iter() {
+            let member = match item {
+                MemberItem::Declaration(member) => member,
+                MemberItem::StaticAssert(assert) => {
+                    self.expression(&assert.condition);
+                    continue;
+                }
+            };
+            self.specifiers(&member.ty, false);
+            for declarator in member.declarators.iter() {
+                self.declarator(&declarator.declarator, None);
+                if let Some(ref width) = declarator.width {
+                    self.expression(width);
+                }
+            }
+        }
+
+        let mut names = Vec::new();
+        member_names(members, &mut names);
+        for (n, name) in names.iter().enumerate() {
+            if names[..n].contains(name) {
+                self.error(**name, format!("duplicate member '{name}'"));
+            }
+        }
+    }
+
+    fn enumeration(&mut self, specifier: &'a EnumSpecifier<'i>, is_forward: bool) {
+        if let Some(ref underlying) = specifier.underlying {
+            self.specifiers(underlying, false);
+        }
+        if let Some(ref tag) = specifier.tag {
+            self.tag(
+                tag,
+                Tag::Enum(specifier),
+                specifier.is_definition(),
+                is_forward,
+            );
+        }
+        for enumerator in specifier.enumerators.iter().flatten() {
+            // The scope of an enumerator starts after its value
+            if let Some(ref value) = enumerator.value {
+                self.expression(value);
+            }
+            let kind = SymbolKind::Enumerator { specifier };
+            self.declare(&enumerator.name, kind, None, true);
+        }
+    }
+
+    /// Walks the array sizes and parameters of a declarator. The parameters of
+    /// `definition`, those of the function being defined, are declared in the current
+    /// scope, other ones in a prototype scope of their own.
+    fn declarator(
+        &mut self,
+        declarator: &'a Declarator<'i>,
+        definition: Option<&'a Parameters<'i>>,
+    ) {
+        match declarator {
+            Declarator::Ident(_) | Declarator::Abstract => {}
+            Declarator::Pointer { inner, .. } | Declarator::Attributed { inner, .. } => {
+                self.declarator(inner, definition);
+            }
+            Declarator::Array { inner, size, .. } => {
+                if let ArraySize::Expression(size) = size {
+                    self.expression(size);
+                }
+                self.declarator(inner, definition);
+            }
+            Declarator::Function { inner, params } => {
+                let is_definition = definition.is_some_and(|d| std::ptr::eq(d, params));
+                if !is_definition {
+                    self.enter(ScopeKind::Prototype);
+                }
+                self.parameters(params);
+                if !is_definition {
+                    self.leave();
+                }
+                self.declarator(inner, definition);
+            }
+        }
+    }
+
+    fn parameters(&mut self, params: &'a Parameters<'i>) {
+        match params {
+            Parameters::Unspecified => {}
+            Parameters::Prototype { params, .. } => {
+                for (specifiers, declarator) in params.iter() {
+                    self.specifiers(specifiers, false);
+                    self.declarator(declarator, None);
+                    if let Some(name) = declared_name(declarator) {
+                        let declaration = Declaration {
+                            specifiers,
+                            declarator,
+                        };
+                        let kind = SymbolKind::Parameter(Some(declaration));
+                        self.declare(name, kind, specifiers.storage, true);
+                    }
+                }
+            }
+            Parameters::Identifiers(names) => {
+                for name in names.iter() {
+                    self.declare(name, SymbolKind::Parameter(None), None, true);
+                }
+            }
+        }
+    }
+
+    fn type_name(&mut self, ty: &'a TypeName<'i>) {
+        self.specifiers(&ty.specifiers, false);
+        self.declarator(&ty.declarator, None);
+    }
+
+    fn initializer(&mut self, initializer: &'a Initializer<'i>) {
+        match initializer {
+            Initializer::Expression(expr) => self.expression(expr),
+            Initializer::List(items) => self.initializer_list(items),
+        }
+    }
+
+    fn initializer_list(&mut self, items: &'a [DesignatedInitializer<'i>]) {
+        for item in items {
+            self.designators(&item.designators);
+            self.initializer(&item.value);
+        }
+    }
+
+    /// Walks the indices of designators. Members are resolved along with types.
+    fn designators(&mut self, designators: &'a [Designator<'i>]) {
+        for designator in designators {
+            match designator {
+                Designator::Member(_) => {}
+                Designator::Index(index) => self.expression(index),
+                Designator::Range(start, end) => {
+                    self.expression(start);
+                    self.expression(end);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression<'i>) {
+        match expr {
+            Expression::Literal(_) | Expression::String(_) | Expression::Error(_) => {}
+            Expression::Ident(name) => self.use_name(name),
+            Expression::Unary { operand, .. }
+            | Expression::Postfix { operand, .. }
+            | Expression::SizeofExpr(operand) => self.expression(operand),
+            Expression::Binary { lhs, rhs, .. } | Expression::Assign { lhs, rhs, .. } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Conditional {
+                condition,
+                then,
+                r#else,
+            } => {
+                self.expression(condition);
+                self.expression(then);
+                self.expression(r#else);
+            }
+            Expression::Cast { ty, expr } => {
+                self.type_name(ty);
+                self.expression(expr);
+            }
+            Expression::CompoundLiteral { ty, init, .. } => {
+                self.type_name(ty);
+                self.initializer_list(init);
+            }
+            Expression::SizeofType(_, ty) | Expression::AlignofType(_, ty) => self.type_name(ty),
+            Expression::Generic(generic) => {
+                self.expression(&generic.controlling);
+                for association in generic.associations.iter() {
+                    if let Some(ref ty) = association.ty {
+                        self.type_name(ty);
+                    }
+                    self.expression(&association.value);
+                }
+            }
+            Expression::Call { callee, args } => {
+                match **callee {
+                    Expression::Ident(ref name) => self.use_callee(name),
+                    ref callee => self.expression(callee),
+                }
+                for arg in args.iter() {
+                    self.expression(arg);
+                }
+            }
+            Expression::Index { array, index } => {
+                self.expression(array);
+                self.expression(index);
+            }
+            // The member depends on the type of the object
+            Expression::Member { object, .. } => self.expression(object),
+            Expression::LabelAddress(label) => self.use_label(label),
+            Expression::Statement(_, body) => self.block(body),
+            Expression::Offsetof {
+                ty, designators, ..
+            } => {
+                self.type_name(ty);
+                self.designators(designators);
+            }
+            Expression::TypesCompatible(_, a, b) => {
+                self.type_name(a);
+                self.type_name(b);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            parse_valid,
+            tree::{control::Return, Expression, Statement},
+        },
+        sema::{Linkage, ScopeKind, SymbolKind},
+        standard::Standard,
+    };
+
+    use super::{resolve, Resolution};
+
+    fn messages(resolution: &Resolution<'_, '_>) -> Vec<String> {
+        let diagnostics = resolution.diagnostics();
+        diagnostics.iter().map(ToString::to_string).collect()
+    }
+
+    /// The statements of the body of the function at `n` in `program`
+    fn body<'a, 'i>(program: &'a [Statement<'i>], n: usize) -> &'a [Statement<'i>] {
+        match program[n] {
+            Statement::FuncDecl(ref f) => f.body.as_deref().unwrap_or_default(),
+            _ => panic!("Unexpected program {program:?}"),
+        }
+    }
+
+    #[test]
+    fn test_scopes() {
+        let program = parse_valid(
+            "int x;
+            struct s { int s; } s;
+            int f(int x) {
+                { int x = 1; }
+                return x;
+            }
+            int g(void) { int y = x; return s.s; }",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        assert!(
+            resolution.diagnostics().is_empty(),
+            "{:?}",
+            messages(&resolution)
+        );
+
+        // The parameter hides the variable at file scope, and the block hides both
+        let [Statement::Compound(ref inner), Statement::Return(Return {
+            value: Some(Expression::Ident(ref x)),
+        })] = body(&program, 2)
+        else {
+            panic!("Unexpected program {program:?}")
+        };
+        let x = resolution.symbol(resolution.binding(x).expect("Expected a binding"));
+        assert!(matches!(x.kind, SymbolKind::Parameter(Some(_))));
+        assert_eq!(x.scope, ScopeKind::Function);
+        let Statement::VarDecl(ref decl) = inner.body[0] else {
+            panic!("Unexpected block {inner:?}")
+        };
+        let Some(inner) = decl.declarators[0].declarator.name() else {
+            panic!("Unexpected declaration {decl:?}")
+        };
+        assert!(resolution.symbols().any(|(_, symbol)| symbol.name == inner
+            && symbol.scope == ScopeKind::Block
+            && matches!(symbol.kind, SymbolKind::Object(_))));
+
+        let Statement::VarDecl(ref decl) = body(&program, 3)[0] else {
+            panic!("Unexpected program {program:?}")
+        };
+        let Some(crate::ast::tree::Initializer::Expression(Expression::Ident(ref x))) =
+            decl.declarators[0].value
+        else {
+            panic!("Unexpected declaration {decl:?}")
+        };
+        let x = resolution.symbol(resolution.binding(x).expect("Expected a binding"));
+        assert_eq!((x.scope, x.linkage), (ScopeKind::File, Linkage::External));
+
+        // Tags, members and ordinary identifiers do not clash
+        let names: Vec<_> = resolution
+            .symbols()
+            .filter(|(_, symbol)| symbol.name.name == "s")
+            .map(|(_, symbol)| symbol.kind.namespace())
+            .collect();
+        assert_eq!(names, [super::Namespace::Tag, super::Namespace::Ordinary]);
+    }
+
+    #[test]
+    fn test_redeclarations() {
+        let program = parse_valid(
+            "extern int a;
+            int a = 1;
+            int a;
+            static int b(void);
+            int b(void) { return 0; }
+            typedef int t;
+            typedef int t;
+            int f(int n, int a[n]);
+            int f(int, int *);
+            void g(void) {
+                extern int a;
+                a = 2;
+                static int local;
+                goto done;
+            done:
+                return;
+            }
+            enum e { A, B = A + 2 };
+            enum e;",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        assert!(
+            resolution.diagnostics().is_empty(),
+            "{:?}",
+            messages(&resolution)
+        );
+
+        let a: Vec<_> = resolution
+            .symbols()
+            .filter(|(_, symbol)| symbol.name.name == "a")
+            .map(|(_, symbol)| (symbol.scope, symbol.declarations.len()))
+            .collect();
+        // The parameter of the prototype is a distinct symbol
+        assert_eq!(a, [(ScopeKind::File, 4), (ScopeKind::Prototype, 1)]);
+        let b = resolution
+            .symbols()
+            .find(|(_, symbol)| symbol.name.name == "b");
+        assert!(b.is_some_and(|(_, b)| b.linkage == Linkage::Internal && b.is_defined));
+        let enumerators: Vec<_> = resolution
+            .symbols()
+            .filter_map(|(_, symbol)| match symbol.kind {
+                SymbolKind::Enumerator { .. } => Some(symbol.name.name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(enumerators, ["A", "B"]);
+    }
+
+    #[test]
+    fn test_resolution_errors() {
+        let program = parse_valid(
+            "int x = 1;
+            int x = 2;
+            static int y;
+            int y;
+            int z;
+            static int z;
+            typedef int t;
+            typedef long t;
+            int t;
+            struct s { int a; int a; };
+            struct s { int b; };
+            union s *u;
+            enum e { A, A };
+            void f(int p, int p) { int p; }
+            int g(void) {
+                int local;
+                extern int local;
+                return missing;
+            }",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        assert_eq!(
+            messages(&resolution),
+            [
+                "redefinition of 'x'",
+                "non-static declaration of 'y' follows static declaration",
+                "static declaration of 'z' follows non-static declaration",
+                "typedef redefinition of 't' with different types",
+                "redefinition of 't' as different kind of symbol",
+                "duplicate member 'a'",
+                "redefinition of 'struct s'",
+                "use of 's' with tag type that does not match previous declaration",
+                "redefinition of enumerator 'A'",
+                "redefinition of parameter 'p'",
+                "redefinition of 'p'",
+                "extern declaration of 'local' follows non-extern declaration",
+                "use of undeclared identifier 'missing'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_implicit_functions() {
+        const SOURCE: &str = "int main(void) { puts(\"hi\"); puts(__func__); return 0; }";
+        let program = parse_valid(SOURCE);
+        let resolution = resolve(&program, Standard::C89);
+        assert!(
+            resolution.diagnostics().is_empty(),
+            "{:?}",
+            messages(&resolution)
+        );
+        let puts = resolution
+            .symbols()
+            .find(|(_, symbol)| symbol.name.name == "puts");
+        assert!(puts.is_some_and(|(_, puts)| {
+            puts.kind == SymbolKind::ImplicitFunction && puts.linkage == Linkage::External
+        }));
+
+        let resolution = resolve(&program, Standard::C99);
+        assert_eq!(
+            messages(&resolution),
+            ["call to undeclared function 'puts'; ISO C99 and later do not support implicit function declarations"]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! The entities names refer to, and the scopes they are declared in.
+
+use crate::{
+    ast::tree::{
+        Attribute, Attributes, DeclSpecifiers, Declarator, EnumSpecifier, KnownAttribute,
+        RecordKind, RecordSpecifier,
+    },
+    lexer::token::Ident,
+};
+
+/// Index of a symbol in a [`Resolution`](super::Resolution)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolId(pub(super) usize);
+
+/// Kind of region of the program a name is visible in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    /// Outside of any function, until the end of the translation unit
+    File,
+    /// Parameters of a function declarator that is not part of a definition, as in
+    /// `void f(int n, int a[n]);`, until the end of the declarator
+    Prototype,
+    /// The outermost block of a function definition, which holds its parameters and
+    /// its labels
+    Function,
+    /// Any other block, and the statements that are blocks on their own, such as
+    /// the body of `if` and the whole of `for`
+    Block,
+}
+
+/// Separate kinds of names, so that `struct s` and a variable `s` can coexist. The
+/// members of each struct or union are a namespace of their own, which is resolved
+/// along with the types of expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    /// Variables, functions, typedef names and enumerators
+    Ordinary,
+    /// Tags of structs, unions and enums
+    Tag,
+    /// Targets of `goto`
+    Label,
+}
+
+/// Whether declarations in different scopes refer to the same entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    /// The same entity in the whole program, as with `extern int x;`
+    External,
+    /// The same entity in the translation unit, as with `static int x;`
+    Internal,
+    /// A distinct entity for each declaration, as with local variables
+    None,
+}
+
+/// Specifiers and declarator of a declaration, from which the type of what it
+/// declares is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Declaration<'a, 'i> {
+    pub specifiers: &'a DeclSpecifiers<'i>,
+    pub declarator: &'a Declarator<'i>,
+}
+impl<'a, 'i> Declaration<'a, 'i> {
+    /// The attribute lists of the declaration, those at its start first
+    pub fn attributes(&self) -> impl Iterator<Item = &'a Attributes<'i>> {
+        std::iter::once(&self.specifiers.attributes).chain(self.declarator.attributes())
+    }
+}
+
+/// The struct, union or enum specifier that declares a tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag<'a, 'i> {
+    Record(&'a RecordSpecifier<'i>),
+    Enum(&'a EnumSpecifier<'i>),
+}
+impl<'a, 'i> Tag<'a, 'i> {
+    /// The keyword the tag is declared with
+    #[must_use]
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Record(record) if record.kind == RecordKind::Struct => "struct",
+            Self::Record(_) => "union",
+            Self::Enum(_) => "enum",
+        }
+    }
+}
+
+/// What a symbol is, along with where it is declared: its definition if there is one,
+/// its first declaration otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind<'a, 'i> {
+    Object(Declaration<'a, 'i>),
+    /// `None` for the parameters of a K&R definition that are not declared, which are
+    /// `int`
+    Parameter(Option<Declaration<'a, 'i>>),
+    Function(Declaration<'a, 'i>),
+    /// A function called without being declared, which C89 declares as `extern int f()`
+    ImplicitFunction,
+    Typedef(Declaration<'a, 'i>),
+    /// Its value is only known once its type is, see `TypeContext::enumerator`
+    Enumerator {
+        specifier: &'a EnumSpecifier<'i>,
+    },
+    Tag(Tag<'a, 'i>),
+    Label,
+}
+impl<'a, 'i> SymbolKind<'a, 'i> {
+    #[must_use]
+    pub fn namespace(&self) -> Namespace {
+        match self {
+            Self::Tag(_) => Namespace::Tag,
+            Self::Label => Namespace::Label,
+            _ => Namespace::Ordinary,
+        }
+    }
+
+    /// Whether `self` and `other` are the same kind of symbol, which declarations of
+    /// the same name in the same scope must be
+    pub(super) fn is_same_kind(&self, other: &Self) -> bool {
+        let kind = |kind: &Self| match kind {
+            Self::Object(_) | Self::Parameter(_) => "variable",
+            Self::Function(_) | Self::ImplicitFunction => "function",
+            Self::Typedef(_) => "typedef",
+            Self::Enumerator { .. } => "enumerator",
+            Self::Tag(tag) => tag.keyword(),
+            Self::Label => "label",
+        };
+        kind(self) == kind(other)
+    }
+
+    /// The attribute lists of the declaration, if any
+    pub(super) fn attributes(&self) -> impl Iterator<Item = &'a Attributes<'i>> {
+        let declaration = match *self {
+            Self::Object(declaration)
+            | Self::Parameter(Some(declaration))
+            | Self::Function(declaration)
+            | Self::Typedef(declaration) => Some(declaration),
+            _ => None,
+        };
+        declaration
+            .into_iter()
+            .flat_map(|declaration| declaration.attributes())
+    }
+}
+
+/// An entity declared in the program, which names bind to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol<'a, 'i> {
+    pub name: Ident<'i>,
+    pub kind: SymbolKind<'a, 'i>,
+    pub linkage: Linkage,
+    /// Kind of the scope of its first declaration
+    pub scope: ScopeKind,
+    /// Whether it has been defined: a function with its body, a struct with its members,
+    /// a variable that is not only `extern` nor a tentative definition at file scope
+    pub is_defined: bool,
+    /// Names of all its declarations, in order
+    pub declarations: Vec<&'a Ident<'i>>,
+    /// Attribute lists of all its declarations, which all apply to it
+    pub attributes: Vec<&'a Attributes<'i>>,
+}
+impl<'a, 'i> Symbol<'a, 'i> {
+    /// The last attribute of the given kind any of its declarations gives it
+    #[must_use]
+    pub fn attribute(&self, kind: KnownAttribute) -> Option<&'a Attribute<'i>> {
+        self.attributes
+            .iter()
+            .rev()
+            .find_map(|list| list.find(kind))
+    }
+}
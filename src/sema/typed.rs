@@ -0,0 +1,222 @@
+//! Expressions with their types, as the type checker sees them: operands converted
+//! explicitly, and names replaced by their symbols.
+
+use crate::{
+    ast::tree::{
+        AssignOp, BinaryOp, DesignatedInitializer, Designator, PostfixOp, Statement, StringLiteral,
+        UnaryOp,
+    },
+    lexer::token::{Ident, Literal},
+};
+
+use super::{constant::Constant, SymbolId, TypeId};
+
+/// Whether an expression designates an object, a function, or is only a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueCategory {
+    /// Designates an object, which may be assigned if it is modifiable, as in `x` or `*p`
+    LValue,
+    /// Designates a function, as `f` in `f(x)`
+    Function,
+    /// Only a value, as in `x + 1`
+    RValue,
+}
+
+/// Conversions the language applies without being asked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastKind {
+    /// Reads the value of an lvalue, which loses its qualifiers
+    LValueToRValue,
+    /// An array becomes a pointer to its first element
+    ArrayToPointer,
+    /// A function becomes a pointer to it
+    FunctionToPointer,
+    /// Between integer types, as in the integer promotions
+    IntegralCast,
+    IntegralToFloating,
+    FloatingToIntegral,
+    /// Between floating types
+    FloatingCast,
+    /// Any scalar compared to zero, when converted to `_Bool`
+    ToBool,
+    /// A null pointer constant becomes a null pointer
+    NullToPointer,
+    /// Between pointer types, as when adding qualifiers or converting to `void *`
+    PointerCast,
+    /// Only in casts, as in `(long)p`
+    PointerToIntegral,
+    /// Only in casts, as in `(int *)4`
+    IntegralToPointer,
+    /// Only in casts, as in `(void)f()`
+    ToVoid,
+}
+
+/// An expression with its type, which is the type of its value after the conversions
+/// around it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedExpr<'a, 'i> {
+    pub kind: ExprKind<'a, 'i>,
+    pub ty: TypeId,
+    pub category: ValueCategory,
+}
+
+impl<'a, 'i> TypedExpr<'a, 'i> {
+    /// The subexpressions of `self`, in the order they are written
+    #[must_use]
+    pub fn operands(&self) -> Vec<&Self> {
+        match self.kind {
+            ExprKind::Unary { ref operand, .. }
+            | ExprKind::Postfix { ref operand, .. }
+            | ExprKind::Cast(ref operand)
+            | ExprKind::ImplicitCast { ref operand, .. }
+            | ExprKind::Member {
+                object: ref operand,
+                ..
+            } => vec![operand],
+            ExprKind::Binary {
+                ref lhs, ref rhs, ..
+            }
+            | ExprKind::Assign {
+                ref lhs, ref rhs, ..
+            }
+            | ExprKind::Index {
+                array: ref lhs,
+                index: ref rhs,
+            } => vec![lhs, rhs],
+            ExprKind::Conditional {
+                ref condition,
+                ref then,
+                ref r#else,
+            } => vec![condition, then, r#else],
+            ExprKind::Call {
+                ref callee,
+                ref args,
+            } => std::iter::once(&**callee).chain(args).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The subexpressions of `self`, which may be replaced
+    pub(crate) fn operands_mut(&mut self) -> Vec<&mut Self> {
+        match self.kind {
+            ExprKind::Unary {
+                ref mut operand, ..
+            }
+            | ExprKind::Postfix {
+                ref mut operand, ..
+            }
+            | ExprKind::Cast(ref mut operand)
+            | ExprKind::ImplicitCast {
+                ref mut operand, ..
+            }
+            | ExprKind::Member {
+                object: ref mut operand,
+                ..
+            } => vec![operand],
+            ExprKind::Binary {
+                ref mut lhs,
+                ref mut rhs,
+                ..
+            }
+            | ExprKind::Assign {
+                ref mut lhs,
+                ref mut rhs,
+                ..
+            }
+            | ExprKind::Index {
+                array: ref mut lhs,
+                index: ref mut rhs,
+            } => vec![lhs, rhs],
+            ExprKind::Conditional {
+                ref mut condition,
+                ref mut then,
+                ref mut r#else,
+            } => vec![condition, then, r#else],
+            ExprKind::Call {
+                ref mut callee,
+                ref mut args,
+            } => std::iter::once(&mut **callee).chain(args).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind<'a, 'i> {
+    Literal(&'a Literal<'i>),
+    String(&'a StringLiteral<'i>),
+    /// The value of a constant subexpression, which it replaces once folded
+    Constant(Constant<'a, 'i>),
+    /// An object, function or enumerator
+    Symbol(SymbolId),
+    /// `__func__` and its GNU spellings, the name of the enclosing function
+    FunctionName(&'a Ident<'i>),
+    Unary {
+        op: UnaryOp,
+        operand: Box<TypedExpr<'a, 'i>>,
+    },
+    Postfix {
+        op: PostfixOp,
+        operand: Box<TypedExpr<'a, 'i>>,
+    },
+    /// Both operands converted to the type the operation is done in, except for shifts
+    /// and pointer arithmetic
+    Binary {
+        op: BinaryOp,
+        lhs: Box<TypedExpr<'a, 'i>>,
+        rhs: Box<TypedExpr<'a, 'i>>,
+    },
+    /// `rhs` converted to the type of `lhs`, or to the type the operation of a compound
+    /// assignment is done in
+    Assign {
+        op: AssignOp,
+        lhs: Box<TypedExpr<'a, 'i>>,
+        rhs: Box<TypedExpr<'a, 'i>>,
+    },
+    Conditional {
+        condition: Box<TypedExpr<'a, 'i>>,
+        then: Box<TypedExpr<'a, 'i>>,
+        r#else: Box<TypedExpr<'a, 'i>>,
+    },
+    /// A cast written in the source, to the type of the expression
+    Cast(Box<TypedExpr<'a, 'i>>),
+    /// A conversion the language applies, to the type of the expression
+    ImplicitCast {
+        kind: CastKind,
+        operand: Box<TypedExpr<'a, 'i>>,
+    },
+    CompoundLiteral(&'a [DesignatedInitializer<'i>]),
+    /// `sizeof` of an expression or a type, which is not evaluated
+    Sizeof(TypeId),
+    Alignof(TypeId),
+    Call {
+        callee: Box<TypedExpr<'a, 'i>>,
+        /// Converted to the types of the parameters, or promoted
+        args: Vec<TypedExpr<'a, 'i>>,
+    },
+    Index {
+        array: Box<TypedExpr<'a, 'i>>,
+        index: Box<TypedExpr<'a, 'i>>,
+    },
+    /// `object.member` or `object->member`, where the member is found by following
+    /// `path`, the indices of the members through anonymous structs and unions
+    Member {
+        object: Box<TypedExpr<'a, 'i>>,
+        path: Vec<usize>,
+        arrow: bool,
+    },
+    LabelAddress(Option<SymbolId>),
+    /// GNU statement expression, whose statements are checked on their own
+    Statement(&'a [Statement<'i>]),
+    Offsetof {
+        /// The struct or union type
+        ty: TypeId,
+        member: Ident<'i>,
+        designators: &'a [Designator<'i>],
+    },
+    TypesCompatible(bool),
+
+    /// Placeholder for an expression that could not be typed, which is never reported
+    /// on again
+    Error,
+}
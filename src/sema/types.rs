@@ -0,0 +1,904 @@
+//! Types as the semantic passes see them, interned in a [`TypeContext`] so that two
+//! types are the same exactly when their [`TypeId`]s are.
+
+mod build;
+mod layout;
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    ast::tree::{EnumSpecifier, RecordKind, RecordSpecifier, TypeQualifiers},
+    lexer::token::Ident,
+    target::Target,
+};
+
+use super::SymbolId;
+
+pub use layout::{DumpLayout, RecordLayout};
+
+/// Handle to a type interned in a [`TypeContext`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeId(u32);
+
+/// Handle to a struct or union in a [`TypeContext`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecordId(u32);
+
+/// Handle to an enum in a [`TypeContext`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnumId(u32);
+
+/// Integer types, in order of conversion rank, signed before unsigned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntKind {
+    Bool,
+    /// Plain `char`, a type of its own even though it is signed here
+    Char,
+    SChar,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+}
+impl IntKind {
+    #[must_use]
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            Self::Char | Self::SChar | Self::Short | Self::Int | Self::Long | Self::LongLong
+        )
+    }
+
+    /// Integer conversion rank, the same for the signed and unsigned types of a size
+    #[must_use]
+    pub fn rank(self) -> u8 {
+        match self {
+            Self::Bool => 0,
+            Self::Char | Self::SChar | Self::UChar => 1,
+            Self::Short | Self::UShort => 2,
+            Self::Int | Self::UInt => 3,
+            Self::Long | Self::ULong => 4,
+            Self::LongLong | Self::ULongLong => 5,
+        }
+    }
+
+    /// The unsigned type of the same rank
+    #[must_use]
+    pub fn to_unsigned(self) -> Self {
+        match self {
+            Self::Char | Self::SChar => Self::UChar,
+            Self::Short => Self::UShort,
+            Self::Int => Self::UInt,
+            Self::Long => Self::ULong,
+            Self::LongLong => Self::ULongLong,
+            unsigned => unsigned,
+        }
+    }
+
+    /// The type the integer promotions convert this one to: `int` for every type of
+    /// lower rank, since it can represent all their values
+    #[must_use]
+    pub fn promoted(self) -> Self {
+        if self.rank() < Self::Int.rank() {
+            Self::Int
+        } else {
+            self
+        }
+    }
+}
+impl Display for IntKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Bool => "_Bool",
+            Self::Char => "char",
+            Self::SChar => "signed char",
+            Self::UChar => "unsigned char",
+            Self::Short => "short",
+            Self::UShort => "unsigned short",
+            Self::Int => "int",
+            Self::UInt => "unsigned int",
+            Self::Long => "long",
+            Self::ULong => "unsigned long",
+            Self::LongLong => "long long",
+            Self::ULongLong => "unsigned long long",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum FloatKind {
+    #[display(fmt = "float")]
+    Float,
+    #[display(fmt = "double")]
+    Double,
+    #[display(fmt = "long double")]
+    LongDouble,
+}
+
+/// Number of elements of an array type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrayLength {
+    Fixed(u64),
+    /// Known only at run time, as in `int a[n]`
+    Variable,
+    /// An incomplete type, as in `extern int a[];`
+    Incomplete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    /// Integer types, including `_Bool`
+    Int(IntKind),
+    Float {
+        kind: FloatKind,
+        is_complex: bool,
+    },
+    Pointer(TypeId),
+    Array {
+        element: TypeId,
+        length: ArrayLength,
+    },
+    Function {
+        ret: TypeId,
+        /// `None` for functions declared without a prototype, as in `int f();`
+        params: Option<Vec<TypeId>>,
+        is_variadic: bool,
+    },
+    Record(RecordId),
+    Enum(EnumId),
+    /// `ty` with qualifiers, `_Atomic` included. `ty` is never qualified itself nor an
+    /// array, whose qualifiers belong to its elements.
+    Qualified {
+        ty: TypeId,
+        qualifiers: TypeQualifiers,
+    },
+
+    /// Placeholder for a type that could not be determined
+    Error,
+}
+
+/// A member of a struct or union
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member<'i> {
+    /// `None` for anonymous struct and union members, and for unnamed bit-fields
+    pub name: Option<Ident<'i>>,
+    pub ty: TypeId,
+    /// Width of a bit-field, as in `unsigned flag : 1;`
+    pub width: Option<u32>,
+    /// Alignment asked for with `_Alignas` or the `aligned` attribute, in bytes
+    pub align: Option<u64>,
+    /// Whether the member, or its whole record, is `packed`: aligned to a byte, or to a
+    /// bit for bit-fields
+    pub is_packed: bool,
+}
+
+/// A struct or union type. There is one per tag declared, even with the same name in
+/// different scopes, and one per anonymous definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a, 'i> {
+    pub kind: RecordKind,
+    pub tag: Option<Ident<'i>>,
+    /// The specifier that defines the members, `None` while the type is incomplete
+    pub definition: Option<&'a RecordSpecifier<'i>>,
+    /// In order, `None` while the type is incomplete
+    pub members: Option<Vec<Member<'i>>>,
+    /// `None` while the type is incomplete, or if a member has no size
+    pub layout: Option<RecordLayout>,
+}
+
+/// An enum type, which is compatible with an integer type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum<'a, 'i> {
+    pub tag: Option<Ident<'i>>,
+    /// The specifier that defines the enumerators, `None` while the type is incomplete
+    pub definition: Option<&'a EnumSpecifier<'i>>,
+    /// The integer type compatible with the enum, able to hold all its values
+    pub underlying: IntKind,
+}
+
+/// What a struct, union or enum type is told apart by: the symbol of its tag, or the
+/// specifier of an anonymous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TagKey {
+    Tag(SymbolId),
+    Anonymous(*const ()),
+}
+
+/// All the types of a program, each stored once
+#[derive(Debug, Default)]
+pub struct TypeContext<'a, 'i> {
+    types: Vec<Type>,
+    ids: HashMap<Type, TypeId>,
+    records: Vec<Record<'a, 'i>>,
+    enums: Vec<Enum<'a, 'i>>,
+    tags: HashMap<TagKey, Type>,
+    /// Types of the typedef names, built once each
+    typedefs: HashMap<SymbolId, TypeId>,
+    /// Types of the variables declared with `__auto_type`, those of their initializers
+    inferred: HashMap<SymbolId, TypeId>,
+    /// Values of the enumerators, computed once each. `None` while being computed, and
+    /// for values that are not constant.
+    enumerators: HashMap<SymbolId, Option<i128>>,
+    /// What the sizes and alignments of types are
+    target: Target,
+}
+impl<'a, 'i> TypeContext<'a, 'i> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lays types out and evaluates constant expressions for `target` rather than for
+    /// x86-64
+    #[must_use]
+    pub fn with_target(self, target: Target) -> Self {
+        Self { target, ..self }
+    }
+
+    #[must_use]
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// The handle to `ty`, the same for every type equal to it
+    pub fn intern(&mut self, ty: Type) -> TypeId {
+        if let Some(&id) = self.ids.get(&ty) {
+            return id;
+        }
+        let id = TypeId(self.types.len() as u32);
+        self.types.push(ty.clone());
+        self.ids.insert(ty, id);
+        id
+    }
+
+    #[must_use]
+    pub fn get(&self, id: TypeId) -> &Type {
+        &self.types[id.0 as usize]
+    }
+
+    #[must_use]
+    pub fn record(&self, id: RecordId) -> &Record<'a, 'i> {
+        &self.records[id.0 as usize]
+    }
+
+    #[must_use]
+    pub fn enumeration(&self, id: EnumId) -> &Enum<'a, 'i> {
+        &self.enums[id.0 as usize]
+    }
+
+    pub fn int(&mut self, kind: IntKind) -> TypeId {
+        self.intern(Type::Int(kind))
+    }
+
+    pub fn pointer(&mut self, to: TypeId) -> TypeId {
+        self.intern(Type::Pointer(to))
+    }
+
+    /// `ty` with `qualifiers` added to its own. Qualifying an array qualifies its
+    /// elements.
+    pub fn qualified(&mut self, ty: TypeId, qualifiers: TypeQualifiers) -> TypeId {
+        if qualifiers.is_empty() {
+            return ty;
+        }
+        match *self.get(ty) {
+            Type::Array { element, length } => {
+                let element = self.qualified(element, qualifiers);
+                self.intern(Type::Array { element, length })
+            }
+            Type::Qualified {
+                ty,
+                qualifiers: inner,
+            } => {
+                let qualifiers = TypeQualifiers {
+                    is_const: qualifiers.is_const || inner.is_const,
+                    is_volatile: qualifiers.is_volatile || inner.is_volatile,
+                    is_restrict: qualifiers.is_restrict || inner.is_restrict,
+                    is_atomic: qualifiers.is_atomic || inner.is_atomic,
+                };
+                self.intern(Type::Qualified { ty, qualifiers })
+            }
+            _ => self.intern(Type::Qualified { ty, qualifiers }),
+        }
+    }
+
+    /// `ty` without its qualifiers, and the qualifiers
+    #[must_use]
+    pub fn unqualified(&self, ty: TypeId) -> (TypeId, TypeQualifiers) {
+        match *self.get(ty) {
+            Type::Qualified { ty, qualifiers } => (ty, qualifiers),
+            _ => (ty, TypeQualifiers::default()),
+        }
+    }
+
+    /// Whether `ty` is a variable length array or is derived from one, as `int (*)[n]`
+    #[must_use]
+    pub fn is_variably_modified(&self, ty: TypeId) -> bool {
+        match *self.get(ty) {
+            Type::Array {
+                length: ArrayLength::Variable,
+                ..
+            } => true,
+            Type::Array { element: ty, .. }
+            | Type::Pointer(ty)
+            | Type::Function { ret: ty, .. }
+            | Type::Qualified { ty, .. } => self.is_variably_modified(ty),
+            _ => false,
+        }
+    }
+
+    /// Whether `ty` is known to have a size: not `void`, not an array of unknown length
+    /// nor a struct, union or enum whose contents are not declared yet, and not a
+    /// function
+    #[must_use]
+    pub fn is_complete(&self, ty: TypeId) -> bool {
+        match *self.get(ty) {
+            Type::Void | Type::Function { .. } => false,
+            Type::Qualified { ty, .. } => self.is_complete(ty),
+            Type::Array { element, length } => {
+                length != ArrayLength::Incomplete && self.is_complete(element)
+            }
+            Type::Record(record) => self.record(record).members.is_some(),
+            Type::Enum(id) => self.enumeration(id).definition.is_some(),
+            Type::Int(_) | Type::Float { .. } | Type::Pointer(_) | Type::Error => true,
+        }
+    }
+
+    /// Size of `ty` in bytes on the target. `None` for incomplete types and variable
+    /// length arrays.
+    #[must_use]
+    pub fn size_of(&self, ty: TypeId) -> Option<u64> {
+        match *self.get(ty) {
+            Type::Int(kind) => Some(self.target.int_size(kind)),
+            Type::Float { kind, is_complex } => {
+                let size = self.target.float_size(kind);
+                Some(if is_complex { 2 * size } else { size })
+            }
+            Type::Pointer(_) => Some(self.target.pointer),
+            Type::Array {
+                element,
+                length: ArrayLength::Fixed(length),
+            } => self.size_of(element)?.checked_mul(length),
+            Type::Record(id) => Some(self.record(id).layout.as_ref()?.size),
+            Type::Enum(id) if self.enumeration(id).definition.is_some() => {
+                Some(self.target.int_size(self.enumeration(id).underlying))
+            }
+            Type::Qualified { ty, .. } => self.size_of(ty),
+            Type::Void
+            | Type::Array { .. }
+            | Type::Function { .. }
+            | Type::Enum(_)
+            | Type::Error => None,
+        }
+    }
+
+    /// Alignment of `ty` in bytes on the target, with the same exceptions as
+    /// [`TypeContext::size_of`] except for arrays, aligned as their elements
+    #[must_use]
+    pub fn align_of(&self, ty: TypeId) -> Option<u64> {
+        match *self.get(ty) {
+            Type::Int(kind) => Some(self.target.int_align(kind)),
+            Type::Float { kind, .. } => Some(self.target.float_align(kind)),
+            Type::Array { element, .. } => self.align_of(element),
+            Type::Record(id) => Some(self.record(id).layout.as_ref()?.align),
+            Type::Enum(id) if self.enumeration(id).definition.is_some() => {
+                Some(self.target.int_align(self.enumeration(id).underlying))
+            }
+            Type::Qualified { ty, .. } => self.align_of(ty),
+            _ => self.size_of(ty),
+        }
+    }
+
+    /// The integer type of `ty`, the underlying one for enums, regardless of qualifiers.
+    /// `None` if `ty` is not an integer type.
+    #[must_use]
+    pub fn int_kind(&self, ty: TypeId) -> Option<IntKind> {
+        match *self.get(self.unqualified(ty).0) {
+            Type::Int(kind) => Some(kind),
+            Type::Enum(id) => Some(self.enumeration(id).underlying),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_integer(&self, ty: TypeId) -> bool {
+        self.int_kind(ty).is_some()
+    }
+
+    /// Whether `ty` is an integer or floating type
+    #[must_use]
+    pub fn is_arithmetic(&self, ty: TypeId) -> bool {
+        self.is_integer(ty) || matches!(self.get(self.unqualified(ty).0), Type::Float { .. })
+    }
+
+    /// Whether `ty` is an arithmetic or pointer type, which can be compared to zero
+    #[must_use]
+    pub fn is_scalar(&self, ty: TypeId) -> bool {
+        self.is_arithmetic(ty) || self.pointee(ty).is_some()
+    }
+
+    /// The type `ty` points to, if it is a pointer
+    #[must_use]
+    pub fn pointee(&self, ty: TypeId) -> Option<TypeId> {
+        match *self.get(self.unqualified(ty).0) {
+            Type::Pointer(to) => Some(to),
+            _ => None,
+        }
+    }
+
+    /// Whether two types may refer to the same object: the same type once typedef names
+    /// are followed, up to the lengths of arrays and parameters of functions that are
+    /// not known
+    #[must_use]
+    pub fn is_compatible(&self, a: TypeId, b: TypeId) -> bool {
+        if a == b {
+            return true;
+        }
+        let ((a, a_qualifiers), (b, b_qualifiers)) = (self.unqualified(a), self.unqualified(b));
+        if a_qualifiers != b_qualifiers {
+            return false;
+        }
+        match (self.get(a), self.get(b)) {
+            (Type::Error, _) | (_, Type::Error) => true,
+            (Type::Pointer(a), Type::Pointer(b)) => self.is_compatible(*a, *b),
+            (
+                Type::Array {
+                    element: a,
+                    length: a_length,
+                },
+                Type::Array {
+                    element: b,
+                    length: b_length,
+                },
+            ) => {
+                let lengths_match = match (a_length, b_length) {
+                    (ArrayLength::Fixed(a), ArrayLength::Fixed(b)) => a == b,
+                    _ => true,
+                };
+                lengths_match && self.is_compatible(*a, *b)
+            }
+            (
+                Type::Function {
+                    ret: a_ret,
+                    params: a_params,
+                    is_variadic: a_variadic,
+                },
+                Type::Function {
+                    ret: b_ret,
+                    params: b_params,
+                    is_variadic: b_variadic,
+                },
+            ) => {
+                let params_match = match (a_params, b_params) {
+                    (Some(a), Some(b)) => {
+                        a_variadic == b_variadic
+                            && a.len() == b.len()
+                            && a.iter().zip(b).all(|(&a, &b)| self.is_compatible(a, b))
+                    }
+                    _ => true,
+                };
+                params_match && self.is_compatible(*a_ret, *b_ret)
+            }
+            (&Type::Enum(id), &Type::Int(kind)) | (&Type::Int(kind), &Type::Enum(id)) => {
+                self.enumeration(id).underlying == kind
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The member named `name` of a struct or union, looked up through its anonymous
+    /// members: the indices of the members that lead to it, and its type
+    #[must_use]
+    pub fn find_member(&self, record: RecordId, name: &str) -> Option<(Vec<usize>, TypeId)> {
+        let members = self.record(record).members.as_ref()?;
+        for (n, member) in members.iter().enumerate() {
+            match member.name {
+                Some(ident) if ident.name == name => return Some((vec![n], member.ty)),
+                Some(_) => {}
+                None => {
+                    let Type::Record(inner) = *self.get(self.unqualified(member.ty).0) else {
+                        continue;
+                    };
+                    if let Some((mut path, ty)) = self.find_member(inner, name) {
+                        path.insert(0, n);
+                        return Some((path, ty));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Writes `ty` as in a cast, in the syntax of declarators, as in `int (*)[4]`
+    #[must_use]
+    pub fn display(&self, ty: TypeId) -> DisplayType<'_, 'a, 'i> {
+        DisplayType { types: self, ty }
+    }
+
+    /// `ty` declaring `inner`, the part of the declarator written so far, as in
+    /// `char *(*)(int)` for a pointer to a function returning a pointer
+    fn declarator_text(&self, ty: TypeId, inner: String) -> String {
+        let (unqualified, qualifiers) = self.unqualified(ty);
+        let base = match *self.get(unqualified) {
+            Type::Pointer(to) => {
+                let mut pointer = String::from("*");
+                if !qualifiers.is_empty() {
+                    pointer.push_str(&qualifiers.to_string());
+                    if !inner.is_empty() {
+                        pointer.push(' ');
+                    }
+                }
+                return self.declarator_text(to, pointer + &inner);
+            }
+            Type::Array { element, length } => {
+                let length = match length {
+                    ArrayLength::Fixed(length) => length.to_string(),
+                    ArrayLength::Variable => "*".to_string(),
+                    ArrayLength::Incomplete => String::new(),
+                };
+                let inner = parenthesize(inner);
+                return self.declarator_text(element, format!("{inner}[{length}]"));
+            }
+            Type::Function {
+                ret,
+                ref params,
+                is_variadic,
+            } => {
+                let mut text = parenthesize(inner) + "(";
+                let params = params.as_deref();
+                for (n, &param) in params.unwrap_or_default().iter().enumerate() {
+                    if n > 0 {
+                        text.push_str(", ");
+                    }
+                    text.push_str(&self.declarator_text(param, String::new()));
+                }
+                match (params, is_variadic) {
+                    (Some([]), false) => text.push_str("void"),
+                    (Some([]), true) => text.push_str("..."),
+                    (Some(_), true) => text.push_str(", ..."),
+                    _ => {}
+                }
+                return self.declarator_text(ret, text + ")");
+            }
+            Type::Void => "void".to_string(),
+            Type::Int(kind) => kind.to_string(),
+            Type::Float { kind, is_complex } if is_complex => format!("_Complex {kind}"),
+            Type::Float { kind, .. } => kind.to_string(),
+            Type::Record(id) => self.record_name(id),
+            Type::Enum(id) => match self.enumeration(id).tag {
+                Some(tag) => format!("enum {tag}"),
+                None => "enum (anonymous)".to_string(),
+            },
+            Type::Qualified { .. } => unreachable!("qualified types are never qualified again"),
+            Type::Error => "<error>".to_string(),
+        };
+        let base = if qualifiers.is_empty() {
+            base
+        } else {
+            format!("{qualifiers} {base}")
+        };
+        if inner.is_empty() || inner.starts_with('[') {
+            base + &inner
+        } else {
+            format!("{base} {inner}")
+        }
+    }
+
+    /// `struct` or `union` and the tag of `record`
+    fn record_name(&self, record: RecordId) -> String {
+        let record = self.record(record);
+        match record.tag {
+            Some(tag) => format!("{} {tag}", record.kind),
+            None => format!("{} (anonymous)", record.kind),
+        }
+    }
+}
+
+/// Wraps a declarator that starts with a pointer in parentheses, before an array or
+/// function suffix binds tighter than it
+fn parenthesize(inner: String) -> String {
+    if inner.starts_with('*') {
+        format!("({inner})")
+    } else {
+        inner
+    }
+}
+
+/// A type written in the syntax of declarators, from [`TypeContext::display`]
+pub struct DisplayType<'c, 'a, 'i> {
+    types: &'c TypeContext<'a, 'i>,
+    ty: TypeId,
+}
+impl<'c, 'a, 'i> Display for DisplayType<'c, 'a, 'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.types.declarator_text(self.ty, String::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::parse_valid,
+        sema::{resolve, Namespace, Resolution, SymbolKind},
+        standard::Standard,
+    };
+
+    use super::{IntKind, Target, Type, TypeContext};
+
+    /// The type of the ordinary symbol named `name`
+    fn symbol_type<'a, 'i>(
+        resolution: &Resolution<'a, 'i>,
+        types: &mut TypeContext<'a, 'i>,
+        name: &str,
+    ) -> super::TypeId {
+        let (id, _) = resolution
+            .symbols()
+            .find(|(_, s)| s.name.name == name && s.kind.namespace() == Namespace::Ordinary)
+            .unwrap_or_else(|| panic!("No symbol '{name}'"));
+        types.symbol(resolution, id).expect("Expected a type")
+    }
+
+    /// The names of the ordinary symbols and tags, with their types
+    fn types<'a, 'i>(
+        resolution: &Resolution<'a, 'i>,
+        types: &mut TypeContext<'a, 'i>,
+    ) -> Vec<(String, String)> {
+        resolution
+            .symbols()
+            .filter_map(|(id, symbol)| {
+                let ty = types.symbol(resolution, id)?;
+                Some((symbol.name.to_string(), types.display(ty).to_string()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_display() {
+        let program = parse_valid(
+            "const char *s;
+            char *const p;
+            int (*fp)(int, ...);
+            int *arr[3];
+            int (*parr)[4];
+            void (*signal(int, void (*)(int)))(int);
+            unsigned long long u;
+            _Bool b;
+            _Complex double z;
+            volatile int v[2];
+            int m[2][3];
+            extern int incomplete[];
+            int g(void);
+            int h();
+            struct s { int x; } rec;
+            enum e { A } en;
+            struct { int y; } anon;",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        let mut context = TypeContext::new();
+        let types = types(&resolution, &mut context);
+        let types: Vec<_> = types
+            .iter()
+            .map(|(n, t)| (n.as_str(), t.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("s", "const char *"),
+                ("p", "char *const"),
+                ("fp", "int (*)(int, ...)"),
+                ("arr", "int *[3]"),
+                ("parr", "int (*)[4]"),
+                ("signal", "void (*(int, void (*)(int)))(int)"),
+                ("u", "unsigned long long"),
+                ("b", "_Bool"),
+                ("z", "_Complex double"),
+                ("v", "volatile int[2]"),
+                ("m", "int[2][3]"),
+                ("incomplete", "int[]"),
+                ("g", "int (void)"),
+                ("h", "int ()"),
+                ("s", "struct s"),
+                ("rec", "struct s"),
+                ("e", "enum e"),
+                ("A", "int"),
+                ("en", "enum e"),
+                ("anon", "struct (anonymous)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interning() {
+        let program = parse_valid(
+            "typedef int T;
+            typedef const T CT;
+            T x;
+            int y;
+            CT cx;
+            const int cy;
+            struct s *p1;
+            struct s *p2;
+            void k(int a[const 3], int fn(void), const int c);
+            void f(int n) {
+                struct s { int z; } *p3;
+                int vla[n];
+            }",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        let mut types = TypeContext::new();
+        let mut ty = |name| symbol_type(&resolution, &mut types, name);
+        let (x, y, cx, cy) = (ty("x"), ty("y"), ty("cx"), ty("cy"));
+        assert_eq!(x, y);
+        assert_eq!(cx, cy);
+        assert_ne!(x, cx);
+        assert_eq!(ty("T"), y);
+        // Tags in different scopes are different types, even with the same name
+        let (p1, p2, p3) = (ty("p1"), ty("p2"), ty("p3"));
+        assert_eq!(p1, p2);
+        assert_ne!(p1, p3);
+        let (k, a, c, vla) = (ty("k"), ty("a"), ty("c"), ty("vla"));
+
+        assert_eq!(
+            types.display(k).to_string(),
+            "void (int *, int (*)(void), int)"
+        );
+        assert_eq!(types.display(a).to_string(), "int *const");
+        assert_eq!(types.display(c).to_string(), "const int");
+        assert_eq!(types.display(vla).to_string(), "int[*]");
+        let Type::Pointer(s) = *types.get(p1) else {
+            panic!("Expected a pointer")
+        };
+        assert!(!types.is_complete(s));
+        assert!(!types.is_complete(k));
+        assert!(types.is_complete(cx));
+    }
+
+    #[test]
+    fn test_records() {
+        let program = parse_valid(
+            "struct node {
+                int value;
+                struct node *next;
+                unsigned flag : 1, : 0;
+                union { int i; float f; };
+            };
+            enum negative { N = -1 };
+            enum positive { P = 1 };
+            enum large { L = 0x100000000 };",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        let mut types = TypeContext::new();
+        let tags: Vec<_> = resolution
+            .symbols()
+            .filter(|(_, symbol)| matches!(symbol.kind, SymbolKind::Tag(_)))
+            .map(|(id, _)| types.symbol(&resolution, id).expect("Expected a type"))
+            .collect();
+
+        let Type::Record(node) = *types.get(tags[0]) else {
+            panic!("Expected a struct")
+        };
+        let members = types
+            .record(node)
+            .members
+            .clone()
+            .expect("Expected members");
+        let members: Vec<_> = members
+            .iter()
+            .map(|m| {
+                let name = m.name.map(|n| n.to_string()).unwrap_or_default();
+                (name, types.display(m.ty).to_string(), m.width)
+            })
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("value".to_string(), "int".to_string(), None),
+                ("next".to_string(), "struct node *".to_string(), None),
+                ("flag".to_string(), "unsigned int".to_string(), Some(1)),
+                (String::new(), "unsigned int".to_string(), Some(0)),
+                (String::new(), "union (anonymous)".to_string(), None),
+            ]
+        );
+
+        let underlying: Vec<_> = tags[1..]
+            .iter()
+            .map(|&ty| match *types.get(ty) {
+                Type::Enum(id) => types.enumeration(id).underlying,
+                _ => panic!("Expected an enum"),
+            })
+            .collect();
+        assert_eq!(underlying, [IntKind::Int, IntKind::UInt, IntKind::ULong]);
+    }
+
+    /// The size, alignment and member offsets in bits of the records `source` defines,
+    /// laid out for `target`
+    fn layouts(source: &str, target: Target) -> Vec<(u64, u64, Vec<u64>)> {
+        let program = parse_valid(source);
+        let resolution = resolve(&program, Standard::C17);
+        let mut context = TypeContext::new().with_target(target);
+        types(&resolution, &mut context);
+        let records: Vec<_> = context.laid_out().collect();
+        records
+            .into_iter()
+            .map(|id| {
+                let layout = context
+                    .record(id)
+                    .layout
+                    .clone()
+                    .expect("Expected a layout");
+                (layout.size, layout.align, layout.offsets)
+            })
+            .collect()
+    }
+
+    const LAYOUTS: &str = "struct mixed { char c; double d; short s; };
+        struct bits { unsigned a : 3; unsigned b : 30; char c; int : 0; char d; long long e : 40; };
+        struct __attribute__((packed)) wire { char tag; int value; short crc; };
+        struct aligned { char c; int x __attribute__((aligned(16))); } __attribute__((aligned(32)));
+        struct flex { short len; int data[]; };
+        union u { char c[5]; int i; };
+        struct ld { char c; long double x; long l; void *p; };
+        struct pb { char c; __attribute__((packed)) int x : 4; _Alignas(8) char y; };";
+
+    #[test]
+    fn test_layouts() {
+        assert_eq!(
+            layouts(LAYOUTS, Target::X86_64),
+            [
+                (24, 8, vec![0, 64, 128]),
+                (24, 8, vec![0, 32, 64, 96, 96, 128]),
+                (7, 1, vec![0, 8, 40]),
+                (32, 32, vec![0, 128]),
+                (4, 4, vec![0, 32]),
+                (8, 4, vec![0, 0]),
+                (48, 16, vec![0, 128, 256, 320]),
+                (16, 8, vec![0, 8, 64]),
+            ]
+        );
+        assert_eq!(
+            layouts(LAYOUTS, Target::I386),
+            [
+                (16, 4, vec![0, 32, 96]),
+                (20, 4, vec![0, 32, 64, 96, 96, 104]),
+                (7, 1, vec![0, 8, 40]),
+                (32, 32, vec![0, 128]),
+                (4, 4, vec![0, 32]),
+                (8, 4, vec![0, 0]),
+                (24, 4, vec![0, 32, 128, 160]),
+                (16, 8, vec![0, 8, 64]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_layout() {
+        let program = parse_valid(
+            "struct point { int x; int y; };
+            struct shape { char kind; unsigned sides : 4; struct point origin; } square;",
+        );
+        let resolution = resolve(&program, Standard::C17);
+        let mut context = TypeContext::new();
+        let shape = symbol_type(&resolution, &mut context, "square");
+        let Type::Record(shape) = *context.get(shape) else {
+            panic!("Expected a struct")
+        };
+        assert_eq!(
+            context.dump_layout(shape).to_string(),
+            "*** Dumping AST Record Layout
+         0 | struct shape
+         0 |   char kind
+     1:0-3 |   unsigned int sides
+         4 |   struct point origin
+         4 |     int x
+         8 |     int y
+           | [sizeof=12, align=4]
+"
+        );
+    }
+}
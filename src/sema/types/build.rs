@@ -0,0 +1,523 @@
+//! Types of declarations, built from their specifiers and declarators, with typedef
+//! names and tags followed to their declarations.
+
+use crate::{
+    ast::tree::{
+        AlignmentSpecifier, ArraySize, Attributes, BaseType, DeclSpecifiers, Declarator,
+        EnumSpecifier, Expression, KnownAttribute, MemberItem, Parameters, RecordSpecifier,
+        Signedness, TypeName, TypeQualifiers, TypeofSpecifier,
+    },
+    lexer::token::Ident,
+    sema::{
+        check,
+        constant::{self, Evaluator},
+        Declaration, Resolution, SymbolId, SymbolKind, Tag,
+    },
+};
+
+use super::{
+    ArrayLength, Enum, EnumId, FloatKind, IntKind, Member, Record, RecordId, TagKey, Type,
+    TypeContext, TypeId,
+};
+
+/// Qualifiers written in the brackets of an array parameter, as in `int a[const 4]`,
+/// which qualify the pointer it is adjusted to
+fn array_qualifiers(declarator: &Declarator<'_>) -> TypeQualifiers {
+    match declarator {
+        Declarator::Array {
+            inner, qualifiers, ..
+        } if inner.is_name() => *qualifiers,
+        Declarator::Ident(_) | Declarator::Abstract => TypeQualifiers::default(),
+        Declarator::Pointer { inner, .. }
+        | Declarator::Array { inner, .. }
+        | Declarator::Function { inner, .. }
+        | Declarator::Attributed { inner, .. } => array_qualifiers(inner),
+    }
+}
+
+/// The attributes written in `declarator`, which apply to what it declares
+fn declarator_attributes<'d, 'i>(declarator: &'d Declarator<'i>) -> Vec<&'d Attributes<'i>> {
+    match declarator {
+        Declarator::Ident(_) | Declarator::Abstract => Vec::new(),
+        Declarator::Attributed { inner, attributes } => {
+            let mut list = declarator_attributes(inner);
+            list.push(attributes);
+            list
+        }
+        Declarator::Pointer { inner, .. }
+        | Declarator::Array { inner, .. }
+        | Declarator::Function { inner, .. } => declarator_attributes(inner),
+    }
+}
+
+impl<'a, 'i: 'a> TypeContext<'a, 'i> {
+    /// The type `declaration` gives to the name it declares
+    pub fn declaration(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        declaration: Declaration<'a, 'i>,
+    ) -> TypeId {
+        let base = self.specifiers(resolution, declaration.specifiers);
+        self.declarator(resolution, base, declaration.declarator)
+    }
+
+    /// The type written in a cast or `sizeof`
+    pub fn type_name(&mut self, resolution: &Resolution<'a, 'i>, ty: &'a TypeName<'i>) -> TypeId {
+        let base = self.specifiers(resolution, &ty.specifiers);
+        self.declarator(resolution, base, &ty.declarator)
+    }
+
+    /// The type of a symbol, or the type a typedef name or tag stands for. Parameters
+    /// declared as arrays and functions have the pointer types they are adjusted to.
+    /// `None` for labels.
+    pub fn symbol(&mut self, resolution: &Resolution<'a, 'i>, id: SymbolId) -> Option<TypeId> {
+        if let Some(&ty) = self.inferred.get(&id) {
+            return Some(ty);
+        }
+        Some(match resolution.symbol(id).kind {
+            SymbolKind::Object(declaration) | SymbolKind::Function(declaration) => {
+                self.declaration(resolution, declaration)
+            }
+            SymbolKind::Parameter(Some(declaration)) => {
+                let ty = self.declaration(resolution, declaration);
+                self.adjust_parameter(ty, array_qualifiers(declaration.declarator))
+            }
+            SymbolKind::Parameter(None) | SymbolKind::Enumerator { .. } => self.int(IntKind::Int),
+            SymbolKind::ImplicitFunction => {
+                let ret = self.int(IntKind::Int);
+                self.intern(Type::Function {
+                    ret,
+                    params: None,
+                    is_variadic: false,
+                })
+            }
+            SymbolKind::Typedef(_) => self.typedef(resolution, id),
+            SymbolKind::Tag(tag) => self.tag(resolution, TagKey::Tag(id), tag),
+            SymbolKind::Label => return None,
+        })
+    }
+
+    /// Gives `ty` to the variable `id` declared with `__auto_type`, that of its
+    /// initializer
+    pub(crate) fn infer(&mut self, id: SymbolId, ty: TypeId) {
+        self.inferred.insert(id, ty);
+    }
+
+    /// The type a typedef name stands for
+    fn typedef(&mut self, resolution: &Resolution<'a, 'i>, id: SymbolId) -> TypeId {
+        if let Some(&ty) = self.typedefs.get(&id) {
+            return ty;
+        }
+        let ty = match resolution.symbol(id).kind {
+            SymbolKind::Typedef(declaration) => self.declaration(resolution, declaration),
+            _ => self.intern(Type::Error),
+        };
+        self.typedefs.insert(id, ty);
+        ty
+    }
+
+    /// The value of an integer constant expression, such as the length of an array.
+    /// `None` if it is not one, as for variable length arrays.
+    pub(crate) fn constant(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        expr: &'a Expression<'i>,
+    ) -> Option<i128> {
+        let typed = check::typed_expression(resolution, self, expr);
+        Evaluator::new(resolution, self).integer(&typed).ok()
+    }
+
+    /// The value of the enumerator `id`, numbered from zero or from the last explicit
+    /// value before it
+    pub(crate) fn enumerator(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        id: SymbolId,
+    ) -> Option<i128> {
+        if let Some(&value) = self.enumerators.get(&id) {
+            return value;
+        }
+        let SymbolKind::Enumerator { specifier, .. } = resolution.symbol(id).kind else {
+            return None;
+        };
+        let mut previous = None;
+        for enumerator in specifier.enumerators.iter().flatten() {
+            let Some(symbol) = resolution.binding(&enumerator.name) else {
+                continue;
+            };
+            let value = match self.enumerators.get(&symbol) {
+                Some(&value) => value,
+                None => {
+                    // Marked first, for values that refer to their own enum
+                    self.enumerators.insert(symbol, None);
+                    let value = match enumerator.value {
+                        Some(ref value) => self.constant(resolution, value),
+                        None => previous.map_or(Some(0), |previous: Option<i128>| {
+                            previous.and_then(|value| value.checked_add(1))
+                        }),
+                    };
+                    // Values a fixed underlying type cannot hold are errors
+                    let value = match specifier.underlying {
+                        Some(_) => {
+                            let kind = self.underlying(resolution, specifier);
+                            value
+                                .filter(|&value| constant::wrap(value, kind, &self.target) == value)
+                        }
+                        None => value,
+                    };
+                    self.enumerators.insert(symbol, value);
+                    value
+                }
+            };
+            if symbol == id {
+                return value;
+            }
+            previous = Some(value);
+        }
+        None
+    }
+
+    /// The type declaration specifiers give before any declarator, which defines the
+    /// struct, union or enum they may define
+    pub(crate) fn specifiers(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        specifiers: &'a DeclSpecifiers<'i>,
+    ) -> TypeId {
+        let int = |signed, unsigned, sign| match sign {
+            Signedness::Signed => Type::Int(signed),
+            Signedness::Unsigned => Type::Int(unsigned),
+        };
+        let float = |kind, is_complex| Type::Float { kind, is_complex };
+        let ty = match specifiers.ty {
+            BaseType::Void => Type::Void,
+            BaseType::Bool => Type::Int(IntKind::Bool),
+            BaseType::Char(None) => Type::Int(IntKind::Char),
+            BaseType::Char(Some(sign)) => int(IntKind::SChar, IntKind::UChar, sign),
+            BaseType::Short(sign) => int(IntKind::Short, IntKind::UShort, sign),
+            BaseType::Int(sign) => int(IntKind::Int, IntKind::UInt, sign),
+            BaseType::Long(sign) => int(IntKind::Long, IntKind::ULong, sign),
+            BaseType::LongLong(sign) => int(IntKind::LongLong, IntKind::ULongLong, sign),
+            BaseType::Float { complex } => float(FloatKind::Float, complex),
+            BaseType::Double { complex } => float(FloatKind::Double, complex),
+            BaseType::LongDouble { complex } => float(FloatKind::LongDouble, complex),
+            BaseType::TypedefName(ref name) => {
+                let ty = match resolution.binding(name) {
+                    Some(id) => self.typedef(resolution, id),
+                    None => self.intern(Type::Error),
+                };
+                return self.qualified(ty, specifiers.qualifiers);
+            }
+            BaseType::Record(ref record) => {
+                let key = self.key(resolution, record.tag.as_ref(), record);
+                let ty = self.tag(resolution, key, Tag::Record(record));
+                return self.qualified(ty, specifiers.qualifiers);
+            }
+            BaseType::Enum(ref specifier) => {
+                let key = self.key(resolution, specifier.tag.as_ref(), specifier);
+                let ty = self.tag(resolution, key, Tag::Enum(specifier));
+                return self.qualified(ty, specifiers.qualifiers);
+            }
+            BaseType::Typeof(ref operand) => match **operand {
+                TypeofSpecifier::Type(ref ty) => {
+                    let ty = self.type_name(resolution, ty);
+                    return self.qualified(ty, specifiers.qualifiers);
+                }
+                TypeofSpecifier::Expression(ref expr) => {
+                    let ty = check::expression_type(resolution, self, expr);
+                    return self.qualified(ty, specifiers.qualifiers);
+                }
+            },
+            BaseType::Atomic(_, ref ty) => {
+                let ty = self.type_name(resolution, ty);
+                let qualifiers = TypeQualifiers {
+                    is_atomic: true,
+                    ..specifiers.qualifiers
+                };
+                return self.qualified(ty, qualifiers);
+            }
+            // Only known from the initializer, see `infer`
+            BaseType::AutoType => Type::Error,
+        };
+        let ty = self.intern(ty);
+        self.qualified(ty, specifiers.qualifiers)
+    }
+
+    /// What tells the type of a struct, union or enum specifier apart
+    fn key<T>(
+        &self,
+        resolution: &Resolution<'a, 'i>,
+        tag: Option<&'a Ident<'i>>,
+        specifier: &'a T,
+    ) -> TagKey {
+        match tag.and_then(|tag| resolution.binding(tag)) {
+            Some(id) => TagKey::Tag(id),
+            None => TagKey::Anonymous(specifier as *const T as *const ()),
+        }
+    }
+
+    /// The struct, union or enum type told apart by `key`, declared by `tag` unless its
+    /// symbol has a definition
+    fn tag(&mut self, resolution: &Resolution<'a, 'i>, key: TagKey, tag: Tag<'a, 'i>) -> TypeId {
+        if let Some(ty) = self.tags.get(&key) {
+            return self.intern(ty.clone());
+        }
+        let tag = match key {
+            TagKey::Tag(id) => match resolution.symbol(id).kind {
+                SymbolKind::Tag(definition) => definition,
+                _ => tag,
+            },
+            TagKey::Anonymous(_) => tag,
+        };
+
+        match tag {
+            Tag::Record(record) => {
+                let id = RecordId(self.records.len() as u32);
+                self.records.push(Record {
+                    kind: record.kind,
+                    tag: record.tag,
+                    definition: record.is_definition().then_some(record),
+                    members: None,
+                    layout: None,
+                });
+                // Members may point to the record itself
+                self.tags.insert(key, Type::Record(id));
+                if let Some(ref members) = record.members {
+                    let is_packed = record.attributes.has(KnownAttribute::Packed);
+                    let members = self.members(resolution, members, is_packed);
+                    let align = self.aligned(resolution, &record.attributes);
+                    let layout = self.layout(record.kind, &members, align);
+                    let record = &mut self.records[id.0 as usize];
+                    record.members = Some(members);
+                    record.layout = layout;
+                }
+                self.intern(Type::Record(id))
+            }
+            Tag::Enum(specifier) => {
+                let id = EnumId(self.enums.len() as u32);
+                let underlying = self.underlying(resolution, specifier);
+                self.enums.push(Enum {
+                    tag: specifier.tag,
+                    definition: specifier.is_definition().then_some(specifier),
+                    underlying,
+                });
+                self.tags.insert(key, Type::Enum(id));
+                self.intern(Type::Enum(id))
+            }
+        }
+    }
+
+    /// The members of a record, all `packed` if the record is
+    fn members(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        members: &'a [MemberItem<'i>],
+        is_packed: bool,
+    ) -> Vec<Member<'i>> {
+        let mut list = Vec::new();
+        for member in members.iter().filter_map(MemberItem::declaration) {
+            let base = self.specifiers(resolution, &member.ty);
+            let align = self.alignment(resolution, &member.ty);
+            let is_packed = is_packed || member.ty.attributes.has(KnownAttribute::Packed);
+            if let BaseType::Record(RecordSpecifier {
+                tag: None,
+                members: Some(_),
+                ..
+            }) = member.ty.ty
+            {
+                if member.declarators.is_empty() {
+                    list.push(Member {
+                        name: None,
+                        ty: base,
+                        width: None,
+                        align,
+                        is_packed,
+                    });
+                }
+            }
+            for declarator in member.declarators.iter() {
+                let width = declarator.width.as_ref().and_then(|width| {
+                    let width = self.constant(resolution, width)?;
+                    u32::try_from(width).ok()
+                });
+                let attributes = declarator_attributes(&declarator.declarator);
+                let mut align = align;
+                for attributes in &attributes {
+                    align = align.max(self.aligned(resolution, attributes));
+                }
+                list.push(Member {
+                    name: declarator.declarator.name(),
+                    ty: self.declarator(resolution, base, &declarator.declarator),
+                    width,
+                    align,
+                    is_packed: is_packed
+                        || attributes
+                            .iter()
+                            .any(|attributes| attributes.has(KnownAttribute::Packed)),
+                });
+            }
+        }
+        list
+    }
+
+    /// The alignment the `aligned` attributes among `attributes` ask for, the largest
+    /// of them. Without an argument, it is the largest alignment of the target.
+    fn aligned(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        attributes: &'a Attributes<'i>,
+    ) -> Option<u64> {
+        let mut align = None;
+        for attribute in attributes.iter() {
+            if attribute.kind() != Some(KnownAttribute::Aligned) {
+                continue;
+            }
+            let value = match attribute.args.as_deref() {
+                None | Some([]) => Some(self.target.max_align),
+                Some([value, ..]) => self
+                    .constant(resolution, value)
+                    .and_then(|value| u64::try_from(value).ok()),
+            };
+            align = align.max(value);
+        }
+        align
+    }
+
+    /// The alignment the specifiers of a declaration ask for with `_Alignas` or the
+    /// `aligned` attribute, the largest of them. `_Alignas(0)` asks for none.
+    fn alignment(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        specifiers: &'a DeclSpecifiers<'i>,
+    ) -> Option<u64> {
+        let mut align = self.aligned(resolution, &specifiers.attributes);
+        for specifier in &specifiers.alignment {
+            let value = match specifier {
+                AlignmentSpecifier::Type(ty) => {
+                    let ty = self.type_name(resolution, ty);
+                    self.align_of(ty)
+                }
+                AlignmentSpecifier::Expression(value) => self
+                    .constant(resolution, value)
+                    .and_then(|value| u64::try_from(value).ok())
+                    .filter(|&value| value > 0),
+            };
+            align = align.max(value);
+        }
+        align
+    }
+
+    /// The integer type of an enum: its fixed underlying type, or `unsigned int` unless
+    /// its values need a signed or larger type, as with GCC
+    fn underlying(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        specifier: &'a EnumSpecifier<'i>,
+    ) -> IntKind {
+        if let Some(ref underlying) = specifier.underlying {
+            let ty = self.specifiers(resolution, underlying);
+            return match *self.get(self.unqualified(ty).0) {
+                Type::Int(kind) => kind,
+                _ => IntKind::UInt,
+            };
+        }
+        let values: Vec<i128> = specifier
+            .enumerators
+            .iter()
+            .flatten()
+            .filter_map(|enumerator| resolution.binding(&enumerator.name))
+            .filter_map(|id| self.enumerator(resolution, id))
+            .collect();
+        let min = values.iter().copied().min().unwrap_or_default();
+        let max = values.iter().copied().max().unwrap_or_default();
+        match (min < 0, min, max) {
+            (false, _, max) if u32::try_from(max).is_ok() => IntKind::UInt,
+            (false, _, _) => IntKind::ULong,
+            (true, min, max) if i32::try_from(min).is_ok() && i32::try_from(max).is_ok() => {
+                IntKind::Int
+            }
+            (true, _, _) => IntKind::Long,
+        }
+    }
+
+    /// Applies the pointers, arrays and functions of `declarator` to `base`, starting
+    /// from the outermost one
+    fn declarator(
+        &mut self,
+        resolution: &Resolution<'a, 'i>,
+        base: TypeId,
+        declarator: &'a Declarator<'i>,
+    ) -> TypeId {
+        match declarator {
+            Declarator::Ident(_) | Declarator::Abstract => base,
+            Declarator::Attributed { inner, .. } => self.declarator(resolution, base, inner),
+            Declarator::Pointer { qualifiers, inner } => {
+                let pointer = self.pointer(base);
+                let pointer = self.qualified(pointer, *qualifiers);
+                self.declarator(resolution, pointer, inner)
+            }
+            Declarator::Array { inner, size, .. } => {
+                let length = match size {
+                    ArraySize::Unspecified => ArrayLength::Incomplete,
+                    ArraySize::Variable => ArrayLength::Variable,
+                    ArraySize::Expression(size) => match self.constant(resolution, size) {
+                        Some(length) => ArrayLength::Fixed(u64::try_from(length).unwrap_or(0)),
+                        None => ArrayLength::Variable,
+                    },
+                };
+                let array = self.intern(Type::Array {
+                    element: base,
+                    length,
+                });
+                self.declarator(resolution, array, inner)
+            }
+            Declarator::Function { inner, params } => {
+                let is_variadic = matches!(
+                    params,
+                    Parameters::Prototype {
+                        is_variadic: true,
+                        ..
+                    }
+                );
+                let params = match params {
+                    Parameters::Prototype { params, .. } => Some(
+                        params
+                            .iter()
+                            .map(|(specifiers, declarator)| {
+                                let declaration = Declaration {
+                                    specifiers,
+                                    declarator,
+                                };
+                                let ty = self.declaration(resolution, declaration);
+                                let ty = self.adjust_parameter(ty, array_qualifiers(declarator));
+                                // Qualifiers of parameters do not change the type
+                                self.unqualified(ty).0
+                            })
+                            .collect(),
+                    ),
+                    Parameters::Unspecified | Parameters::Identifiers(_) => None,
+                };
+                let function = self.intern(Type::Function {
+                    ret: base,
+                    params,
+                    is_variadic,
+                });
+                self.declarator(resolution, function, inner)
+            }
+        }
+    }
+
+    /// The type of a parameter declared as `ty`: arrays are pointers to their elements,
+    /// qualified with `qualifiers`, and functions are pointers to functions
+    fn adjust_parameter(&mut self, ty: TypeId, qualifiers: TypeQualifiers) -> TypeId {
+        let (unqualified, _) = self.unqualified(ty);
+        match *self.get(unqualified) {
+            Type::Array { element, .. } => {
+                let pointer = self.pointer(element);
+                self.qualified(pointer, qualifiers)
+            }
+            Type::Function { .. } => self.pointer(ty),
+            _ => ty,
+        }
+    }
+}
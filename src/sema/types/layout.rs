@@ -0,0 +1,197 @@
+//! Layout of structs and unions on System V targets, as GCC computes it: where each
+//! member is placed, and the size and alignment of the whole.
+
+use std::fmt::Display;
+
+use crate::ast::tree::RecordKind;
+
+use super::{ArrayLength, Member, RecordId, Type, TypeContext};
+
+/// Where the members of a struct or union are placed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordLayout {
+    /// In bytes, padding at the end included
+    pub size: u64,
+    /// In bytes
+    pub align: u64,
+    /// Offset in bits of each member, in the order of [`super::Record::members`]
+    pub offsets: Vec<u64>,
+}
+
+/// `value` rounded up to a multiple of `align`
+fn round_up(value: u64, align: u64) -> u64 {
+    value.div_ceil(align) * align
+}
+
+impl<'a, 'i> TypeContext<'a, 'i> {
+    /// The layout of a struct or union with `members`, aligned to at least `align` when
+    /// the record asks for it. `None` if a member has no size, except for a flexible
+    /// array member ending a struct.
+    pub(super) fn layout(
+        &self,
+        kind: RecordKind,
+        members: &[Member<'i>],
+        align: Option<u64>,
+    ) -> Option<RecordLayout> {
+        let is_union = kind == RecordKind::Union;
+        // The end of the last member, and of the largest one for unions, in bits
+        let mut end = 0;
+        let mut size = 0;
+        let mut record_align = align.unwrap_or(1);
+        let mut offsets = Vec::with_capacity(members.len());
+        for (n, member) in members.iter().enumerate() {
+            let is_flexible = !is_union
+                && n + 1 == members.len()
+                && matches!(
+                    *self.get(self.unqualified(member.ty).0),
+                    Type::Array {
+                        length: ArrayLength::Incomplete,
+                        ..
+                    }
+                );
+            let ty_align = self.align_of(member.ty)?;
+            let ty_size = match self.size_of(member.ty) {
+                Some(size) => size,
+                None if is_flexible => 0,
+                None => return None,
+            };
+            let natural = if member.is_packed { 1 } else { ty_align };
+            let member_align = member.align.map_or(natural, |align| align.max(natural));
+            let start = if is_union { 0 } else { end };
+
+            let (offset, width) = match member.width {
+                Some(width) => {
+                    let width = u64::from(width);
+                    let offset = if width == 0 {
+                        // Ends the storage unit, even in packed records
+                        round_up(start, ty_align * 8)
+                    } else if let Some(align) = member.align {
+                        round_up(start, align * 8)
+                    } else if member.is_packed {
+                        start
+                    } else {
+                        // A bit-field never straddles a unit of its type aligned as
+                        // the type is
+                        let unit = start / (ty_align * 8) * (ty_align * 8);
+                        if start + width > unit + ty_size * 8 {
+                            round_up(start, ty_align * 8)
+                        } else {
+                            start
+                        }
+                    };
+                    // Unnamed bit-fields do not align the record
+                    if member.name.is_some() {
+                        record_align = record_align.max(member_align);
+                    }
+                    (offset, width)
+                }
+                None => {
+                    record_align = record_align.max(member_align);
+                    (round_up(start, member_align * 8), ty_size * 8)
+                }
+            };
+            offsets.push(offset);
+            end = offset + width;
+            size = size.max(end);
+        }
+        Some(RecordLayout {
+            size: round_up(size.div_ceil(8), record_align),
+            align: record_align,
+            offsets,
+        })
+    }
+
+    /// Offset in bytes of the member reached by `path` from the start of `record`,
+    /// through anonymous members, as [`TypeContext::find_member`] gives it
+    #[must_use]
+    pub fn offset_of(&self, record: RecordId, path: &[usize]) -> Option<u64> {
+        let (&first, rest) = path.split_first()?;
+        let record = self.record(record);
+        let offset = record.layout.as_ref()?.offsets[first] / 8;
+        if rest.is_empty() {
+            return Some(offset);
+        }
+        let member = &record.members.as_ref()?[first];
+        let Type::Record(inner) = *self.get(self.unqualified(member.ty).0) else {
+            return None;
+        };
+        Some(offset + self.offset_of(inner, rest)?)
+    }
+
+    /// The records that have a layout, in the order they were first named
+    pub fn laid_out(&self) -> impl Iterator<Item = RecordId> + '_ {
+        (0..self.records.len() as u32)
+            .map(RecordId)
+            .filter(|&id| self.record(id).layout.is_some())
+    }
+
+    /// Writes the layout of `record` as Clang's `-fdump-record-layouts` does, with the
+    /// members of nested records
+    #[must_use]
+    pub fn dump_layout(&self, record: RecordId) -> DumpLayout<'_, 'a, 'i> {
+        DumpLayout {
+            types: self,
+            record,
+        }
+    }
+
+    fn dump_members(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        record: RecordId,
+        base: u64,
+        depth: usize,
+    ) -> std::fmt::Result {
+        let record = self.record(record);
+        let (Some(members), Some(layout)) = (&record.members, &record.layout) else {
+            return Ok(());
+        };
+        for (member, &offset) in members.iter().zip(&layout.offsets) {
+            let offset = base * 8 + offset;
+            let name = member.name.map(|name| name.to_string()).unwrap_or_default();
+            let text = self.declarator_text(member.ty, name);
+            let indent = "  ".repeat(depth);
+            match member.width {
+                Some(width) => {
+                    let bit = offset % 8;
+                    let position = match width {
+                        0 => format!("{}:{bit}", offset / 8),
+                        _ => format!("{}:{bit}-{}", offset / 8, bit + u64::from(width) - 1),
+                    };
+                    writeln!(f, "{position:>10} | {indent}{text}")?;
+                }
+                None => {
+                    writeln!(f, "{:>10} | {indent}{text}", offset / 8)?;
+                    if let Type::Record(inner) = *self.get(self.unqualified(member.ty).0) {
+                        self.dump_members(f, inner, offset / 8, depth + 1)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The layout of a record, from [`TypeContext::dump_layout`]
+pub struct DumpLayout<'c, 'a, 'i> {
+    types: &'c TypeContext<'a, 'i>,
+    record: RecordId,
+}
+impl<'c, 'a, 'i> Display for DumpLayout<'c, 'a, 'i> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let types = self.types;
+        let record = types.record(self.record);
+        let Some(ref layout) = record.layout else {
+            return Ok(());
+        };
+        let ty = types.record_name(self.record);
+        writeln!(f, "*** Dumping AST Record Layout")?;
+        writeln!(f, "{:>10} | {ty}", 0)?;
+        types.dump_members(f, self.record, 0, 1)?;
+        writeln!(
+            f,
+            "{:>10} | [sizeof={}, align={}]",
+            "", layout.size, layout.align
+        )
+    }
+}
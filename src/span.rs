@@ -12,6 +12,7 @@ where
     pub(crate) fn new(input: &'i str, start: usize, end: usize) -> Self {
         Self::new_with(input, start, end, Default::default())
     }
+    #[cfg(test)]
     pub(crate) fn new_remaining(input: &'i str, start: usize) -> Self {
         Self::new_remaining_with(input, start, Default::default())
     }
@@ -61,6 +62,10 @@ impl<'i, X> Span<'i, X> {
     pub fn get(&self) -> &'i str {
         self.input.get(self.start..self.end).unwrap()
     }
+    /// The whole source this span points into
+    pub(crate) fn input(&self) -> &'i str {
+        self.input
+    }
     pub fn len(&self) -> usize {
         self.end - self.start
     }
@@ -79,6 +84,22 @@ impl<'i, X> Span<'i, X> {
     pub fn end(&self) -> usize {
         self.end
     }
+
+    /// Whether only blanks come before the span on its line
+    #[must_use]
+    pub fn is_at_line_start(&self) -> bool {
+        let before = self.input[..self.start].trim_end_matches([' ', '\t']);
+        before.is_empty() || before.ends_with('\n')
+    }
+
+    /// 1-based line and column where the span starts
+    #[must_use]
+    pub fn line_col(&self) -> (usize, usize) {
+        let before = &self.input[..self.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rfind('\n').map_or(before, |nl| &before[nl + 1..]);
+        (line, column.chars().count() + 1)
+    }
 }
 impl<'i, X> std::fmt::Display for Span<'i, X> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -146,4 +167,13 @@ mod tests {
         assert_eq!(span, "hello");
         assert_eq!(span.len(), 5);
     }
+
+    #[test]
+    fn test_line_col() {
+        let input = "int a;\n  int b;";
+        assert_eq!(Span::new_with(input, 0, 3, ()).line_col(), (1, 1));
+        assert_eq!(Span::new_with(input, 13, 14, ()).line_col(), (2, 7));
+        assert!(Span::new_with(input, 9, 12, ()).is_at_line_start());
+        assert!(!Span::new_with(input, 13, 14, ()).is_at_line_start());
+    }
 }
@@ -0,0 +1,109 @@
+//! Revisions of the C standard and their GNU dialects, selected with `-std=`.
+
+use std::{fmt::Display, str::FromStr};
+
+/// Revision of ISO C the source is written in. Later revisions compare greater.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, derive_more::Display)]
+pub enum Standard {
+    /// ANSI C, where functions may still be defined with K&R identifier lists
+    #[display(fmt = "c89")]
+    C89,
+    #[display(fmt = "c99")]
+    C99,
+    #[display(fmt = "c11")]
+    C11,
+    #[default]
+    #[display(fmt = "c17")]
+    C17,
+    #[display(fmt = "c23")]
+    C23,
+}
+
+impl FromStr for Standard {
+    type Err = String;
+
+    /// Parses the name given to `-std=`, accepting the same aliases as GCC
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "c89" | "c90" | "ansi" | "iso9899:1990" => Self::C89,
+            "c99" | "c9x" | "iso9899:1999" => Self::C99,
+            "c11" | "c1x" | "iso9899:2011" => Self::C11,
+            "c17" | "c18" | "iso9899:2017" | "iso9899:2018" => Self::C17,
+            "c23" | "c2x" | "iso9899:2024" => Self::C23,
+            _ => return Err(format!("unknown standard '{s}'")),
+        })
+    }
+}
+
+/// The language selected with `-std=`: a revision of ISO C, which GNU extensions are
+/// part of in the `gnu` dialects, as in `-std=gnu11`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dialect {
+    pub standard: Standard,
+    pub gnu_extensions: bool,
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("gnu") {
+            Some(revision) => Ok(Self {
+                standard: format!("c{revision}")
+                    .parse()
+                    .map_err(|_| format!("unknown standard '{s}'"))?,
+                gnu_extensions: true,
+            }),
+            None => Ok(Self {
+                standard: s.parse()?,
+                gnu_extensions: false,
+            }),
+        }
+    }
+}
+
+impl Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let standard = self.standard.to_string();
+        match standard.strip_prefix('c') {
+            Some(revision) if self.gnu_extensions => write!(f, "gnu{revision}"),
+            _ => write!(f, "{standard}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dialect, Standard};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("c89".parse(), Ok(Standard::C89));
+        assert_eq!("ansi".parse(), Ok(Standard::C89));
+        assert_eq!("c18".parse(), Ok(Standard::C17));
+        assert_eq!("c2x".parse(), Ok(Standard::C23));
+        assert!("c42".parse::<Standard>().is_err());
+        assert!(Standard::C89 < Standard::C99);
+    }
+
+    #[test]
+    fn test_dialects() {
+        let gnu11 = Dialect {
+            standard: Standard::C11,
+            gnu_extensions: true,
+        };
+        assert_eq!("gnu11".parse(), Ok(gnu11));
+        assert_eq!("gnu1x".parse(), Ok(gnu11));
+        assert_eq!(gnu11.to_string(), "gnu11");
+        assert_eq!(
+            "c99".parse(),
+            Ok(Dialect {
+                standard: Standard::C99,
+                gnu_extensions: false,
+            })
+        );
+        assert_eq!(Dialect::default().to_string(), "c17");
+        assert!("gnuansi".parse::<Dialect>().is_err());
+        assert!("gnu42".parse::<Dialect>().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Targets the compiler knows the ABI of, selected with `--target`. A target only
+//! describes what sizes and alignments its ABI gives to types, so that adding one is
+//! adding a description.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::sema::{FloatKind, IntKind};
+
+/// Sizes and alignments in bytes of the scalar types of an ABI. Types not listed are
+/// as large as their width and aligned to their size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    /// Name given to `--target`
+    pub name: &'static str,
+    /// Size and alignment of pointers
+    pub pointer: u64,
+    /// Size and alignment of `long`
+    pub long: u64,
+    pub long_long_align: u64,
+    pub double_align: u64,
+    pub long_double_size: u64,
+    pub long_double_align: u64,
+    /// The largest alignment of any type, which `aligned` without an argument gives
+    pub max_align: u64,
+}
+
+impl Target {
+    /// x86-64 System V, as on Linux and the BSDs: LP64
+    pub const X86_64: Self = Self {
+        name: "x86_64",
+        pointer: 8,
+        long: 8,
+        long_long_align: 8,
+        double_align: 8,
+        long_double_size: 16,
+        long_double_align: 16,
+        max_align: 16,
+    };
+
+    /// i386 System V: ILP32, where 8-byte types are only aligned to 4 bytes and
+    /// `long double` is the 80-bit x87 format padded to 12 bytes
+    pub const I386: Self = Self {
+        name: "i386",
+        pointer: 4,
+        long: 4,
+        long_long_align: 4,
+        double_align: 4,
+        long_double_size: 12,
+        long_double_align: 4,
+        max_align: 16,
+    };
+
+    pub const ALL: [Self; 2] = [Self::X86_64, Self::I386];
+
+    #[must_use]
+    pub fn int_size(&self, kind: IntKind) -> u64 {
+        match kind {
+            IntKind::Bool | IntKind::Char | IntKind::SChar | IntKind::UChar => 1,
+            IntKind::Short | IntKind::UShort => 2,
+            IntKind::Int | IntKind::UInt => 4,
+            IntKind::Long | IntKind::ULong => self.long,
+            IntKind::LongLong | IntKind::ULongLong => 8,
+        }
+    }
+
+    /// Width in bits of the integer type `kind`, padding bits included
+    #[must_use]
+    pub fn int_bits(&self, kind: IntKind) -> u32 {
+        self.int_size(kind) as u32 * 8
+    }
+
+    #[must_use]
+    pub fn int_align(&self, kind: IntKind) -> u64 {
+        match kind {
+            IntKind::LongLong | IntKind::ULongLong => self.long_long_align,
+            _ => self.int_size(kind),
+        }
+    }
+
+    #[must_use]
+    pub fn float_size(&self, kind: FloatKind) -> u64 {
+        match kind {
+            FloatKind::Float => 4,
+            FloatKind::Double => 8,
+            FloatKind::LongDouble => self.long_double_size,
+        }
+    }
+
+    #[must_use]
+    pub fn float_align(&self, kind: FloatKind) -> u64 {
+        match kind {
+            FloatKind::Float => 4,
+            FloatKind::Double => self.double_align,
+            FloatKind::LongDouble => self.long_double_align,
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self::X86_64
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parses a target name, or the architecture of a GCC target triple
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arch = s.split('-').next().unwrap_or(s);
+        Ok(match arch {
+            "x86_64" | "amd64" => Self::X86_64,
+            "i386" | "i486" | "i586" | "i686" | "x86" => Self::I386,
+            _ => return Err(format!("unknown target '{s}'")),
+        })
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Target;
+    use crate::sema::{FloatKind, IntKind};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("x86_64".parse(), Ok(Target::X86_64));
+        assert_eq!("x86_64-linux-gnu".parse(), Ok(Target::X86_64));
+        assert_eq!("i686-pc-linux-gnu".parse(), Ok(Target::I386));
+        assert!("sparc".parse::<Target>().is_err());
+        for target in Target::ALL {
+            assert_eq!(target.to_string().parse(), Ok(target));
+        }
+    }
+
+    #[test]
+    fn test_scalars() {
+        let i386 = Target::I386;
+        assert_eq!(i386.int_size(IntKind::ULong), 4);
+        assert_eq!(i386.int_size(IntKind::LongLong), 8);
+        assert_eq!(i386.int_bits(IntKind::Long), 32);
+        assert_eq!(Target::X86_64.int_bits(IntKind::ULong), 64);
+        assert_eq!(i386.int_align(IntKind::LongLong), 4);
+        assert_eq!(i386.float_size(FloatKind::LongDouble), 12);
+        assert_eq!(Target::X86_64.int_align(IntKind::Long), 8);
+        assert_eq!(Target::X86_64.float_align(FloatKind::LongDouble), 16);
+    }
+}